@@ -414,6 +414,20 @@ impl<P: Provider> BoundlessMarketService<P> {
         client_sig: impl Into<Bytes>,
         priority_gas: Option<u64>,
     ) -> Result<u64, MarketError> {
+        let receipt = self.lock_request_receipt(request, client_sig, priority_gas).await?;
+        Ok(receipt.block_number.context("TXN Receipt missing block number")?)
+    }
+
+    /// Lock the request to the prover, as with [Self::lock_request], returning the receipt of
+    /// the lock transaction.
+    ///
+    /// This method should be called from the address of the prover.
+    pub async fn lock_request_receipt(
+        &self,
+        request: &ProofRequest,
+        client_sig: impl Into<Bytes>,
+        priority_gas: Option<u64>,
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!("Calling requestIsLocked({:x})", request.id);
         let is_locked_in: bool =
             self.instance.requestIsLocked(request.id).call().await.context("call failed")?;
@@ -461,7 +475,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         self.check_stake_balance().await?;
 
-        Ok(receipt.block_number.context("TXN Receipt missing block number")?)
+        Ok(receipt)
     }
 
     /// Lock the request to the prover, giving them exclusive rights to be paid to
@@ -595,8 +609,8 @@ impl<P: Provider> BoundlessMarketService<P> {
         }
     }
 
-    /// Submits a `FulfillmentTx`.
-    pub async fn fulfill(&self, tx: FulfillmentTx) -> Result<(), MarketError> {
+    /// Submits a `FulfillmentTx`, returning the receipt of the transaction.
+    pub async fn fulfill(&self, tx: FulfillmentTx) -> Result<TransactionReceipt, MarketError> {
         let FulfillmentTx { root, unlocked_requests, fulfillments, assessor_receipt, withdraw } =
             tx;
         let price = !unlocked_requests.is_empty();
@@ -656,7 +670,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         &self,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         let fill_ids = fulfillments.iter().map(|fill| fill.id).collect::<Vec<_>>();
         tracing::trace!("Calling fulfill({fulfillments:?}, {assessor_fill:?})");
         let call = self.instance.fulfill(fulfillments, assessor_fill).from(self.caller);
@@ -668,7 +682,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         tracing::info!("Submitted proof for batch {:?}: {}", fill_ids, receipt.transaction_hash);
 
-        Ok(())
+        Ok(receipt)
     }
 
     /// Fulfill a batch of requests by delivering the proof for each application and withdraw from the prover balance.
@@ -678,7 +692,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         &self,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         let fill_ids = fulfillments.iter().map(|fill| fill.id).collect::<Vec<_>>();
         tracing::trace!("Calling fulfillAndWithdraw({fulfillments:?}, {assessor_fill:?})");
         let call = self.instance.fulfillAndWithdraw(fulfillments, assessor_fill).from(self.caller);
@@ -690,7 +704,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         tracing::info!("Submitted proof for batch {:?}: {}", fill_ids, receipt.transaction_hash);
 
-        Ok(())
+        Ok(receipt)
     }
 
     /// Combined function to submit a new merkle root to the set-verifier and call `fulfill`.
@@ -700,7 +714,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         root: Root,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!(
             "Calling submitRootAndFulfill({:?}, {:x}, {fulfillments:?}, {assessor_fill:?})",
             root.root,
@@ -723,7 +737,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         tracing::info!("Submitted merkle root and proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// Combined function to submit a new merkle root to the set-verifier and call `fulfillAndWithdraw`.
@@ -733,7 +747,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         root: Root,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!("Calling submitRootAndFulfillAndWithdraw({:?}, {:x}, {fulfillments:?}, {assessor_fill:?})", root.root, root.seal);
         let call = self
             .instance
@@ -752,7 +766,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         tracing::info!("Submitted merkle root and proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// A combined call to `IBoundlessMarket.priceRequest` and `IBoundlessMarket.fulfill`.
@@ -764,7 +778,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
         priority_gas: Option<u64>,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!("Calling priceAndFulfill({fulfillments:?}, {assessor_fill:?})");
 
         let (requests, client_sigs): (Vec<_>, Vec<_>) =
@@ -795,7 +809,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         tracing::info!("Fulfilled proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// A combined call to `IBoundlessMarket.priceRequest` and `IBoundlessMarket.fulfillAndWithdraw`.
@@ -807,7 +821,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
         priority_gas: Option<u64>,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         tracing::trace!("Calling priceAndFulfillAndWithdraw({fulfillments:?}, {assessor_fill:?})");
 
         let (requests, client_sigs): (Vec<_>, Vec<_>) =
@@ -838,7 +852,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         tracing::info!("Fulfilled proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// Combined function to submit a new merkle root to the set-verifier and call `priceAndfulfill`.
@@ -849,7 +863,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         unlocked_requests: Vec<UnlockedRequest>,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         let (requests, client_sigs): (Vec<_>, Vec<_>) =
             unlocked_requests.into_iter().map(|ur| (ur.request, ur.client_sig)).unzip();
         tracing::trace!("Calling submitRootAndPriceAndFulfill({:?}, {:x}, {:?}, {:?}, {fulfillments:?}, {assessor_fill:?})", root.root, root.seal, requests, client_sigs);
@@ -877,7 +891,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         tracing::info!("Submitted merkle root and proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// Combined function to submit a new merkle root to the set-verifier and call `priceAndFulfillAndWithdraw`.
@@ -888,7 +902,7 @@ impl<P: Provider> BoundlessMarketService<P> {
         unlocked_requests: Vec<UnlockedRequest>,
         fulfillments: Vec<Fulfillment>,
         assessor_fill: AssessorReceipt,
-    ) -> Result<(), MarketError> {
+    ) -> Result<TransactionReceipt, MarketError> {
         let (requests, client_sigs): (Vec<_>, Vec<_>) =
             unlocked_requests.into_iter().map(|ur| (ur.request, ur.client_sig)).unzip();
         tracing::trace!("Calling submitRootAndPriceAndFulfillAndWithdraw({:?}, {:x}, {:?}, {:?}, {fulfillments:?}, {assessor_fill:?})", root.root, root.seal, requests, client_sigs);
@@ -916,7 +930,7 @@ impl<P: Provider> BoundlessMarketService<P> {
 
         tracing::info!("Submitted merkle root and proof for batch {}", tx_receipt.transaction_hash);
 
-        Ok(())
+        Ok(tx_receipt)
     }
 
     /// Checks if a request is locked in.
@@ -1481,6 +1495,11 @@ impl Offer {
         }
     }
 
+    /// Calculates the price at each of the given times, in seconds since the UNIX epoch.
+    pub fn price_schedule(&self, timestamps: &[u64]) -> Result<Vec<U256>, MarketError> {
+        timestamps.iter().map(|&timestamp| self.price_at(timestamp)).collect()
+    }
+
     /// UNIX timestamp after which the request is considered completely expired.
     pub fn deadline(&self) -> u64 {
         self.biddingStart + (self.timeout as u64)
@@ -1639,6 +1658,20 @@ mod tests {
         assert_eq!(offer.price_at(500).unwrap(), ether("2"));
     }
 
+    #[test]
+    fn test_price_schedule() {
+        let offer = &test_offer(100);
+
+        let timestamps = [90, 100, 150, 200, 600, 601];
+        let schedule = offer.price_schedule(&timestamps).unwrap();
+        assert_eq!(
+            schedule,
+            timestamps.iter().map(|&t| offer.price_at(t).unwrap()).collect::<Vec<_>>()
+        );
+        // The price drops to zero once the lock deadline has passed.
+        assert_eq!(schedule[5], U256::ZERO);
+    }
+
     #[test]
     fn test_time_at_price() {
         let offer = &test_offer(100);
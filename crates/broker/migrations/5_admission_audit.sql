@@ -0,0 +1,7 @@
+CREATE TABLE admission_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER,
+    data JSONB
+);
+
+CREATE INDEX admission_audit_timestamp ON admission_audit (timestamp);
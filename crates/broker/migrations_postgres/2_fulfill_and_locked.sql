@@ -0,0 +1,10 @@
+CREATE TABLE fulfilled_requests (
+    id TEXT PRIMARY KEY,
+    block_number BIGINT
+);
+
+CREATE TABLE locked_requests (
+    id TEXT PRIMARY KEY,
+    locker TEXT,
+    block_number BIGINT
+);
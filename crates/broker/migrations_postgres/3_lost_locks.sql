@@ -0,0 +1,8 @@
+CREATE TABLE lost_locks (
+    id TEXT PRIMARY KEY,
+    winner TEXT,
+    lock_price TEXT,
+    timestamp BIGINT
+);
+
+CREATE INDEX lost_locks_timestamp ON lost_locks (timestamp);
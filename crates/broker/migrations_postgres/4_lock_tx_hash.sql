@@ -0,0 +1 @@
+ALTER TABLE orders ADD COLUMN lock_tx_hash TEXT;
@@ -0,0 +1,7 @@
+CREATE TABLE admission_audit (
+    id BIGSERIAL PRIMARY KEY,
+    timestamp BIGINT,
+    data JSONB
+);
+
+CREATE INDEX admission_audit_timestamp ON admission_audit (timestamp);
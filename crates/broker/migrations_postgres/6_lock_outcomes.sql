@@ -0,0 +1,9 @@
+CREATE TABLE lock_outcomes (
+    id BIGSERIAL PRIMARY KEY,
+    request_id TEXT,
+    outcome TEXT,
+    timestamp BIGINT
+);
+
+CREATE INDEX lock_outcomes_request_id ON lock_outcomes (request_id);
+CREATE INDEX lock_outcomes_timestamp ON lock_outcomes (timestamp);
@@ -0,0 +1,9 @@
+CREATE TABLE lock_prices (
+    id TEXT PRIMARY KEY,
+    submission_price TEXT,
+    target_price TEXT,
+    confirmation_price TEXT,
+    timestamp BIGINT
+);
+
+CREATE INDEX lock_prices_timestamp ON lock_prices (timestamp);
@@ -0,0 +1,18 @@
+ALTER TABLE lock_outcomes ADD COLUMN winner TEXT;
+ALTER TABLE lock_outcomes ADD COLUMN lock_price TEXT;
+
+-- Lost races were recorded in both tables, attribute the existing outcomes to their winner.
+UPDATE lock_outcomes
+SET winner = (SELECT winner FROM lost_locks WHERE lost_locks.id = lock_outcomes.request_id),
+    lock_price = (SELECT lock_price FROM lost_locks WHERE lost_locks.id = lock_outcomes.request_id)
+WHERE outcome = 'lost';
+
+-- Races lost before locking was attempted were only recorded as lost locks.
+INSERT INTO lock_outcomes (request_id, outcome, winner, lock_price, timestamp)
+SELECT id, 'lost', winner, lock_price, timestamp FROM lost_locks
+WHERE NOT EXISTS (
+    SELECT 1 FROM lock_outcomes
+    WHERE lock_outcomes.request_id = lost_locks.id AND lock_outcomes.outcome = 'lost'
+);
+
+DROP TABLE lost_locks;
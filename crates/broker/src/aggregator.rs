@@ -790,6 +790,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -837,6 +839,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -951,6 +955,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             request: order_request,
             boundless_market_address: Address::ZERO,
             chain_id,
@@ -1013,6 +1019,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             request: order_request,
             boundless_market_address: Address::ZERO,
             chain_id,
@@ -1125,6 +1133,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -1239,6 +1249,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -1361,6 +1373,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -1400,6 +1414,8 @@ mod tests {
             lock_price: Some(U256::from(min_price)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id,
             total_cycles: None,
@@ -1472,6 +1488,8 @@ mod tests {
             lock_price: Some(U256::from(1)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
@@ -1513,6 +1531,8 @@ mod tests {
             lock_price: Some(U256::from(1)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
@@ -0,0 +1,114 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{sync::Arc, time::Duration};
+
+use alloy::primitives::U256;
+use async_trait::async_trait;
+use reqwest::header::CONTENT_TYPE;
+use url::Url;
+
+/// Timeout of a request posting an alert to a webhook.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Receives operational events severe enough to page an operator, such as through a webhook.
+///
+/// Unlike metrics, which are logged for every iteration, alerts are only raised for events that
+/// need immediate attention.
+#[async_trait]
+pub(crate) trait AlertSink: Send + Sync {
+    /// Called when a request was locked but could not be recorded for proving, so its stake will
+    /// be slashed unless it is proven by other means before the lock expires.
+    async fn on_stake_at_risk(&self, request_id: U256, lock_price: U256);
+}
+
+pub(crate) type AlertSinkObj = Arc<dyn AlertSink>;
+
+/// Ignores alerts, leaving them to the logs.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct NoopAlertSink;
+
+#[async_trait]
+impl AlertSink for NoopAlertSink {
+    async fn on_stake_at_risk(&self, _request_id: U256, _lock_price: U256) {}
+}
+
+/// Posts alerts as JSON to a webhook, such as one paging an operator.
+///
+/// Alerts that fail to be posted are logged rather than retried, as they are only raised for
+/// events that were already logged.
+pub(crate) struct WebhookAlertSink {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl WebhookAlertSink {
+    pub(crate) fn new(url: Url) -> Self {
+        Self { client: reqwest::Client::new(), url }
+    }
+
+    async fn post(&self, alert: serde_json::Value) -> Result<(), reqwest::Error> {
+        self.client
+            .post(self.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(alert.to_string())
+            .timeout(WEBHOOK_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AlertSink for WebhookAlertSink {
+    async fn on_stake_at_risk(&self, request_id: U256, lock_price: U256) {
+        let alert = serde_json::json!({
+            "alert": "stake_at_risk",
+            "request_id": format!("0x{request_id:x}"),
+            "lock_price_wei": lock_price.to_string(),
+        });
+        if let Err(err) = self.post(alert).await {
+            tracing::error!(
+                "Failed to post stake at risk alert for request 0x{request_id:x} to {}: {err:?}",
+                self.url
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn webhook_stake_at_risk() {
+        let server = MockServer::start();
+        let alert_mock = server.mock(|when, then| {
+            when.method(POST).path("/alerts").header("content-type", "application/json").json_body(
+                serde_json::json!({
+                    "alert": "stake_at_risk",
+                    "request_id": "0x2a",
+                    "lock_price_wei": "100",
+                }),
+            );
+            then.status(200);
+        });
+
+        let sink = WebhookAlertSink::new(Url::parse(&server.url("/alerts")).unwrap());
+        sink.on_stake_at_risk(U256::from(42), U256::from(100)).await;
+        alert_mock.assert();
+    }
+}
@@ -88,6 +88,13 @@ async fn main() -> Result<()> {
     let provider = NonceProvider::new(base_provider, wallet.clone());
     let broker = Broker::new(args.clone(), provider.clone()).await?;
 
+    if let Some(path) = args.replay_session.as_ref() {
+        return broker.replay_session(path).await.context("Failed to replay session");
+    }
+    if let Some(order_id) = args.explain_order.as_ref() {
+        return broker.explain_order(order_id).await.context("Failed to explain order");
+    }
+
     // TODO: Move this code somewhere else / monitor our balanceOf and top it up as needed
     if let Some(deposit_amount) = args.deposit_amount.as_ref() {
         let boundless_market = BoundlessMarketService::new(
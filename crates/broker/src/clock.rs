@@ -0,0 +1,38 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use crate::now_timestamp;
+
+/// Source of the current UNIX timestamp in seconds, used by the order monitor's decisions.
+///
+/// Defaults to the system clock. Recorded sessions are replayed against the timestamps they were
+/// recorded at, see [crate::replay::RecordedSession].
+pub(crate) trait Clock: Send + Sync {
+    /// Returns the current UNIX timestamp in seconds.
+    fn now(&self) -> u64;
+}
+
+pub(crate) type ClockObj = Arc<dyn Clock>;
+
+/// Reads the system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        now_timestamp()
+    }
+}
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     sync::{Arc, RwLock},
 };
@@ -77,6 +77,22 @@ mod defaults {
     pub const fn max_concurrent_preflights() -> u32 {
         4
     }
+
+    pub const fn requestor_balance_retry_backoff_secs() -> u64 {
+        30
+    }
+
+    pub const fn gas_price_fallback_margin_percent() -> u64 {
+        20
+    }
+
+    pub const fn gas_estimation_concurrency() -> usize {
+        8
+    }
+
+    pub const fn admission_audit_retention_secs() -> u64 {
+        7 * 24 * 60 * 60
+    }
 }
 
 /// Order pricing priority mode for determining which orders to price first
@@ -105,6 +121,8 @@ pub enum OrderCommitmentPriority {
     Random,
     /// Process orders by shortest expiry first (lock expiry for lock-and-fulfill orders, request expiry for others)
     ShortestExpiry,
+    /// Process orders by highest expected return on the locked stake, net of gas costs
+    RoiAdjusted,
 }
 
 impl Default for OrderCommitmentPriority {
@@ -113,6 +131,174 @@ impl Default for OrderCommitmentPriority {
     }
 }
 
+/// How proving capacity is accounted for orders admitted but not yet started, see
+/// [MarketConf::capacity_accounting]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CapacityAccounting {
+    /// Only the configured capacity limits apply
+    Optimistic,
+    /// Orders admitted but not yet picked up by the prover also occupy capacity
+    Pessimistic,
+}
+
+impl Default for CapacityAccounting {
+    fn default() -> Self {
+        Self::Optimistic
+    }
+}
+
+/// Point in time of a lock whose price is recorded as the lock price, see
+/// [MarketConf::lock_price_basis]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LockPriceBasis {
+    /// When the lock transaction was submitted
+    Submission,
+    /// The order's target timestamp, falling back to the confirmation if it has none
+    Target,
+    /// The timestamp of the block the lock was confirmed in
+    Confirmation,
+}
+
+impl Default for LockPriceBasis {
+    fn default() -> Self {
+        Self::Confirmation
+    }
+}
+
+/// Alert raised when the balance does not cover committed orders, see
+/// [MarketConf::balance_underwater_alert]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceUnderwaterAlert {
+    /// Log an error
+    Log,
+    /// Log an error and emit the `balance_underwater` metric
+    Metric,
+}
+
+/// Balance expected to arrive shortly, such as from a deposit in flight, see
+/// [MarketConf::expected_incoming_balance]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedIncomingBalance {
+    /// Amount expected, in wei
+    pub amount_wei: u128,
+    /// Unix timestamp after which the amount is no longer expected
+    pub expires_at: u64,
+}
+
+/// Band of priority gas adapted to recent lock races, see
+/// [MarketConf::adaptive_lockin_priority_gas]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityGasBand {
+    /// Priority gas used while winning every recent lock race
+    pub min: u64,
+    /// Priority gas used while losing every recent lock race
+    pub max: u64,
+}
+
+/// Automatic deposits restoring the stake balance, see [MarketConf::auto_stake_topup]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct StakeTopUp {
+    /// Stake balance to restore, in stake tokens
+    pub target: String,
+    /// Minimum seconds between deposits
+    pub cooldown_secs: u64,
+}
+
+/// Proving capacity withheld from lock-and-fulfill orders for orders whose lock expired
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(untagged)]
+pub enum CapacityReserve {
+    /// Number of concurrent proofs
+    Count(u32),
+    /// Fraction of the max concurrent proofs, rounded up
+    Fraction(f64),
+}
+
+impl CapacityReserve {
+    /// Returns the number of concurrent proofs reserved out of `max_concurrent_proofs`.
+    pub fn slots(&self, max_concurrent_proofs: u32) -> u32 {
+        match *self {
+            Self::Count(count) => count.min(max_concurrent_proofs),
+            Self::Fraction(fraction) => {
+                let slots = (fraction.clamp(0.0, 1.0) * max_concurrent_proofs as f64).ceil();
+                slots as u32
+            }
+        }
+    }
+}
+
+/// Handling of orders requiring a proof type, see [MarketConf::proof_type_policies]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofTypePolicy {
+    /// Skip orders requiring the proof type
+    Deny,
+    /// Add the given gas to the estimated cost of orders requiring the proof type
+    ///
+    /// Lowers the profitability and priority of such orders, in proportion to the gas price.
+    SurchargeGas(u64),
+}
+
+/// Handling of lock-and-fulfill orders without a lock stake, see [MarketConf::zero_stake_policy]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ZeroStakePolicy {
+    /// Commit to zero-stake orders only after orders with a lock stake
+    Deprioritize,
+    /// Skip zero-stake orders
+    Skip,
+}
+
+/// Estimator of the probability of winning lock races, see [MarketConf::contest_estimator]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContestEstimatorKind {
+    /// The fraction of recent lock races won, for every order
+    History,
+    /// The fraction of recent lock races won, raised for orders early in their price ramp-up,
+    /// which are profitable to fewer competitors
+    Ramp,
+}
+
+impl Default for ContestEstimatorKind {
+    fn default() -> Self {
+        Self::History
+    }
+}
+
+/// Overrides of the market configuration for orders on a specific chain
+///
+/// Fields that are unset fall back to the global market configuration.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ChainOverrides {
+    /// Minimum deadline in seconds, see [MarketConf::min_deadline]
+    pub min_deadline: Option<u64>,
+    /// Gas estimate for lockin call, see [MarketConf::lockin_gas_estimate]
+    pub lockin_gas_estimate: Option<u64>,
+    /// Gas estimate for fulfill call, see [MarketConf::fulfill_gas_estimate]
+    pub fulfill_gas_estimate: Option<u64>,
+    /// Gas estimate for proof verification, see [MarketConf::groth16_verify_gas_estimate]
+    pub groth16_verify_gas_estimate: Option<u64>,
+    /// lockRequest priority gas, see [MarketConf::lockin_priority_gas]
+    pub lockin_priority_gas: Option<u64>,
+    /// Minimum expected profit in wei after gas, see [MarketConf::min_profit_wei]
+    pub min_profit_wei: Option<i64>,
+}
+
+/// Market configuration resolved for orders on a specific chain, see [ChainOverrides]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainMarketConf {
+    pub min_deadline: u64,
+    pub lockin_gas_estimate: u64,
+    pub fulfill_gas_estimate: u64,
+    pub groth16_verify_gas_estimate: u64,
+    pub lockin_priority_gas: Option<u64>,
+    pub min_profit_wei: Option<i64>,
+}
+
 /// All configuration related to markets mechanics
 #[derive(Debug, Deserialize, Serialize)]
 #[non_exhaustive]
@@ -249,8 +435,487 @@ pub struct MarketConf {
     /// Determines how orders are prioritized when committing to prove them. Options:
     /// - "random": Process orders in random order to distribute competition among provers (default)
     /// - "shortest_expiry": Process orders by shortest expiry first (lock expiry for lock-and-fulfill orders, request expiry for others)
+    /// - "roi_adjusted": Process orders by highest expected return on the locked stake, net of gas costs
     #[serde(default, alias = "expired_order_fulfillment_priority")]
     pub order_commitment_priority: OrderCommitmentPriority,
+    /// Max number of lock retries for orders whose requestor has insufficient balance
+    ///
+    /// Requestors sometimes top up their balance after an order fails to lock. When set above zero,
+    /// such orders are kept and retried after `requestor_balance_retry_backoff_secs`, up to this many
+    /// times, before being skipped. Defaults to 0, skipping the order on the first failure.
+    #[serde(default)]
+    pub requestor_balance_retry_attempts: u32,
+    /// Seconds to wait before retrying to lock an order whose requestor had insufficient balance
+    #[serde(default = "defaults::requestor_balance_retry_backoff_secs")]
+    pub requestor_balance_retry_backoff_secs: u64,
+    /// Pause locking of new orders
+    ///
+    /// When set, the broker stops locking new orders while continuing to prove and fulfill orders
+    /// that are already committed or that do not require a lock. Intended as a kill-switch during
+    /// incidents, and can be toggled without restarting the broker.
+    #[serde(default)]
+    pub pause_locking: bool,
+    /// Safety margin, in percent, added to the last known gas price when the gas price cannot be
+    /// fetched
+    ///
+    /// If fetching the current gas price fails, the last successfully fetched gas price increased by
+    /// this margin is used to estimate gas costs instead, so that transient gas oracle failures do
+    /// not stall committing to orders. If no gas price has been fetched yet, the failure is returned.
+    #[serde(default = "defaults::gas_price_fallback_margin_percent")]
+    pub gas_price_fallback_margin_percent: u64,
+    /// Maximum number of gas estimations to run concurrently when applying capacity limits
+    ///
+    /// Gas costs for all candidate orders are estimated up front before deciding which orders
+    /// fit within the available balance.
+    #[serde(default = "defaults::gas_estimation_concurrency")]
+    pub gas_estimation_concurrency: usize,
+    /// Maximum number of proof cycles to be committed to at once
+    ///
+    /// When set, proving capacity is measured in cycles rather than in concurrent proofs and takes
+    /// precedence over `max_concurrent_proofs`. Orders are admitted while their cycles fit within
+    /// the budget left by committed orders. If `peak_prove_khz` is also set, committed orders only
+    /// count the cycles estimated to remain unproven, so a partially proven large order frees up
+    /// budget for smaller orders.
+    #[serde(default)]
+    pub max_committed_cycles: Option<u64>,
+    /// Maximum wall-clock time in seconds to spend evaluating cached orders in a single iteration
+    /// of the order monitor
+    ///
+    /// Once reached, the iteration stops evaluating further orders and proceeds with the orders
+    /// evaluated so far, leaving the rest cached for the next iteration. At least one order is
+    /// evaluated per iteration. Lock transactions and proofs that have already started are not
+    /// interrupted, so their own timeouts still apply. If unset, there is no deadline.
+    #[serde(default)]
+    pub monitor_loop_deadline_secs: Option<u64>,
+    /// Minimum expected profit in wei after gas required to commit to an order
+    ///
+    /// Orders whose price minus the estimated gas cost to lock and fulfill them is below this
+    /// value are skipped. May be negative to allow committing to orders at a loss. Only applies to
+    /// orders locked by us, as other orders are not rewarded in wei. If unset, orders are not
+    /// filtered on profit.
+    #[serde(default)]
+    pub min_profit_wei: Option<i64>,
+    /// Number of times to retry fetching the block a lock was included in
+    ///
+    /// The block is fetched right after locking to determine the lock price, and may not be
+    /// indexed by the RPC provider yet. If unset, the general RPC retry settings are used.
+    #[serde(default)]
+    pub block_fetch_retry_count: Option<u64>,
+    /// Milliseconds to wait between retries of fetching the block a lock was included in
+    ///
+    /// If unset, the general RPC retry settings are used.
+    #[serde(default)]
+    pub block_fetch_retry_sleep_ms: Option<u64>,
+    /// Maximum total milliseconds to spend retrying to fetch the block a lock was included in
+    ///
+    /// Bounds the time spent on retries regardless of `block_fetch_retry_count`, so a slow RPC
+    /// provider does not eat into the time left to prove the order. If unset, only the number of
+    /// retries is bounded.
+    #[serde(default)]
+    pub block_fetch_retry_max_total_ms: Option<u64>,
+    /// Number of failed attempts to fetch the block a lock was included in by number, after which
+    /// the block is looked up by the hash in the lock transaction's receipt instead
+    ///
+    /// Some RPC nodes index blocks by hash sooner than by number, providing a second path to the
+    /// lock timestamp. If unset, the block is only fetched by number.
+    #[serde(default)]
+    pub block_fetch_receipt_fallback_after: Option<u64>,
+    /// Maximum time in seconds an order may be proving before it is considered stale
+    ///
+    /// On startup, committed orders that started proving longer ago than this, e.g. left behind
+    /// by a previous process that crashed, are reconciled so they no longer consume proving
+    /// capacity: orders fulfilled on chain are marked complete and all others are marked failed.
+    /// If unset, committed orders are not reconciled.
+    #[serde(default)]
+    pub max_proving_duration_secs: Option<u64>,
+    /// Seed for the hash used to break ties between equally prioritized orders
+    ///
+    /// Orders that are equal under the order commitment priority are ordered by a hash of their
+    /// request ID salted with this seed, so the same orders are selected across runs. Brokers with
+    /// different seeds prefer different orders among equals, reducing competition for locks.
+    #[serde(default)]
+    pub order_tie_breaker_seed: u64,
+    /// Proving capacity reserved for orders whose lock expired
+    ///
+    /// Either a number of concurrent proofs or a fraction of `max_concurrent_proofs`. While orders
+    /// that can be fulfilled after a lock expired compete with lock-and-fulfill orders, this
+    /// capacity is withheld from lock-and-fulfill orders. The reserve is released to
+    /// lock-and-fulfill orders when there are no such orders, so capacity does not go idle. Only
+    /// applies if `max_concurrent_proofs` is set.
+    #[serde(default)]
+    pub salvage_capacity_reserve: Option<CapacityReserve>,
+    /// Cycles assumed for orders with an unknown cycle count when budgeting proving capacity
+    ///
+    /// Used by `max_committed_cycles` and `peak_prove_khz` for orders whose cycle count could not
+    /// be determined, so that they are not budgeted as free. Should be set conservatively. If
+    /// unset, such orders are budgeted as zero cycles and a warning is logged.
+    #[serde(default)]
+    pub fallback_order_cycles: Option<u64>,
+    /// Overrides of the market configuration keyed by chain ID
+    ///
+    /// When evaluating an order, fields set for the chain of the order take precedence over the
+    /// global values. See [ChainOverrides] for the fields that can be overridden.
+    #[serde(default)]
+    pub chains: HashMap<String, ChainOverrides>,
+    /// Maximum number of lock transactions to submit concurrently
+    ///
+    /// Limits the load on the RPC node's mempool and nonce handling when many orders are selected
+    /// for locking at once. Orders beyond the limit wait for an earlier lock to complete. If
+    /// unset, all selected orders are locked concurrently.
+    #[serde(default, alias = "max_inflight_locks", alias = "lock_concurrency")]
+    pub max_concurrent_lock_txs: Option<u32>,
+    /// Minimum estimated probability of winning the race to lock an order
+    ///
+    /// Orders the broker is estimated to be less likely to lock than this, based on recent lost
+    /// lock races, are not locked to save the gas of lock transactions likely to revert. If unset,
+    /// all orders are locked regardless of competition.
+    #[serde(default)]
+    pub min_lock_win_probability: Option<f64>,
+    /// Estimator of the probability of winning the race to lock an order: "history" or "ramp"
+    ///
+    /// Used with `min_lock_win_probability`. Read at startup.
+    #[serde(default)]
+    pub contest_estimator: ContestEstimatorKind,
+    /// Handling of orders by the proof type required by their selector
+    ///
+    /// Keyed by proof type (`any`, `groth16` or `inclusion`), allowing orders that are expensive to
+    /// verify on chain to be denied or deprioritized rather than unsupported entirely. Proof types
+    /// without a policy are handled as usual.
+    #[serde(default)]
+    pub proof_type_policies: HashMap<String, ProofTypePolicy>,
+    /// Maximum time in seconds ahead of the current block that an order's target timestamp may be
+    ///
+    /// Orders whose target timestamp is further in the future are kept cached but held back until
+    /// the target comes within this horizon, keeping the set of orders considered for commitment
+    /// to the near term. Unlike `max_target_timestamp_horizon_secs`, which drops such orders at
+    /// ingestion, the orders are kept. If unset, there is no horizon.
+    #[serde(default)]
+    pub max_target_horizon_secs: Option<u64>,
+    /// Alert raised once when the balance no longer covers the estimated gas of committed orders
+    ///
+    /// Unlike the per-iteration log of admission being deferred, the alert is raised once when the
+    /// balance becomes insufficient and cleared once it recovers. If unset, no alert is raised.
+    #[serde(default)]
+    pub balance_underwater_alert: Option<BalanceUnderwaterAlert>,
+    /// URL alerts needing an operator's immediate attention are posted to as JSON, such as a
+    /// locked order whose stake is at risk
+    ///
+    /// Read at startup. If unset, such alerts are only logged.
+    #[serde(default)]
+    pub alert_webhook_url: Option<String>,
+    /// RPC URL whose gas price is used instead of the gas price of the broker's RPC, both to
+    /// cost orders and to price lock transactions
+    ///
+    /// For chains where a separate node or gas oracle estimates the gas price better. Read at
+    /// startup.
+    #[serde(default)]
+    pub gas_price_rpc_url: Option<String>,
+    /// Select orders to maximize the number proven before their expiration under `peak_prove_khz`
+    ///
+    /// By default, orders are admitted greedily in priority order, so an order with a large cycle
+    /// count can use up the proving time that several smaller orders needed to complete before
+    /// their expiration. When set, orders are instead selected to maximize the number of orders
+    /// that can be proven in time, deferring the others. Has no effect without `peak_prove_khz`.
+    #[serde(default)]
+    pub optimize_khz_allocation: bool,
+    /// Submit requests on chain before locking them, unless they are already locked or fulfilled
+    ///
+    /// Supports requests that are only distributed off chain with the client signature, so that
+    /// they are publicly recorded on chain. Costs an additional transaction for each request locked,
+    /// as requests already submitted but not locked cannot be told apart without searching the logs.
+    #[serde(default)]
+    pub submit_request_before_lock: bool,
+    /// Number of confirmations behind the chain head to check that a request is still open at
+    /// before locking it
+    ///
+    /// Avoids lock attempts based on recent blocks that may be reorged, at the cost of some
+    /// latency. If unset, the status at the latest block is used.
+    #[serde(default)]
+    pub lock_status_confirmations: Option<u64>,
+    /// Verify that the lock event of a request records this prover as the locker before proving
+    /// it
+    ///
+    /// Guards against proving requests whose lock was won by a competing lock ordered ahead in the
+    /// same block. Costs an additional RPC query for each lock.
+    #[serde(default)]
+    pub verify_lock_owner: bool,
+    /// Max lock stake of a single order, denominated in the Boundless staking token
+    ///
+    /// Orders requiring a higher lock stake are skipped rather than locked, limiting the stake at
+    /// risk on any single order. If unset, only `max_stake` applies.
+    #[serde(default)]
+    pub max_single_order_lock_stake: Option<String>,
+    /// Fraction of an order's lifetime that must remain before it expires to prove it
+    ///
+    /// The lifetime is measured from the start of bidding to the expiration of the order. If set,
+    /// the larger of this and `min_deadline` is required, so that long-lived orders keep headroom
+    /// proportional to their lifetime.
+    #[serde(default)]
+    pub min_deadline_fraction: Option<f64>,
+    /// Balance expected to arrive shortly, such as from a deposit transaction in flight
+    ///
+    /// Counted towards the balance available to new orders until `expires_at`, after which it is
+    /// ignored so that a stale expectation does not over-commit. Never counted towards covering
+    /// committed orders.
+    #[serde(default)]
+    pub expected_incoming_balance: Option<ExpectedIncomingBalance>,
+    /// Orders with fewer seconds than this remaining before they expire are admitted first,
+    /// regardless of `order_commitment_priority`
+    ///
+    /// Orders already locked by us within this window are also moved ahead of every other valid
+    /// order, as our stake is at risk if they are not proven in time.
+    #[serde(default)]
+    pub urgent_deadline_secs: Option<u64>,
+    /// Priority gas used to lock orders within `urgent_deadline_secs` of expiring, if higher than
+    /// `lockin_priority_gas`
+    #[serde(default)]
+    pub urgent_lockin_priority_gas: Option<u64>,
+    /// Max total lock stake committed at once, denominated in the Boundless staking token
+    ///
+    /// Sums the lock stake of committed lock-and-fulfill orders and the orders being admitted.
+    /// Orders that would take the total above this cap are skipped. If unset, the total lock stake
+    /// is unlimited.
+    #[serde(default)]
+    pub max_total_stake_committed: Option<String>,
+    /// Minimum age in seconds of a lock by another prover before orders are skipped because of it
+    ///
+    /// Lock records younger than this, or whose block can no longer be found, may still be
+    /// reverted by a reorg, so the order is re-checked on the next iteration instead. If unset,
+    /// orders are skipped as soon as another prover's lock is recorded.
+    #[serde(default)]
+    pub lock_record_min_age_secs: Option<u64>,
+    /// Seconds after the target timestamp over which an order's price must remain profitable
+    ///
+    /// When checking `min_profit_wei`, the offer price is evaluated at both the target timestamp
+    /// and this many seconds later, and the lower of the two is used. This guards against the
+    /// lock landing later than planned, e.g. after the lock deadline when the price drops to zero.
+    /// If unset, only the price at the target timestamp is used.
+    #[serde(default)]
+    pub lock_price_window_secs: Option<u64>,
+    /// Band within which the lockRequest priority gas adapts to recent lock races
+    ///
+    /// If set, the priority gas is interpolated between `min` and `max` by the fraction of lock
+    /// races lost to other provers within the last hour, replacing `lockin_priority_gas`. Frequent
+    /// losses raise the priority gas towards `max` to win more races, while consistent wins lower
+    /// it towards `min` to save on gas.
+    #[serde(default)]
+    pub adaptive_lockin_priority_gas: Option<PriorityGasBand>,
+    /// Re-ingest orders whose lock expired before we locked them as fulfill-after-lock-expire orders
+    ///
+    /// Instead of only skipping such orders, they are sent back to the order picker to be priced
+    /// again as orders that can be fulfilled without locking them, recovering otherwise discarded
+    /// work. Disabled by default.
+    #[serde(default)]
+    pub reingest_lock_expired_orders: bool,
+    /// Seconds before an order's target timestamp to estimate its gas
+    ///
+    /// Orders held in the cache until their target timestamp have their gas estimated ahead of
+    /// time, so admitting them does not add latency while racing to lock. If unset, gas is only
+    /// estimated when orders are admitted.
+    #[serde(default)]
+    pub gas_estimate_prewarm_secs: Option<u64>,
+    /// Commit to orders whose image and input are resolved ahead of equally prioritized orders
+    /// that still need them fetched
+    ///
+    /// Resolving an image or input may require a slow fetch before proving can start, so under
+    /// capacity pressure ready-to-prove orders are committed to first. Most useful with slow
+    /// storage backends.
+    #[serde(default)]
+    pub deprioritize_unresolved_orders: bool,
+    /// Read the prover balance from the pending block when admitting orders
+    ///
+    /// Reflects transactions that have been submitted but not yet mined, such as a deposit to the
+    /// prover, so orders are not under-admitted while they confirm. Pending state is less reliable
+    /// than mined state, as pending transactions may be dropped or replaced, so this is off by
+    /// default.
+    #[serde(default)]
+    pub use_pending_balance: bool,
+    /// Max age in seconds of the chain head before the chain is considered stalled
+    ///
+    /// If the latest block is older than this, e.g. as the chain stalled or the RPC node stopped
+    /// responding, orders are neither locked nor proven until the head advances, as targets and
+    /// deadlines would be checked against an outdated time. If unset, the head is always trusted.
+    #[serde(default)]
+    pub max_chain_head_age_secs: Option<u64>,
+    /// Estimated compute cost of proving, in wei per cycle
+    ///
+    /// Covers costs such as electricity and GPU time. The cost of proving an order's cycles is
+    /// added to its gas cost when checking `min_profit_wei`, so orders that only cover their gas
+    /// are skipped. If set without `min_profit_wei`, orders must at least break even. If unset,
+    /// only gas is counted.
+    #[serde(default)]
+    pub proving_cost_per_cycle_wei: Option<u64>,
+    /// Keep orders skipped for transient reasons in the cache to be evaluated again
+    ///
+    /// Orders skipped as the prover's balance is insufficient, the expected profit is too low or the
+    /// stake cap is reached are neither dropped from the cache nor recorded as skipped in the DB, so
+    /// they can still be locked once the condition clears. Orders skipped for permanent reasons,
+    /// such as being expired, fulfilled or locked by another prover, are always dropped.
+    #[serde(default)]
+    pub retain_transient_skips: bool,
+    /// Floor on the max concurrent proofs when scaled down to the prover's available resources
+    ///
+    /// When the prover reports low memory or GPU availability, `max_concurrent_proofs` is reduced
+    /// in proportion, but not below this value. If unset, it may be reduced to zero.
+    #[serde(default)]
+    pub min_concurrent_proofs: Option<u32>,
+    /// Scale `max_concurrent_proofs` by the fraction of the host's memory that is available
+    ///
+    /// For provers running on the same host as the broker. Read at startup.
+    #[serde(default)]
+    pub probe_available_memory: bool,
+    /// URL reporting the number of idle proving workers, used as the proving capacity
+    ///
+    /// Must respond to GET requests with a JSON object such as `{"idle_workers": 4}`. Replaces
+    /// `max_concurrent_proofs` and `max_committed_cycles`, so admission follows workers crashing
+    /// or being added. Read at startup.
+    #[serde(default)]
+    pub worker_pool_url: Option<String>,
+    /// Max risk of locking an order
+    ///
+    /// The risk combines the order's lock stake in stake tokens, times the cycles to prove it in
+    /// millions, divided by the seconds until its lock expires. A high-stake order with many cycles
+    /// and little time left is risky, a low-stake order with a generous deadline is safe. Orders
+    /// above this risk are skipped rather than locked. If unset, the risk is not limited.
+    #[serde(default)]
+    pub max_lock_risk: Option<f64>,
+    /// Skip the admission pass while the orders are unchanged
+    ///
+    /// If the valid orders, the committed orders and whether locking is paused are unchanged
+    /// since the last iteration, capacity, gas prices and balance are not fetched again, reducing
+    /// RPC load. The pass still runs at least once a minute, when the config changes and when a
+    /// refresh is requested, to pick up changes in prices, balance and gas prices.
+    #[serde(default)]
+    pub skip_unchanged_admission: bool,
+    /// Reject orders at ingestion whose max price can never cover their costs
+    ///
+    /// Lock-and-fulfill orders whose max price is below `min_profit_wei` plus their estimated gas
+    /// and proving costs at the current gas price are skipped as soon as they are received,
+    /// rather than being cached and evaluated every iteration. Has no effect unless
+    /// `min_profit_wei` or `proving_cost_per_cycle_wei` is set.
+    #[serde(default)]
+    pub reject_unprofitable_at_ingestion: bool,
+    /// Deposit stake from the wallet when the stake balance is low
+    ///
+    /// When the stake balance falls below `stake_balance_warn_threshold`, stake tokens are
+    /// deposited to restore it to `target`, at most once every `cooldown_secs`. Requires
+    /// `stake_balance_warn_threshold`. If unset, low stake balances are only logged.
+    #[serde(default)]
+    pub auto_stake_topup: Option<StakeTopUp>,
+    /// Maximum time in seconds ahead of now that an order's target timestamp may be at ingestion
+    ///
+    /// Orders whose target timestamp is further in the future are skipped as soon as they are
+    /// received, rather than being cached and evaluated every iteration until their target is
+    /// reached. Unlike `max_target_horizon_secs`, which holds such orders back in the cache, the
+    /// orders are dropped. If unset, orders are cached regardless of their target timestamp.
+    #[serde(default)]
+    pub max_target_timestamp_horizon_secs: Option<u64>,
+    /// Log the state of every iteration of the order monitor, even when unchanged
+    ///
+    /// By default, the orders ready for locking and/or proving and the orders admitted are only
+    /// logged at info level when they change, to avoid repeating the same logs every block on busy
+    /// markets. Set this when debugging to log them every iteration.
+    #[serde(default)]
+    pub verbose_loop_logs: bool,
+    /// Fulfill transactions to keep balance in reserve for, at the average committed order cost
+    ///
+    /// The cost of this many additional fulfill transactions, at the average estimated gas cost of
+    /// the committed orders, is withheld from the balance available to new orders, so that the
+    /// reserve adapts to the gas cost of the committed work. No reserve is kept while there are no
+    /// committed orders. If unset, no reserve is kept.
+    #[serde(default)]
+    pub balance_reserve_per_committed_order: Option<u32>,
+    /// Maximum number of RPC calls per second made by the order monitor
+    ///
+    /// Calls to fetch balances, gas prices, request statuses and blocks are delayed as needed to
+    /// stay under the quota of rate-limited RPC providers, letting bursts of up to a second's worth
+    /// of calls through. If unset, calls are not rate limited.
+    #[serde(default)]
+    pub max_rpc_calls_per_sec: Option<u32>,
+    /// How proving capacity is accounted for orders admitted but not yet started
+    ///
+    /// Options:
+    /// - "optimistic": Without `max_concurrent_proofs` or `max_committed_cycles`, a full batch of
+    ///   orders is admitted every iteration (default)
+    /// - "pessimistic": Without `max_concurrent_proofs` or `max_committed_cycles`, orders admitted
+    ///   but not yet picked up by the prover take up room in the next batch, so that admission
+    ///   does not outpace proving
+    #[serde(default)]
+    pub capacity_accounting: CapacityAccounting,
+    /// Handling of lock-and-fulfill orders that do not require any lock stake
+    ///
+    /// Options:
+    /// - "deprioritize": Commit to zero-stake orders only after orders with a lock stake
+    /// - "skip": Skip zero-stake orders
+    ///
+    /// If unset, zero-stake orders are handled like any other order.
+    #[serde(default)]
+    pub zero_stake_policy: Option<ZeroStakePolicy>,
+    /// Keep orders whose status could not be fetched before locking in the cache to lock again
+    ///
+    /// When the RPC call checking the status of a request fails, the order was never evaluated and
+    /// is retried on the next iteration rather than recorded as skipped in the DB.
+    #[serde(default)]
+    pub retain_lock_rpc_failures: bool,
+    /// Record every admission decision in the `admission_audit` table of the DB
+    ///
+    /// One row is written per order evaluated when admitting orders, with the decision, its reason,
+    /// the offer price and the estimated gas cost, as a durable record beyond the logs.
+    #[serde(default)]
+    pub admission_audit: bool,
+    /// Seconds the decisions recorded with `admission_audit` are kept for before being pruned
+    ///
+    /// Orders deferred across iterations are recorded on every iteration, so the table grows with
+    /// the number of pending orders. Set to 0 to keep them indefinitely.
+    #[serde(default = "defaults::admission_audit_retention_secs")]
+    pub admission_audit_retention_secs: u64,
+    /// Safety margin in seconds required between the estimated completion of an order's proof and
+    /// its expiration
+    ///
+    /// Added on top of the proving time estimated from `peak_prove_khz` when checking whether an
+    /// order can be completed before it expires, to account for queueing, the confirmation of the
+    /// fulfillment transaction and settlement. Unlike `min_deadline`, it scales with the prover's
+    /// backlog as it applies to the estimated completion rather than the time remaining.
+    #[serde(default)]
+    pub fulfillment_safety_margin_secs: u64,
+    /// Only log orders whose chain id or market address don't match the chain and market the
+    /// broker is connected to, rather than rejecting them when they are received
+    ///
+    /// Orders are rejected by default, as such orders cannot be locked or fulfilled and are most
+    /// likely misrouted.
+    #[serde(default)]
+    pub allow_mismatched_chain_orders: bool,
+    /// Run as a prove-only prover that never locks orders
+    ///
+    /// When set, orders that require a lock are dropped when received and only orders that can be
+    /// fulfilled without locking them are proven, so no stake is ever posted. Unlike
+    /// `pause_locking`, which keeps such orders cached until locking resumes, this is intended for
+    /// operators who never lock. Cannot be combined with `auto_stake_topup`.
+    #[serde(default)]
+    pub prove_only: bool,
+    /// Settlement backlog, in proven orders not yet fulfilled, at which new orders stop being
+    /// admitted
+    ///
+    /// Admission is reduced as the backlog of the aggregation and submission of proofs approaches
+    /// this limit, so proven orders do not pile up and miss their fulfillment deadlines. If unset,
+    /// admission does not depend on the settlement backlog.
+    #[serde(default)]
+    pub max_settlement_backlog: Option<u32>,
+    /// URL reporting the settlement backlog, for batchers that report their own
+    ///
+    /// Must respond to GET requests with a JSON object such as `{"pending_settlement": 4}`. If
+    /// unset, the backlog is the number of proven orders of the DB that are not yet fulfilled.
+    /// Only used if `max_settlement_backlog` is set. Read at startup.
+    #[serde(default)]
+    pub settlement_backlog_url: Option<String>,
+    /// Point in time of a lock whose price is recorded as the order's lock price: "submission",
+    /// "target" or "confirmation"
+    ///
+    /// The prices at all three are also recorded in the DB, to analyze the slippage between
+    /// deciding to lock an order and the lock being confirmed. Defaults to the confirmation, the
+    /// price paid by the market.
+    #[serde(default)]
+    pub lock_price_basis: LockPriceBasis,
 }
 
 impl Default for MarketConf {
@@ -286,6 +951,97 @@ impl Default for MarketConf {
             max_concurrent_preflights: defaults::max_concurrent_preflights(),
             order_pricing_priority: OrderPricingPriority::default(),
             order_commitment_priority: OrderCommitmentPriority::default(),
+            requestor_balance_retry_attempts: 0,
+            requestor_balance_retry_backoff_secs: defaults::requestor_balance_retry_backoff_secs(),
+            pause_locking: false,
+            gas_price_fallback_margin_percent: defaults::gas_price_fallback_margin_percent(),
+            gas_estimation_concurrency: defaults::gas_estimation_concurrency(),
+            max_committed_cycles: None,
+            monitor_loop_deadline_secs: None,
+            min_profit_wei: None,
+            block_fetch_retry_count: None,
+            block_fetch_retry_sleep_ms: None,
+            block_fetch_retry_max_total_ms: None,
+            block_fetch_receipt_fallback_after: None,
+            max_proving_duration_secs: None,
+            order_tie_breaker_seed: 0,
+            salvage_capacity_reserve: None,
+            fallback_order_cycles: None,
+            chains: HashMap::new(),
+            max_concurrent_lock_txs: None,
+            min_lock_win_probability: None,
+            contest_estimator: ContestEstimatorKind::default(),
+            proof_type_policies: HashMap::new(),
+            max_target_horizon_secs: None,
+            balance_underwater_alert: None,
+            alert_webhook_url: None,
+            gas_price_rpc_url: None,
+            optimize_khz_allocation: false,
+            submit_request_before_lock: false,
+            lock_status_confirmations: None,
+            verify_lock_owner: false,
+            max_single_order_lock_stake: None,
+            min_deadline_fraction: None,
+            expected_incoming_balance: None,
+            urgent_deadline_secs: None,
+            urgent_lockin_priority_gas: None,
+            max_total_stake_committed: None,
+            lock_record_min_age_secs: None,
+            lock_price_window_secs: None,
+            adaptive_lockin_priority_gas: None,
+            reingest_lock_expired_orders: false,
+            gas_estimate_prewarm_secs: None,
+            deprioritize_unresolved_orders: false,
+            use_pending_balance: false,
+            max_chain_head_age_secs: None,
+            proving_cost_per_cycle_wei: None,
+            retain_transient_skips: false,
+            min_concurrent_proofs: None,
+            probe_available_memory: false,
+            worker_pool_url: None,
+            max_lock_risk: None,
+            skip_unchanged_admission: false,
+            reject_unprofitable_at_ingestion: false,
+            auto_stake_topup: None,
+            max_target_timestamp_horizon_secs: None,
+            verbose_loop_logs: false,
+            balance_reserve_per_committed_order: None,
+            max_rpc_calls_per_sec: None,
+            capacity_accounting: CapacityAccounting::default(),
+            zero_stake_policy: None,
+            retain_lock_rpc_failures: false,
+            admission_audit: false,
+            admission_audit_retention_secs: defaults::admission_audit_retention_secs(),
+            fulfillment_safety_margin_secs: 0,
+            allow_mismatched_chain_orders: false,
+            prove_only: false,
+            max_settlement_backlog: None,
+            settlement_backlog_url: None,
+            lock_price_basis: LockPriceBasis::default(),
+        }
+    }
+}
+
+impl MarketConf {
+    /// Returns the overrides configured for the given chain, if any.
+    pub fn chain_overrides(&self, chain_id: u64) -> Option<&ChainOverrides> {
+        self.chains.get(&chain_id.to_string())
+    }
+
+    /// Returns the market configuration resolved for orders on the given chain.
+    pub fn for_chain(&self, chain_id: u64) -> ChainMarketConf {
+        let overrides = self.chain_overrides(chain_id).cloned().unwrap_or_default();
+        ChainMarketConf {
+            min_deadline: overrides.min_deadline.unwrap_or(self.min_deadline),
+            lockin_gas_estimate: overrides.lockin_gas_estimate.unwrap_or(self.lockin_gas_estimate),
+            fulfill_gas_estimate: overrides
+                .fulfill_gas_estimate
+                .unwrap_or(self.fulfill_gas_estimate),
+            groth16_verify_gas_estimate: overrides
+                .groth16_verify_gas_estimate
+                .unwrap_or(self.groth16_verify_gas_estimate),
+            lockin_priority_gas: overrides.lockin_priority_gas.or(self.lockin_priority_gas),
+            min_profit_wei: overrides.min_profit_wei.or(self.min_profit_wei),
         }
     }
 }
@@ -481,6 +1237,11 @@ impl ConfigLock {
         self.config.read().map_err(|_| ConfigErr::LockFailed)
     }
 
+    /// Returns the market configuration resolved for orders on the given chain.
+    pub fn market_for_chain(&self, chain_id: u64) -> Result<ChainMarketConf, ConfigErr> {
+        Ok(self.lock_all()?.market.for_chain(chain_id))
+    }
+
     #[cfg(test)]
     pub fn load_write(&self) -> Result<std::sync::RwLockWriteGuard<Config>, ConfigErr> {
         self.config.write().map_err(|_| ConfigErr::LockFailed)
@@ -600,6 +1361,9 @@ lookback_blocks = 100
 max_stake = "0.1"
 max_file_size = 50_000_000
 
+[market.chains.8453]
+min_deadline = 600
+
 [prover]
 bonsai_r0_zkvm_ver = "1.0.1"
 status_poll_retry_count = 3
@@ -674,6 +1438,8 @@ error = ?"#;
         assert_eq!(config.market.max_stake, "0.1");
         assert_eq!(config.market.max_file_size, 50_000_000);
         assert_eq!(config.market.lockin_priority_gas, None);
+        assert_eq!(config.market.for_chain(8453).min_deadline, 600);
+        assert_eq!(config.market.for_chain(1).min_deadline, 300);
 
         assert_eq!(config.prover.status_poll_ms, 1000);
         assert_eq!(config.prover.status_poll_retry_count, 3);
@@ -693,6 +1459,22 @@ error = ?"#;
         assert_eq!(config.batcher.batch_poll_time_ms, None);
     }
 
+    #[test]
+    fn capacity_reserve() {
+        #[derive(Deserialize)]
+        struct Reserve {
+            salvage_capacity_reserve: CapacityReserve,
+        }
+
+        let count: Reserve = toml::from_str("salvage_capacity_reserve = 1").unwrap();
+        assert_eq!(count.salvage_capacity_reserve, CapacityReserve::Count(1));
+        assert_eq!(count.salvage_capacity_reserve.slots(4), 1);
+
+        let fraction: Reserve = toml::from_str("salvage_capacity_reserve = 0.3").unwrap();
+        assert_eq!(fraction.salvage_capacity_reserve, CapacityReserve::Fraction(0.3));
+        assert_eq!(fraction.salvage_capacity_reserve.slots(4), 2);
+    }
+
     #[tokio::test]
     #[should_panic(expected = "TOML parse error")]
     async fn bad_config() {
@@ -0,0 +1,145 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{clock::ClockObj, db::LockOutcomeStats, OrderRequest};
+
+/// Window of lock outcome history passed to a [ContestEstimator].
+pub(crate) const CONTEST_HISTORY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Estimates the probability of winning the race to lock an order against other provers.
+///
+/// Used by the order monitor to skip locking orders it is likely to lose, saving the gas spent on
+/// lock transactions that revert.
+#[async_trait]
+pub(crate) trait ContestEstimator: Send + Sync {
+    /// Returns the probability, between 0 and 1, that locking the order will succeed, given the
+    /// outcomes of recent lock races.
+    async fn win_probability(&self, order: &OrderRequest, recent: &LockOutcomeStats)
+        -> Result<f64>;
+}
+
+pub(crate) type ContestEstimatorObj = Arc<dyn ContestEstimator>;
+
+/// Estimates the win probability of every order as the fraction of recent lock races won.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct HistoryContestEstimator;
+
+#[async_trait]
+impl ContestEstimator for HistoryContestEstimator {
+    async fn win_probability(
+        &self,
+        _order: &OrderRequest,
+        recent: &LockOutcomeStats,
+    ) -> Result<f64> {
+        // Without lost races there is no evidence of competition.
+        Ok(recent.win_loss_ratio().map_or(1.0, |ratio| ratio / (1.0 + ratio)))
+    }
+}
+
+/// Estimates the win probability of an order from the fraction of recent lock races won, raised
+/// in proportion to how much of the order's price ramp-up remains.
+///
+/// Early in the ramp-up the order is only profitable to the cheapest provers, so fewer compete
+/// for it. Once the ramp-up completes, the estimate is that of [HistoryContestEstimator].
+pub(crate) struct RampContestEstimator {
+    clock: ClockObj,
+}
+
+impl RampContestEstimator {
+    pub(crate) fn new(clock: ClockObj) -> Self {
+        Self { clock }
+    }
+}
+
+#[async_trait]
+impl ContestEstimator for RampContestEstimator {
+    async fn win_probability(
+        &self,
+        order: &OrderRequest,
+        recent: &LockOutcomeStats,
+    ) -> Result<f64> {
+        let history = HistoryContestEstimator.win_probability(order, recent).await?;
+        let offer = &order.request.offer;
+        let ramp_progress = match offer.rampUpPeriod {
+            0 => 1.0,
+            ramp_up_period => {
+                let elapsed = self.clock.now().saturating_sub(offer.biddingStart);
+                (elapsed as f64 / ramp_up_period as f64).min(1.0)
+            }
+        };
+        Ok(history + (1.0 - history) * (1.0 - ramp_progress))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, U256};
+    use boundless_market::contracts::{
+        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
+        Requirements,
+    };
+    use risc0_zkvm::Digest;
+
+    use super::*;
+    use crate::{clock::Clock, FulfillmentType};
+
+    struct FixedClock(u64);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn ramp_win_probability() {
+        let request = ProofRequest::new(
+            RequestId::new(Address::ZERO, 1),
+            Requirements::new(
+                Digest::ZERO,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com/image",
+            RequestInput { inputType: RequestInputType::Inline, data: Default::default() },
+            Offer {
+                minPrice: U256::from(1),
+                maxPrice: U256::from(2),
+                biddingStart: 1_000,
+                rampUpPeriod: 100,
+                timeout: 200,
+                lockTimeout: 100,
+                lockStake: U256::ZERO,
+            },
+        );
+        let order = OrderRequest::new(
+            request,
+            Default::default(),
+            FulfillmentType::LockAndFulfill,
+            Address::ZERO,
+            31337,
+        );
+        // One race won for every three lost.
+        let recent = LockOutcomeStats { wins: 1, losses: 3, ..Default::default() };
+
+        for (now, expected) in [(1_000, 1.0), (1_050, 0.625), (1_100, 0.25), (2_000, 0.25)] {
+            let estimator = RampContestEstimator::new(Arc::new(FixedClock(now)));
+            assert_eq!(estimator.win_probability(&order, &recent).await.unwrap(), expected);
+        }
+    }
+}
@@ -14,9 +14,10 @@
 
 use std::{default::Default, str::FromStr, sync::Arc};
 
-use alloy::primitives::{ruint::ParseError as RuintParseErr, Bytes, B256, U256};
+use alloy::primitives::{ruint::ParseError as RuintParseErr, Address, Bytes, B256, U256};
 use async_trait::async_trait;
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use sqlx::{
     sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions},
     Row,
@@ -32,6 +33,11 @@ use tracing::instrument;
 
 #[cfg(test)]
 mod fuzz_db;
+#[cfg(feature = "postgres")]
+mod postgres;
+
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresDb;
 
 #[derive(Error)]
 pub enum DbError {
@@ -80,6 +86,10 @@ pub enum DbError {
 
     #[error("{code} Duplicate order id accepted {0}", code = self.code())]
     DuplicateOrderId(String),
+
+    #[cfg(not(feature = "postgres"))]
+    #[error("{code} Postgres DB URLs require the broker to be built with the postgres feature", code = self.code())]
+    PostgresNotEnabled,
 }
 
 impl_coded_debug!(DbError);
@@ -113,6 +123,17 @@ impl From<sqlx::Error> for DbError {
     }
 }
 
+/// Connects to the broker DB, using [PostgresDb] for `postgres://` URLs and [SqliteDb] otherwise.
+pub async fn connect(db_url: &str) -> Result<DbObj, DbError> {
+    if db_url.starts_with("postgres://") || db_url.starts_with("postgresql://") {
+        #[cfg(feature = "postgres")]
+        return Ok(Arc::new(PostgresDb::new(db_url).await?));
+        #[cfg(not(feature = "postgres"))]
+        return Err(DbError::PostgresNotEnabled);
+    }
+    Ok(Arc::new(SqliteDb::new(db_url).await?))
+}
+
 /// Struct containing the information about an order used by the aggregation worker.
 #[derive(Clone, Debug)]
 pub struct AggregationOrder {
@@ -130,6 +151,15 @@ pub trait BrokerDb {
         order_request: &OrderRequest,
         lock_price: U256,
     ) -> Result<Order, DbError>;
+    /// Inserts an order we locked, recording the hash of the lock transaction alongside it.
+    async fn insert_locked_request(
+        &self,
+        order_request: &OrderRequest,
+        lock_price: U256,
+        lock_tx_hash: B256,
+    ) -> Result<Order, DbError>;
+    /// Returns the hash of the transaction that locked the order, if it was locked by us.
+    async fn get_order_lock_tx_hash(&self, id: &str) -> Result<Option<B256>, DbError>;
     async fn get_order(&self, id: &str) -> Result<Option<Order>, DbError>;
     async fn get_orders(&self, ids: &[&str]) -> Result<Vec<Order>, DbError>;
     async fn get_submission_order(
@@ -141,6 +171,15 @@ pub trait BrokerDb {
     async fn set_order_complete(&self, id: &str) -> Result<(), DbError>;
     /// Get all orders that are committed to be prove and be fulfilled.
     async fn get_committed_orders(&self) -> Result<Vec<Order>, DbError>;
+    /// Get the committed orders of requests from the given client.
+    async fn get_committed_orders_by_client(&self, client: Address) -> Result<Vec<Order>, DbError>;
+    /// Get the committed orders of the given fulfillment type.
+    async fn get_committed_orders_by_type(
+        &self,
+        fulfillment_type: FulfillmentType,
+    ) -> Result<Vec<Order>, DbError>;
+    /// Get the orders, whatever their status, tagged with the given label.
+    async fn get_orders_by_label(&self, label: &str) -> Result<Vec<Order>, DbError>;
     /// Get all orders that are committed to be proved but have expired based on their expire_timestamp.
     async fn get_expired_committed_orders(
         &self,
@@ -179,6 +218,36 @@ pub trait BrokerDb {
     async fn is_request_locked(&self, request_id: U256) -> Result<bool, DbError>;
     // Checks the locked table for the given request_id
     async fn get_request_locked(&self, request_id: U256) -> Result<Option<(String, u64)>, DbError>;
+    /// Records the decisions taken on orders when admitting them for locking and/or proving.
+    async fn insert_admission_audit(&self, entries: &[AdmissionAuditEntry]) -> Result<(), DbError>;
+    /// Returns the admission decisions recorded since the given UNIX timestamp, oldest first.
+    async fn admission_audit(&self, since: u64) -> Result<Vec<AdmissionAuditEntry>, DbError>;
+    /// Deletes the admission decisions recorded before the given UNIX timestamp, returning the
+    /// number deleted.
+    async fn prune_admission_audit(&self, before: u64) -> Result<u64, DbError>;
+    /// Records the outcome of an attempt to lock a request.
+    ///
+    /// `winner` is the address of the prover that locked the request instead of us, if known, and
+    /// `lock_price` is the price the request was, or would have been, locked at.
+    async fn insert_lock_outcome(
+        &self,
+        request_id: U256,
+        outcome: LockOutcome,
+        winner: Option<&str>,
+        lock_price: U256,
+        timestamp: u64,
+    ) -> Result<(), DbError>;
+    /// Summarizes the outcomes of the lock attempts made since the given UNIX timestamp.
+    async fn get_lock_outcome_stats(&self, since: u64) -> Result<LockOutcomeStats, DbError>;
+    /// Records the prices of a request we locked along the timing of its lock.
+    async fn insert_lock_prices(
+        &self,
+        request_id: U256,
+        prices: &LockPrices,
+        timestamp: u64,
+    ) -> Result<(), DbError>;
+    /// Returns the prices recorded when the request was locked, if it was locked by us.
+    async fn get_lock_prices(&self, request_id: U256) -> Result<Option<LockPrices>, DbError>;
     /// Update a batch with the results of an aggregation step.
     ///
     /// Sets the aggreagtion state, and adds the given orders to the batch, updating the batch fees
@@ -202,6 +271,119 @@ pub trait BrokerDb {
 
 pub type DbObj = Arc<dyn BrokerDb + Send + Sync>;
 
+/// Maximum number of competing provers reported in [LockOutcomeStats::top_winners].
+const LOST_LOCK_TOP_WINNERS: i64 = 5;
+
+/// Outcome of an attempt to lock a request, see [BrokerDb::insert_lock_outcome].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockOutcome {
+    /// The request was locked by us.
+    Won,
+    /// The request was locked by another prover first.
+    Lost,
+    /// The lock failed for another reason, such as an RPC or transaction error.
+    Error,
+}
+
+impl LockOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            LockOutcome::Won => "won",
+            LockOutcome::Lost => "lost",
+            LockOutcome::Error => "error",
+        }
+    }
+}
+
+/// Summary of lock attempt outcomes, as returned by [BrokerDb::get_lock_outcome_stats].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LockOutcomeStats {
+    /// Number of requests we locked.
+    pub wins: u64,
+    /// Number of lock attempts that lost the race to another prover.
+    pub losses: u64,
+    /// Number of lock attempts that failed for another reason.
+    pub errors: u64,
+    /// Provers that won the most lock races against us, with their win counts, most wins first.
+    pub top_winners: Vec<(String, u64)>,
+}
+
+impl LockOutcomeStats {
+    /// Ratio of won to lost lock races, or None if no races were lost.
+    pub fn win_loss_ratio(&self) -> Option<f64> {
+        (self.losses > 0).then(|| self.wins as f64 / self.losses as f64)
+    }
+
+    /// Fraction of lock races that were lost, or None if no races were won or lost.
+    pub fn loss_rate(&self) -> Option<f64> {
+        let races = self.wins + self.losses;
+        (races > 0).then(|| self.losses as f64 / races as f64)
+    }
+
+    /// Builds the stats from the number of attempts per stored outcome and the top winners.
+    fn from_counts(counts: Vec<(String, i64)>, top_winners: Vec<(String, i64)>) -> Self {
+        let mut stats = Self {
+            top_winners: top_winners
+                .into_iter()
+                .map(|(winner, wins)| (winner, wins as u64))
+                .collect(),
+            ..Default::default()
+        };
+        for (outcome, count) in counts {
+            let count = count as u64;
+            match outcome.as_str() {
+                "won" => stats.wins = count,
+                "lost" => stats.losses = count,
+                _ => stats.errors += count,
+            }
+        }
+        stats
+    }
+}
+
+/// Prices of a request at the points in time of its lock, see [BrokerDb::insert_lock_prices].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockPrices {
+    /// Price when the lock transaction was submitted.
+    pub submission: U256,
+    /// Price at the order's target timestamp, if it has one.
+    pub target: Option<U256>,
+    /// Price at the timestamp of the block the lock was confirmed in.
+    pub confirmation: U256,
+}
+
+/// Decision taken on an order when admitting orders, see [AdmissionAuditEntry].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdmissionDecision {
+    /// Admitted for locking and/or proving.
+    Admitted,
+    /// Left in the cache to be evaluated again.
+    Deferred,
+    /// Dropped, without being locked or proven.
+    Skipped,
+}
+
+/// Record of an admission decision, as returned by [BrokerDb::admission_audit].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdmissionAuditEntry {
+    /// ID of the order.
+    pub order_id: String,
+    /// ID of the request of the order.
+    pub request_id: U256,
+    /// Decision taken on the order.
+    pub decision: AdmissionDecision,
+    /// Reason the order was not admitted, if any.
+    pub reason: Option<String>,
+    /// Price of the offer at the time of the decision.
+    pub price_wei: U256,
+    /// Estimated gas cost of locking and/or fulfilling the order, zero if the order was deferred
+    /// before it was estimated.
+    pub gas_cost_wei: U256,
+    /// UNIX timestamp of the decision.
+    pub timestamp: u64,
+}
+
 pub struct SqliteDb {
     pool: SqlitePool,
 }
@@ -266,15 +448,21 @@ impl SqliteDb {
 
     /// Insert an accepted order, overwriting only if the existing order is skipped.
     /// Returns true if inserted/updated, false if ignored due to existing non-skipped order.
-    async fn insert_accepted_order(&self, order: &Order) -> Result<(), DbError> {
+    async fn insert_accepted_order(
+        &self,
+        order: &Order,
+        lock_tx_hash: Option<B256>,
+    ) -> Result<(), DbError> {
         let result = sqlx::query(
-            r#"INSERT INTO orders (id, data) VALUES ($1, $2) 
+            r#"INSERT INTO orders (id, data, lock_tx_hash) VALUES ($1, $2, $3) 
                ON CONFLICT(id) DO UPDATE SET 
-                   data = excluded.data 
+                   data = excluded.data,
+                   lock_tx_hash = excluded.lock_tx_hash
                WHERE orders.data->>'status' = 'Skipped'"#,
         )
         .bind(order.id())
         .bind(sqlx::types::Json(&order))
+        .bind(lock_tx_hash.map(|tx_hash| tx_hash.to_string()))
         .execute(&self.pool)
         .await?;
 
@@ -328,10 +516,38 @@ impl BrokerDb for SqliteDb {
         lock_price: U256,
     ) -> Result<Order, DbError> {
         let order = order_request.to_proving_order(lock_price);
-        self.insert_accepted_order(&order).await?;
+        self.insert_accepted_order(&order, None).await?;
+        Ok(order)
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{}", order_request.id())))]
+    async fn insert_locked_request(
+        &self,
+        order_request: &OrderRequest,
+        lock_price: U256,
+        lock_tx_hash: B256,
+    ) -> Result<Order, DbError> {
+        let order = order_request.to_proving_order(lock_price);
+        self.insert_accepted_order(&order, Some(lock_tx_hash)).await?;
         Ok(order)
     }
 
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn get_order_lock_tx_hash(&self, id: &str) -> Result<Option<B256>, DbError> {
+        let lock_tx_hash: Option<Option<String>> =
+            sqlx::query_scalar("SELECT lock_tx_hash FROM orders WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        let Some(lock_tx_hash) = lock_tx_hash else {
+            return Err(DbError::OrderNotFound(id.to_string()));
+        };
+        lock_tx_hash
+            .map(|tx_hash| B256::from_str(&tx_hash))
+            .transpose()
+            .map_err(|_| DbError::InvalidOrder(id.to_string(), "lock_tx_hash"))
+    }
+
     #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
     async fn get_order(&self, id: &str) -> Result<Option<Order>, DbError> {
         let order: Option<DbOrder> = sqlx::query_as("SELECT * FROM orders WHERE id = $1 LIMIT 1")
@@ -458,6 +674,48 @@ impl BrokerDb for SqliteDb {
         orders.into_iter().map(|elm| Ok(elm.data)).collect()
     }
 
+    #[instrument(level = "trace", skip(self))]
+    async fn get_committed_orders_by_client(&self, client: Address) -> Result<Vec<Order>, DbError> {
+        // The client address is packed into the request ID, so it is matched after decoding.
+        let orders = self.get_committed_orders().await?;
+        Ok(orders.into_iter().filter(|order| order.request.client_address() == client).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_committed_orders_by_type(
+        &self,
+        fulfillment_type: FulfillmentType,
+    ) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as(
+            r#"SELECT * FROM orders
+            WHERE data->>'status' IN ($1, $2, $3, $4, $5, $6) AND data->>'fulfillment_type' = $7"#,
+        )
+        .bind(OrderStatus::PendingProving)
+        .bind(OrderStatus::Proving)
+        .bind(OrderStatus::PendingAgg)
+        .bind(OrderStatus::Aggregating)
+        .bind(OrderStatus::SkipAggregation)
+        .bind(OrderStatus::PendingSubmission)
+        .bind(fulfillment_type)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|elm| elm.data).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_orders_by_label(&self, label: &str) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as(
+            r#"SELECT * FROM orders
+            WHERE EXISTS (SELECT 1 FROM json_each(orders.data, '$.labels') WHERE value = $1)"#,
+        )
+        .bind(label)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|elm| elm.data).collect())
+    }
+
     #[instrument(level = "trace", skip(self))]
     async fn get_expired_committed_orders(
         &self,
@@ -491,6 +749,7 @@ impl BrokerDb for SqliteDb {
                 (SELECT id
                 FROM orders
                 WHERE data->>'status' = $3
+                ORDER BY COALESCE(data->>'urgent', 0) DESC
                 LIMIT 1)
             RETURNING *
             "#,
@@ -1017,6 +1276,132 @@ impl BrokerDb for SqliteDb {
         Ok(res.map(|r| (r.locker, r.block_number)))
     }
 
+    #[instrument(level = "trace", skip_all, fields(entries = entries.len()))]
+    async fn insert_admission_audit(&self, entries: &[AdmissionAuditEntry]) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+        for entry in entries {
+            sqlx::query("INSERT INTO admission_audit (timestamp, data) VALUES ($1, $2)")
+                .bind(entry.timestamp as i64)
+                .bind(sqlx::types::Json(entry))
+                .execute(&mut *txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn admission_audit(&self, since: u64) -> Result<Vec<AdmissionAuditEntry>, DbError> {
+        let entries: Vec<sqlx::types::Json<AdmissionAuditEntry>> = sqlx::query_scalar(
+            "SELECT data FROM admission_audit WHERE timestamp >= $1 ORDER BY id",
+        )
+        .bind(since as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries.into_iter().map(|entry| entry.0).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn prune_admission_audit(&self, before: u64) -> Result<u64, DbError> {
+        let res = sqlx::query("DELETE FROM admission_audit WHERE timestamp < $1")
+            .bind(before as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn insert_lock_outcome(
+        &self,
+        request_id: U256,
+        outcome: LockOutcome,
+        winner: Option<&str>,
+        lock_price: U256,
+        timestamp: u64,
+    ) -> Result<(), DbError> {
+        sqlx::query(
+            r#"INSERT INTO lock_outcomes (request_id, outcome, winner, lock_price, timestamp)
+            VALUES ($1, $2, $3, $4, $5)"#,
+        )
+        .bind(format!("0x{request_id:x}"))
+        .bind(outcome.as_str())
+        .bind(winner)
+        .bind(lock_price.to_string())
+        .bind(timestamp as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_lock_outcome_stats(&self, since: u64) -> Result<LockOutcomeStats, DbError> {
+        let counts: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT outcome, COUNT(*) FROM lock_outcomes WHERE timestamp >= $1 GROUP BY outcome",
+        )
+        .bind(since as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let top_winners: Vec<(String, i64)> = sqlx::query_as(
+            r#"SELECT winner, COUNT(*) AS wins FROM lock_outcomes
+            WHERE timestamp >= $1 AND outcome = $2 AND winner IS NOT NULL
+            GROUP BY winner ORDER BY wins DESC, winner ASC LIMIT $3"#,
+        )
+        .bind(since as i64)
+        .bind(LockOutcome::Lost.as_str())
+        .bind(LOST_LOCK_TOP_WINNERS)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(LockOutcomeStats::from_counts(counts, top_winners))
+    }
+
+    #[instrument(level = "trace", skip(self, prices))]
+    async fn insert_lock_prices(
+        &self,
+        request_id: U256,
+        prices: &LockPrices,
+        timestamp: u64,
+    ) -> Result<(), DbError> {
+        sqlx::query(
+            r#"INSERT INTO lock_prices (id, submission_price, target_price, confirmation_price, timestamp)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT(id) DO NOTHING"#,
+        )
+        .bind(format!("0x{request_id:x}"))
+        .bind(prices.submission.to_string())
+        .bind(prices.target.map(|price| price.to_string()))
+        .bind(prices.confirmation.to_string())
+        .bind(timestamp as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_lock_prices(&self, request_id: U256) -> Result<Option<LockPrices>, DbError> {
+        let row: Option<(String, Option<String>, String)> = sqlx::query_as(
+            "SELECT submission_price, target_price, confirmation_price FROM lock_prices WHERE id = $1",
+        )
+        .bind(format!("0x{request_id:x}"))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|(submission, target, confirmation)| {
+            Ok(LockPrices {
+                submission: U256::from_str(&submission)?,
+                target: target.as_deref().map(U256::from_str).transpose()?,
+                confirmation: U256::from_str(&confirmation)?,
+            })
+        })
+        .transpose()
+    }
+
     #[cfg(test)]
     async fn add_batch(&self, batch_id: usize, batch: Batch) -> Result<(), DbError> {
         let res = sqlx::query("INSERT INTO batches (id, data) VALUES ($1, $2)")
@@ -1068,7 +1453,7 @@ mod tests {
     use risc0_zkvm::sha::Digest;
     use tracing_test::traced_test;
 
-    fn create_order_request() -> OrderRequest {
+    pub(super) fn create_order_request() -> OrderRequest {
         OrderRequest::new(
             ProofRequest::new(
                 RequestId::new(Address::ZERO, 1),
@@ -1109,6 +1494,106 @@ mod tests {
         db.insert_accepted_request(&order, U256::ZERO).await.unwrap();
     }
 
+    #[sqlx::test]
+    async fn lock_tx_hash(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let mut order = create_order_request();
+        let lock_tx_hash = B256::repeat_byte(0xab);
+        db.insert_locked_request(&order, U256::from(10), lock_tx_hash).await.unwrap();
+        assert_eq!(db.get_order_lock_tx_hash(&order.id()).await.unwrap(), Some(lock_tx_hash));
+
+        order.request.id = U256::from(2);
+        db.insert_accepted_request(&order, U256::ZERO).await.unwrap();
+        assert_eq!(db.get_order_lock_tx_hash(&order.id()).await.unwrap(), None);
+
+        assert!(matches!(
+            db.get_order_lock_tx_hash("missing").await,
+            Err(DbError::OrderNotFound(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn order_labels(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+
+        let mut labeled = create_order_request();
+        labeled.labels = vec!["customer-A".to_string(), "promo".to_string()];
+        db.insert_accepted_request(&labeled, U256::ZERO).await.unwrap();
+
+        let mut other = create_order_request();
+        other.request.id = U256::from(2);
+        other.labels = vec!["customer-B".to_string()];
+        db.insert_skipped_request(&other).await.unwrap();
+
+        let mut unlabeled = create_order_request();
+        unlabeled.request.id = U256::from(3);
+        db.insert_accepted_request(&unlabeled, U256::ZERO).await.unwrap();
+
+        let order = db.get_order(&labeled.id()).await.unwrap().unwrap();
+        assert_eq!(order.labels, labeled.labels);
+        assert!(db.get_order(&unlabeled.id()).await.unwrap().unwrap().labels.is_empty());
+
+        let ids = |orders: Vec<Order>| orders.iter().map(|order| order.id()).collect::<Vec<_>>();
+        assert_eq!(ids(db.get_orders_by_label("promo").await.unwrap()), vec![labeled.id()]);
+        assert_eq!(ids(db.get_orders_by_label("customer-B").await.unwrap()), vec![other.id()]);
+        assert!(db.get_orders_by_label("customer").await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn get_committed_orders_filtered(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+        let client = Address::repeat_byte(0x22);
+
+        let locked = create_order_request();
+        db.insert_locked_request(&locked, U256::from(1), B256::ZERO).await.unwrap();
+
+        let mut client_locked = create_order_request();
+        client_locked.request.id = RequestId::new(client, 2).into();
+        db.insert_locked_request(&client_locked, U256::from(1), B256::ZERO).await.unwrap();
+
+        let mut client_after_expire = create_order_request();
+        client_after_expire.request.id = RequestId::new(client, 3).into();
+        client_after_expire.fulfillment_type = FulfillmentType::FulfillAfterLockExpire;
+        db.insert_accepted_request(&client_after_expire, U256::ZERO).await.unwrap();
+
+        // Skipped orders are not committed.
+        let mut client_skipped = create_order_request();
+        client_skipped.request.id = RequestId::new(client, 4).into();
+        db.insert_skipped_request(&client_skipped).await.unwrap();
+
+        let ids = |orders: Vec<Order>| {
+            let mut ids: Vec<String> = orders.iter().map(|order| order.id()).collect();
+            ids.sort();
+            ids
+        };
+        let mut client_ids = vec![client_locked.id(), client_after_expire.id()];
+        client_ids.sort();
+        assert_eq!(ids(db.get_committed_orders_by_client(client).await.unwrap()), client_ids);
+        assert_eq!(
+            ids(db.get_committed_orders_by_client(Address::ZERO).await.unwrap()),
+            vec![locked.id()]
+        );
+
+        let mut lock_ids = vec![locked.id(), client_locked.id()];
+        lock_ids.sort();
+        assert_eq!(
+            ids(db.get_committed_orders_by_type(FulfillmentType::LockAndFulfill).await.unwrap()),
+            lock_ids
+        );
+        assert_eq!(
+            ids(db
+                .get_committed_orders_by_type(FulfillmentType::FulfillAfterLockExpire)
+                .await
+                .unwrap()),
+            vec![client_after_expire.id()]
+        );
+        assert!(db
+            .get_committed_orders_by_type(FulfillmentType::FulfillWithoutLocking)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
     #[sqlx::test]
     async fn get_order(pool: SqlitePool) {
         let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
@@ -1218,6 +1703,27 @@ mod tests {
         assert_eq!(db_order.status, OrderStatus::Proving);
     }
 
+    #[sqlx::test]
+    async fn get_proving_order_urgent_first(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+
+        let mut order = create_order();
+        order.status = OrderStatus::PendingProving;
+        order.request.id = U256::from(1);
+        db.add_order(&order).await.unwrap();
+        let mut urgent_order = create_order();
+        urgent_order.status = OrderStatus::PendingProving;
+        urgent_order.request.id = U256::from(2);
+        urgent_order.urgent = true;
+        db.add_order(&urgent_order).await.unwrap();
+
+        let db_order = db.get_proving_order().await.unwrap().unwrap();
+        assert_eq!(db_order.id(), urgent_order.id());
+        assert!(db_order.urgent);
+        let db_order = db.get_proving_order().await.unwrap().unwrap();
+        assert_eq!(db_order.id(), order.id());
+    }
+
     #[sqlx::test]
     async fn set_order_proof_id(pool: SqlitePool) {
         let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
@@ -1539,6 +2045,99 @@ mod tests {
         assert!(!db.is_request_locked(U256::from(413)).await.unwrap());
     }
 
+    #[sqlx::test]
+    async fn admission_audit(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+
+        let entry = |id: u64, decision, reason: Option<&str>, timestamp| AdmissionAuditEntry {
+            order_id: format!("0x{id:x}"),
+            request_id: U256::from(id),
+            decision,
+            reason: reason.map(str::to_string),
+            price_wei: U256::from(10),
+            gas_cost_wei: U256::from(2),
+            timestamp,
+        };
+        let now = Utc::now().timestamp() as u64;
+        let old = entry(1, AdmissionDecision::Admitted, None, now - 100);
+        let admitted = entry(2, AdmissionDecision::Admitted, None, now);
+        let deferred = entry(3, AdmissionDecision::Deferred, Some("insufficient balance"), now);
+        db.insert_admission_audit(&[old.clone(), admitted.clone(), deferred.clone()])
+            .await
+            .unwrap();
+        // The same order may be recorded again when evaluated in a later iteration.
+        let skipped = entry(3, AdmissionDecision::Skipped, Some("stake cap reached"), now);
+        db.insert_admission_audit(&[skipped.clone()]).await.unwrap();
+
+        assert_eq!(db.admission_audit(now - 10).await.unwrap(), vec![admitted, deferred, skipped]);
+        assert_eq!(db.admission_audit(now - 100).await.unwrap()[0], old);
+
+        assert_eq!(db.prune_admission_audit(now - 10).await.unwrap(), 1);
+        assert_eq!(db.admission_audit(0).await.unwrap().len(), 3);
+    }
+
+    #[sqlx::test]
+    async fn lock_outcome_stats(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+
+        let now = Utc::now().timestamp() as u64;
+        let price = U256::from(10);
+        let record = |id: u64, outcome, winner, timestamp| {
+            let db = db.clone();
+            async move {
+                db.insert_lock_outcome(U256::from(id), outcome, winner, price, timestamp)
+                    .await
+                    .unwrap()
+            }
+        };
+        record(1, LockOutcome::Won, None, now).await;
+        record(2, LockOutcome::Won, None, now).await;
+        record(3, LockOutcome::Lost, Some("winner_a"), now).await;
+        record(4, LockOutcome::Error, None, now).await;
+        // Retries of the same request are recorded as separate attempts.
+        record(4, LockOutcome::Lost, Some("winner_b"), now).await;
+        record(6, LockOutcome::Lost, Some("winner_a"), now).await;
+        record(7, LockOutcome::Lost, None, now).await;
+        // Outside of the window
+        record(5, LockOutcome::Won, None, now - 100).await;
+        record(8, LockOutcome::Lost, Some("winner_b"), now - 100).await;
+
+        let stats = db.get_lock_outcome_stats(now - 10).await.unwrap();
+        assert_eq!(
+            stats,
+            LockOutcomeStats {
+                wins: 2,
+                losses: 4,
+                errors: 1,
+                top_winners: vec![("winner_a".to_string(), 2), ("winner_b".to_string(), 1)],
+            }
+        );
+        assert_eq!(stats.win_loss_ratio(), Some(0.5));
+        assert_eq!(stats.loss_rate(), Some(4.0 / 6.0));
+        assert_eq!(db.get_lock_outcome_stats(now - 100).await.unwrap().wins, 3);
+        assert_eq!(db.get_lock_outcome_stats(now + 1).await.unwrap(), LockOutcomeStats::default());
+    }
+
+    #[sqlx::test]
+    async fn lock_prices(pool: SqlitePool) {
+        let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
+
+        let prices = LockPrices {
+            submission: U256::from(10),
+            target: Some(U256::from(5)),
+            confirmation: U256::from(12),
+        };
+        db.insert_lock_prices(U256::from(1), &prices, 100).await.unwrap();
+        let untargeted = LockPrices { target: None, ..prices.clone() };
+        db.insert_lock_prices(U256::from(2), &untargeted, 100).await.unwrap();
+        // The prices of the first lock are kept.
+        db.insert_lock_prices(U256::from(1), &untargeted, 200).await.unwrap();
+
+        assert_eq!(db.get_lock_prices(U256::from(1)).await.unwrap(), Some(prices));
+        assert_eq!(db.get_lock_prices(U256::from(2)).await.unwrap(), Some(untargeted));
+        assert_eq!(db.get_lock_prices(U256::from(3)).await.unwrap(), None);
+    }
+
     #[sqlx::test]
     async fn get_expired_committed_orders(pool: SqlitePool) {
         let db: DbObj = Arc::new(SqliteDb::from(pool).await.unwrap());
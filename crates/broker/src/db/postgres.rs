@@ -0,0 +1,1142 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+
+use alloy::primitives::{Address, Bytes, B256, U256};
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{
+    postgres::{PgPool, PgPoolOptions},
+    types::Json,
+    Row,
+};
+use tracing::instrument;
+
+use super::{
+    AdmissionAuditEntry, AggregationOrder, BrokerDb, DbBatch, DbError, DbOrder, LockOutcome,
+    LockOutcomeStats, LockPrices, LOST_LOCK_TOP_WINNERS,
+};
+use crate::{
+    AggregationState, Batch, BatchStatus, FulfillmentType, Order, OrderRequest, OrderStatus,
+    ProofRequest,
+};
+
+/// Default size of the Postgres connection pool.
+const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+
+/// Postgres implementation of [BrokerDb].
+///
+/// Stores the same JSON documents as [super::SqliteDb], using JSONB operators in place of the
+/// SQLite JSON functions. Unlike SQLite, Postgres allows multiple connections, so rows claimed by
+/// a worker are locked with `FOR UPDATE SKIP LOCKED`.
+pub struct PostgresDb {
+    pool: PgPool,
+}
+
+impl PostgresDb {
+    pub async fn new(conn_str: &str) -> Result<Self, DbError> {
+        let max_connections = match std::env::var("BROKER_PG_MAX_CONNECTIONS") {
+            Ok(val) => val.parse()?,
+            Err(_) => DEFAULT_MAX_CONNECTIONS,
+        };
+
+        let pool = PgPoolOptions::new().max_connections(max_connections).connect(conn_str).await?;
+
+        sqlx::migrate!("./migrations_postgres").run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+
+    async fn new_batch(&self) -> Result<usize, DbError> {
+        let batch = Batch { start_time: Utc::now(), ..Default::default() };
+
+        let res: i64 = sqlx::query_scalar("INSERT INTO batches (data) VALUES ($1) RETURNING id")
+            .bind(Json(&batch))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(res as usize)
+    }
+
+    /// Insert an order into the database, ignoring duplicates.
+    async fn insert_order_ignore_duplicates(&self, order: &Order) -> Result<(), DbError> {
+        let result =
+            sqlx::query("INSERT INTO orders (id, data) VALUES ($1, $2) ON CONFLICT(id) DO NOTHING")
+                .bind(order.id())
+                .bind(Json(&order))
+                .execute(&self.pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            tracing::debug!("Order {} already exists in the database", order.id());
+        }
+
+        Ok(())
+    }
+
+    /// Insert an accepted order, overwriting only if the existing order is skipped.
+    async fn insert_accepted_order(
+        &self,
+        order: &Order,
+        lock_tx_hash: Option<B256>,
+    ) -> Result<(), DbError> {
+        let result = sqlx::query(
+            r#"INSERT INTO orders (id, data, lock_tx_hash) VALUES ($1, $2, $3)
+               ON CONFLICT(id) DO UPDATE SET
+                   data = excluded.data,
+                   lock_tx_hash = excluded.lock_tx_hash
+               WHERE orders.data->>'status' = 'Skipped'"#,
+        )
+        .bind(order.id())
+        .bind(Json(&order))
+        .bind(lock_tx_hash.map(|tx_hash| tx_hash.to_string()))
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DbError::DuplicateOrderId(order.id()));
+        }
+
+        Ok(())
+    }
+
+    /// Sets the status and update time of an order.
+    async fn set_order_status(&self, id: &str, status: OrderStatus) -> Result<(), DbError> {
+        let res = sqlx::query(
+            r#"
+            UPDATE orders
+            SET data = jsonb_set(
+                       jsonb_set(data,
+                       '{status}', $1),
+                       '{updated_at}', to_jsonb($2::bigint))
+            WHERE
+                id = $3"#,
+        )
+        .bind(Json(status))
+        .bind(Utc::now().timestamp())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::OrderNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Sets a string field and the update time of an order.
+    async fn set_order_field(&self, id: &str, field: &str, value: &str) -> Result<(), DbError> {
+        let res = sqlx::query(
+            r#"
+            UPDATE orders
+            SET data = jsonb_set(
+                       jsonb_set(data,
+                       ARRAY[$1], to_jsonb($2::text)),
+                       '{updated_at}', to_jsonb($3::bigint))
+            WHERE
+                id = $4"#,
+        )
+        .bind(field)
+        .bind(value)
+        .bind(Utc::now().timestamp())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::OrderNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Moves every order in one of `from` to `status`, returning them for aggregation.
+    async fn claim_aggregation_orders(
+        &self,
+        status: OrderStatus,
+        from: &[OrderStatus],
+    ) -> Result<Vec<AggregationOrder>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as(
+            r#"
+            UPDATE orders
+            SET data = jsonb_set(
+                       jsonb_set(data,
+                       '{status}', $1),
+                       '{update_at}', to_jsonb($2::bigint))
+            WHERE
+                data->'status' = ANY($3)
+            RETURNING *
+            "#,
+        )
+        .bind(Json(status))
+        .bind(Utc::now().timestamp())
+        .bind(from.iter().map(Json).collect::<Vec<_>>())
+        .fetch_all(&self.pool)
+        .await?;
+
+        orders
+            .into_iter()
+            .map(|order| {
+                Ok(AggregationOrder {
+                    proof_id: order
+                        .data
+                        .proof_id
+                        .ok_or(DbError::InvalidOrder(order.id.clone(), "proof_id"))?,
+                    expiration: order
+                        .data
+                        .expire_timestamp
+                        .ok_or(DbError::InvalidOrder(order.id.clone(), "expire_timestamp"))?,
+                    fee: order
+                        .data
+                        .lock_price
+                        .ok_or(DbError::InvalidOrder(order.id.clone(), "lock_price"))?,
+                    order_id: order.id,
+                })
+            })
+            .collect()
+    }
+
+    /// Sets the status of a batch.
+    async fn set_batch_status_inner(
+        &self,
+        batch_id: usize,
+        status: BatchStatus,
+    ) -> Result<(), DbError> {
+        let res =
+            sqlx::query("UPDATE batches SET data = jsonb_set(data, '{status}', $1) WHERE id = $2")
+                .bind(Json(status))
+                .bind(batch_id as i64)
+                .execute(&self.pool)
+                .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::BatchNotFound(batch_id));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BrokerDb for PostgresDb {
+    #[cfg(test)]
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{}", order.id())))]
+    async fn add_order(&self, order: &Order) -> Result<(), DbError> {
+        self.insert_order_ignore_duplicates(order).await
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{}", order_request.id())))]
+    async fn insert_skipped_request(&self, order_request: &OrderRequest) -> Result<(), DbError> {
+        self.insert_order_ignore_duplicates(&order_request.to_skipped_order()).await
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{}", order_request.id())))]
+    async fn insert_accepted_request(
+        &self,
+        order_request: &OrderRequest,
+        lock_price: U256,
+    ) -> Result<Order, DbError> {
+        let order = order_request.to_proving_order(lock_price);
+        self.insert_accepted_order(&order, None).await?;
+        Ok(order)
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{}", order_request.id())))]
+    async fn insert_locked_request(
+        &self,
+        order_request: &OrderRequest,
+        lock_price: U256,
+        lock_tx_hash: B256,
+    ) -> Result<Order, DbError> {
+        let order = order_request.to_proving_order(lock_price);
+        self.insert_accepted_order(&order, Some(lock_tx_hash)).await?;
+        Ok(order)
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn get_order_lock_tx_hash(&self, id: &str) -> Result<Option<B256>, DbError> {
+        let lock_tx_hash: Option<Option<String>> =
+            sqlx::query_scalar("SELECT lock_tx_hash FROM orders WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        let Some(lock_tx_hash) = lock_tx_hash else {
+            return Err(DbError::OrderNotFound(id.to_string()));
+        };
+        lock_tx_hash
+            .map(|tx_hash| B256::from_str(&tx_hash))
+            .transpose()
+            .map_err(|_| DbError::InvalidOrder(id.to_string(), "lock_tx_hash"))
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn get_order(&self, id: &str) -> Result<Option<Order>, DbError> {
+        let order: Option<DbOrder> = sqlx::query_as("SELECT * FROM orders WHERE id = $1 LIMIT 1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(order.map(|x| x.data))
+    }
+
+    async fn get_orders(&self, ids: &[&str]) -> Result<Vec<Order>, DbError> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let orders: Vec<DbOrder> = sqlx::query_as("SELECT * FROM orders WHERE id = ANY($1)")
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(orders.into_iter().map(|x| x.data).collect())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn get_submission_order(
+        &self,
+        id: &str,
+    ) -> Result<(ProofRequest, Bytes, String, B256, U256, FulfillmentType), DbError> {
+        let order = self.get_order(id).await?;
+        if let Some(order) = order {
+            Ok((
+                order.request.clone(),
+                order.client_sig.clone(),
+                order.proof_id.ok_or(DbError::MissingElm("proof_id"))?,
+                order.request.requirements.imageId,
+                order.lock_price.ok_or(DbError::MissingElm("lock_price"))?,
+                order.fulfillment_type,
+            ))
+        } else {
+            Err(DbError::OrderNotFound(id.to_string()))
+        }
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn get_order_compressed_proof_id(&self, id: &str) -> Result<String, DbError> {
+        let order = self.get_order(id).await?;
+        if let Some(order) = order {
+            Ok(order.compressed_proof_id.ok_or(DbError::MissingElm("compressed_proof_id"))?)
+        } else {
+            Err(DbError::OrderNotFound(id.to_string()))
+        }
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn set_order_failure(&self, id: &str, failure_str: &'static str) -> Result<(), DbError> {
+        let res = sqlx::query(
+            r#"
+            UPDATE orders
+            SET data = jsonb_set(
+                       jsonb_set(
+                       jsonb_set(data,
+                       '{status}', $1),
+                       '{updated_at}', to_jsonb($2::bigint)),
+                       '{error_msg}', to_jsonb($3::text))
+            WHERE
+                id = $4"#,
+        )
+        .bind(Json(OrderStatus::Failed))
+        .bind(Utc::now().timestamp())
+        .bind(failure_str)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::OrderNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn set_order_complete(&self, id: &str) -> Result<(), DbError> {
+        self.set_order_status(id, OrderStatus::Done).await
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn get_committed_orders(&self) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> =
+            sqlx::query_as("SELECT * FROM orders WHERE data->'status' = ANY($1)")
+                .bind(vec![
+                    Json(OrderStatus::PendingProving),
+                    Json(OrderStatus::Proving),
+                    Json(OrderStatus::PendingAgg),
+                    Json(OrderStatus::Aggregating),
+                    Json(OrderStatus::SkipAggregation),
+                    Json(OrderStatus::PendingSubmission),
+                ])
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(orders.into_iter().map(|elm| elm.data).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_committed_orders_by_client(&self, client: Address) -> Result<Vec<Order>, DbError> {
+        // The client address is packed into the request ID, so it is matched after decoding.
+        let orders = self.get_committed_orders().await?;
+        Ok(orders.into_iter().filter(|order| order.request.client_address() == client).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_committed_orders_by_type(
+        &self,
+        fulfillment_type: FulfillmentType,
+    ) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as(
+            "SELECT * FROM orders WHERE data->'status' = ANY($1) AND data->'fulfillment_type' = $2",
+        )
+        .bind(vec![
+            Json(OrderStatus::PendingProving),
+            Json(OrderStatus::Proving),
+            Json(OrderStatus::PendingAgg),
+            Json(OrderStatus::Aggregating),
+            Json(OrderStatus::SkipAggregation),
+            Json(OrderStatus::PendingSubmission),
+        ])
+        .bind(Json(fulfillment_type))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|elm| elm.data).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_orders_by_label(&self, label: &str) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> =
+            sqlx::query_as("SELECT * FROM orders WHERE data->'labels' @> $1")
+                .bind(Json([label]))
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(orders.into_iter().map(|elm| elm.data).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_expired_committed_orders(
+        &self,
+        grace_period_secs: i64,
+    ) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as(
+            r#"
+            SELECT * FROM orders
+                WHERE data->'status' = ANY($1)
+                AND (data->>'expire_timestamp')::bigint < $2"#,
+        )
+        .bind(vec![
+            Json(OrderStatus::PendingProving),
+            Json(OrderStatus::Proving),
+            Json(OrderStatus::PendingAgg),
+            Json(OrderStatus::SkipAggregation),
+            Json(OrderStatus::PendingSubmission),
+        ])
+        .bind(Utc::now().timestamp().saturating_sub(grace_period_secs))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|db_order| db_order.data).collect())
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn get_proving_order(&self) -> Result<Option<Order>, DbError> {
+        let elm: Option<DbOrder> = sqlx::query_as(
+            r#"
+            UPDATE orders
+            SET data = jsonb_set(jsonb_set(data, '{status}', $1), '{update_at}', to_jsonb($2::bigint))
+            WHERE id =
+                (SELECT id
+                FROM orders
+                WHERE data->'status' = $3
+                ORDER BY COALESCE((data->>'urgent')::boolean, false) DESC
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED)
+            RETURNING *
+            "#,
+        )
+        .bind(Json(OrderStatus::Proving))
+        .bind(Utc::now().timestamp())
+        .bind(Json(OrderStatus::PendingProving))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(elm.map(|order| order.data))
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn get_active_proofs(&self) -> Result<Vec<Order>, DbError> {
+        let orders: Vec<DbOrder> = sqlx::query_as("SELECT * FROM orders WHERE data->'status' = $1")
+            .bind(Json(OrderStatus::Proving))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(orders.into_iter().map(|elm| elm.data).collect())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn set_order_proof_id(&self, id: &str, proof_id: &str) -> Result<(), DbError> {
+        self.set_order_field(id, "proof_id", proof_id).await
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn set_order_compressed_proof_id(
+        &self,
+        id: &str,
+        compressed_proof_id: &str,
+    ) -> Result<(), DbError> {
+        self.set_order_field(id, "compressed_proof_id", compressed_proof_id).await
+    }
+
+    #[instrument(level = "trace", skip_all, fields(id = %format!("{id}")))]
+    async fn set_aggregation_status(&self, id: &str, status: OrderStatus) -> Result<(), DbError> {
+        self.set_order_status(id, status).await
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn get_aggregation_proofs(&self) -> Result<Vec<AggregationOrder>, DbError> {
+        self.claim_aggregation_orders(
+            OrderStatus::Aggregating,
+            &[OrderStatus::PendingAgg, OrderStatus::Aggregating],
+        )
+        .await
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn get_groth16_proofs(&self) -> Result<Vec<AggregationOrder>, DbError> {
+        self.claim_aggregation_orders(OrderStatus::SkipAggregation, &[OrderStatus::SkipAggregation])
+            .await
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn complete_batch(&self, batch_id: usize, g16_proof_id: &str) -> Result<(), DbError> {
+        let batch = self.get_batch(batch_id).await?;
+        if batch.aggregation_state.is_none() {
+            return Err(DbError::BatchAggregationStateIsNone(batch_id));
+        }
+
+        let res = sqlx::query(
+            r#"
+            UPDATE batches
+            SET data = jsonb_set(
+                       jsonb_set(data,
+                       '{status}', $1),
+                       '{aggregation_state,groth16_proof_id}', to_jsonb($2::text))
+            WHERE
+                id = $3"#,
+        )
+        .bind(Json(BatchStatus::Complete))
+        .bind(g16_proof_id)
+        .bind(batch_id as i64)
+        .execute(&self.pool)
+        .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::BatchNotFound(batch_id));
+        }
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn get_complete_batch(&self) -> Result<Option<(usize, Batch)>, DbError> {
+        let elm: Option<DbBatch> = sqlx::query_as(
+            r#"
+            UPDATE batches
+            SET
+                data = jsonb_set(data, '{status}', $1)
+            WHERE id =
+                (SELECT id
+                FROM batches
+                WHERE data->'status' = $2
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED)
+            RETURNING *
+            "#,
+        )
+        .bind(Json(BatchStatus::PendingSubmission))
+        .bind(Json(BatchStatus::Complete))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(elm.map(|db_batch| (db_batch.id as usize, db_batch.data)))
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn set_batch_submitted(&self, batch_id: usize) -> Result<(), DbError> {
+        self.set_batch_status_inner(batch_id, BatchStatus::Submitted).await
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn set_batch_failure(&self, batch_id: usize, err: String) -> Result<(), DbError> {
+        let res = sqlx::query(
+            r#"
+            UPDATE batches
+            SET
+                data = jsonb_set(
+                       jsonb_set(data,
+                       '{status}', $1),
+                       '{error_msg}', to_jsonb($2::text))
+            WHERE
+                id = $3"#,
+        )
+        .bind(Json(BatchStatus::Failed))
+        .bind(err)
+        .bind(batch_id as i64)
+        .execute(&self.pool)
+        .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::BatchNotFound(batch_id));
+        }
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn get_current_batch(&self) -> Result<usize, DbError> {
+        let cur_batch: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM batches WHERE data->'status' = ANY($1) LIMIT 1")
+                .bind(vec![Json(BatchStatus::Aggregating), Json(BatchStatus::PendingCompression)])
+                .fetch_optional(&self.pool)
+                .await?;
+
+        match cur_batch {
+            Some(id) => Ok(id as usize),
+            None => self.new_batch().await,
+        }
+    }
+
+    #[instrument(level = "trace", skip(self, aggreagtion_state, orders, assessor_proof_id))]
+    async fn update_batch(
+        &self,
+        batch_id: usize,
+        aggreagtion_state: &AggregationState,
+        orders: &[AggregationOrder],
+        assessor_proof_id: Option<String>,
+    ) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+
+        let rows = sqlx::query(
+            r#"SELECT data->>'fees' AS fees, (data->>'deadline')::bigint AS deadline
+            FROM batches WHERE id = $1 FOR UPDATE"#,
+        )
+        .bind(batch_id as i64)
+        .fetch_optional(&mut *txn)
+        .await?;
+
+        let Some(rows) = rows else {
+            return Err(DbError::BatchNotFound(batch_id));
+        };
+
+        let db_fees: String = rows.try_get("fees")?;
+        let db_deadline: Option<i64> = rows.try_get("deadline")?;
+
+        let new_deadline = orders
+            .iter()
+            .fold(db_deadline, |min, order| {
+                Some(i64::min(min.unwrap_or(i64::MAX), order.expiration as i64))
+            })
+            .unwrap_or(i64::MAX);
+
+        let db_fees = U256::from_str(&db_fees)?;
+        let new_fees = orders.iter().fold(db_fees, |sum, order| sum + order.fee);
+        let order_ids: Vec<&str> = orders.iter().map(|order| order.order_id.as_str()).collect();
+
+        // Update the batch fees, deadline, aggregation state, and orders in one statement.
+        let res = sqlx::query(
+            r#"
+            UPDATE batches
+            SET
+                data = jsonb_set(
+                       jsonb_set(
+                       jsonb_set(
+                       jsonb_set(data,
+                       '{deadline}', to_jsonb($1::bigint)),
+                       '{fees}', to_jsonb($2::text)),
+                       '{aggregation_state}', $3),
+                       '{orders}', COALESCE(data->'orders', '[]'::jsonb) || to_jsonb($4::text[]))
+            WHERE
+                id = $5"#,
+        )
+        .bind(new_deadline)
+        .bind(format!("0x{new_fees:x}"))
+        .bind(Json(aggreagtion_state))
+        .bind(&order_ids)
+        .bind(batch_id as i64)
+        .execute(&mut *txn)
+        .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::BatchNotFound(batch_id));
+        }
+
+        for order in orders {
+            let res = sqlx::query(
+                r#"
+                UPDATE orders
+                SET data = jsonb_set(
+                           jsonb_set(data,
+                           '{status}', $1),
+                           '{updated_at}', to_jsonb($2::bigint))
+                WHERE
+                    id = $3"#,
+            )
+            .bind(Json(OrderStatus::PendingSubmission))
+            .bind(Utc::now().timestamp())
+            .bind(&order.order_id)
+            .execute(&mut *txn)
+            .await?;
+
+            if res.rows_affected() == 0 {
+                return Err(DbError::OrderNotFound(order.order_id.clone()));
+            }
+        }
+
+        if let Some(assessor_proof_id) = assessor_proof_id {
+            let res = sqlx::query(
+                r#"
+                UPDATE batches
+                SET
+                    data = jsonb_set(
+                           jsonb_set(data,
+                           '{status}', $1),
+                           '{assessor_proof_id}', to_jsonb($2::text))
+                WHERE
+                    id = $3"#,
+            )
+            .bind(Json(BatchStatus::PendingCompression))
+            .bind(assessor_proof_id)
+            .bind(batch_id as i64)
+            .execute(&mut *txn)
+            .await?;
+
+            if res.rows_affected() == 0 {
+                return Err(DbError::BatchNotFound(batch_id));
+            }
+        }
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_batch(&self, batch_id: usize) -> Result<Batch, DbError> {
+        let batch: Option<DbBatch> = sqlx::query_as("SELECT * FROM batches WHERE id = $1")
+            .bind(batch_id as i64)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        batch.map(|batch| batch.data).ok_or(DbError::BatchNotFound(batch_id))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn set_request_fulfilled(
+        &self,
+        request_id: U256,
+        block_number: u64,
+    ) -> Result<(), DbError> {
+        sqlx::query("INSERT INTO fulfilled_requests (id, block_number) VALUES ($1, $2)")
+            .bind(format!("0x{request_id:x}"))
+            .bind(block_number as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn is_request_fulfilled(&self, request_id: U256) -> Result<bool, DbError> {
+        let res = sqlx::query("SELECT id FROM fulfilled_requests WHERE id = $1")
+            .bind(format!("0x{request_id:x}"))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(res.is_some())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn set_request_locked(
+        &self,
+        request_id: U256,
+        locker: &str,
+        block_number: u64,
+    ) -> Result<(), DbError> {
+        sqlx::query("INSERT INTO locked_requests (id, locker, block_number) VALUES ($1, $2, $3)")
+            .bind(format!("0x{request_id:x}"))
+            .bind(locker)
+            .bind(block_number as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn is_request_locked(&self, request_id: U256) -> Result<bool, DbError> {
+        let res = sqlx::query("SELECT id FROM locked_requests WHERE id = $1")
+            .bind(format!("0x{request_id:x}"))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(res.is_some())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_request_locked(&self, request_id: U256) -> Result<Option<(String, u64)>, DbError> {
+        // Postgres has no unsigned integers, so the block number is read back as a BIGINT.
+        let res: Option<(String, i64)> =
+            sqlx::query_as("SELECT locker, block_number FROM locked_requests WHERE id = $1")
+                .bind(format!("0x{request_id:x}"))
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(res.map(|(locker, block_number)| (locker, block_number as u64)))
+    }
+
+    #[instrument(level = "trace", skip_all, fields(entries = entries.len()))]
+    async fn insert_admission_audit(&self, entries: &[AdmissionAuditEntry]) -> Result<(), DbError> {
+        let mut txn = self.pool.begin().await?;
+        for entry in entries {
+            sqlx::query("INSERT INTO admission_audit (timestamp, data) VALUES ($1, $2)")
+                .bind(entry.timestamp as i64)
+                .bind(Json(entry))
+                .execute(&mut *txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn admission_audit(&self, since: u64) -> Result<Vec<AdmissionAuditEntry>, DbError> {
+        let entries: Vec<Json<AdmissionAuditEntry>> = sqlx::query_scalar(
+            "SELECT data FROM admission_audit WHERE timestamp >= $1 ORDER BY id",
+        )
+        .bind(since as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries.into_iter().map(|entry| entry.0).collect())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn prune_admission_audit(&self, before: u64) -> Result<u64, DbError> {
+        let res = sqlx::query("DELETE FROM admission_audit WHERE timestamp < $1")
+            .bind(before as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn insert_lock_outcome(
+        &self,
+        request_id: U256,
+        outcome: LockOutcome,
+        winner: Option<&str>,
+        lock_price: U256,
+        timestamp: u64,
+    ) -> Result<(), DbError> {
+        sqlx::query(
+            r#"INSERT INTO lock_outcomes (request_id, outcome, winner, lock_price, timestamp)
+            VALUES ($1, $2, $3, $4, $5)"#,
+        )
+        .bind(format!("0x{request_id:x}"))
+        .bind(outcome.as_str())
+        .bind(winner)
+        .bind(lock_price.to_string())
+        .bind(timestamp as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_lock_outcome_stats(&self, since: u64) -> Result<LockOutcomeStats, DbError> {
+        let counts: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT outcome, COUNT(*) FROM lock_outcomes WHERE timestamp >= $1 GROUP BY outcome",
+        )
+        .bind(since as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let top_winners: Vec<(String, i64)> = sqlx::query_as(
+            r#"SELECT winner, COUNT(*) AS wins FROM lock_outcomes
+            WHERE timestamp >= $1 AND outcome = $2 AND winner IS NOT NULL
+            GROUP BY winner ORDER BY wins DESC, winner ASC LIMIT $3"#,
+        )
+        .bind(since as i64)
+        .bind(LockOutcome::Lost.as_str())
+        .bind(LOST_LOCK_TOP_WINNERS)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(LockOutcomeStats::from_counts(counts, top_winners))
+    }
+
+    #[instrument(level = "trace", skip(self, prices))]
+    async fn insert_lock_prices(
+        &self,
+        request_id: U256,
+        prices: &LockPrices,
+        timestamp: u64,
+    ) -> Result<(), DbError> {
+        sqlx::query(
+            r#"INSERT INTO lock_prices (id, submission_price, target_price, confirmation_price, timestamp)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT(id) DO NOTHING"#,
+        )
+        .bind(format!("0x{request_id:x}"))
+        .bind(prices.submission.to_string())
+        .bind(prices.target.map(|price| price.to_string()))
+        .bind(prices.confirmation.to_string())
+        .bind(timestamp as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_lock_prices(&self, request_id: U256) -> Result<Option<LockPrices>, DbError> {
+        let row: Option<(String, Option<String>, String)> = sqlx::query_as(
+            "SELECT submission_price, target_price, confirmation_price FROM lock_prices WHERE id = $1",
+        )
+        .bind(format!("0x{request_id:x}"))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|(submission, target, confirmation)| {
+            Ok(LockPrices {
+                submission: U256::from_str(&submission)?,
+                target: target.as_deref().map(U256::from_str).transpose()?,
+                confirmation: U256::from_str(&confirmation)?,
+            })
+        })
+        .transpose()
+    }
+
+    #[cfg(test)]
+    async fn add_batch(&self, batch_id: usize, batch: Batch) -> Result<(), DbError> {
+        let res = sqlx::query("INSERT INTO batches (id, data) VALUES ($1, $2)")
+            .bind(batch_id as i64)
+            .bind(Json(batch))
+            .execute(&self.pool)
+            .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::BatchInsertFailure(batch_id));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    async fn set_batch_status(&self, batch_id: usize, status: BatchStatus) -> Result<(), DbError> {
+        self.set_batch_status_inner(batch_id, status).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{tests::create_order_request, AdmissionDecision, DbObj};
+    use risc0_aggregation::GuestState;
+    use serial_test::serial;
+    use std::sync::Arc;
+
+    /// Connects to the Postgres instance named by `BROKER_TEST_POSTGRES_URL`, e.g. a container
+    /// started with `docker run -e POSTGRES_PASSWORD=password -p 5432:5432 postgres`, and clears
+    /// any state left by a previous run.
+    async fn test_db() -> DbObj {
+        let url = std::env::var("BROKER_TEST_POSTGRES_URL")
+            .expect("BROKER_TEST_POSTGRES_URL must be set");
+        let db = PostgresDb::new(&url).await.unwrap();
+        sqlx::query(
+            "TRUNCATE orders, batches, last_block, fulfilled_requests, locked_requests, admission_audit, lock_outcomes, lock_prices",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        Arc::new(db)
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore = "requires a Postgres instance at BROKER_TEST_POSTGRES_URL"]
+    async fn order_monitor_queries() {
+        let db = test_db().await;
+
+        // Accepted and locked orders are both committed and cannot be accepted twice.
+        let mut accepted = create_order_request();
+        accepted.expire_timestamp = Some(100);
+        db.insert_accepted_request(&accepted, U256::from(1)).await.unwrap();
+        assert!(matches!(
+            db.insert_accepted_request(&accepted, U256::from(1)).await,
+            Err(DbError::DuplicateOrderId(_))
+        ));
+
+        let mut locked = create_order_request();
+        locked.request.id = U256::from(2);
+        let lock_tx_hash = B256::repeat_byte(0x11);
+        db.insert_locked_request(&locked, U256::from(2), lock_tx_hash).await.unwrap();
+        assert_eq!(db.get_order_lock_tx_hash(&locked.id()).await.unwrap(), Some(lock_tx_hash));
+        assert_eq!(db.get_order_lock_tx_hash(&accepted.id()).await.unwrap(), None);
+
+        let committed = db.get_committed_orders().await.unwrap();
+        assert_eq!(committed.len(), 2);
+        assert_eq!(db.get_committed_orders_by_client(Address::ZERO).await.unwrap().len(), 2);
+        assert_eq!(
+            db.get_committed_orders_by_type(FulfillmentType::LockAndFulfill).await.unwrap().len(),
+            2
+        );
+        assert!(db
+            .get_committed_orders_by_type(FulfillmentType::FulfillAfterLockExpire)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let orders = db.get_orders(&[&accepted.id(), &locked.id()]).await.unwrap();
+        assert_eq!(orders.len(), 2);
+
+        db.set_order_failure(&accepted.id(), "test failure").await.unwrap();
+        let failed = db.get_order(&accepted.id()).await.unwrap().unwrap();
+        assert_eq!(failed.status, OrderStatus::Failed);
+        assert_eq!(failed.error_msg.as_deref(), Some("test failure"));
+        assert_eq!(db.get_committed_orders().await.unwrap().len(), 1);
+
+        // Lock and fulfillment state observed on chain.
+        let request_id = U256::from(3);
+        assert!(!db.is_request_locked(request_id).await.unwrap());
+        db.set_request_locked(request_id, "0x01", u64::from(u32::MAX) + 1).await.unwrap();
+        assert!(db.is_request_locked(request_id).await.unwrap());
+        assert_eq!(
+            db.get_request_locked(request_id).await.unwrap(),
+            Some(("0x01".to_string(), u64::from(u32::MAX) + 1))
+        );
+
+        assert!(!db.is_request_fulfilled(request_id).await.unwrap());
+        db.set_request_fulfilled(request_id, 10).await.unwrap();
+        assert!(db.is_request_fulfilled(request_id).await.unwrap());
+
+        // Admission decisions are returned in the order they were recorded.
+        let entry = AdmissionAuditEntry {
+            order_id: "0x7".to_string(),
+            request_id: U256::from(7),
+            decision: AdmissionDecision::Deferred,
+            reason: Some("insufficient balance".to_string()),
+            price_wei: U256::from(10),
+            gas_cost_wei: U256::from(2),
+            timestamp: 100,
+        };
+        let admitted = AdmissionAuditEntry {
+            decision: AdmissionDecision::Admitted,
+            reason: None,
+            ..entry.clone()
+        };
+        db.insert_admission_audit(&[entry.clone(), admitted.clone()]).await.unwrap();
+        assert_eq!(db.admission_audit(100).await.unwrap(), vec![entry, admitted]);
+        assert!(db.admission_audit(101).await.unwrap().is_empty());
+        assert_eq!(db.prune_admission_audit(101).await.unwrap(), 2);
+        assert!(db.admission_audit(0).await.unwrap().is_empty());
+
+        // Lock attempt outcomes are counted per outcome, with the provers that won against us.
+        let price = U256::from(5);
+        db.insert_lock_outcome(U256::from(8), LockOutcome::Won, None, price, 100).await.unwrap();
+        db.insert_lock_outcome(U256::from(9), LockOutcome::Lost, Some("0x02"), price, 100)
+            .await
+            .unwrap();
+        db.insert_lock_outcome(U256::from(10), LockOutcome::Lost, Some("0x02"), price, 200)
+            .await
+            .unwrap();
+        db.insert_lock_outcome(U256::from(9), LockOutcome::Error, None, price, 50).await.unwrap();
+        assert_eq!(
+            db.get_lock_outcome_stats(100).await.unwrap(),
+            LockOutcomeStats {
+                wins: 1,
+                losses: 2,
+                errors: 0,
+                top_winners: vec![("0x02".to_string(), 2)],
+            }
+        );
+
+        // Lock prices are recorded once per request.
+        let prices =
+            LockPrices { submission: U256::from(10), target: None, confirmation: U256::from(12) };
+        db.insert_lock_prices(U256::from(8), &prices, 100).await.unwrap();
+        assert_eq!(db.get_lock_prices(U256::from(8)).await.unwrap(), Some(prices));
+        assert_eq!(db.get_lock_prices(U256::from(9)).await.unwrap(), None);
+
+        // Only the committed order with an expiry is returned, once past the grace period.
+        let expired = db.get_expired_committed_orders(0).await.unwrap();
+        assert_eq!(expired.len(), 0);
+        db.set_aggregation_status(&accepted.id(), OrderStatus::PendingProving).await.unwrap();
+        let expired = db.get_expired_committed_orders(0).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id(), accepted.id());
+        assert!(db.get_expired_committed_orders(i64::MAX).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore = "requires a Postgres instance at BROKER_TEST_POSTGRES_URL"]
+    async fn proving_and_batch_queries() {
+        let db = test_db().await;
+
+        let mut order = create_order_request();
+        order.expire_timestamp = Some(1000);
+        db.insert_accepted_request(&order, U256::from(7)).await.unwrap();
+
+        let proving = db.get_proving_order().await.unwrap().unwrap();
+        assert_eq!(proving.id(), order.id());
+        assert!(db.get_proving_order().await.unwrap().is_none());
+        assert_eq!(db.get_active_proofs().await.unwrap().len(), 1);
+
+        db.set_order_proof_id(&order.id(), "proof").await.unwrap();
+        db.set_aggregation_status(&order.id(), OrderStatus::PendingAgg).await.unwrap();
+        let agg_orders = db.get_aggregation_proofs().await.unwrap();
+        assert_eq!(agg_orders.len(), 1);
+        assert_eq!(agg_orders[0].proof_id, "proof");
+
+        let batch_id = db.get_current_batch().await.unwrap();
+        let agg_state = AggregationState {
+            guest_state: GuestState::initial([3u32; 8]),
+            proof_id: "agg".to_string(),
+            claim_digests: vec![],
+            groth16_proof_id: None,
+        };
+        db.update_batch(batch_id, &agg_state, &agg_orders, Some("assessor".into())).await.unwrap();
+        let batch = db.get_batch(batch_id).await.unwrap();
+        assert_eq!(batch.orders, vec![order.id()]);
+        assert_eq!(batch.fees, U256::from(7));
+        assert_eq!(batch.deadline, Some(1000));
+        assert_eq!(batch.status, BatchStatus::PendingCompression);
+
+        db.complete_batch(batch_id, "g16").await.unwrap();
+        let (complete_id, batch) = db.get_complete_batch().await.unwrap().unwrap();
+        assert_eq!(complete_id, batch_id);
+        assert_eq!(batch.aggregation_state.unwrap().groth16_proof_id.as_deref(), Some("g16"));
+        db.set_batch_submitted(batch_id).await.unwrap();
+        assert_eq!(db.get_batch(batch_id).await.unwrap().status, BatchStatus::Submitted);
+
+        let order = db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::PendingSubmission);
+        db.set_order_complete(&order.id()).await.unwrap();
+    }
+}
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use std::future::Future;
-use tokio::time::Duration;
+use tokio::time::{Duration, Instant};
 
 /// Retry a future with a specified number of retries and sleep duration between attempts.
 pub async fn retry<T, E, F, Fut>(
@@ -22,6 +22,25 @@ pub async fn retry<T, E, F, Fut>(
     operation: F,
     function_name: &str,
 ) -> Result<T, E>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Debug,
+{
+    retry_within(retry_count, retry_sleep_ms, None, operation, function_name).await
+}
+
+/// Retry a future with a specified number of retries and sleep duration between attempts, giving
+/// up once `max_total` has elapsed since the first attempt, if set, regardless of the retries left.
+///
+/// An attempt in progress is not interrupted, but no retry is started past `max_total`.
+pub async fn retry_within<T, E, F, Fut>(
+    retry_count: u64,
+    retry_sleep_ms: u64,
+    max_total: Option<Duration>,
+    operation: F,
+    function_name: &str,
+) -> Result<T, E>
 where
     F: Fn() -> Fut,
     Fut: Future<Output = Result<T, E>>,
@@ -31,11 +50,21 @@ where
         return operation().await;
     }
 
+    let deadline = max_total.map(|max_total| Instant::now() + max_total);
     let mut last_error = None;
     for attempt in 0..=retry_count {
         match operation().await {
             Ok(result) => return Ok(result),
             Err(err) => {
+                let sleep = Duration::from_millis(retry_sleep_ms);
+                if deadline.is_some_and(|deadline| Instant::now() + sleep >= deadline) {
+                    tracing::warn!(
+                        "Operation [{}] failed: {err:?}, not retrying as the total time limit of {:?} would be exceeded",
+                        function_name,
+                        max_total.unwrap()
+                    );
+                    return Err(err);
+                }
                 if attempt < retry_count {
                     tracing::warn!(
                         "Operation [{}] failed: {err:?}, starting retry {}/{}",
@@ -224,6 +253,35 @@ mod tests {
         ));
     }
 
+    #[tokio::test(start_paused = true)]
+    #[traced_test]
+    async fn test_retry_within_total_time() {
+        let counter = Arc::new(AtomicU32::new(0));
+        let counter_clone = counter.clone();
+        let start = Instant::now();
+
+        let result: Result<(), &str> = retry_within(
+            100,
+            100,
+            Some(Duration::from_millis(350)),
+            || {
+                let counter = counter_clone.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Err("Always fails")
+                }
+            },
+            "test operation",
+        )
+        .await;
+
+        // Attempts at 0, 100, 200 and 300ms, as another retry would start past 350ms.
+        assert!(result.is_err());
+        assert_eq!(counter.load(Ordering::SeqCst), 4);
+        assert!(start.elapsed() <= Duration::from_millis(350));
+        assert!(logs_contain("not retrying as the total time limit of 350ms would be exceeded"));
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_retry_only_specific_errors() {
@@ -0,0 +1,76 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use alloy::providers::Provider;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use crate::chain_monitor::ChainMonitorService;
+
+/// Source of the gas price used to cost orders and price lock transactions.
+///
+/// The order monitor defaults to the node's gas price, as reported by the chain monitor. Chains
+/// where the node estimates gas poorly may use an external oracle instead.
+#[async_trait]
+pub(crate) trait GasPriceSource: Send + Sync {
+    /// Returns the current gas price in wei.
+    async fn gas_price(&self) -> Result<u128>;
+}
+
+pub(crate) type GasPriceSourceObj = Arc<dyn GasPriceSource>;
+
+#[async_trait]
+impl<P: Provider> GasPriceSource for ChainMonitorService<P> {
+    async fn gas_price(&self) -> Result<u128> {
+        self.current_gas_price().await
+    }
+}
+
+/// Gas price reported by a separate RPC endpoint, such as a node or gas oracle estimating the
+/// gas price of the chain better than the broker's RPC.
+pub(crate) struct RpcGasPriceSource<P> {
+    provider: P,
+}
+
+impl<P> RpcGasPriceSource<P> {
+    pub(crate) fn new(provider: P) -> Self {
+        Self { provider }
+    }
+}
+
+#[async_trait]
+impl<P: Provider> GasPriceSource for RpcGasPriceSource<P> {
+    async fn gas_price(&self) -> Result<u128> {
+        self.provider.get_gas_price().await.context("Failed to get gas price")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{node_bindings::Anvil, providers::ProviderBuilder};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn rpc_gas_price_source() {
+        let anvil = Anvil::new().spawn();
+        let provider = ProviderBuilder::new().connect_http(anvil.endpoint_url());
+        let expected = provider.get_gas_price().await.unwrap();
+
+        let source = RpcGasPriceSource::new(provider);
+        assert_eq!(source.gas_price().await.unwrap(), expected);
+    }
+}
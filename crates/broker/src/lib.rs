@@ -12,13 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{path::PathBuf, sync::Arc, time::SystemTime};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 
 use crate::storage::create_uri_handler;
 use alloy::{
     network::Ethereum,
     primitives::{Address, Bytes, FixedBytes, U256},
-    providers::{Provider, WalletProvider},
+    providers::{Provider, ProviderBuilder, WalletProvider},
     signers::local::PrivateKeySigner,
 };
 use anyhow::{Context, Result};
@@ -32,13 +36,13 @@ use chrono::{serde::ts_seconds, DateTime, Utc};
 use clap::Parser;
 pub use config::Config;
 use config::ConfigWatcher;
-use db::{DbObj, SqliteDb};
+use db::DbObj;
 use provers::ProverObj;
 use risc0_ethereum_contracts::set_verifier::SetVerifierService;
 use risc0_zkvm::sha::Digest;
 pub use rpc_retry_policy::CustomRetryPolicy;
 use serde::{Deserialize, Serialize};
-use task::{RetryPolicy, Supervisor};
+use task::{RetryPolicy, RetryTask, Supervisor};
 use tokio::sync::mpsc;
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
@@ -47,13 +51,21 @@ use url::Url;
 const NEW_ORDER_CHANNEL_CAPACITY: usize = 1000;
 const PRICING_CHANNEL_CAPACITY: usize = 1000;
 const ORDER_STATE_CHANNEL_CAPACITY: usize = 1000;
+const TICK_TRACE_CHANNEL_CAPACITY: usize = 100;
+/// Number of order monitor iterations summarized in the status file.
+const STATUS_TICK_HISTORY: usize = 20;
 
 pub(crate) mod aggregator;
+pub(crate) mod alerts;
 pub(crate) mod chain_monitor;
+pub(crate) mod clock;
 pub mod config;
+pub(crate) mod contest;
 pub(crate) mod db;
 pub(crate) mod errors;
+pub(crate) mod gas_price;
 pub mod futures_retry;
+pub(crate) mod market_client;
 pub(crate) mod market_monitor;
 pub(crate) mod offchain_market_monitor;
 pub(crate) mod order_monitor;
@@ -61,17 +73,22 @@ pub(crate) mod order_picker;
 pub(crate) mod prioritization;
 pub(crate) mod provers;
 pub(crate) mod proving;
+pub(crate) mod rate_limit;
 pub(crate) mod reaper;
+pub(crate) mod replay;
+pub(crate) mod resource_probe;
 pub(crate) mod rpc_retry_policy;
+pub(crate) mod settlement_backlog;
 pub(crate) mod storage;
 pub(crate) mod submitter;
 pub(crate) mod task;
 pub(crate) mod utils;
+pub(crate) mod worker_pool;
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    /// sqlite database connection url
+    /// Database connection url, either sqlite or postgres (requires the `postgres` feature)
     #[clap(short = 's', long, env, default_value = "sqlite::memory:")]
     pub db_url: String,
 
@@ -136,6 +153,49 @@ pub struct Args {
     /// Log JSON
     #[clap(long, env, default_value_t = false)]
     pub log_json: bool,
+
+    /// Start the order monitor in standby
+    ///
+    /// A standby observes orders without locking or committing to them until it is promoted to
+    /// active by sending the process SIGUSR1. Used for hot standby instances sharing a database.
+    #[clap(long, env, default_value_t = false)]
+    pub standby: bool,
+
+    /// Log a trace of the decisions made by the order monitor on every iteration
+    #[clap(long, env, default_value_t = false)]
+    pub log_tick_traces: bool,
+
+    /// Record the orders and chain conditions observed by the order monitor to a file
+    ///
+    /// Appends a JSON line per iteration, to be replayed with `--replay-session`.
+    #[clap(long, env)]
+    pub record_session: Option<PathBuf>,
+
+    /// Replay a session recorded with `--record-session` and exit
+    ///
+    /// Logs the iterations in which the replayed order monitor admits different orders than
+    /// recorded. No transactions are sent, but the replayed orders are written to the database, so
+    /// point `--db-url` at a copy of it.
+    #[clap(long, env, conflicts_with = "record_session")]
+    pub replay_session: Option<PathBuf>,
+
+    /// Periodically write the state of the order monitor to a file as JSON
+    ///
+    /// Includes a summary of recent iterations, the orders committed to, the balance runway and the
+    /// gas wasted on reverted locks, for dashboards and operator tooling.
+    #[clap(long, env)]
+    pub status_file: Option<PathBuf>,
+
+    /// Seconds between writes of `--status-file`
+    #[clap(long, env, default_value_t = 30)]
+    pub status_interval_secs: u64,
+
+    /// Log every admission check of the order with the given ID as of the current block and exit
+    ///
+    /// The order is looked up in the database, where orders are stored once skipped or committed
+    /// to.
+    #[clap(long, env, conflicts_with_all = ["record_session", "replay_session"])]
+    pub explain_order: Option<String>,
 }
 
 /// Status of a persistent order as it moves through the lifecycle in the database.
@@ -192,7 +252,7 @@ fn format_order_id(
 /// Order request from the network.
 ///
 /// This will turn into an [`Order`] once it is locked or skipped.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 struct OrderRequest {
     request: ProofRequest,
     client_sig: Bytes,
@@ -204,6 +264,18 @@ struct OrderRequest {
     total_cycles: Option<u64>,
     target_timestamp: Option<u64>,
     expire_timestamp: Option<u64>,
+    /// Operator-defined labels, such as a customer name, set by the pricing stage for routing and
+    /// reporting.
+    #[serde(default)]
+    labels: Vec<String>,
+    /// Priority tier set by the pricing stage, such as for orders of strategic value. Orders of a
+    /// higher tier are committed to first, ahead of orders without a tier.
+    #[serde(default)]
+    priority_tier: Option<u8>,
+    /// Set for orders locked by us that are close to expiring, so that they are proven ahead of
+    /// the other orders.
+    #[serde(default)]
+    urgent: bool,
 }
 
 impl OrderRequest {
@@ -225,6 +297,9 @@ impl OrderRequest {
             total_cycles: None,
             target_timestamp: None,
             expire_timestamp: None,
+            labels: Vec::new(),
+            priority_tier: None,
+            urgent: false,
         }
     }
 
@@ -256,6 +331,8 @@ impl OrderRequest {
             compressed_proof_id: None,
             lock_price: None,
             error_msg: None,
+            labels: self.labels.clone(),
+            urgent: self.urgent,
         }
     }
 
@@ -354,9 +431,35 @@ struct Order {
     lock_price: Option<U256>,
     /// Failure message
     error_msg: Option<String>,
+    /// Operator-defined labels of the order request
+    #[serde(default)]
+    labels: Vec<String>,
+    /// Whether the order was locked by us close to expiring, in which case it is picked for
+    /// proving ahead of the other orders
+    #[serde(default)]
+    urgent: bool,
 }
 
 impl Order {
+    /// Returns the order request the order was created from.
+    fn to_request(&self) -> OrderRequest {
+        OrderRequest {
+            request: self.request.clone(),
+            client_sig: self.client_sig.clone(),
+            fulfillment_type: self.fulfillment_type,
+            boundless_market_address: self.boundless_market_address,
+            chain_id: self.chain_id,
+            image_id: self.image_id.clone(),
+            input_id: self.input_id.clone(),
+            total_cycles: self.total_cycles,
+            target_timestamp: self.target_timestamp,
+            expire_timestamp: self.expire_timestamp,
+            labels: self.labels.clone(),
+            priority_tier: None,
+            urgent: self.urgent,
+        }
+    }
+
     // An Order is identified by the request_id, the fulfillment type, and the hash of the proof request.
     // This structure supports multiple different ProofRequests with the same request_id, and different
     // fulfillment types.
@@ -444,7 +547,7 @@ where
             ConfigWatcher::new(&args.config_file).await.context("Failed to load broker config")?;
 
         let db: DbObj =
-            Arc::new(SqliteDb::new(&args.db_url).await.context("Failed to connect to sqlite DB")?);
+            db::connect(&args.db_url).await.context("Failed to connect to broker DB")?;
 
         let chain_id = provider.get_chain_id().await.context("Failed to get chain ID")?;
 
@@ -624,6 +727,102 @@ where
         Ok(())
     }
 
+    /// Builds an order monitor that is not run as part of the service, for one-off operator
+    /// commands. Orders are not received from the picker.
+    async fn standalone_order_monitor(
+        &self,
+        chain_monitor: Arc<chain_monitor::ChainMonitorService<P>>,
+        block_time: u64,
+    ) -> Result<order_monitor::OrderMonitor<P>> {
+        let chain_id = self.provider.get_chain_id().await.context("Failed to get chain ID")?;
+        let stake_token_decimals = BoundlessMarketService::new(
+            self.deployment().boundless_market_address,
+            self.provider.clone(),
+            Address::ZERO,
+        )
+        .stake_token_decimals()
+        .await
+        .context("Failed to get stake token decimals. Possible RPC error.")?;
+        let (_pricing_tx, pricing_rx) = mpsc::channel(PRICING_CHANNEL_CAPACITY);
+
+        Ok(order_monitor::OrderMonitor::new(
+            self.db.clone(),
+            self.provider.clone(),
+            chain_monitor,
+            self.config_watcher.config.clone(),
+            block_time,
+            self.args.private_key.address(),
+            self.deployment().boundless_market_address,
+            pricing_rx,
+            stake_token_decimals,
+            order_monitor::RpcRetryConfig {
+                retry_count: self.args.rpc_retry_max.into(),
+                retry_sleep_ms: self.args.rpc_retry_backoff,
+            },
+        )?
+        .with_chain_id(chain_id))
+    }
+
+    /// Replays a session recorded with `--record-session` against the broker's config and
+    /// database, logging the iterations in which the admitted orders diverge from the recording.
+    pub async fn replay_session(&self, path: &Path) -> Result<()> {
+        let session = replay::RecordedSession::load(path)?;
+        let chain_monitor = Arc::new(
+            chain_monitor::ChainMonitorService::new(self.provider.clone())
+                .await
+                .context("Failed to initialize chain monitor")?,
+        );
+        // Prices are replayed from the recorded orders rather than received from the picker.
+        let order_monitor =
+            self.standalone_order_monitor(chain_monitor, session.block_time()).await?;
+
+        let replayed = order_monitor.replay(&session).await.context("Failed to replay session")?;
+        let mut diverged = 0;
+        for (recorded, replayed) in session.ticks.iter().zip(&replayed.ticks) {
+            if recorded.admitted != replayed.admitted {
+                diverged += 1;
+                tracing::warn!(
+                    "Block {}: recorded admitting {:?}, replayed admitting {:?}",
+                    recorded.block_number,
+                    recorded.admitted,
+                    replayed.admitted
+                );
+            }
+        }
+        tracing::info!(
+            "Replayed {} iterations from {}, {diverged} diverged from the recording",
+            session.ticks.len(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Logs every admission check of the order with the given ID as of the current block, see
+    /// `--explain-order`.
+    pub async fn explain_order(&self, order_id: &str) -> Result<()> {
+        let order = self
+            .db
+            .get_order(order_id)
+            .await
+            .context("Failed to get order")?
+            .with_context(|| format!("Order {order_id} not found in the database"))?;
+        let chain_monitor = Arc::new(
+            chain_monitor::ChainMonitorService::new(self.provider.clone())
+                .await
+                .context("Failed to initialize chain monitor")?,
+        );
+        // The chain monitor is run only for as long as the checks query the chain head.
+        let cancel_token = CancellationToken::new();
+        tokio::spawn(chain_monitor.spawn(cancel_token.clone()));
+        // The block time only paces the iterations of the monitor, which are not run.
+        let order_monitor = self.standalone_order_monitor(chain_monitor, 1).await?;
+
+        let explanation = order_monitor.explain_order(&order.to_request()).await;
+        cancel_token.cancel();
+        tracing::info!("{explanation}");
+        Ok(())
+    }
+
     pub async fn start_service(&self) -> Result<()> {
         let mut supervisor_tasks: JoinSet<Result<()>> = JoinSet::new();
 
@@ -810,7 +1009,12 @@ where
 
         let prover_addr = self.args.private_key.address();
 
-        let order_monitor = Arc::new(order_monitor::OrderMonitor::new(
+        let monitor_role = if self.args.standby {
+            order_monitor::MonitorRole::Standby
+        } else {
+            order_monitor::MonitorRole::Active
+        };
+        let mut order_monitor = order_monitor::OrderMonitor::new(
             self.db.clone(),
             self.provider.clone(),
             chain_monitor.clone(),
@@ -824,7 +1028,98 @@ where
                 retry_count: self.args.rpc_retry_max.into(),
                 retry_sleep_ms: self.args.rpc_retry_backoff,
             },
-        )?);
+        )?
+        .with_role(monitor_role)
+        .with_chain_id(chain_id)
+        .with_reingest_sender(new_order_tx.clone());
+        if let Some(path) = &self.args.record_session {
+            let session_recorder = replay::SessionRecorder::to_file(path)?;
+            order_monitor = order_monitor.with_session_recorder(Arc::new(session_recorder));
+        }
+        let (
+            alert_webhook_url,
+            gas_price_rpc_url,
+            contest_estimator,
+            probe_available_memory,
+            worker_pool_url,
+            settlement_backlog_url,
+        ) = {
+            let config = config.lock_all().context("Failed to lock config")?;
+            (
+                config.market.alert_webhook_url.clone(),
+                config.market.gas_price_rpc_url.clone(),
+                config.market.contest_estimator,
+                config.market.probe_available_memory,
+                config.market.worker_pool_url.clone(),
+                config.market.settlement_backlog_url.clone(),
+            )
+        };
+        if let Some(url) = gas_price_rpc_url {
+            let url = Url::parse(&url).context("Failed to parse gas price RPC URL")?;
+            let gas_price_provider = ProviderBuilder::new().connect_http(url);
+            order_monitor = order_monitor.with_gas_price_source(Arc::new(
+                gas_price::RpcGasPriceSource::new(gas_price_provider),
+            ));
+        }
+        if let Some(url) = alert_webhook_url {
+            let url = Url::parse(&url).context("Failed to parse alert webhook URL")?;
+            order_monitor =
+                order_monitor.with_alert_sink(Arc::new(alerts::WebhookAlertSink::new(url)));
+        }
+        if contest_estimator == config::ContestEstimatorKind::Ramp {
+            order_monitor = order_monitor.with_contest_estimator(Arc::new(
+                contest::RampContestEstimator::new(Arc::new(clock::SystemClock)),
+            ));
+        }
+        if probe_available_memory {
+            order_monitor = order_monitor
+                .with_resource_probe(Arc::new(resource_probe::MemInfoResourceProbe::default()));
+        }
+        if let Some(url) = worker_pool_url {
+            let url = Url::parse(&url).context("Failed to parse worker pool URL")?;
+            order_monitor =
+                order_monitor.with_worker_pool(Arc::new(worker_pool::HttpWorkerPool::new(url)));
+        }
+        if let Some(url) = settlement_backlog_url {
+            let url = Url::parse(&url).context("Failed to parse settlement backlog URL")?;
+            order_monitor = order_monitor.with_settlement_backlog(Arc::new(
+                settlement_backlog::HttpSettlementBacklog::new(url),
+            ));
+        }
+        if self.args.status_file.is_some() {
+            order_monitor = order_monitor.with_tick_history(STATUS_TICK_HISTORY);
+        }
+        if self.args.log_tick_traces {
+            let (tick_trace_tx, mut tick_trace_rx) = mpsc::channel(TICK_TRACE_CHANNEL_CAPACITY);
+            order_monitor = order_monitor.with_tick_trace_sender(tick_trace_tx);
+            tokio::spawn(async move {
+                while let Some(trace) = tick_trace_rx.recv().await {
+                    tracing::info!("Order monitor tick: {trace:?}");
+                }
+            });
+        }
+        let order_monitor = Arc::new(order_monitor);
+        let gas_spends = order_monitor.gas_spends();
+        let signaled_monitor = order_monitor.clone();
+        if let Some(path) = self.args.status_file.clone() {
+            let status_monitor = order_monitor.clone();
+            let interval = std::time::Duration::from_secs(self.args.status_interval_secs.max(1));
+            let cancel_token = non_critical_cancel_token.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        _ = cancel_token.cancelled() => break,
+                        _ = tokio::time::sleep(interval) => {}
+                    }
+                    if let Err(err) = status_monitor.write_status(&path).await {
+                        tracing::warn!(
+                            "Failed to write order monitor status to {}: {err:?}",
+                            path.display()
+                        );
+                    }
+                }
+            });
+        }
         let cloned_config = config.clone();
         let cancel_token = non_critical_cancel_token.clone();
         supervisor_tasks.spawn(async move {
@@ -878,15 +1173,18 @@ where
             Ok(())
         });
 
-        let submitter = Arc::new(submitter::Submitter::new(
-            self.db.clone(),
-            config.clone(),
-            prover.clone(),
-            self.provider.clone(),
-            self.deployment().set_verifier_address,
-            self.deployment().boundless_market_address,
-            set_builder_img_id,
-        )?);
+        let submitter = Arc::new(
+            submitter::Submitter::new(
+                self.db.clone(),
+                config.clone(),
+                prover.clone(),
+                self.provider.clone(),
+                self.deployment().set_verifier_address,
+                self.deployment().boundless_market_address,
+                set_builder_img_id,
+            )?
+            .with_gas_spends(gas_spends),
+        );
         let cloned_config = config.clone();
         let cancel_token = critical_cancel_token.clone();
         supervisor_tasks.spawn(async move {
@@ -903,6 +1201,12 @@ where
             .expect("Failed to install SIGTERM handler");
         let mut sigint = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())
             .expect("Failed to install SIGINT handler");
+        let mut sigusr1 =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1())
+                .expect("Failed to install SIGUSR1 handler");
+        let mut sigusr2 =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined2())
+                .expect("Failed to install SIGUSR2 handler");
         loop {
             tracing::info!("Waiting for supervisor tasks to complete...");
             tokio::select! {
@@ -942,6 +1246,16 @@ where
                     tracing::info!("Received SIGINT, starting graceful shutdown...");
                     break;
                 }
+                // Promote a standby order monitor to active
+                _ = sigusr1.recv() => {
+                    tracing::info!("Received SIGUSR1, promoting order monitor to active");
+                    signaled_monitor.promote();
+                }
+                // Re-evaluate cached orders without waiting for the next block
+                _ = sigusr2.recv() => {
+                    tracing::info!("Received SIGUSR2, refreshing order monitor");
+                    signaled_monitor.refresh_now();
+                }
             }
         }
 
@@ -1094,6 +1408,13 @@ pub mod test_utils {
                 rpc_retry_backoff: 200,
                 rpc_retry_cu: 1000,
                 log_json: false,
+                standby: false,
+                log_tick_traces: false,
+                record_session: None,
+                replay_session: None,
+                status_file: None,
+                status_interval_secs: 30,
+                explain_order: None,
             };
             Self { args, provider: ctx.prover_provider.clone(), config_file }
         }
@@ -0,0 +1,203 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use alloy::{
+    eips::BlockId,
+    primitives::{Address, Bytes, TxHash, U256},
+    providers::Provider,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use boundless_market::contracts::{
+    boundless_market::{BoundlessMarketService, MarketError},
+    ProofRequest, RequestStatus,
+};
+
+/// Subset of the Boundless market contract interactions used by the broker when committing to
+/// orders.
+///
+/// Abstracted to allow testing the order monitor against a mock market without deploying
+/// contracts.
+#[async_trait]
+pub(crate) trait MarketClient: Send + Sync {
+    /// Returns the status of the request, as with [BoundlessMarketService::get_status].
+    async fn get_status(
+        &self,
+        request_id: U256,
+        expires_at: Option<u64>,
+    ) -> Result<RequestStatus, MarketError>;
+
+    /// Returns the status of the request as of the given block rather than the latest block.
+    async fn get_status_at(
+        &self,
+        request_id: U256,
+        expires_at: Option<u64>,
+        block_number: u64,
+    ) -> Result<RequestStatus, MarketError>;
+
+    /// Locks the request, returning the block number the lock was included in and the hash of the
+    /// lock transaction.
+    async fn lock_request(
+        &self,
+        request: &ProofRequest,
+        client_sig: Bytes,
+        priority_gas: Option<u64>,
+    ) -> Result<(u64, TxHash), MarketError>;
+
+    /// Returns the prover recorded as the locker of the request by the lock event in the given
+    /// block, if the request was locked in that block.
+    async fn get_locker(
+        &self,
+        request_id: U256,
+        block_number: u64,
+    ) -> Result<Option<Address>, MarketError>;
+
+    /// Submits the request on chain unless it is already locked or fulfilled, returning whether it
+    /// was submitted.
+    ///
+    /// Submissions are only recorded on chain as events, so the request status is read instead of
+    /// searching the logs. A request that was submitted but not locked yet is submitted again.
+    async fn submit_request_if_absent(
+        &self,
+        request: &ProofRequest,
+        client_sig: Bytes,
+    ) -> Result<bool, MarketError>;
+
+    /// Deposits the given amount of native token into the market.
+    async fn deposit(&self, value: U256) -> Result<(), MarketError>;
+
+    /// Returns the stake balance deposited into the market by the given account.
+    async fn balance_of_stake(&self, account: Address) -> Result<U256, MarketError>;
+
+    /// Approves the market to spend the given amount of stake tokens, then deposits them.
+    async fn deposit_stake(&self, value: U256) -> Result<(), MarketError>;
+
+    /// Returns the number of decimals of the stake token.
+    async fn stake_token_decimals(&self) -> Result<u8, MarketError>;
+}
+
+pub(crate) type MarketClientObj = Arc<dyn MarketClient>;
+
+#[async_trait]
+impl<P> MarketClient for BoundlessMarketService<P>
+where
+    P: Provider + 'static,
+{
+    async fn get_status(
+        &self,
+        request_id: U256,
+        expires_at: Option<u64>,
+    ) -> Result<RequestStatus, MarketError> {
+        BoundlessMarketService::get_status(self, request_id, expires_at).await
+    }
+
+    async fn get_status_at(
+        &self,
+        request_id: U256,
+        expires_at: Option<u64>,
+        block_number: u64,
+    ) -> Result<RequestStatus, MarketError> {
+        // Mirrors BoundlessMarketService::get_status, with every call pinned to the block.
+        let instance = self.instance();
+        let block_id = BlockId::number(block_number);
+        let timestamp = instance
+            .provider()
+            .get_block_by_number(block_number.into())
+            .await
+            .context("Failed to get block")?
+            .with_context(|| format!("Block {block_number} not found"))?
+            .header
+            .timestamp;
+
+        if instance.requestIsFulfilled(request_id).block(block_id).call().await? {
+            return Ok(RequestStatus::Fulfilled);
+        }
+        if expires_at.is_some_and(|expires_at| timestamp > expires_at) {
+            return Ok(RequestStatus::Expired);
+        }
+        if instance.requestIsLocked(request_id).block(block_id).call().await? {
+            let deadline = instance.requestDeadline(request_id).block(block_id).call().await?;
+            if timestamp > deadline && deadline > 0 {
+                return Ok(RequestStatus::Expired);
+            }
+            return Ok(RequestStatus::Locked);
+        }
+        Ok(RequestStatus::Unknown)
+    }
+
+    async fn lock_request(
+        &self,
+        request: &ProofRequest,
+        client_sig: Bytes,
+        priority_gas: Option<u64>,
+    ) -> Result<(u64, TxHash), MarketError> {
+        let receipt =
+            BoundlessMarketService::lock_request_receipt(self, request, client_sig, priority_gas)
+                .await?;
+        let block_number = receipt.block_number.context("TXN Receipt missing block number")?;
+        Ok((block_number, receipt.transaction_hash))
+    }
+
+    async fn get_locker(
+        &self,
+        request_id: U256,
+        block_number: u64,
+    ) -> Result<Option<Address>, MarketError> {
+        let locks = self
+            .instance()
+            .RequestLocked_filter()
+            .from_block(block_number)
+            .to_block(block_number)
+            .query()
+            .await
+            .context("Failed to query RequestLocked events")?;
+        Ok(locks
+            .into_iter()
+            .find(|(event, _)| U256::from(event.requestId) == request_id)
+            .map(|(event, _)| event.prover))
+    }
+
+    async fn submit_request_if_absent(
+        &self,
+        request: &ProofRequest,
+        client_sig: Bytes,
+    ) -> Result<bool, MarketError> {
+        if BoundlessMarketService::is_locked(self, request.id).await?
+            || BoundlessMarketService::is_fulfilled(self, request.id).await?
+        {
+            return Ok(false);
+        }
+        BoundlessMarketService::submit_request_with_signature(self, request, client_sig).await?;
+        Ok(true)
+    }
+
+    async fn deposit(&self, value: U256) -> Result<(), MarketError> {
+        BoundlessMarketService::deposit(self, value).await
+    }
+
+    async fn balance_of_stake(&self, account: Address) -> Result<U256, MarketError> {
+        BoundlessMarketService::balance_of_stake(self, account).await
+    }
+
+    async fn deposit_stake(&self, value: U256) -> Result<(), MarketError> {
+        self.approve_deposit_stake(value).await?;
+        BoundlessMarketService::deposit_stake(self, value).await
+    }
+
+    async fn stake_token_decimals(&self) -> Result<u8, MarketError> {
+        BoundlessMarketService::stake_token_decimals(self).await
+    }
+}
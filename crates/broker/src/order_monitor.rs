@@ -15,43 +15,151 @@
 use crate::chain_monitor::ChainHead;
 use crate::OrderRequest;
 use crate::{
+    alerts::{AlertSinkObj, NoopAlertSink},
     chain_monitor::ChainMonitorService,
-    config::{ConfigLock, OrderCommitmentPriority},
-    db::DbObj,
+    clock::{ClockObj, SystemClock},
+    config::{
+        BalanceUnderwaterAlert, CapacityAccounting, CapacityReserve, ChainOverrides, ConfigLock,
+        ExpectedIncomingBalance, LockPriceBasis, OrderCommitmentPriority, PriorityGasBand,
+        ProofTypePolicy, ZeroStakePolicy,
+    },
+    contest::{ContestEstimatorObj, HistoryContestEstimator, CONTEST_HISTORY_WINDOW},
+    db::{
+        AdmissionAuditEntry, AdmissionDecision, DbObj, LockOutcome, LockOutcomeStats, LockPrices,
+    },
     errors::CodedError,
-    impl_coded_debug, now_timestamp,
+    gas_price::{GasPriceSource, GasPriceSourceObj},
+    impl_coded_debug,
+    market_client::MarketClientObj,
+    prioritization::{
+        break_priority_ties, deprioritize_zero_stake, roi_adjusted_score, sort_orders_by_roi,
+        sort_orders_by_tier, worst_case_reward_wei,
+    },
+    rate_limit::RateLimiter,
+    replay::{RecordedSession, RecordedTick, ReplayConditions, SessionRecorder},
+    resource_probe::{scaled_concurrent_proofs, ConstantResourceProbe, ResourceProbeObj},
+    settlement_backlog::{DbSettlementBacklog, SettlementBacklogObj},
     task::{RetryRes, RetryTask, SupervisorErr},
-    utils, FulfillmentType, Order,
+    utils,
+    worker_pool::WorkerPoolObj,
+    FulfillmentType, Order, OrderStatus,
 };
 use alloy::{
     network::Ethereum,
     primitives::{
-        utils::{format_ether, parse_units},
-        Address, U256,
+        utils::{format_ether, format_units, parse_units},
+        Address, TxHash, I256, U256,
     },
     providers::{Provider, WalletProvider},
+    rpc::types::TransactionReceipt,
 };
 use anyhow::{Context, Result};
 use boundless_market::contracts::{
     boundless_market::{BoundlessMarketService, MarketError},
     IBoundlessMarket::IBoundlessMarketErrors,
-    RequestStatus, TxnErr,
+    RequestError, RequestStatus, TxnErr,
 };
 use boundless_market::selector::SupportedSelectors;
-use moka::{future::Cache, Expiry};
-use std::sync::Arc;
+use clap::ValueEnum;
+use futures::{StreamExt, TryStreamExt};
+use moka::{future::Cache, notification::RemovalCause, Expiry};
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
 use std::time::{Duration, Instant};
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    path::Path,
+};
 use thiserror::Error;
-use tokio::sync::{mpsc, Mutex};
+use tokio::{
+    sync::{mpsc, Mutex, Notify, Semaphore},
+    task::JoinHandle,
+};
 use tokio_util::sync::CancellationToken;
 
 /// Hard limit on the number of orders to concurrently kick off proving work for.
 const MAX_PROVING_BATCH_SIZE: u32 = 10;
 
+/// Environment variable lowering [MAX_PROVING_BATCH_SIZE], read at startup to throttle the broker
+/// during incidents regardless of the config.
+const ENV_VAR_MAX_PROVING_BATCH: &str = "BROKER_MAX_PROVING_BATCH";
+
+/// Returns the proving batch size, lowered by the value of [ENV_VAR_MAX_PROVING_BATCH] if set.
+fn max_proving_batch_size(env_value: Option<&str>) -> Result<u32> {
+    let Some(value) = env_value else {
+        return Ok(MAX_PROVING_BATCH_SIZE);
+    };
+    let max = value.parse::<u32>().with_context(|| {
+        format!("Invalid {ENV_VAR_MAX_PROVING_BATCH} environment variable: {value:?}")
+    })?;
+    tracing::warn!(
+        "Proving batch size limited to {max} by {ENV_VAR_MAX_PROVING_BATCH} environment variable"
+    );
+    Ok(max.min(MAX_PROVING_BATCH_SIZE))
+}
+
+/// Number of recent realized-vs-estimated gas ratios kept to suggest an estimate multiplier.
+const GAS_RATIO_WINDOW: usize = 32;
+
+/// Window of recent lock race outcomes the priority gas adapts to.
+const LOCK_OUTCOME_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Window of recently confirmed transactions' gas costs used to estimate the spend rate.
+const GAS_SPEND_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Interval at which the config is checked for changes that require re-evaluating orders.
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time for which an order's gas estimate is reused, bounding how long config changes to the gas
+/// estimates take to apply.
+const GAS_ESTIMATE_TTL: Duration = Duration::from_secs(60);
+
+/// Longest the admission pass is skipped for while the orders are unchanged, so that conditions
+/// that change over time, such as prices ramping up, balance and gas prices, are re-evaluated.
+const MAX_UNCHANGED_ADMISSION_SKIP: Duration = Duration::from_secs(60);
+
+/// Time after which an order's lock retry attempt count is forgotten if it is not retried.
+const LOCK_RETRY_ATTEMPTS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Underlying cause of a failed lock transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockFailureCause {
+    /// The lock transaction reverted, most likely as another prover locked the request first.
+    RevertedByCompetitor,
+    /// The lock transaction was rejected for paying too little gas.
+    Underpriced,
+    /// The lock transaction was rejected for using an invalid nonce.
+    NonceError,
+    /// Any other cause.
+    Other,
+}
+
+impl LockFailureCause {
+    /// Classifies the cause of a failed lock transaction from its error message.
+    fn from_error_msg(msg: &str) -> Self {
+        let msg = msg.to_lowercase();
+        if msg.contains("nonce") {
+            LockFailureCause::NonceError
+        } else if msg.contains("underpriced")
+            || msg.contains("fee too low")
+            || msg.contains("less than block base fee")
+        {
+            LockFailureCause::Underpriced
+        } else {
+            LockFailureCause::Other
+        }
+    }
+}
+
 #[derive(Error)]
 pub enum OrderMonitorErr {
-    #[error("{code} Failed to lock order: {0}", code = self.code())]
-    LockTxFailed(String),
+    #[error("{code} Failed to lock order: {detail}", code = self.code())]
+    LockTxFailed { cause: LockFailureCause, detail: String },
 
     #[error("{code} Failed to confirm lock tx: {0}", code = self.code())]
     LockTxNotConfirmed(String),
@@ -65,6 +173,24 @@ pub enum OrderMonitorErr {
     #[error("{code} RPC error: {0:?}", code = self.code())]
     RpcErr(anyhow::Error),
 
+    #[error("{code} Order has no target timestamp set", code = self.code())]
+    MissingTargetTimestamp,
+
+    #[error("{code} Requestor has insufficient balance at lock time: {0}", code = self.code())]
+    RequestorInsufficientBalance(String),
+
+    #[error("{code} Failed to submit request before locking: {0}", code = self.code())]
+    SubmitRequestFailed(String),
+
+    #[error("{code} Lock tx confirmed but request locked by {0}", code = self.code())]
+    LockNotOwned(Address),
+
+    #[error("{code} Request expired before locking", code = self.code())]
+    RequestExpired,
+
+    #[error("{code} Request already fulfilled", code = self.code())]
+    RequestFulfilled,
+
     #[error("{code} Unexpected error: {0:?}", code = self.code())]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -75,10 +201,16 @@ impl CodedError for OrderMonitorErr {
     fn code(&self) -> &str {
         match self {
             OrderMonitorErr::LockTxNotConfirmed(_) => "[B-OM-006]",
-            OrderMonitorErr::LockTxFailed(_) => "[B-OM-007]",
+            OrderMonitorErr::LockTxFailed { .. } => "[B-OM-007]",
             OrderMonitorErr::AlreadyLocked => "[B-OM-009]",
             OrderMonitorErr::InsufficientBalance => "[B-OM-010]",
             OrderMonitorErr::RpcErr(_) => "[B-OM-011]",
+            OrderMonitorErr::MissingTargetTimestamp => "[B-OM-012]",
+            OrderMonitorErr::RequestorInsufficientBalance(_) => "[B-OM-013]",
+            OrderMonitorErr::SubmitRequestFailed(_) => "[B-OM-014]",
+            OrderMonitorErr::LockNotOwned(_) => "[B-OM-015]",
+            OrderMonitorErr::RequestExpired => "[B-OM-016]",
+            OrderMonitorErr::RequestFulfilled => "[B-OM-017]",
             OrderMonitorErr::UnexpectedError(_) => "[B-OM-500]",
         }
     }
@@ -92,34 +224,387 @@ enum Capacity {
     /// There are orders that have been picked for proving but not fulfilled yet.
     /// Number indicates available slots.
     Available(u32),
+    /// Capacity is limited by a cycle budget rather than a number of concurrent proofs.
+    /// Number indicates the cycles that can still be committed to.
+    Cycles(u64),
     /// There is no concurrent lock limit.
     Unlimited,
 }
 
 impl Capacity {
     /// Returns the number of proofs we can kick off in the current iteration. Capped at
-    /// [MAX_PROVING_BATCH_SIZE] to limit number of proving tasks spawned at once.
-    fn request_capacity(&self, request: u32) -> u32 {
+    /// `max_batch_size` to limit number of proving tasks spawned at once.
+    fn request_capacity(&self, request: u32, max_batch_size: u32) -> u32 {
         match self {
             Capacity::Available(capacity) => {
                 if request > *capacity {
-                    std::cmp::min(*capacity, MAX_PROVING_BATCH_SIZE)
+                    std::cmp::min(*capacity, max_batch_size)
                 } else {
-                    std::cmp::min(request, MAX_PROVING_BATCH_SIZE)
+                    std::cmp::min(request, max_batch_size)
                 }
             }
-            Capacity::Unlimited => std::cmp::min(MAX_PROVING_BATCH_SIZE, request),
+            Capacity::Cycles(_) | Capacity::Unlimited => std::cmp::min(max_batch_size, request),
+        }
+    }
+}
+
+/// Cache an order evaluated by the monitor was pulled from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OrderSource {
+    /// Orders to lock before proving them.
+    LockAndProve,
+    /// Orders to prove without locking them, e.g. as their lock by another prover expired.
+    Prove,
+}
+
+/// Formats an amount in wei for logging, along with its value in ether.
+fn format_wei(wei: U256) -> String {
+    format!("{wei} wei ({} ether)", format_ether(wei))
+}
+
+/// Returns the key of an order's gas estimate.
+///
+/// Orders for the same image share their estimate, as it only depends on the properties of the
+/// request included in the key. Orders whose image is not known yet are keyed by their id.
+fn gas_estimate_key(order: &OrderRequest) -> String {
+    let Some(image_id) = &order.image_id else {
+        return order.id();
+    };
+    let callback_gas_limit =
+        order.request.requirements.callback.as_option().map(|callback| callback.gasLimit);
+    format!(
+        "{image_id}-{}-{:?}-{}-{callback_gas_limit:?}-{}",
+        order.request.requirements.selector,
+        order.fulfillment_type,
+        order.chain_id,
+        order.request.is_smart_contract_signed()
+    )
+}
+
+/// Returns whether enough time remains before the order expires to prove it.
+fn is_within_deadline(
+    order: &OrderRequest,
+    current_block_timestamp: u64,
+    now: u64,
+    config: &OrderMonitorConfig,
+) -> bool {
+    let min_deadline = config.required_deadline_secs(order);
+    // The lock deadline for orders we lock, as our stake is at risk if they are not
+    // fulfilled by then, and the request's own deadline for orders we fulfill without
+    // locking them.
+    let expiration = order.expiry();
+    if expiration < current_block_timestamp {
+        tracing::debug!("Request {:x} has now expired. Skipping.", order.request.id);
+        false
+    } else if expiration.saturating_sub(now) < min_deadline {
+        tracing::debug!("Request {:x} deadline at {} is less than the minimum deadline {} seconds required to prove an order. Skipping.", order.request.id, expiration, min_deadline);
+        false
+    } else {
+        true
+    }
+}
+
+/// Returns whether the order's target timestamp has been reached.
+fn is_target_time_reached(
+    order: &OrderRequest,
+    current_block_timestamp: u64,
+) -> Result<bool, OrderMonitorErr> {
+    // Note: this could use current timestamp, but avoiding cases where clock has drifted.
+    // A missing target timestamp is not strictly enforced at compile time, so it is
+    // surfaced as an error for the caller to skip the order rather than waiting forever.
+    let target_timestamp = order.target_timestamp.ok_or(OrderMonitorErr::MissingTargetTimestamp)?;
+    if current_block_timestamp < target_timestamp {
+        tracing::trace!(
+            "Request {:x} target timestamp {} not yet reached (current: {}). Waiting.",
+            order.request.id,
+            target_timestamp,
+            current_block_timestamp
+        );
+        Ok(false)
+    } else {
+        Ok(true)
+    }
+}
+
+/// Returns whether the order's target timestamp is further than `max_target_horizon_secs` ahead
+/// of the current block, in which case it is held back in the cache.
+fn is_beyond_target_horizon(
+    order: &OrderRequest,
+    current_block_timestamp: u64,
+    config: &OrderMonitorConfig,
+) -> bool {
+    let (Some(horizon), Some(target_timestamp)) =
+        (config.max_target_horizon_secs, order.target_timestamp)
+    else {
+        return false;
+    };
+    if target_timestamp.saturating_sub(current_block_timestamp) > horizon {
+        tracing::trace!(
+            "Request {:x} target timestamp {} is beyond the horizon of {} seconds (current: {}). Holding.",
+            order.request.id,
+            target_timestamp,
+            horizon,
+            current_block_timestamp
+        );
+        true
+    } else {
+        false
+    }
+}
+
+/// Verifies that the order's request id belongs to the client that signed the request. Smart
+/// contract signatures can only be checked on chain, which is left to the market monitor.
+fn verify_request_id(order: &OrderRequest) -> Result<(), RequestError> {
+    if order.request.is_smart_contract_signed() {
+        return Ok(());
+    }
+    order.request.verify_signature(
+        &order.client_sig,
+        order.boundless_market_address,
+        order.chain_id,
+    )
+}
+
+/// Returns the lock stake posted by the committed orders.
+fn committed_lock_stake(committed_orders: &[Order]) -> U256 {
+    committed_orders
+        .iter()
+        .filter(|order| order.fulfillment_type == FulfillmentType::LockAndFulfill)
+        .fold(U256::ZERO, |sum, order| sum.saturating_add(order.request.offer.lockStake))
+}
+
+/// Returns the risk of locking an order, combining the stake at risk, the work to prove it and
+/// the time left to do so.
+///
+/// Computed as the lock stake in stake tokens, times the cycles to prove in millions, divided by
+/// the seconds until the lock expires. An order whose lock has expired has infinite risk.
+fn lock_risk_score(stake_tokens: f64, cycles: u64, lock_secs_remaining: u64) -> f64 {
+    if lock_secs_remaining == 0 {
+        return f64::INFINITY;
+    }
+    stake_tokens * (cycles as f64 / 1_000_000.0) / lock_secs_remaining as f64
+}
+
+/// Converts a number of orders to a `u32`, saturating rather than panicking on absurd counts.
+fn saturating_count(count: usize) -> u32 {
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
+
+/// Formats an amount of stake in base units along with its amount in stake tokens.
+fn format_stake(amount: U256, stake_token_decimals: u8) -> String {
+    match format_units(amount, stake_token_decimals) {
+        Ok(tokens) => format!("{amount} ({tokens} stake token)"),
+        Err(_) => amount.to_string(),
+    }
+}
+
+/// Deposits stake to restore the stake balance of the prover to the target of the top-up if it
+/// fell below the threshold, recording the time of the deposit in `last_stake_topup`.
+async fn top_up_stake(
+    market: &MarketClientObj,
+    prover_addr: Address,
+    topup: StakeTopUpConfig,
+    stake_token_decimals: u8,
+    last_stake_topup: &mut Option<Instant>,
+) -> Result<()> {
+    let balance =
+        market.balance_of_stake(prover_addr).await.context("Failed to get stake balance")?;
+    let amount = topup.target.saturating_sub(balance);
+    if balance >= topup.threshold || amount.is_zero() {
+        return Ok(());
+    }
+    tracing::info!(
+        "Stake balance of {} is below the warning threshold, depositing {} to restore it to {}",
+        format_stake(balance, stake_token_decimals),
+        format_stake(amount, stake_token_decimals),
+        format_stake(topup.target, stake_token_decimals)
+    );
+    // Start the cooldown before depositing, so that a failing deposit is not retried every block.
+    *last_stake_topup = Some(Instant::now());
+    market.deposit_stake(amount).await.context("Failed to deposit stake")?;
+    Ok(())
+}
+
+/// Returns the cycles to budget for an order with the given cycle count, falling back to
+/// `fallback_cycles` if the cycle count is unknown.
+fn budgeted_cycles(total_cycles: Option<u64>, fallback_cycles: Option<u64>) -> u64 {
+    total_cycles.or(fallback_cycles).unwrap_or_default()
+}
+
+/// Returns the number of cycles of the committed orders that are estimated to remain unproven.
+///
+/// Without a peak khz limit, committed orders are assumed to be unproven. Otherwise, committed
+/// orders are assumed to be proven one after another at the peak khz since the earliest of them
+/// started proving, so an order that is partially proven only counts its remaining cycles.
+fn remaining_committed_cycles(
+    committed_orders: &[Order],
+    peak_prove_khz: Option<u64>,
+    additional_proof_cycles: u64,
+    fallback_cycles: Option<u64>,
+    now: u64,
+) -> u64 {
+    let total_cycles = committed_orders
+        .iter()
+        .map(|order| budgeted_cycles(order.total_cycles, fallback_cycles) + additional_proof_cycles)
+        .sum::<u64>();
+    let Some(peak_prove_khz) = peak_prove_khz else {
+        return total_cycles;
+    };
+
+    let started_proving_at =
+        committed_orders.iter().filter_map(|order| order.proving_started_at).min().unwrap_or(now);
+    let proven_cycles =
+        now.saturating_sub(started_proving_at).saturating_mul(peak_prove_khz.saturating_mul(1_000));
+    total_cycles.saturating_sub(proven_cycles)
+}
+
+/// Selects the jobs, given as `(duration, deadline)` pairs, that maximize the number completed by
+/// their deadline when run one after another from `start`, using the Moore-Hodgson algorithm.
+///
+/// Returns the indices of the selected jobs, earliest deadline first.
+fn max_jobs_before_deadlines(jobs: &[(u64, u64)], start: u64) -> Vec<usize> {
+    let mut by_deadline: Vec<usize> = (0..jobs.len()).collect();
+    by_deadline.sort_by_key(|&idx| jobs[idx].1);
+
+    let mut selected: Vec<usize> = Vec::with_capacity(jobs.len());
+    let mut completion = start;
+    for idx in by_deadline {
+        let (duration, deadline) = jobs[idx];
+        selected.push(idx);
+        completion += duration;
+        if completion > deadline {
+            // Drop the longest selected job, which frees the most time for the others.
+            let (longest, _) = selected
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, &idx)| jobs[idx].0)
+                .expect("a job was just selected");
+            completion -= jobs[selected.remove(longest)].0;
+        }
+    }
+    selected
+}
+
+/// Returns the gas price from the given query result, caching it on success. On failure, falls
+/// back to the last known gas price increased by `margin_percent`, if there is one.
+fn gas_price_or_fallback(
+    gas_price: Result<u128>,
+    last_gas_price: &mut Option<u128>,
+    margin_percent: u64,
+) -> Result<u128> {
+    match gas_price {
+        Ok(gas_price) => {
+            *last_gas_price = Some(gas_price);
+            Ok(gas_price)
+        }
+        Err(err) => {
+            let Some(last) = *last_gas_price else {
+                return Err(err.context("Failed to get gas price"));
+            };
+            let fallback = last.saturating_add(last.saturating_mul(margin_percent as u128) / 100);
+            tracing::warn!(
+                "Failed to get gas price, using last known gas price {last} with a {margin_percent}% safety margin: {fallback} - {err:?}"
+            );
+            Ok(fallback)
+        }
+    }
+}
+
+/// Runs `estimate` for each order with at most `concurrency` estimations in flight, returning the
+/// estimated costs paired with their orders in the original order.
+async fn estimate_order_costs<F, Fut>(
+    orders: Vec<Arc<OrderRequest>>,
+    concurrency: usize,
+    estimate: F,
+) -> Result<Vec<(U256, Arc<OrderRequest>)>, OrderMonitorErr>
+where
+    F: Fn(Arc<OrderRequest>) -> Fut,
+    Fut: Future<Output = Result<U256, OrderMonitorErr>>,
+{
+    let mut costs: Vec<(usize, U256, Arc<OrderRequest>)> =
+        futures::stream::iter(orders.into_iter().enumerate())
+            .map(|(idx, order)| {
+                let cost = estimate(order.clone());
+                async move { cost.await.map(|cost| (idx, cost, order)) }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .try_collect()
+            .await?;
+    costs.sort_by_key(|(idx, _, _)| *idx);
+    Ok(costs.into_iter().map(|(_, cost, order)| (cost, order)).collect())
+}
+
+/// Counters of the changes made to an order cache.
+#[derive(Debug, Default)]
+struct CacheStats {
+    inserts: AtomicU64,
+    invalidations: AtomicU64,
+    expiries: AtomicU64,
+}
+
+impl CacheStats {
+    fn record_removal(&self, order_id: &str, cause: RemovalCause) {
+        match cause {
+            RemovalCause::Explicit => {
+                self.invalidations.fetch_add(1, Ordering::Relaxed);
+            }
+            RemovalCause::Expired => {
+                self.expiries.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!(order_cache_expired = 1, "Order {order_id} expired from the cache");
+            }
+            RemovalCause::Replaced | RemovalCause::Size => {}
         }
     }
+
+    fn snapshot<V: Send + Sync + Clone + 'static>(
+        &self,
+        cache: &Cache<String, V>,
+    ) -> CacheStatsSnapshot {
+        CacheStatsSnapshot {
+            inserts: self.inserts.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            expiries: self.expiries.load(Ordering::Relaxed),
+            size: cache.entry_count(),
+        }
+    }
+}
+
+/// Totals of the changes made to an order cache since the monitor started.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStatsSnapshot {
+    pub inserts: u64,
+    pub invalidations: u64,
+    /// Orders removed as they expired before being locked or proven.
+    pub expiries: u64,
+    /// Approximate number of orders in the cache.
+    pub size: u64,
+}
+
+/// Snapshot of the [CacheStats] of both order caches of the monitor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OrderCacheStats {
+    pub lock_and_prove: CacheStatsSnapshot,
+    pub prove: CacheStatsSnapshot,
 }
 
-struct OrderExpiry;
+/// Builds an order cache evicting orders at their expiry according to `clock`, recording removals
+/// in `stats`.
+fn order_cache(stats: Arc<CacheStats>, clock: ClockObj) -> Cache<String, Arc<OrderRequest>> {
+    Cache::builder()
+        .expire_after(OrderExpiry(clock))
+        .eviction_listener(move |order_id: Arc<String>, _, cause| {
+            stats.record_removal(&order_id, cause)
+        })
+        .build()
+}
+
+struct OrderExpiry(ClockObj);
 
 impl<K: std::hash::Hash + Eq, V: std::borrow::Borrow<OrderRequest>> Expiry<K, V> for OrderExpiry {
     fn expire_after_create(&self, _key: &K, value: &V, _now: Instant) -> Option<Duration> {
         let order: &OrderRequest = value.borrow();
         order.expire_timestamp.map(|t| {
-            let time_until_expiry = t.saturating_sub(now_timestamp());
+            let time_until_expiry = t.saturating_sub(self.0.now());
             Duration::from_secs(time_until_expiry)
         })
     }
@@ -130,10 +615,583 @@ struct OrderMonitorConfig {
     min_deadline: u64,
     peak_prove_khz: Option<u64>,
     max_concurrent_proofs: Option<u32>,
+    max_committed_cycles: Option<u64>,
     additional_proof_cycles: u64,
     batch_buffer_time_secs: u64,
     order_commitment_priority: OrderCommitmentPriority,
     priority_addresses: Option<Vec<Address>>,
+    gas_estimation_concurrency: usize,
+    loop_deadline: Option<Duration>,
+    min_profit_wei: Option<i64>,
+    tie_breaker_seed: u64,
+    salvage_capacity_reserve: Option<CapacityReserve>,
+    fallback_cycles: Option<u64>,
+    chains: HashMap<String, ChainOverrides>,
+    proof_type_policies: HashMap<String, ProofTypePolicy>,
+    max_target_horizon_secs: Option<u64>,
+    optimize_khz_allocation: bool,
+    max_single_order_lock_stake: Option<U256>,
+    min_deadline_fraction: Option<f64>,
+    expected_incoming_balance: Option<ExpectedIncomingBalance>,
+    urgent_deadline_secs: Option<u64>,
+    max_total_stake_committed: Option<U256>,
+    lock_record_min_age_secs: Option<u64>,
+    lock_price_window_secs: u64,
+    reingest_lock_expired_orders: bool,
+    gas_estimate_prewarm_secs: Option<u64>,
+    deprioritize_unresolved_orders: bool,
+    use_pending_balance: bool,
+    max_chain_head_age_secs: Option<u64>,
+    proving_cost_per_cycle_wei: Option<u64>,
+    retain_transient_skips: bool,
+    min_concurrent_proofs: Option<u32>,
+    max_lock_risk: Option<f64>,
+    skip_unchanged_admission: bool,
+    reject_unprofitable_at_ingestion: bool,
+    stake_topup: Option<StakeTopUpConfig>,
+    max_target_timestamp_horizon_secs: Option<u64>,
+    verbose_loop_logs: bool,
+    balance_reserve_per_committed_order: Option<u32>,
+    capacity_accounting: CapacityAccounting,
+    zero_stake_policy: Option<ZeroStakePolicy>,
+    admission_audit: bool,
+    admission_audit_retention_secs: u64,
+    fulfillment_safety_margin_secs: u64,
+    allow_mismatched_chain_orders: bool,
+    prove_only: bool,
+    max_settlement_backlog: Option<u32>,
+    stake_balance_warn_threshold: Option<U256>,
+    stake_balance_error_threshold: Option<U256>,
+}
+
+/// Parsed [crate::config::StakeTopUp] config.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct StakeTopUpConfig {
+    /// Stake balance below which stake is deposited, from `stake_balance_warn_threshold`.
+    threshold: U256,
+    target: U256,
+    cooldown: Duration,
+}
+
+/// Contradictory settings of the config, see [OrderMonitorConfig::validate].
+#[derive(Clone, Debug, Error, PartialEq)]
+enum ConfigIssue {
+    #[error("market.max_concurrent_proofs is 0, no order can ever be committed to")]
+    NoConcurrentProofs,
+
+    #[error("market.min_concurrent_proofs ({min}) is above market.max_concurrent_proofs ({max})")]
+    MinConcurrentProofsAboveMax { min: u32, max: u32 },
+
+    #[error("market.peak_prove_khz is 0, no order can be proven in time")]
+    NoProvingThroughput,
+
+    #[error(
+        "market.min_deadline ({min_deadline}s) is below batcher.block_deadline_buffer_secs ({buffer}s), leaving no time to prove admitted orders"
+    )]
+    MinDeadlineBelowBatchBuffer { min_deadline: u64, buffer: u64 },
+
+    #[error("market.min_deadline_fraction ({0}) is not between 0 and 1")]
+    MinDeadlineFractionOutOfRange(f64),
+
+    #[error(
+        "market.stake_balance_error_threshold ({error}) is above market.stake_balance_warn_threshold ({warn})"
+    )]
+    StakeErrorThresholdAboveWarn { error: U256, warn: U256 },
+
+    #[error(
+        "market.auto_stake_topup.target ({target}) is not above market.stake_balance_warn_threshold ({threshold})"
+    )]
+    StakeTopUpTargetNotAboveThreshold { target: U256, threshold: U256 },
+
+    #[error(
+        "market.auto_stake_topup is set in market.prove_only mode, which never locks with stake"
+    )]
+    StakeTopUpInProveOnly,
+}
+
+impl OrderMonitorConfig {
+    /// Checks the config for contradictory settings, which would otherwise silently misbehave,
+    /// returning all the issues found.
+    fn validate(&self) -> Result<(), Vec<ConfigIssue>> {
+        let mut issues = Vec::new();
+        match (self.min_concurrent_proofs, self.max_concurrent_proofs) {
+            (_, Some(0)) => issues.push(ConfigIssue::NoConcurrentProofs),
+            (Some(min), Some(max)) if min > max => {
+                issues.push(ConfigIssue::MinConcurrentProofsAboveMax { min, max })
+            }
+            _ => {}
+        }
+        if self.peak_prove_khz == Some(0) {
+            issues.push(ConfigIssue::NoProvingThroughput);
+        }
+        if self.min_deadline < self.batch_buffer_time_secs {
+            issues.push(ConfigIssue::MinDeadlineBelowBatchBuffer {
+                min_deadline: self.min_deadline,
+                buffer: self.batch_buffer_time_secs,
+            });
+        }
+        if let Some(fraction) = self.min_deadline_fraction {
+            if !(0.0..=1.0).contains(&fraction) {
+                issues.push(ConfigIssue::MinDeadlineFractionOutOfRange(fraction));
+            }
+        }
+        if let (Some(error), Some(warn)) =
+            (self.stake_balance_error_threshold, self.stake_balance_warn_threshold)
+        {
+            if error > warn {
+                issues.push(ConfigIssue::StakeErrorThresholdAboveWarn { error, warn });
+            }
+        }
+        if self.prove_only && self.stake_topup.is_some() {
+            issues.push(ConfigIssue::StakeTopUpInProveOnly);
+        }
+        if let Some(topup) = self.stake_topup {
+            if topup.target <= topup.threshold {
+                issues.push(ConfigIssue::StakeTopUpTargetNotAboveThreshold {
+                    target: topup.target,
+                    threshold: topup.threshold,
+                });
+            }
+        }
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    fn chain_overrides(&self, chain_id: u64) -> Option<&ChainOverrides> {
+        self.chains.get(&chain_id.to_string())
+    }
+
+    /// Returns the minimum deadline for orders on the given chain.
+    fn min_deadline_for(&self, chain_id: u64) -> u64 {
+        self.chain_overrides(chain_id)
+            .and_then(|overrides| overrides.min_deadline)
+            .unwrap_or(self.min_deadline)
+    }
+
+    /// Returns the seconds that must remain before the order expires to prove it, the larger of
+    /// the minimum deadline and the fraction of the order's lifetime if configured.
+    fn required_deadline_secs(&self, order: &OrderRequest) -> u64 {
+        let min_deadline = self.min_deadline_for(order.chain_id);
+        let Some(fraction) = self.min_deadline_fraction else {
+            return min_deadline;
+        };
+        let lifetime = order.expiry().saturating_sub(order.request.offer.biddingStart);
+        min_deadline.max((lifetime as f64 * fraction).ceil() as u64)
+    }
+
+    /// Returns the minimum profit after gas and proving costs for orders on the given chain. If
+    /// only a proving cost is configured, orders must at least break even.
+    fn min_profit_wei_for(&self, chain_id: u64) -> Option<I256> {
+        let min_profit_wei = self
+            .chain_overrides(chain_id)
+            .and_then(|overrides| overrides.min_profit_wei)
+            .or(self.min_profit_wei)
+            .or(self.proving_cost_per_cycle_wei.map(|_| 0))?;
+        // Every i64 fits in an I256, so it is converted through its magnitude, which cannot fail.
+        let magnitude = I256::from_raw(U256::from(min_profit_wei.unsigned_abs()));
+        Some(if min_profit_wei < 0 { -magnitude } else { magnitude })
+    }
+
+    /// Returns the estimated compute cost of proving the order, zero if no proving cost is
+    /// configured.
+    fn proving_cost_wei(&self, order: &OrderRequest) -> U256 {
+        self.proving_cost_per_cycle_wei.map_or(U256::ZERO, |cost_per_cycle| {
+            let cycles = budgeted_cycles(order.total_cycles, self.fallback_cycles)
+                + self.additional_proof_cycles;
+            U256::from(cycles) * U256::from(cost_per_cycle)
+        })
+    }
+
+    /// Returns whether the order requires a lock, which is never taken in prove-only mode.
+    fn denied_in_prove_only(&self, order: &OrderRequest) -> bool {
+        self.prove_only && order.fulfillment_type == FulfillmentType::LockAndFulfill
+    }
+
+    /// Returns whether the order's lock stake exceeds the maximum for a single order.
+    fn exceeds_max_lock_stake(&self, order: &OrderRequest) -> bool {
+        self.max_single_order_lock_stake.is_some_and(|max| order.request.offer.lockStake > max)
+    }
+
+    /// Returns whether the order is skipped for not requiring any lock stake.
+    fn skips_zero_stake(&self, order: &OrderRequest) -> bool {
+        self.zero_stake_policy == Some(ZeroStakePolicy::Skip)
+            && order.request.offer.lockStake.is_zero()
+    }
+
+    /// Returns whether the order's target timestamp is further than the configured horizon from
+    /// `now`.
+    fn beyond_target_horizon(&self, order: &OrderRequest, now: u64) -> bool {
+        match (self.max_target_timestamp_horizon_secs, order.target_timestamp) {
+            (Some(horizon), Some(target_timestamp)) => {
+                target_timestamp > now.saturating_add(horizon)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the profit of the order after its gas and proving costs, at the lowest price it
+    /// may be locked at. None if the order's reward is not paid in wei.
+    fn expected_profit_wei(&self, order: &OrderRequest, gas_cost_wei: U256) -> Option<I256> {
+        let reward_wei = worst_case_reward_wei(order, self.lock_price_window_secs)?;
+        Some(
+            I256::from_raw(reward_wei)
+                - I256::from_raw(gas_cost_wei)
+                - I256::from_raw(self.proving_cost_wei(order)),
+        )
+    }
+
+    /// Returns the profit of the order after its gas and proving costs at its max price, the
+    /// most it can be paid however far its price ramps up.
+    fn max_profit_wei(&self, order: &OrderRequest, gas_cost_wei: U256) -> I256 {
+        I256::from_raw(order.request.offer.maxPrice)
+            - I256::from_raw(gas_cost_wei)
+            - I256::from_raw(self.proving_cost_wei(order))
+    }
+
+    /// Returns the names of the fields that differ between this config and `other`.
+    fn changed_fields(&self, other: &Self) -> Vec<&'static str> {
+        macro_rules! changed_fields {
+            ($current:ident, $other:ident; $($field:ident),* $(,)?) => {{
+                // Destructure to ensure every field is compared.
+                let Self { $($field),* } = $current;
+                let mut changed = Vec::new();
+                $(
+                    if *$field != $other.$field {
+                        changed.push(stringify!($field));
+                    }
+                )*
+                changed
+            }};
+        }
+
+        changed_fields!(
+            self, other;
+            min_deadline,
+            peak_prove_khz,
+            max_concurrent_proofs,
+            max_committed_cycles,
+            additional_proof_cycles,
+            batch_buffer_time_secs,
+            order_commitment_priority,
+            priority_addresses,
+            gas_estimation_concurrency,
+            loop_deadline,
+            min_profit_wei,
+            tie_breaker_seed,
+            salvage_capacity_reserve,
+            fallback_cycles,
+            chains,
+            proof_type_policies,
+            max_target_horizon_secs,
+            optimize_khz_allocation,
+            max_single_order_lock_stake,
+            min_deadline_fraction,
+            expected_incoming_balance,
+            urgent_deadline_secs,
+            max_total_stake_committed,
+            lock_record_min_age_secs,
+            lock_price_window_secs,
+            reingest_lock_expired_orders,
+            gas_estimate_prewarm_secs,
+            deprioritize_unresolved_orders,
+            use_pending_balance,
+            max_chain_head_age_secs,
+            proving_cost_per_cycle_wei,
+            retain_transient_skips,
+            min_concurrent_proofs,
+            max_lock_risk,
+            skip_unchanged_admission,
+            reject_unprofitable_at_ingestion,
+            stake_topup,
+            max_target_timestamp_horizon_secs,
+            verbose_loop_logs,
+            balance_reserve_per_committed_order,
+            capacity_accounting,
+            zero_stake_policy,
+            admission_audit,
+            admission_audit_retention_secs,
+            fulfillment_safety_margin_secs,
+            allow_mismatched_chain_orders,
+            prove_only,
+            max_settlement_backlog,
+            stake_balance_warn_threshold,
+            stake_balance_error_threshold,
+        )
+    }
+}
+
+/// Structured record of the decisions made during a single iteration of the order monitor.
+///
+/// Only collected when a receiver is registered with [OrderMonitor::with_tick_trace_sender] or the
+/// history is enabled with [OrderMonitor::with_tick_history], as it is intended for debugging
+/// rather than regular operation. Orders are identified by their ids.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TickTrace {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    /// Orders in the caches at the start of the iteration.
+    pub pulled: Vec<String>,
+    /// Orders skipped during the iteration, with the reason they were skipped.
+    pub filtered: Vec<(String, String)>,
+    /// Orders locked by us that are close to expiring, moved ahead of the other valid orders.
+    pub urgent: Vec<String>,
+    /// Valid orders in the order they were prioritized.
+    pub prioritized: Vec<String>,
+    /// Proving capacity computed for the iteration.
+    pub capacity: Option<String>,
+    /// Orders admitted after applying capacity limits.
+    pub admitted: Vec<String>,
+    /// Orders successfully locked.
+    pub locked: Vec<String>,
+    /// Orders that failed to be locked or committed to, with the error.
+    pub failures: Vec<(String, String)>,
+}
+
+/// Condensed [TickTrace] kept in the history returned by [OrderMonitor::recent_ticks].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TickSummary {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    /// Number of orders in the caches at the start of the iteration.
+    pub considered: usize,
+    /// Orders admitted after applying capacity limits.
+    pub admitted: Vec<String>,
+    /// Orders skipped during the iteration, with the reason they were skipped.
+    pub skipped: Vec<(String, String)>,
+}
+
+impl From<&TickTrace> for TickSummary {
+    fn from(trace: &TickTrace) -> Self {
+        Self {
+            block_number: trace.block_number,
+            block_timestamp: trace.block_timestamp,
+            considered: trace.pulled.len(),
+            admitted: trace.admitted.clone(),
+            skipped: trace.filtered.clone(),
+        }
+    }
+}
+
+/// Ring buffer of the summaries of recent iterations of the monitor.
+#[derive(Debug, Default)]
+struct TickHistory {
+    /// Maximum number of summaries kept, zero if the history is disabled.
+    capacity: usize,
+    /// Summaries of recent iterations, oldest first.
+    ticks: VecDeque<TickSummary>,
+}
+
+impl TickHistory {
+    fn record(&mut self, summary: TickSummary) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.ticks.len() == self.capacity {
+            self.ticks.pop_front();
+        }
+        self.ticks.push_back(summary);
+    }
+}
+
+/// Outcome of a single admission check, as reported in an [OrderExplanation].
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    /// Values the outcome of the check was computed from.
+    pub detail: String,
+}
+
+impl OrderCheck {
+    fn new(name: &'static str, passed: bool, detail: impl Into<String>) -> Self {
+        Self { name, passed, detail: detail.into() }
+    }
+}
+
+/// Admission checks of an order, as returned by [OrderMonitor::explain_order].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrderExplanation {
+    pub order_id: String,
+    pub block_timestamp: u64,
+    /// Checks in the order they were run. If a check could not be run, a failed `error` check
+    /// is reported last.
+    pub checks: Vec<OrderCheck>,
+}
+
+impl OrderExplanation {
+    /// Returns whether the order passed every check.
+    pub fn admissible(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+}
+
+impl std::fmt::Display for OrderExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verdict = if self.admissible() { "admissible" } else { "not admissible" };
+        write!(
+            f,
+            "Order {} is {verdict} at block timestamp {}",
+            self.order_id, self.block_timestamp
+        )?;
+        for check in &self.checks {
+            let outcome = if check.passed { "pass" } else { "FAIL" };
+            write!(f, "\n  [{outcome}] {}: {}", check.name, check.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// A committed order, as returned by [OrderMonitor::committed_orders_snapshot].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CommittedOrderSnapshot {
+    pub order_id: String,
+    pub fulfillment_type: FulfillmentType,
+    pub status: OrderStatus,
+    pub total_cycles: Option<u64>,
+    pub proving_started_at: Option<u64>,
+    /// UNIX timestamp at which the order is estimated to be proven, if `peak_prove_khz` is set.
+    pub estimated_completion: Option<u64>,
+    /// Stake at risk until the order is fulfilled, zero for orders we did not lock.
+    pub stake: U256,
+    /// Gas units estimated to be required to fulfill the order.
+    pub gas_units: u64,
+    /// Operator-defined labels of the order.
+    pub labels: Vec<String>,
+}
+
+/// State of the monitor for operators, as written by [OrderMonitor::write_status].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct MonitorStatus {
+    /// UNIX timestamp the status was taken at.
+    pub timestamp: u64,
+    /// Summaries of the recent iterations of the monitor, oldest first.
+    pub recent_ticks: Vec<TickSummary>,
+    /// Whether the chain head was considered stalled as of the last iteration.
+    pub chain_stalled: bool,
+    /// Total gas paid for lock transactions that reverted.
+    pub wasted_gas_wei: U256,
+    /// Seconds the balance lasts at the recent rate of gas spend, None if nothing was spent.
+    pub runway_secs: Option<u64>,
+    /// Orders currently committed to.
+    pub committed_orders: Vec<CommittedOrderSnapshot>,
+}
+
+/// Returns the timestamps at which the committed orders are estimated to be proven, assuming
+/// they are proven one after another at the peak khz since the earliest of them started proving.
+fn estimated_completions(
+    committed_orders: &[Order],
+    peak_prove_khz: u64,
+    additional_proof_cycles: u64,
+    fallback_cycles: Option<u64>,
+    now: u64,
+) -> Vec<u64> {
+    let started_proving_at =
+        committed_orders.iter().filter_map(|order| order.proving_started_at).min().unwrap_or(now);
+    let mut cycles = 0u64;
+    committed_orders
+        .iter()
+        .map(|order| {
+            cycles = cycles.saturating_add(
+                budgeted_cycles(order.total_cycles, fallback_cycles) + additional_proof_cycles,
+            );
+            started_proving_at + cycles.div_ceil(1_000).div_ceil(peak_prove_khz.max(1))
+        })
+        .collect()
+}
+
+/// Role of an order monitor sharing its database with other instances.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MonitorRole {
+    /// Locks and commits to orders.
+    #[default]
+    Active,
+    /// Observes orders without locking or committing to them, or writing to the database, until
+    /// promoted to active. Used for hot standby instances.
+    Standby,
+}
+
+/// Recent ratios of realized to estimated gas, used to calibrate the `*_gas_estimate` config.
+#[derive(Debug, Default)]
+pub(crate) struct GasRatios {
+    ratios: VecDeque<f64>,
+}
+
+impl GasRatios {
+    /// Records the gas used by a transaction against its estimate, returning the ratio.
+    pub(crate) fn record(&mut self, estimated: u64, actual: u64) -> Option<f64> {
+        if estimated == 0 {
+            return None;
+        }
+        let ratio = actual as f64 / estimated as f64;
+        if self.ratios.len() == GAS_RATIO_WINDOW {
+            self.ratios.pop_front();
+        }
+        self.ratios.push_back(ratio);
+        Some(ratio)
+    }
+
+    /// Suggested multiplier for the configured estimate, the highest of the recent ratios so the
+    /// estimate covers every recently observed transaction.
+    pub(crate) fn suggested_multiplier(&self) -> Option<f64> {
+        self.ratios.iter().copied().reduce(f64::max)
+    }
+}
+
+/// Outcome of a failed attempt to lock an order, lost if another prover locked it first.
+fn lock_outcome(err: &OrderMonitorErr) -> LockOutcome {
+    match err {
+        OrderMonitorErr::AlreadyLocked
+        | OrderMonitorErr::LockNotOwned(_)
+        | OrderMonitorErr::LockTxFailed { cause: LockFailureCause::RevertedByCompetitor, .. } => {
+            LockOutcome::Lost
+        }
+        _ => LockOutcome::Error,
+    }
+}
+
+/// Priority gas interpolated within the band by the fraction of recent lock races lost, see
+/// [crate::config::MarketConf::adaptive_lockin_priority_gas]
+pub(crate) fn adapted_priority_gas(band: PriorityGasBand, loss_rate: f64) -> u64 {
+    let (min, max) = (band.min.min(band.max), band.min.max(band.max));
+    min + ((max - min) as f64 * loss_rate.clamp(0.0, 1.0)).round() as u64
+}
+
+/// Gas paid by lock and fulfillment transactions confirmed within the last [GAS_SPEND_WINDOW],
+/// used to estimate the runway of the balance.
+#[derive(Debug, Default)]
+pub(crate) struct GasSpends {
+    spends: VecDeque<(u64, U256)>,
+}
+
+impl GasSpends {
+    /// Records the gas paid by a transaction confirmed at `timestamp`.
+    pub(crate) fn record(&mut self, timestamp: u64, cost_wei: U256) {
+        self.prune(timestamp);
+        self.spends.push_back((timestamp, cost_wei));
+    }
+
+    /// Returns how long `balance_wei` lasts at the spend rate over the window ending at `now`, or
+    /// [Duration::MAX] if nothing was spent.
+    pub(crate) fn runway(&mut self, balance_wei: U256, now: u64) -> Duration {
+        self.prune(now);
+        let spent_wei =
+            self.spends.iter().fold(U256::ZERO, |total, (_, cost)| total.saturating_add(*cost));
+        if spent_wei.is_zero() {
+            return Duration::MAX;
+        }
+        let secs = balance_wei.saturating_mul(U256::from(GAS_SPEND_WINDOW.as_secs())) / spent_wei;
+        Duration::from_secs(secs.try_into().unwrap_or(u64::MAX))
+    }
+
+    fn prune(&mut self, now: u64) {
+        let since = now.saturating_sub(GAS_SPEND_WINDOW.as_secs());
+        while self.spends.front().is_some_and(|(timestamp, _)| *timestamp < since) {
+            self.spends.pop_front();
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -148,19 +1206,89 @@ pub struct OrderMonitor<P> {
     chain_monitor: Arc<ChainMonitorService<P>>,
     block_time: u64,
     config: ConfigLock,
-    market: BoundlessMarketService<Arc<P>>,
+    market: MarketClientObj,
     provider: Arc<P>,
     prover_addr: Address,
+    /// Address of the market the orders are locked and fulfilled on.
+    market_addr: Address,
+    /// Chain id of the provider, orders for another chain or market are rejected when received
+    /// if set, see [OrderMonitor::with_chain_id].
+    chain_id: Option<u64>,
     priced_order_rx: Arc<Mutex<mpsc::Receiver<Box<OrderRequest>>>>,
     lock_and_prove_cache: Arc<Cache<String, Arc<OrderRequest>>>,
     prove_cache: Arc<Cache<String, Arc<OrderRequest>>>,
+    lock_and_prove_cache_stats: Arc<CacheStats>,
+    prove_cache_stats: Arc<CacheStats>,
+    /// Number of times locking an order has been deferred, keyed by order id.
+    lock_retry_attempts: Arc<Cache<String, u32>>,
+    /// Whether locking of new orders was paused as of the last check.
+    locking_paused: Arc<AtomicBool>,
+    /// Whether the chain head was older than `max_chain_head_age_secs` as of the last check.
+    chain_stalled: Arc<AtomicBool>,
+    /// Fingerprint of the orders the last admission pass ran on, and the block timestamp it ran
+    /// at, used to skip the pass while they are unchanged if `skip_unchanged_admission` is set.
+    admission_fingerprint: Arc<std::sync::Mutex<Option<(u64, u64)>>>,
+    /// When stake was last deposited by `auto_stake_topup`, locked for the duration of a top-up.
+    last_stake_topup: Arc<Mutex<Option<Instant>>>,
+    /// Last message of each per-iteration log, keyed by log, used to only log them on change.
+    loop_logs: Arc<std::sync::Mutex<HashMap<&'static str, String>>>,
+    /// Whether the balance did not cover committed orders as of the last check.
+    balance_underwater: Arc<AtomicBool>,
+    /// Last successfully fetched gas price, used as a fallback if fetching fails.
+    last_gas_price: Arc<std::sync::Mutex<Option<u128>>>,
+    /// Trace of the current iteration, only collected if a trace receiver is registered.
+    tick_trace: Arc<std::sync::Mutex<Option<TickTrace>>>,
+    tick_trace_tx: Option<mpsc::Sender<TickTrace>>,
+    /// Summaries of recent iterations, only kept if enabled with [OrderMonitor::with_tick_history].
+    tick_history: Arc<std::sync::Mutex<TickHistory>>,
+    /// Channel to the order picker, used to re-ingest orders whose lock expired before we locked
+    /// them if `reingest_lock_expired_orders` is set.
+    reingest_tx: Option<mpsc::Sender<Box<OrderRequest>>>,
+    /// Whether the monitor is a standby, shared between clones so it can be promoted at runtime.
+    standby: Arc<AtomicBool>,
+    /// Realized-vs-estimated gas ratios of recent lock transactions.
+    lock_gas_ratios: Arc<std::sync::Mutex<GasRatios>>,
+    /// Gas units estimated to lock and/or fulfill orders, see [gas_estimate_key].
+    gas_estimates: Arc<Cache<String, u64>>,
+    /// Gas paid by recently confirmed lock and fulfillment transactions, shared with the submitter.
+    gas_spends: Arc<std::sync::Mutex<GasSpends>>,
+    /// Total gas paid for lock transactions that reverted.
+    wasted_gas_wei: Arc<std::sync::Mutex<U256>>,
+    /// Estimates the probability of winning lock races, used if `min_lock_win_probability` is set.
+    contest_estimator: ContestEstimatorObj,
+    /// Pool of proving workers whose idle count is used as the proving capacity, if set.
+    worker_pool: Option<WorkerPoolObj>,
+    /// Notified of events that need an operator's immediate attention.
+    alert_sink: AlertSinkObj,
+    /// Limits the rate of RPC calls if `max_rpc_calls_per_sec` is set.
+    rpc_rate_limiter: Arc<RateLimiter>,
+    /// Source of the gas price used for admission and lock transactions, the node's gas price
+    /// if unset.
+    gas_price_source: Option<GasPriceSourceObj>,
+    /// Reports the prover's available resources, used to scale down `max_concurrent_proofs`.
+    resource_probe: ResourceProbeObj,
+    /// Reports the backlog of the settlement stage, used if `max_settlement_backlog` is set.
+    settlement_backlog: SettlementBacklogObj,
+    /// Clock the decisions are made against.
+    clock: ClockObj,
+    /// Records the orders and chain conditions of each iteration, if set.
+    session_recorder: Option<Arc<SessionRecorder>>,
+    /// Conditions of the recorded tick being replayed, see [OrderMonitor::replay].
+    replay_conditions: Option<Arc<ReplayConditions>>,
+    /// Notified to run an iteration immediately, see [OrderMonitor::refresh_now].
+    refresh: Arc<Notify>,
+    /// Last config read successfully, applied to received orders while the config is invalid.
+    last_valid_config: Arc<std::sync::Mutex<Option<Arc<OrderMonitorConfig>>>>,
+    /// Maximum number of orders to kick off proving work for in an iteration.
+    max_proving_batch_size: u32,
+    stake_token_decimals: u8,
     supported_selectors: SupportedSelectors,
     rpc_retry_config: RpcRetryConfig,
 }
 
 impl<P> OrderMonitor<P>
 where
-    P: Provider<Ethereum> + WalletProvider,
+    P: Provider<Ethereum> + WalletProvider + 'static,
 {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -190,52 +1318,281 @@ where
         }
         {
             let config = config.lock_all()?;
+            let parse_threshold = |threshold: &Option<String>, field: &str| {
+                threshold
+                    .as_ref()
+                    .map(|s| {
+                        parse_units(s, stake_token_decimals)
+                            .map(Into::into)
+                            .with_context(|| format!("Invalid market.{field} in config: {s:?}"))
+                    })
+                    .transpose()
+            };
 
             market = market.with_stake_balance_alert(
-                &config
-                    .market
-                    .stake_balance_warn_threshold
-                    .as_ref()
-                    .map(|s| parse_units(s, stake_token_decimals).unwrap().into()),
-                &config
-                    .market
-                    .stake_balance_error_threshold
-                    .as_ref()
-                    .map(|s| parse_units(s, stake_token_decimals).unwrap().into()),
+                &parse_threshold(
+                    &config.market.stake_balance_warn_threshold,
+                    "stake_balance_warn_threshold",
+                )?,
+                &parse_threshold(
+                    &config.market.stake_balance_error_threshold,
+                    "stake_balance_error_threshold",
+                )?,
             );
         }
+        let max_proving_batch_size =
+            max_proving_batch_size(std::env::var(ENV_VAR_MAX_PROVING_BATCH).ok().as_deref())?;
+        let lock_and_prove_cache_stats = Arc::new(CacheStats::default());
+        let prove_cache_stats = Arc::new(CacheStats::default());
+        let settlement_backlog = Arc::new(DbSettlementBacklog);
         let monitor = Self {
             db,
             chain_monitor,
             block_time,
             config,
-            market,
+            market: Arc::new(market),
             provider,
             prover_addr,
+            market_addr,
+            chain_id: None,
             priced_order_rx: Arc::new(Mutex::new(priced_orders_rx)),
-            lock_and_prove_cache: Arc::new(Cache::builder().expire_after(OrderExpiry).build()),
-            prove_cache: Arc::new(Cache::builder().expire_after(OrderExpiry).build()),
+            lock_and_prove_cache: Arc::new(order_cache(
+                lock_and_prove_cache_stats.clone(),
+                Arc::new(SystemClock),
+            )),
+            prove_cache: Arc::new(order_cache(prove_cache_stats.clone(), Arc::new(SystemClock))),
+            lock_and_prove_cache_stats,
+            prove_cache_stats,
+            lock_retry_attempts: Arc::new(
+                Cache::builder().time_to_idle(LOCK_RETRY_ATTEMPTS_TTL).build(),
+            ),
+            locking_paused: Arc::new(AtomicBool::new(false)),
+            chain_stalled: Arc::new(AtomicBool::new(false)),
+            admission_fingerprint: Arc::new(std::sync::Mutex::new(None)),
+            last_stake_topup: Arc::new(Mutex::new(None)),
+            loop_logs: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            balance_underwater: Arc::new(AtomicBool::new(false)),
+            last_gas_price: Arc::new(std::sync::Mutex::new(None)),
+            tick_trace: Arc::new(std::sync::Mutex::new(None)),
+            tick_trace_tx: None,
+            tick_history: Arc::new(std::sync::Mutex::new(TickHistory::default())),
+            reingest_tx: None,
+            standby: Arc::new(AtomicBool::new(false)),
+            lock_gas_ratios: Arc::new(std::sync::Mutex::new(GasRatios::default())),
+            gas_estimates: Arc::new(Cache::builder().time_to_live(GAS_ESTIMATE_TTL).build()),
+            gas_spends: Arc::new(std::sync::Mutex::new(GasSpends::default())),
+            wasted_gas_wei: Arc::new(std::sync::Mutex::new(U256::ZERO)),
+            contest_estimator: Arc::new(HistoryContestEstimator),
+            worker_pool: None,
+            alert_sink: Arc::new(NoopAlertSink),
+            rpc_rate_limiter: Arc::new(RateLimiter::default()),
+            gas_price_source: None,
+            resource_probe: Arc::new(ConstantResourceProbe::default()),
+            settlement_backlog,
+            clock: Arc::new(SystemClock),
+            session_recorder: None,
+            replay_conditions: None,
+            refresh: Arc::new(Notify::new()),
+            last_valid_config: Arc::new(std::sync::Mutex::new(None)),
+            max_proving_batch_size,
+            stake_token_decimals,
             supported_selectors: SupportedSelectors::default(),
             rpc_retry_config,
         };
+        if let Err(issues) = monitor.load_monitor_config()?.validate() {
+            let issues = issues.iter().map(ToString::to_string).collect::<Vec<_>>();
+            anyhow::bail!("Invalid config: {}", issues.join("; "));
+        }
         Ok(monitor)
     }
 
-    async fn lock_order(&self, order: &OrderRequest) -> Result<U256, OrderMonitorErr> {
-        let request_id = order.request.id;
+    /// Sets the chain id of the provider, enabling the rejection of orders received for another
+    /// chain or market.
+    pub fn with_chain_id(self, chain_id: u64) -> Self {
+        Self { chain_id: Some(chain_id), ..self }
+    }
 
-        let order_status = self
-            .market
-            .get_status(request_id, Some(order.request.expires_at()))
-            .await
-            .context("Failed to get request status")
-            .map_err(OrderMonitorErr::RpcErr)?;
-        if order_status != RequestStatus::Unknown {
-            tracing::info!("Request {:x} not open: {order_status:?}, skipping", request_id);
-            // TODO: fetch some chain data to find out who / and for how much the order
-            // was locked in at
-            return Err(OrderMonitorErr::AlreadyLocked);
-        }
+    /// Sets the role of the monitor, see [MonitorRole].
+    pub fn with_role(self, role: MonitorRole) -> Self {
+        self.standby.store(role == MonitorRole::Standby, Ordering::Relaxed);
+        self
+    }
+
+    /// Returns the current role of the monitor.
+    pub fn role(&self) -> MonitorRole {
+        if self.standby.load(Ordering::Relaxed) {
+            MonitorRole::Standby
+        } else {
+            MonitorRole::Active
+        }
+    }
+
+    /// Promotes a standby monitor to active, after which it starts locking and committing to
+    /// orders.
+    pub fn promote(&self) {
+        if self.standby.swap(false, Ordering::Relaxed) {
+            tracing::info!("Order monitor promoted from standby to active");
+        }
+    }
+
+    /// Triggers an iteration of the monitor immediately rather than waiting for the next block, to
+    /// re-evaluate cached orders after the chain state is known to have changed.
+    ///
+    /// Requests made while an iteration is running trigger a single iteration once it completes.
+    pub fn refresh_now(&self) {
+        self.reset_admission_fingerprint();
+        self.refresh.notify_one();
+    }
+
+    /// Emits a [TickTrace] for every iteration of the monitor to the given channel.
+    ///
+    /// Traces are dropped if the channel is full, so a slow receiver does not stall the monitor.
+    pub fn with_tick_trace_sender(self, tick_trace_tx: mpsc::Sender<TickTrace>) -> Self {
+        Self { tick_trace_tx: Some(tick_trace_tx), ..self }
+    }
+
+    /// Keeps a summary of the last `len` iterations of the monitor, see [OrderMonitor::recent_ticks].
+    pub fn with_tick_history(self, len: usize) -> Self {
+        let history = TickHistory { capacity: len, ticks: VecDeque::with_capacity(len) };
+        Self { tick_history: Arc::new(std::sync::Mutex::new(history)), ..self }
+    }
+
+    /// Returns the summaries of up to the last `n` iterations of the monitor, oldest first.
+    ///
+    /// Empty unless the history was enabled with [OrderMonitor::with_tick_history].
+    pub fn recent_ticks(&self, n: usize) -> Vec<TickSummary> {
+        self.tick_history
+            .lock()
+            .map(|history| {
+                history.ticks.iter().skip(history.ticks.len().saturating_sub(n)).cloned().collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Sends orders whose lock expired before we locked them to the given channel to be priced
+    /// again as fulfill-after-lock-expire orders, if `reingest_lock_expired_orders` is set.
+    pub fn with_reingest_sender(self, reingest_tx: mpsc::Sender<Box<OrderRequest>>) -> Self {
+        Self { reingest_tx: Some(reingest_tx), ..self }
+    }
+
+    /// Replaces the market client used to interact with the market contract.
+    #[cfg(test)]
+    pub(crate) fn with_market_client(self, market: MarketClientObj) -> Self {
+        Self { market, ..self }
+    }
+
+    /// Replaces the estimator used to decide whether to contest orders.
+    pub(crate) fn with_contest_estimator(self, contest_estimator: ContestEstimatorObj) -> Self {
+        Self { contest_estimator, ..self }
+    }
+
+    /// Uses the given source for the gas price, such as an external oracle, instead of the node's
+    /// gas price, both when admitting orders and when sending lock transactions.
+    pub(crate) fn with_gas_price_source(self, gas_price_source: GasPriceSourceObj) -> Self {
+        Self { gas_price_source: Some(gas_price_source), ..self }
+    }
+
+    /// Makes decisions against the given clock rather than the system clock.
+    ///
+    /// Rebuilds the order caches so that orders expire according to the clock, so it must be set
+    /// before any order is received.
+    pub(crate) fn with_clock(self, clock: ClockObj) -> Self {
+        Self {
+            lock_and_prove_cache: Arc::new(order_cache(
+                self.lock_and_prove_cache_stats.clone(),
+                clock.clone(),
+            )),
+            prove_cache: Arc::new(order_cache(self.prove_cache_stats.clone(), clock.clone())),
+            clock,
+            ..self
+        }
+    }
+
+    /// Records the orders and chain conditions of each iteration into the given recorder, to be
+    /// replayed with [OrderMonitor::replay].
+    pub(crate) fn with_session_recorder(self, session_recorder: Arc<SessionRecorder>) -> Self {
+        Self { session_recorder: Some(session_recorder), ..self }
+    }
+
+    /// Sets the sink notified of events that need an operator's immediate attention, such as a
+    /// locked order whose stake is at risk.
+    pub(crate) fn with_alert_sink(self, alert_sink: AlertSinkObj) -> Self {
+        Self { alert_sink, ..self }
+    }
+
+    /// Replaces the probe reporting the prover's available resources.
+    pub(crate) fn with_resource_probe(self, resource_probe: ResourceProbeObj) -> Self {
+        Self { resource_probe, ..self }
+    }
+
+    /// Replaces the source of the settlement backlog, which defaults to the proven orders of the
+    /// DB that are not yet fulfilled.
+    pub(crate) fn with_settlement_backlog(self, settlement_backlog: SettlementBacklogObj) -> Self {
+        Self { settlement_backlog, ..self }
+    }
+
+    /// Sets the pool of proving workers used to determine the proving capacity.
+    pub(crate) fn with_worker_pool(self, worker_pool: WorkerPoolObj) -> Self {
+        Self { worker_pool: Some(worker_pool), ..self }
+    }
+
+    /// Locks the order, returning the price it was locked at and the hash of the lock transaction.
+    async fn lock_order(&self, order: &OrderRequest) -> Result<(U256, TxHash), OrderMonitorErr> {
+        let request_id = order.request.id;
+
+        if self.replay_conditions.is_some() {
+            // Replayed locks are not sent, they are assumed to succeed at the current price.
+            let lock_price =
+                order.request.offer.price_at(self.now()).unwrap_or(order.request.offer.minPrice);
+            return Ok((lock_price, TxHash::ZERO));
+        }
+
+        let status_confirmations = {
+            let config = self.config.lock_all().context("Failed to read config")?;
+            config.market.lock_status_confirmations
+        };
+        let order_status = match status_confirmations {
+            Some(confirmations) => {
+                // Check the status behind the head, so that locks in blocks that may still be
+                // reorged are not relied on.
+                let head = self
+                    .chain_monitor
+                    .current_chain_head()
+                    .await
+                    .map_err(OrderMonitorErr::RpcErr)?;
+                self.throttle_rpc().await;
+                self.market
+                    .get_status_at(
+                        request_id,
+                        Some(order.request.expires_at()),
+                        head.block_number.saturating_sub(confirmations),
+                    )
+                    .await
+            }
+            None => {
+                self.throttle_rpc().await;
+                self.market.get_status(request_id, Some(order.request.expires_at())).await
+            }
+        }
+        .context("Failed to get request status")
+        .map_err(OrderMonitorErr::RpcErr)?;
+        match order_status {
+            RequestStatus::Unknown => {}
+            RequestStatus::Locked => {
+                tracing::info!("Request {:x} already locked, skipping", request_id);
+                // TODO: fetch some chain data to find out who / and for how much the order
+                // was locked in at
+                return Err(OrderMonitorErr::AlreadyLocked);
+            }
+            RequestStatus::Fulfilled => {
+                tracing::info!("Request {:x} already fulfilled, skipping", request_id);
+                return Err(OrderMonitorErr::RequestFulfilled);
+            }
+            RequestStatus::Expired => {
+                tracing::info!("Request {:x} expired, skipping", request_id);
+                return Err(OrderMonitorErr::RequestExpired);
+            }
+        }
 
         let is_locked = self
             .db
@@ -247,117 +1604,401 @@ where
             return Err(OrderMonitorErr::AlreadyLocked);
         }
 
-        let conf_priority_gas = {
+        let (
+            adaptive_lockin_priority_gas,
+            lockin_priority_gas,
+            urgent_lockin_priority_gas,
+            block_fetch_retry_count,
+            block_fetch_retry_sleep_ms,
+            block_fetch_retry_max_total,
+            block_fetch_receipt_fallback_after,
+            submit_request_before_lock,
+            verify_lock_owner,
+            lock_price_basis,
+        ) = {
             let conf = self.config.lock_all().context("Failed to lock config")?;
-            conf.market.lockin_priority_gas
+            (
+                conf.market.adaptive_lockin_priority_gas,
+                conf.market.for_chain(order.chain_id).lockin_priority_gas,
+                match (conf.market.urgent_deadline_secs, conf.market.urgent_lockin_priority_gas) {
+                    (Some(urgent_secs), Some(urgent_gas)) if self.is_urgent(order, urgent_secs) => {
+                        Some(urgent_gas)
+                    }
+                    _ => None,
+                },
+                conf.market.block_fetch_retry_count.unwrap_or(self.rpc_retry_config.retry_count),
+                conf.market
+                    .block_fetch_retry_sleep_ms
+                    .unwrap_or(self.rpc_retry_config.retry_sleep_ms),
+                conf.market.block_fetch_retry_max_total_ms.map(Duration::from_millis),
+                conf.market.block_fetch_receipt_fallback_after,
+                conf.market.submit_request_before_lock,
+                conf.market.verify_lock_owner,
+                conf.market.lock_price_basis,
+            )
         };
 
+        let priority_gas = match adaptive_lockin_priority_gas {
+            Some(band) => Some(self.adaptive_priority_gas(band).await),
+            None => lockin_priority_gas,
+        };
+        // Raise the priority gas of orders close to their deadline, as there is little time left to
+        // retry.
+        let conf_priority_gas = match urgent_lockin_priority_gas {
+            Some(urgent_gas) => Some(priority_gas.map_or(urgent_gas, |gas| gas.max(urgent_gas))),
+            None => priority_gas,
+        };
+
+        if submit_request_before_lock {
+            let submitted = self
+                .market
+                .submit_request_if_absent(&order.request, order.client_sig.clone())
+                .await
+                .map_err(|err| OrderMonitorErr::SubmitRequestFailed(err.to_string()))?;
+            if submitted {
+                tracing::info!("Submitted request 0x{:x} before locking", request_id);
+            }
+        }
+
+        let conf_priority_gas = self.priority_gas_from_source(conf_priority_gas).await;
+
         tracing::info!(
             "Locking request: 0x{:x} for stake: {}",
             request_id,
-            order.request.offer.lockStake
+            self.format_stake(order.request.offer.lockStake)
         );
-        let lock_block = self
+        let submission_timestamp = self.now();
+        let lock_result = self
             .market
             .lock_request(&order.request, order.client_sig.clone(), conf_priority_gas)
-            .await
-            .map_err(|e| -> OrderMonitorErr {
-                match e {
-                    MarketError::TxnError(txn_err) => match txn_err {
-                        TxnErr::BoundlessMarketErr(IBoundlessMarketErrors::RequestIsLocked(_)) => {
-                            OrderMonitorErr::AlreadyLocked
+            .await;
+        if let Err(MarketError::LockRevert(tx_hash)) = &lock_result {
+            self.record_wasted_lock_gas(*tx_hash).await;
+        }
+        let lock_result = lock_result.map_err(|e| -> OrderMonitorErr {
+            match e {
+                MarketError::TxnError(txn_err) => match txn_err {
+                    TxnErr::BoundlessMarketErr(IBoundlessMarketErrors::RequestIsLocked(_)) => {
+                        OrderMonitorErr::AlreadyLocked
+                    }
+                    _ => {
+                        let detail = txn_err.to_string();
+                        OrderMonitorErr::LockTxFailed {
+                            cause: LockFailureCause::from_error_msg(&detail),
+                            detail,
                         }
-                        _ => OrderMonitorErr::LockTxFailed(txn_err.to_string()),
-                    },
-                    MarketError::RequestAlreadyLocked(_e) => OrderMonitorErr::AlreadyLocked,
-                    MarketError::TxnConfirmationError(e) => {
-                        OrderMonitorErr::LockTxNotConfirmed(e.to_string())
                     }
-                    MarketError::LockRevert(e) => {
-                        // Note: lock revert could be for any number of reasons;
-                        // 1/ someone may have locked in the block before us,
-                        // 2/ the lock may have expired,
-                        // 3/ the request may have been fulfilled,
-                        // 4/ the requestor may have withdrawn their funds
-                        // Currently we don't have a way to determine the cause of the revert.
-                        OrderMonitorErr::LockTxFailed(format!("Tx hash 0x{e:x}"))
+                },
+                MarketError::RequestAlreadyLocked(_e) => OrderMonitorErr::AlreadyLocked,
+                MarketError::TxnConfirmationError(e) => {
+                    OrderMonitorErr::LockTxNotConfirmed(e.to_string())
+                }
+                MarketError::LockRevert(e) => {
+                    // Note: lock revert could be for any number of reasons;
+                    // 1/ someone may have locked in the block before us,
+                    // 2/ the lock may have expired,
+                    // 3/ the request may have been fulfilled,
+                    // 4/ the requestor may have withdrawn their funds
+                    // Currently we don't have a way to determine the cause of the revert, so
+                    // attribute it to the most likely cause.
+                    OrderMonitorErr::LockTxFailed {
+                        cause: LockFailureCause::RevertedByCompetitor,
+                        detail: format!("Tx hash 0x{e:x}"),
                     }
-                    MarketError::Error(e) => {
-                        // Insufficient balance error is thrown both when the requestor has insufficient balance,
-                        // Requestor having insufficient balance can happen and is out of our control. The prover
-                        // having insufficient balance is unexpected as we should have checked for that before
-                        // committing to locking the order.
-                        let prover_addr_str =
-                            self.prover_addr.to_string().to_lowercase().replace("0x", "");
-                        if e.to_string().contains("InsufficientBalance") {
-                            if e.to_string().to_lowercase().contains(&prover_addr_str) {
-                                OrderMonitorErr::InsufficientBalance
-                            } else {
-                                OrderMonitorErr::LockTxFailed(format!(
-                                    "Requestor has insufficient balance at lock time: {e}"
-                                ))
-                            }
-                        } else if e.to_string().contains("RequestIsLocked") {
-                            OrderMonitorErr::AlreadyLocked
+                }
+                MarketError::Error(e) => {
+                    // Insufficient balance error is thrown both when the requestor has insufficient balance,
+                    // Requestor having insufficient balance can happen and is out of our control. The prover
+                    // having insufficient balance is unexpected as we should have checked for that before
+                    // committing to locking the order.
+                    let prover_addr_str =
+                        self.prover_addr.to_string().to_lowercase().replace("0x", "");
+                    if e.to_string().contains("InsufficientBalance") {
+                        if e.to_string().to_lowercase().contains(&prover_addr_str) {
+                            OrderMonitorErr::InsufficientBalance
                         } else {
-                            OrderMonitorErr::UnexpectedError(e)
+                            OrderMonitorErr::RequestorInsufficientBalance(e.to_string())
                         }
+                    } else if e.to_string().contains("RequestIsLocked") {
+                        OrderMonitorErr::AlreadyLocked
+                    } else {
+                        OrderMonitorErr::UnexpectedError(e)
                     }
-                    _ => {
-                        if e.to_string().contains("RequestIsLocked") {
-                            OrderMonitorErr::AlreadyLocked
-                        } else {
-                            OrderMonitorErr::UnexpectedError(e.into())
-                        }
+                }
+                _ => {
+                    if e.to_string().contains("RequestIsLocked") {
+                        OrderMonitorErr::AlreadyLocked
+                    } else {
+                        OrderMonitorErr::UnexpectedError(e.into())
                     }
                 }
-            })?;
+            }
+        });
+        let (lock_block, lock_tx_hash) = lock_result?;
+
+        if verify_lock_owner {
+            self.verify_lock_owner(request_id, lock_block).await?;
+        }
 
         // Fetch the block to retrieve the lock timestamp. This has been observed to return
         // inconsistent state between the receipt being available but the block not yet.
-        let lock_timestamp = crate::futures_retry::retry(
-            self.rpc_retry_config.retry_count,
-            self.rpc_retry_config.retry_sleep_ms,
+        let failed_block_fetches = AtomicU64::new(0);
+        let lock_timestamp = crate::futures_retry::retry_within(
+            block_fetch_retry_count,
+            block_fetch_retry_sleep_ms,
+            block_fetch_retry_max_total,
             || async {
-                Ok(self
-                    .provider
-                    .get_block_by_number(lock_block.into())
-                    .await
-                    .with_context(|| format!("failed to get block {lock_block}"))?
-                    .with_context(|| format!("failed to get block {lock_block}: block not found"))?
-                    .header
-                    .timestamp)
+                let use_receipt = block_fetch_receipt_fallback_after
+                    .is_some_and(|after| failed_block_fetches.load(Ordering::Relaxed) >= after);
+                self.throttle_rpc().await;
+                let result = if use_receipt {
+                    self.block_timestamp_from_receipt(lock_tx_hash).await
+                } else {
+                    self.provider
+                        .get_block_by_number(lock_block.into())
+                        .await
+                        .with_context(|| format!("failed to get block {lock_block}"))
+                        .and_then(|block| {
+                            block.with_context(|| {
+                                format!("failed to get block {lock_block}: block not found")
+                            })
+                        })
+                        .map(|block| block.header.timestamp)
+                };
+                if result.is_err() {
+                    failed_block_fetches.fetch_add(1, Ordering::Relaxed);
+                }
+                result
             },
             "get_block_by_number",
         )
         .await
         .map_err(OrderMonitorErr::UnexpectedError)?;
 
-        let lock_price = order
-            .request
-            .offer
-            .price_at(lock_timestamp)
-            .context("Failed to calculate lock price")?;
+        // Time taken to lock the order once it became eligible, large delays indicate lost races
+        // or insufficient capacity.
+        if let Some(target_timestamp) = order.target_timestamp {
+            let delay_secs = lock_timestamp.saturating_sub(target_timestamp);
+            tracing::info!(
+                target_to_lock_secs = delay_secs,
+                "Locked request 0x{:x} {delay_secs} seconds after its target timestamp",
+                request_id
+            );
+        }
+
+        let offer = &order.request.offer;
+        let lock_prices = LockPrices {
+            submission: offer
+                .price_at(submission_timestamp)
+                .context("Failed to calculate submission price")?,
+            target: order
+                .target_timestamp
+                .map(|target_timestamp| offer.price_at(target_timestamp))
+                .transpose()
+                .context("Failed to calculate target price")?,
+            confirmation: offer
+                .price_at(lock_timestamp)
+                .context("Failed to calculate lock price")?,
+        };
+        if let Err(err) =
+            self.db.insert_lock_prices(U256::from(request_id), &lock_prices, lock_timestamp).await
+        {
+            tracing::warn!("Failed to record lock prices of request 0x{:x}: {err:?}", request_id);
+        }
+        let lock_price = match lock_price_basis {
+            LockPriceBasis::Submission => lock_prices.submission,
+            LockPriceBasis::Target => lock_prices.target.unwrap_or(lock_prices.confirmation),
+            LockPriceBasis::Confirmation => lock_prices.confirmation,
+        };
 
-        Ok(lock_price)
+        Ok((lock_price, lock_tx_hash))
+    }
+
+    /// Returns the timestamp of the block the transaction was included in, looking the block up
+    /// by the hash recorded in the transaction's receipt.
+    async fn block_timestamp_from_receipt(&self, tx_hash: TxHash) -> Result<u64> {
+        let receipt = self
+            .provider
+            .get_transaction_receipt(tx_hash)
+            .await
+            .with_context(|| format!("failed to get receipt of tx 0x{tx_hash:x}"))?
+            .with_context(|| format!("receipt of tx 0x{tx_hash:x} not found"))?;
+        let block_hash = receipt
+            .block_hash
+            .with_context(|| format!("receipt of tx 0x{tx_hash:x} has no block hash"))?;
+        let block = self
+            .provider
+            .get_block_by_hash(block_hash)
+            .await
+            .with_context(|| format!("failed to get block {block_hash}"))?
+            .with_context(|| format!("failed to get block {block_hash}: block not found"))?;
+        Ok(block.header.timestamp)
+    }
+
+    /// Formats an amount of the staking token for logging, in both base units and whole tokens.
+    fn format_stake(&self, amount: U256) -> String {
+        format_stake(amount, self.stake_token_decimals)
+    }
+
+    /// Checks that the lock event of the request in the lock block records us as the locker, as
+    /// a competing lock ordered ahead of ours in the same block may have won even though our
+    /// transaction confirmed.
+    ///
+    /// Fails only if another locker is recorded, as skipping an order we did lock would forfeit
+    /// the stake.
+    async fn verify_lock_owner(
+        &self,
+        request_id: U256,
+        lock_block: u64,
+    ) -> Result<(), OrderMonitorErr> {
+        match self.market.get_locker(request_id, lock_block).await {
+            Ok(Some(locker)) if locker != self.prover_addr => {
+                Err(OrderMonitorErr::LockNotOwned(locker))
+            }
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => {
+                tracing::warn!(
+                    "Lock event of request 0x{request_id:x} not found in block {lock_block}, assuming it is owned"
+                );
+                Ok(())
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to verify the locker of request 0x{request_id:x}, assuming it is owned: {err:?}"
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Records the gas paid by the lock transaction of the order towards the runway estimate, and
+    /// compares the gas used to the estimate, to help operators calibrate `lockin_gas_estimate`.
+    ///
+    /// Called once the lock is recorded, so the receipt lookup does not delay other locks.
+    async fn reconcile_lock_gas(&self, order: &OrderRequest, lock_tx_hash: TxHash) -> Result<()> {
+        if self.replay_conditions.is_some() {
+            // Replayed locks are not sent.
+            return Ok(());
+        }
+        let receipt = self
+            .provider
+            .get_transaction_receipt(lock_tx_hash)
+            .await
+            .with_context(|| format!("failed to get receipt of tx 0x{lock_tx_hash:x}"))?
+            .with_context(|| format!("receipt of tx 0x{lock_tx_hash:x} not found"))?;
+        self.record_gas_spend(&receipt);
+
+        let estimated = utils::estimate_gas_to_lock(&self.config, order).await?;
+        let (ratio, multiplier) = {
+            let mut ratios = self
+                .lock_gas_ratios
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Lock gas ratios lock poisoned"))?;
+            (ratios.record(estimated, receipt.gas_used), ratios.suggested_multiplier())
+        };
+        let Some(ratio) = ratio else {
+            return Ok(());
+        };
+        tracing::info!(
+            lock_gas_estimate_ratio = ratio,
+            "Lock of request 0x{:x} used {} gas, estimated {estimated} (ratio {ratio:.3}, suggested lockin_gas_estimate multiplier {:.3})",
+            order.request.id,
+            receipt.gas_used,
+            multiplier.unwrap_or(ratio),
+        );
+
+        Ok(())
     }
 
     async fn get_proving_order_capacity(
         &self,
-        max_concurrent_proofs: Option<u32>,
+        config: &OrderMonitorConfig,
         prev_orders_by_status: &mut String,
     ) -> Result<Capacity, OrderMonitorErr> {
-        if max_concurrent_proofs.is_none() {
-            return Ok(Capacity::Unlimited);
+        if let Some(worker_pool) = &self.worker_pool {
+            match worker_pool.idle_workers().await {
+                Ok(idle_workers) => {
+                    let committed_orders = self
+                        .db
+                        .get_committed_orders()
+                        .await
+                        .map_err(|e| OrderMonitorErr::UnexpectedError(e.into()))?;
+                    // Orders waiting to be proven will occupy idle workers once picked up.
+                    let pending_proving = committed_orders
+                        .iter()
+                        .filter(|order| order.status == OrderStatus::PendingProving)
+                        .count();
+                    Self::log_capacity(
+                        prev_orders_by_status,
+                        committed_orders,
+                        format!("{idle_workers} idle workers"),
+                    )
+                    .await;
+                    return Ok(Capacity::Available(
+                        idle_workers.saturating_sub(saturating_count(pending_proving)),
+                    ));
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to get idle workers from the worker pool, falling back to configured capacity: {err:?}"
+                    );
+                }
+            }
+        }
+
+        if config.max_committed_cycles.is_none() && config.max_concurrent_proofs.is_none() {
+            if config.capacity_accounting == CapacityAccounting::Optimistic {
+                return Ok(Capacity::Unlimited);
+            }
+            // Committed orders only occupy capacity under a limit, so without one the orders
+            // admitted but not yet picked up by the prover take up room in the next batch instead.
+            let committed_orders = self
+                .db
+                .get_committed_orders()
+                .await
+                .map_err(|e| OrderMonitorErr::UnexpectedError(e.into()))?;
+            let pending_proving = committed_orders
+                .iter()
+                .filter(|order| order.status == OrderStatus::PendingProving)
+                .count();
+            Self::log_capacity(
+                prev_orders_by_status,
+                committed_orders,
+                format!("{} orders per batch", self.max_proving_batch_size),
+            )
+            .await;
+            return Ok(Capacity::Available(
+                self.max_proving_batch_size.saturating_sub(saturating_count(pending_proving)),
+            ));
         };
 
-        let max = max_concurrent_proofs.unwrap();
         let committed_orders = self
             .db
             .get_committed_orders()
             .await
             .map_err(|e| OrderMonitorErr::UnexpectedError(e.into()))?;
-        let committed_orders_count: u32 = committed_orders.len().try_into().unwrap();
+
+        if let Some(max_cycles) = config.max_committed_cycles {
+            let remaining_cycles = remaining_committed_cycles(
+                &committed_orders,
+                config.peak_prove_khz,
+                config.additional_proof_cycles,
+                config.fallback_cycles,
+                self.now(),
+            );
+            Self::log_capacity(
+                prev_orders_by_status,
+                committed_orders,
+                format!("{max_cycles} cycles ({remaining_cycles} cycles remaining to prove)"),
+            )
+            .await;
+            return Ok(Capacity::Cycles(max_cycles.saturating_sub(remaining_cycles)));
+        }
+
+        let max = self
+            .effective_max_concurrent_proofs(config.max_concurrent_proofs.unwrap(), config)
+            .await;
+        let committed_orders_count = saturating_count(committed_orders.len());
 
         Self::log_capacity(prev_orders_by_status, committed_orders, max).await;
 
@@ -365,12 +2006,70 @@ where
         Ok(Capacity::Available(available_slots))
     }
 
+    /// Scales the configured `max_concurrent_proofs` down to the resources available to the
+    /// prover, falling back to the configured value if the resources cannot be probed.
+    async fn effective_max_concurrent_proofs(
+        &self,
+        max_concurrent_proofs: u32,
+        config: &OrderMonitorConfig,
+    ) -> u32 {
+        match self.resource_probe.available_fraction().await {
+            Ok(available) => {
+                let max = scaled_concurrent_proofs(
+                    max_concurrent_proofs,
+                    config.min_concurrent_proofs,
+                    available,
+                );
+                if max < max_concurrent_proofs {
+                    tracing::debug!(
+                        "Only {:.0}% of prover resources available, reducing max concurrent proofs from {max_concurrent_proofs} to {max}",
+                        available * 100.0
+                    );
+                }
+                max
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to probe prover resources, using the configured max concurrent proofs: {err:?}"
+                );
+                max_concurrent_proofs
+            }
+        }
+    }
+
+    /// Limits the capacity granted in an iteration to the room left before the settlement backlog
+    /// reaches `max_backlog`, leaving it unchanged if the backlog cannot be determined.
+    async fn throttle_for_settlement_backlog(
+        &self,
+        capacity_granted: u32,
+        max_backlog: u32,
+        committed_orders: &[Order],
+    ) -> u32 {
+        match self.settlement_backlog.pending_settlement(committed_orders).await {
+            Ok(backlog) => {
+                let room = max_backlog.saturating_sub(backlog);
+                if room < capacity_granted {
+                    tracing::debug!(
+                        "Settlement backlog of {backlog} orders, admitting at most {room} of the {capacity_granted} orders granted capacity"
+                    );
+                }
+                capacity_granted.min(room)
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to get the settlement backlog, not throttling admission: {err:?}"
+                );
+                capacity_granted
+            }
+        }
+    }
+
     async fn log_capacity(
         prev_orders_by_status: &mut String,
         commited_orders: Vec<Order>,
-        max: u32,
+        max: impl std::fmt::Display,
     ) {
-        let committed_orders_count: u32 = commited_orders.len().try_into().unwrap();
+        let committed_orders_count = saturating_count(commited_orders.len());
         let request_id_and_status = commited_orders
             .iter()
             .map(|order| format!("[{:?}]: {order}", order.status))
@@ -391,10 +2090,29 @@ where
         }
     }
 
+    /// Logs a message emitted every iteration at info level only when it differs from the last
+    /// message of the same log, or if `verbose` is set. Repeated messages are logged at debug level.
+    fn log_on_change(&self, log: &'static str, message: String, verbose: bool) {
+        let Ok(mut loop_logs) = self.loop_logs.lock() else {
+            tracing::info!("{message}");
+            return;
+        };
+        if verbose || loop_logs.get(log) != Some(&message) {
+            tracing::info!("{message}");
+            loop_logs.insert(log, message);
+        } else {
+            tracing::debug!("{message}");
+        }
+    }
+
     /// Helper method to skip an order in the database and invalidate the appropriate cache
     async fn skip_order(&self, order: &OrderRequest, reason: &str) {
-        if let Err(e) = self.db.insert_skipped_request(order).await {
-            tracing::error!("Failed to skip order ({}): {} - {e:?}", reason, order.id());
+        self.trace_tick(|trace| trace.filtered.push((order.id(), reason.to_string())));
+        // A standby leaves recording skipped orders in the shared database to the active monitor.
+        if self.role() == MonitorRole::Active {
+            if let Err(e) = self.db.insert_skipped_request(order).await {
+                tracing::error!("Failed to skip order ({}): {} - {e:?}", reason, order.id());
+            }
         }
 
         match order.fulfillment_type {
@@ -407,150 +2125,719 @@ where
         }
     }
 
-    async fn get_valid_orders(
+    /// Skips an order for a reason that may clear in a later iteration, such as high gas prices or
+    /// exhausted capacity.
+    ///
+    /// If `retain` is set, the order is left in the cache to be evaluated again next iteration and
+    /// is not recorded as skipped in the DB, so it can still be locked or committed to once the
+    /// condition clears. Otherwise it is skipped like any other order.
+    async fn skip_order_transient(&self, order: &OrderRequest, reason: &str, retain: bool) {
+        if !retain {
+            self.skip_order(order, reason).await;
+            return;
+        }
+        tracing::debug!("Order {} skipped this iteration ({reason}), retaining it", order.id());
+        self.trace_tick(|trace| trace.filtered.push((order.id(), reason.to_string())));
+    }
+
+    /// Adds the gas paid for a reverted lock transaction to the total wasted on lost races.
+    async fn record_wasted_lock_gas(&self, tx_hash: TxHash) {
+        let receipt = match self.provider.get_transaction_receipt(tx_hash).await {
+            Ok(Some(receipt)) => receipt,
+            Ok(None) => {
+                tracing::warn!("Receipt of reverted lock transaction 0x{tx_hash:x} not found");
+                return;
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to get receipt of reverted lock transaction 0x{tx_hash:x}: {err:?}"
+                );
+                return;
+            }
+        };
+        self.record_gas_spend(&receipt);
+        let wasted_wei = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+        let Ok(mut total_wei) = self.wasted_gas_wei.lock() else {
+            tracing::warn!("Wasted gas lock poisoned, not recording wasted gas");
+            return;
+        };
+        *total_wei += wasted_wei;
+        let total_wei = *total_wei;
+        tracing::info!(
+            wasted_gas_wei = %total_wei,
+            "Reverted lock transaction 0x{tx_hash:x} wasted {} ether of gas, {} ether in total",
+            format_ether(wasted_wei),
+            format_ether(total_wei)
+        );
+    }
+
+    /// Returns the total gas paid for lock transactions that reverted.
+    pub fn wasted_gas_wei(&self) -> U256 {
+        self.wasted_gas_wei.lock().map_or(U256::ZERO, |total_wei| *total_wei)
+    }
+
+    /// Returns the age of a lock recorded in `lock_block` as of `current_block_timestamp`, or None
+    /// if the block could not be fetched, e.g. because it was reorged.
+    async fn lock_record_age_secs(
         &self,
+        lock_block: u64,
         current_block_timestamp: u64,
-        min_deadline: u64,
-    ) -> Result<Vec<Arc<OrderRequest>>> {
-        let mut candidate_orders: Vec<Arc<OrderRequest>> = Vec::new();
-
-        fn is_within_deadline(
-            order: &OrderRequest,
-            current_block_timestamp: u64,
-            min_deadline: u64,
-        ) -> bool {
-            let expiration = order.expiry();
-            if expiration < current_block_timestamp {
-                tracing::debug!("Request {:x} has now expired. Skipping.", order.request.id);
-                false
-            } else if expiration.saturating_sub(now_timestamp()) < min_deadline {
-                tracing::debug!("Request {:x} deadline at {} is less than the minimum deadline {} seconds required to prove an order. Skipping.", order.request.id, expiration, min_deadline);
-                false
-            } else {
-                true
+    ) -> Option<u64> {
+        self.throttle_rpc().await;
+        match self.provider.get_block_by_number(lock_block.into()).await {
+            Ok(Some(block)) => Some(current_block_timestamp.saturating_sub(block.header.timestamp)),
+            Ok(None) => None,
+            Err(err) => {
+                tracing::warn!("Failed to get block {lock_block} of lock record: {err:?}");
+                None
             }
         }
+    }
 
-        fn is_target_time_reached(order: &OrderRequest, current_block_timestamp: u64) -> bool {
-            // Note: this could use current timestamp, but avoiding cases where clock has drifted.
-            match order.target_timestamp {
-                Some(target_timestamp) => {
-                    if current_block_timestamp < target_timestamp {
-                        tracing::trace!(
-                            "Request {:x} target timestamp {} not yet reached (current: {}). Waiting.",
-                            order.request.id,
-                            target_timestamp,
-                            current_block_timestamp
-                        );
-                        false
-                    } else {
-                        true
+    /// Records the outcome of an attempt to lock the order, for the statistics of lock races.
+    ///
+    /// `lock_price` is the price the order was locked at, if it was locked. Lost races are
+    /// attributed to the winner if the lock has been observed.
+    async fn record_lock_outcome(
+        &self,
+        order: &OrderRequest,
+        outcome: LockOutcome,
+        lock_price: Option<U256>,
+    ) {
+        let request_id = U256::from(order.request.id);
+        let now = self.now();
+        let lock_price = lock_price.unwrap_or_else(|| {
+            order.request.offer.price_at(now).unwrap_or(order.request.offer.minPrice)
+        });
+        let winner = match outcome {
+            LockOutcome::Lost => {
+                let winner = match self.db.get_request_locked(request_id).await {
+                    Ok(locked) => locked.map(|(locker, _)| locker),
+                    Err(err) => {
+                        tracing::warn!("Failed to get locker of request 0x{request_id:x}: {err:?}");
+                        None
+                    }
+                };
+                if let Some(ref winner) = winner {
+                    if winner.parse::<Address>().ok() == Some(self.prover_addr) {
+                        return;
                     }
                 }
-                None => {
-                    // Should not happen, just warning for safety as this condition is not strictly
-                    // enforced at compile time.
-                    tracing::warn!("Request {:x} has no target timestamp set", order.request.id);
-                    false
-                }
+                tracing::info!(
+                    "Lost lock of request 0x{request_id:x} to {} at price: {}",
+                    winner.as_deref().unwrap_or("unknown prover"),
+                    format_wei(lock_price)
+                );
+                winner
             }
+            LockOutcome::Won | LockOutcome::Error => None,
+        };
+        if let Err(err) = self
+            .db
+            .insert_lock_outcome(request_id, outcome, winner.as_deref(), lock_price, now)
+            .await
+        {
+            tracing::warn!(
+                "Failed to record the lock outcome of request 0x{request_id:x}: {err:?}"
+            );
         }
+    }
 
-        for (_, order) in self.prove_cache.iter() {
-            let is_fulfilled = self
-                .db
-                .is_request_fulfilled(U256::from(order.request.id))
-                .await
-                .context("Failed to check if request is fulfilled")?;
-            if is_fulfilled {
+    /// Priority gas within the band, interpolated by the fraction of lock races lost within the
+    /// last [LOCK_OUTCOME_WINDOW]. The minimum of the band is used if no races were recorded.
+    async fn adaptive_priority_gas(&self, band: PriorityGasBand) -> u64 {
+        let since = self.now().saturating_sub(LOCK_OUTCOME_WINDOW.as_secs());
+        let loss_rate = match self.db.get_lock_outcome_stats(since).await {
+            Ok(stats) => stats.loss_rate().unwrap_or(0.0),
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to get lock outcome stats, not adapting priority gas: {err:?}"
+                );
+                0.0
+            }
+        };
+        let priority_gas = adapted_priority_gas(band, loss_rate);
+        tracing::debug!("Adapted lockin priority gas to {priority_gas}");
+        priority_gas
+    }
+
+    /// Returns whether the estimated probability of winning the race to lock the order is at least
+    /// the given minimum. Orders are contested if the probability cannot be estimated.
+    async fn should_contest(
+        &self,
+        order: &OrderRequest,
+        recent: &LockOutcomeStats,
+        min_probability: f64,
+    ) -> bool {
+        let order_id = order.id();
+        match self.contest_estimator.win_probability(order, recent).await {
+            Ok(probability) if probability < min_probability => {
                 tracing::debug!(
-                    "Request 0x{:x} was locked by another prover and was fulfilled. Skipping.",
-                    order.request.id
+                    lock_win_probability = probability,
+                    "Not locking order {order_id}, estimated win probability is below minimum of \
+                     {min_probability}"
                 );
-                self.skip_order(&order, "was fulfilled by other").await;
-            } else if !is_within_deadline(&order, current_block_timestamp, min_deadline) {
-                self.skip_order(&order, "expired").await;
-            } else if is_target_time_reached(&order, current_block_timestamp) {
-                tracing::info!("Request 0x{:x} was locked by another prover but expired unfulfilled, setting status to pending proving", order.request.id);
-                candidate_orders.push(order);
-            }
-        }
-
-        for (_, order) in self.lock_and_prove_cache.iter() {
-            let is_lock_expired = order.request.lock_expires_at() < current_block_timestamp;
-            if is_lock_expired {
-                tracing::debug!("Request {:x} was scheduled to be locked by us, but its lock has now expired. Skipping.", order.request.id);
-                self.skip_order(&order, "lock expired before we locked").await;
-            } else if let Some((locker, _)) =
-                self.db.get_request_locked(U256::from(order.request.id)).await?
-            {
-                let our_address = self.provider.default_signer_address().to_string().to_lowercase();
-                let locker_address = locker.to_lowercase();
-                // Compare normalized addresses (lowercase without 0x prefix)
-                let our_address_normalized = our_address.trim_start_matches("0x");
-                let locker_address_normalized = locker_address.trim_start_matches("0x");
-
-                if locker_address_normalized != our_address_normalized {
-                    tracing::debug!("Request 0x{:x} was scheduled to be locked by us ({}), but is already locked by another prover ({}). Skipping.", order.request.id, our_address, locker_address);
-                    self.skip_order(&order, "locked by another prover").await;
-                } else {
-                    // Edge case where we locked the order, but due to some reason was not moved to proving state. Should not happen.
-                    tracing::debug!("Request 0x{:x} was scheduled to be locked by us, but is already locked by us. Proceeding to prove.", order.request.id);
-                    candidate_orders.push(order);
-                }
-            } else if !is_within_deadline(&order, current_block_timestamp, min_deadline) {
-                self.skip_order(&order, "insufficient deadline").await;
-            } else if is_target_time_reached(&order, current_block_timestamp) {
-                candidate_orders.push(order);
+                false
+            }
+            Ok(_) => true,
+            Err(err) => {
+                tracing::warn!("Failed to estimate lock win probability of {order_id}: {err:?}");
+                true
             }
         }
+    }
 
-        if candidate_orders.is_empty() {
-            tracing::trace!(
-                "No orders to lock and/or prove as of block timestamp {}",
-                current_block_timestamp
-            );
-            return Ok(Vec::new());
+    /// Defers locking an order whose requestor had insufficient balance by re-inserting it into
+    /// the cache with a later target timestamp. Returns false if the order should not be retried.
+    async fn defer_order_lock(&self, order: &OrderRequest) -> bool {
+        let (max_attempts, backoff_secs) = match self.config.lock_all() {
+            Ok(config) => (
+                config.market.requestor_balance_retry_attempts,
+                config.market.requestor_balance_retry_backoff_secs,
+            ),
+            Err(err) => {
+                tracing::error!("Failed to read config for deferring order lock: {err:?}");
+                return false;
+            }
+        };
+
+        let order_id = order.id();
+        let attempts = self.lock_retry_attempts.get(&order_id).await.unwrap_or(0);
+        if attempts >= max_attempts {
+            self.lock_retry_attempts.invalidate(&order_id).await;
+            return false;
         }
+        self.lock_retry_attempts.insert(order_id.clone(), attempts + 1).await;
 
-        tracing::debug!(
-            "Valid orders that reached target timestamp; ready for locking/proving, num: {}, ids: {}",
-            candidate_orders.len(),
-            candidate_orders.iter().map(|order| order.id()).collect::<Vec<_>>().join(", ")
+        let retry_at = self.now() + backoff_secs;
+        tracing::info!(
+            "Deferring lock of order {order_id} until {retry_at} as requestor has insufficient balance, attempt {}/{max_attempts}",
+            attempts + 1
         );
+        let mut deferred_order = order.clone();
+        deferred_order.target_timestamp = Some(retry_at);
+        self.cache_order(Arc::new(deferred_order)).await;
+        true
+    }
 
-        Ok(candidate_orders)
+    /// Returns the risk of locking the order at the given time, see [lock_risk_score].
+    fn lock_risk(&self, order: &OrderRequest, config: &OrderMonitorConfig, now: u64) -> f64 {
+        // An unparsable stake is treated as the riskiest possible.
+        let stake_tokens = format_units(order.request.offer.lockStake, self.stake_token_decimals)
+            .ok()
+            .and_then(|stake| stake.parse::<f64>().ok())
+            .unwrap_or(f64::INFINITY);
+        let cycles = budgeted_cycles(order.total_cycles, config.fallback_cycles)
+            + config.additional_proof_cycles;
+        lock_risk_score(stake_tokens, cycles, order.request.lock_expires_at().saturating_sub(now))
     }
 
-    async fn lock_and_prove_orders(&self, orders: &[Arc<OrderRequest>]) -> Result<()> {
-        let lock_jobs = orders.iter().map(|order| {
-            async move {
-                let order_id = order.id();
-                if order.fulfillment_type == FulfillmentType::LockAndFulfill {
-                    let request_id = order.request.id;
-                    match self.lock_order(order).await {
-                        Ok(lock_price) => {
-                            tracing::info!("Locked request: 0x{:x}", request_id);
-                            if let Err(err) = self.db.insert_accepted_request(order, lock_price).await {
-                                tracing::error!(
-                                    "FATAL STAKE AT RISK: {} failed to move from locking -> proving status {}",
-                                    order_id,
-                                    err
-                                );
-                            }
-                        }
-                        Err(ref err) => {
-                            match err {
-                                OrderMonitorErr::UnexpectedError(inner) => {
-                                    tracing::error!(
-                                        "Failed to lock order: {order_id} - {} - {inner:?}",
-                                        err.code()
-                                    );
+    /// Returns the lock risk of the order if it exceeds the configured maximum.
+    fn exceeded_lock_risk(
+        &self,
+        order: &OrderRequest,
+        config: &OrderMonitorConfig,
+        now: u64,
+    ) -> Option<f64> {
+        let max_risk = config.max_lock_risk?;
+        let risk = self.lock_risk(order, config, now);
+        (risk > max_risk).then_some(risk)
+    }
+
+    /// Returns whether a lock recorded for `locker` was taken by us.
+    fn is_our_lock(&self, locker: &str) -> bool {
+        // Compare normalized addresses (lowercase without 0x prefix)
+        let our_address = self.provider.default_signer_address().to_string().to_lowercase();
+        let locker_address = locker.to_lowercase();
+        our_address.trim_start_matches("0x") == locker_address.trim_start_matches("0x")
+    }
+
+    /// Returns whether a lock recorded in `lock_block` is old enough not to be reorged, as
+    /// configured by `lock_record_min_age_secs`.
+    async fn is_lock_settled(
+        &self,
+        lock_block: u64,
+        current_block_timestamp: u64,
+        config: &OrderMonitorConfig,
+    ) -> bool {
+        match config.lock_record_min_age_secs {
+            Some(min_age_secs) => self
+                .lock_record_age_secs(lock_block, current_block_timestamp)
+                .await
+                .is_some_and(|age_secs| age_secs >= min_age_secs),
+            None => true,
+        }
+    }
+
+    /// Returns the policy configured for the proof type required by the order's selector, if any.
+    fn proof_type_policy(
+        &self,
+        config: &OrderMonitorConfig,
+        order: &OrderRequest,
+    ) -> Option<ProofTypePolicy> {
+        let proof_type =
+            self.supported_selectors.proof_type(order.request.requirements.selector)?;
+        let name = proof_type.to_possible_value()?;
+        config.proof_type_policies.get(name.get_name()).copied()
+    }
+
+    /// Returns the cached orders that are ready to be locked and/or proven, skipping orders that
+    /// can no longer be completed.
+    ///
+    /// If a deadline is given, evaluation stops once it is reached, leaving the orders not yet
+    /// evaluated in the caches.
+    async fn get_valid_orders(
+        &self,
+        current_block_timestamp: u64,
+        config: &OrderMonitorConfig,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<Arc<OrderRequest>>> {
+        let mut candidate_orders: Vec<Arc<OrderRequest>> = Vec::new();
+        let mut urgent_orders: Vec<Arc<OrderRequest>> = Vec::new();
+        let mut num_evaluated = 0;
+        let mut truncated = false;
+        // At least one order is evaluated so that progress is made regardless of the deadline.
+        let deadline_reached = |num_evaluated: usize| {
+            num_evaluated > 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        };
+
+        // Evaluate the orders of both caches in a single pass, so that the checks they share are
+        // applied identically.
+        let pulled_orders =
+            self.prove_cache.iter().map(|(_, order)| (OrderSource::Prove, order)).chain(
+                self.lock_and_prove_cache
+                    .iter()
+                    .map(|(_, order)| (OrderSource::LockAndProve, order)),
+            );
+        for (source, order) in pulled_orders {
+            if deadline_reached(num_evaluated) {
+                truncated = true;
+                break;
+            }
+            num_evaluated += 1;
+            if self.proof_type_policy(config, &order) == Some(ProofTypePolicy::Deny) {
+                tracing::debug!(
+                    "Request 0x{:x} requires a denied proof type. Skipping.",
+                    order.request.id
+                );
+                self.skip_order(&order, "denied proof type").await;
+                continue;
+            }
+
+            // Orders cached before prove-only mode was enabled are never locked.
+            if config.denied_in_prove_only(&order) {
+                self.skip_order(&order, "prove-only mode").await;
+                continue;
+            }
+
+            // Checks specific to the cache the order was pulled from, which may settle the order.
+            match source {
+                OrderSource::Prove => {
+                    let is_fulfilled = self
+                        .db
+                        .is_request_fulfilled(U256::from(order.request.id))
+                        .await
+                        .context("Failed to check if request is fulfilled")?;
+                    if is_fulfilled {
+                        tracing::debug!(
+                            "Request 0x{:x} was locked by another prover and was fulfilled. Skipping.",
+                            order.request.id
+                        );
+                        self.skip_order(&order, "was fulfilled by other").await;
+                        continue;
+                    }
+                }
+                OrderSource::LockAndProve => {
+                    if config.exceeds_max_lock_stake(&order) {
+                        tracing::debug!(
+                            "Request 0x{:x} lock stake {} exceeds the maximum of {} for a single order. Skipping.",
+                            order.request.id,
+                            order.request.offer.lockStake,
+                            config.max_single_order_lock_stake.unwrap_or_default()
+                        );
+                        self.skip_order(&order, "stake too high").await;
+                        continue;
+                    }
+                    if config.skips_zero_stake(&order) {
+                        tracing::debug!(
+                            "Request 0x{:x} does not require any lock stake. Skipping.",
+                            order.request.id
+                        );
+                        self.skip_order(&order, "zero lock stake").await;
+                        continue;
+                    }
+                    let is_lock_expired = order.request.lock_expires_at() < current_block_timestamp;
+                    if is_lock_expired {
+                        tracing::debug!("Request {:x} was scheduled to be locked by us, but its lock has now expired. Skipping.", order.request.id);
+                        if config.reingest_lock_expired_orders {
+                            self.reingest_lock_expired_order(&order);
+                        }
+                        self.skip_order(&order, "lock expired before we locked").await;
+                        continue;
+                    }
+                    if let Some((locker, lock_block)) =
+                        self.db.get_request_locked(U256::from(order.request.id)).await?
+                    {
+                        if !self.is_our_lock(&locker) {
+                            if self
+                                .is_lock_settled(lock_block, current_block_timestamp, config)
+                                .await
+                            {
+                                tracing::debug!("Request 0x{:x} was scheduled to be locked by us ({}), but is already locked by another prover ({}). Skipping.", order.request.id, self.provider.default_signer_address(), locker);
+                                self.skip_order(&order, "locked by another prover").await;
+                            } else {
+                                tracing::debug!("Request 0x{:x} was locked by another prover ({}) in block {lock_block}, which may still be reorged. Deferring.", order.request.id, locker);
+                                self.trace_tick(|trace| {
+                                    trace
+                                        .filtered
+                                        .push((order.id(), "lock record too recent".to_string()))
+                                });
+                            }
+                        } else {
+                            // Edge case where we locked the order, but due to some reason was not moved to proving state. Should not happen.
+                            tracing::debug!("Request 0x{:x} was scheduled to be locked by us, but is already locked by us. Proceeding to prove.", order.request.id);
+                            if config
+                                .urgent_deadline_secs
+                                .is_some_and(|urgent_secs| self.is_urgent(&order, urgent_secs))
+                            {
+                                // Annotated so that it is proven ahead of the other orders.
+                                let mut urgent_order = (*order).clone();
+                                urgent_order.urgent = true;
+                                urgent_orders.push(Arc::new(urgent_order));
+                            } else {
+                                candidate_orders.push(order);
+                            }
+                        }
+                        continue;
+                    }
+                    if let Some(risk) =
+                        self.exceeded_lock_risk(&order, config, current_block_timestamp)
+                    {
+                        tracing::debug!(
+                            "Request 0x{:x} lock risk {risk:.4} exceeds the maximum of {}. Skipping.",
+                            order.request.id,
+                            config.max_lock_risk.unwrap_or_default()
+                        );
+                        self.skip_order(&order, "risk threshold exceeded").await;
+                        continue;
+                    }
+                }
+            }
+
+            if !is_within_deadline(&order, current_block_timestamp, self.now(), config) {
+                let reason = match source {
+                    OrderSource::Prove => "expired",
+                    OrderSource::LockAndProve => "insufficient deadline",
+                };
+                self.skip_order(&order, reason).await;
+            } else if is_beyond_target_horizon(&order, current_block_timestamp, config) {
+                self.trace_tick(|trace| {
+                    trace.filtered.push((order.id(), "target beyond horizon".to_string()))
+                });
+            } else {
+                match is_target_time_reached(&order, current_block_timestamp) {
+                    Ok(true) => {
+                        if source == OrderSource::Prove {
+                            tracing::info!("Request 0x{:x} was locked by another prover but expired unfulfilled, setting status to pending proving", order.request.id);
+                        }
+                        candidate_orders.push(order);
+                    }
+                    Ok(false) => {}
+                    Err(err) => {
+                        tracing::warn!("Request 0x{:x} skipped: {err}", order.request.id);
+                        self.skip_order(&order, "missing target timestamp").await;
+                    }
+                }
+            }
+        }
+
+        if truncated {
+            tracing::warn!(
+                monitor_loop_truncated = 1,
+                "Order monitor loop deadline reached after evaluating {num_evaluated} orders, remaining orders are deferred to the next iteration"
+            );
+        }
+
+        if !urgent_orders.is_empty() {
+            // Our stake is at risk for orders we hold the lock of, so those close to expiring are
+            // proven ahead of any other order.
+            urgent_orders.sort_by_key(|order| order.expiry());
+            let urgent_ids: Vec<String> = urgent_orders.iter().map(|order| order.id()).collect();
+            tracing::warn!(
+                urgent_locked_orders = urgent_orders.len(),
+                "Prioritizing {} orders locked by us that are close to expiring: {}",
+                urgent_orders.len(),
+                urgent_ids.join(", ")
+            );
+            self.trace_tick(|trace| trace.urgent = urgent_ids);
+            urgent_orders.append(&mut candidate_orders);
+            candidate_orders = urgent_orders;
+        }
+
+        if candidate_orders.is_empty() {
+            tracing::trace!(
+                "No orders to lock and/or prove as of block timestamp {}",
+                current_block_timestamp
+            );
+            return Ok(Vec::new());
+        }
+
+        tracing::debug!(
+            "Valid orders that reached target timestamp; ready for locking/proving, num: {}, ids: {}",
+            candidate_orders.len(),
+            candidate_orders.iter().map(|order| order.id()).collect::<Vec<_>>().join(", ")
+        );
+
+        Ok(candidate_orders)
+    }
+
+    /// Returns whether the chain head at `block_timestamp` is older than `max_chain_head_age_secs`,
+    /// logging whenever the stalled state changes. A stale head, e.g. from a stalled chain or an
+    /// unresponsive RPC node, would have targets and deadlines checked against the wrong time.
+    fn check_chain_stalled(&self, block_timestamp: u64, config: &OrderMonitorConfig) -> bool {
+        let head_age_secs = self.now().saturating_sub(block_timestamp);
+        let stalled = config.max_chain_head_age_secs.is_some_and(|max_age| head_age_secs > max_age);
+        if self.chain_stalled.swap(stalled, Ordering::Relaxed) != stalled {
+            if stalled {
+                tracing::warn!(
+                    chain_stalled = 1,
+                    "Chain stalled, the chain head is {head_age_secs} seconds old. Pausing until it advances"
+                );
+            } else {
+                tracing::info!(chain_stalled = 0, "Chain head advanced, resuming");
+            }
+        }
+        stalled
+    }
+
+    /// Returns whether the chain head was considered stalled as of the last iteration.
+    pub fn is_chain_stalled(&self) -> bool {
+        self.chain_stalled.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether locking of new orders is paused, logging whenever the paused state changes.
+    fn is_locking_paused(&self) -> Result<bool> {
+        let paused = self.config.lock_all().context("Failed to read config")?.market.pause_locking;
+        if self.locking_paused.swap(paused, Ordering::Relaxed) != paused {
+            if paused {
+                tracing::warn!(
+                    locking_paused = 1,
+                    "Locking of new orders is paused, only proving orders that do not require a lock"
+                );
+            } else {
+                tracing::info!(locking_paused = 0, "Locking of new orders resumed");
+            }
+        }
+        Ok(paused)
+    }
+
+    /// Raises the configured alert when the balance stops covering the gas of committed orders,
+    /// and clears it once the balance recovers.
+    fn alert_balance_underwater(
+        &self,
+        underwater: bool,
+        balance_wei: U256,
+        committed_cost_wei: U256,
+    ) -> Result<()> {
+        if self.balance_underwater.swap(underwater, Ordering::Relaxed) == underwater {
+            return Ok(());
+        }
+        let Some(alert) = self
+            .config
+            .lock_all()
+            .context("Failed to read config")?
+            .market
+            .balance_underwater_alert
+        else {
+            return Ok(());
+        };
+        let metric = alert == BalanceUnderwaterAlert::Metric;
+        match (underwater, metric) {
+            (true, false) => tracing::error!(
+                "Balance underwater for committed obligations. Balance: {} ether, gas cost of committed orders: {} ether",
+                format_ether(balance_wei),
+                format_ether(committed_cost_wei)
+            ),
+            (true, true) => tracing::error!(
+                balance_underwater = 1,
+                "Balance underwater for committed obligations. Balance: {} ether, gas cost of committed orders: {} ether",
+                format_ether(balance_wei),
+                format_ether(committed_cost_wei)
+            ),
+            (false, false) => {
+                tracing::info!("Balance recovered to cover committed obligations")
+            }
+            (false, true) => tracing::info!(
+                balance_underwater = 0,
+                "Balance recovered to cover committed obligations"
+            ),
+        }
+        Ok(())
+    }
+
+    async fn lock_and_prove_orders(&self, orders: &[Arc<OrderRequest>]) -> Result<()> {
+        if self.role() == MonitorRole::Standby {
+            // Leave the orders in the cache so they can be committed to once promoted.
+            tracing::debug!(
+                "Order monitor is on standby, not committing to {} orders",
+                orders.len()
+            );
+            return Ok(());
+        }
+
+        // Drop orders the DB already knows to be locked by another prover before spending any RPC
+        // calls on them. The status check in lock_order remains the authoritative guard before
+        // locking. Urgent orders are locked by us and go straight to proving.
+        let mut unlocked_orders = Vec::with_capacity(orders.len());
+        for order in orders {
+            if order.fulfillment_type == FulfillmentType::LockAndFulfill && !order.urgent {
+                let lock = self
+                    .db
+                    .get_request_locked(U256::from(order.request.id))
+                    .await
+                    .context("Failed to check if request is locked")?;
+                if let Some((locker, _)) = lock.filter(|(locker, _)| !self.is_our_lock(locker)) {
+                    tracing::info!(
+                        "Request 0x{:x} already locked by another prover ({locker}) according to the DB, skipping",
+                        order.request.id
+                    );
+                    self.record_lock_outcome(order, LockOutcome::Lost, None).await;
+                    self.skip_order(order, "already locked").await;
+                    continue;
+                }
+            }
+            unlocked_orders.push(order.clone());
+        }
+        let orders = unlocked_orders.as_slice();
+
+        let locking_paused = self.is_locking_paused()?;
+        let (
+            max_concurrent_lock_txs,
+            min_lock_win_probability,
+            retain_transient_skips,
+            retain_lock_rpc_failures,
+        ) = {
+            let config = self.config.lock_all().context("Failed to read config")?;
+            (
+                config.market.max_concurrent_lock_txs,
+                config.market.min_lock_win_probability,
+                config.market.retain_transient_skips,
+                config.market.retain_lock_rpc_failures,
+            )
+        };
+        // Bounds the number of lock transactions in flight, independently of how many orders were
+        // selected in this iteration.
+        let lock_tx_permits = &Semaphore::new(
+            max_concurrent_lock_txs.map_or(Semaphore::MAX_PERMITS, |max| max.max(1) as usize),
+        );
+        let contest = match min_lock_win_probability {
+            Some(min_probability)
+                if !locking_paused
+                    && orders
+                        .iter()
+                        .any(|order| order.fulfillment_type == FulfillmentType::LockAndFulfill) =>
+            {
+                let since = self.now().saturating_sub(CONTEST_HISTORY_WINDOW.as_secs());
+                match self.db.get_lock_outcome_stats(since).await {
+                    Ok(recent) => Some((recent, min_probability)),
+                    Err(err) => {
+                        tracing::warn!(
+                            "Failed to get lock outcome stats, contesting all orders: {err:?}"
+                        );
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        let contest = &contest;
+        let lock_jobs = orders.iter().map(|order| {
+            async move {
+                let order_id = order.id();
+                // Urgent orders are already locked by us and go straight to proving.
+                if order.fulfillment_type == FulfillmentType::LockAndFulfill && !order.urgent {
+                    if locking_paused {
+                        // Leave the order in the cache so it can be locked once unpaused.
+                        tracing::debug!("Locking paused, not locking order {order_id}");
+                        return;
+                    }
+                    if let Some((recent, min_probability)) = contest {
+                        if !self.should_contest(order, recent, *min_probability).await {
+                            // Leave the order in the cache, the estimate may change as the price
+                            // ramps up or competition changes.
+                            self.trace_tick(|trace| {
+                                trace
+                                    .filtered
+                                    .push((order_id.clone(), "unlikely to win lock".to_string()))
+                            });
+                            return;
+                        }
+                    }
+                    let request_id = order.request.id;
+                    let lock_result = {
+                        let _permit =
+                            lock_tx_permits.acquire().await.expect("lock semaphore is never closed");
+                        self.lock_order(order).await
+                    };
+                    match lock_result {
+                        Ok((lock_price, lock_tx_hash)) => {
+                            self.record_lock_outcome(order, LockOutcome::Won, Some(lock_price))
+                                .await;
+                            tracing::info!(
+                                "Locked request: 0x{:x} at price: {} for stake: {}",
+                                request_id,
+                                format_wei(lock_price),
+                                self.format_stake(order.request.offer.lockStake)
+                            );
+                            self.trace_tick(|trace| trace.locked.push(order_id.clone()));
+                            self.lock_retry_attempts.invalidate(&order_id).await;
+                            if let Err(err) = self
+                                .db
+                                .insert_locked_request(order, lock_price, lock_tx_hash)
+                                .await
+                            {
+                                tracing::error!(
+                                    "FATAL STAKE AT RISK: {} failed to move from locking -> proving status {}",
+                                    order_id,
+                                    err
+                                );
+                                self.alert_sink
+                                    .on_stake_at_risk(U256::from(request_id), lock_price)
+                                    .await;
+                            }
+                            if let Err(err) = self.reconcile_lock_gas(order, lock_tx_hash).await {
+                                tracing::warn!(
+                                    "Failed to compare realized lock gas for request 0x{request_id:x}: {err:?}"
+                                );
+                            }
+                        }
+                        Err(ref err) => {
+                            self.trace_tick(|trace| {
+                                trace.failures.push((order_id.clone(), err.to_string()))
+                            });
+                            match err {
+                                OrderMonitorErr::UnexpectedError(inner) => {
+                                    tracing::error!(
+                                        "Failed to lock order: {order_id} - {} - {inner:?}",
+                                        err.code()
+                                    );
                                 }
                                 OrderMonitorErr::AlreadyLocked => {
                                     // For order already locked, we don't need to print the error backtrace.
                                     tracing::warn!("Soft failed to lock request: {order_id} - {}", err.code());
                                 }
+                                OrderMonitorErr::LockNotOwned(_) => {
+                                    tracing::warn!(
+                                        "Not proving request {order_id}, lost the lock despite the lock tx confirming: {err}"
+                                    );
+                                }
+                                OrderMonitorErr::RpcErr(inner) => {
+                                    // The order itself was never evaluated, the failure was ours.
+                                    tracing::warn!(
+                                        "Failed to check the status of request {order_id} before locking - {} - {inner:?}",
+                                        err.code()
+                                    );
+                                }
                                 _ => {
                                     tracing::warn!(
                                         "Soft failed to lock request: {order_id} - {} - {err:?}",
@@ -558,6 +2845,30 @@ where
                                     );
                                 }
                             }
+                            if matches!(err, OrderMonitorErr::RequestorInsufficientBalance(_))
+                                && self.defer_order_lock(order).await
+                            {
+                                // The deferred order replaced the cached entry, so it must remain
+                                // in the cache to be retried.
+                                return;
+                            }
+                            if matches!(err, OrderMonitorErr::RpcErr(_)) && retain_lock_rpc_failures
+                            {
+                                // Leave the order in the cache to be locked on the next iteration.
+                                return;
+                            }
+                            // Deferred and retained orders are recorded once they are attempted
+                            // again, so they are only counted once.
+                            self.record_lock_outcome(order, lock_outcome(err), None).await;
+                            if matches!(err, OrderMonitorErr::InsufficientBalance)
+                                && retain_transient_skips
+                            {
+                                // Leave the order in the cache to be locked once the prover's
+                                // balance is topped up.
+                                self.skip_order_transient(order, "insufficient balance", true)
+                                    .await;
+                                return;
+                            }
                             if let Err(err) = self.db.insert_skipped_request(order).await {
                                 tracing::error!(
                                     "Failed to set DB failure state for order: {order_id} - {err:?}"
@@ -568,12 +2879,19 @@ where
                     self.lock_and_prove_cache.invalidate(&order_id).await;
                 } else {
                     if let Err(err) = self.db.insert_accepted_request(order, U256::ZERO).await {
+                        self.trace_tick(|trace| {
+                            trace.failures.push((order_id.clone(), err.to_string()))
+                        });
                         tracing::error!(
                             "Failed to set order status to pending proving: {} - {err:?}",
                             order_id
                         );
                     }
-                    self.prove_cache.invalidate(&order_id).await;
+                    if order.urgent {
+                        self.lock_and_prove_cache.invalidate(&order_id).await;
+                    } else {
+                        self.prove_cache.invalidate(&order_id).await;
+                    }
                 }
             }
         });
@@ -583,218 +2901,1985 @@ where
         Ok(())
     }
 
-    /// Calculate the gas units needed for an order and the corresponding cost in wei
-    async fn calculate_order_gas_cost_wei(
-        &self,
-        order: &OrderRequest,
-        gas_price: u128,
-    ) -> Result<U256, OrderMonitorErr> {
-        // Calculate gas units needed for this order (lock + fulfill)
-        let order_gas_units = if order.fulfillment_type == FulfillmentType::LockAndFulfill {
-            U256::from(utils::estimate_gas_to_lock(&self.config, order).await?).saturating_add(
-                U256::from(
-                    utils::estimate_gas_to_fulfill(
-                        &self.config,
-                        &self.supported_selectors,
-                        &order.request,
-                    )
-                    .await?,
-                ),
-            )
-        } else {
-            U256::from(
-                utils::estimate_gas_to_fulfill(
-                    &self.config,
-                    &self.supported_selectors,
-                    &order.request,
-                )
-                .await?,
-            )
-        };
+    /// Returns the current gas price, falling back to the last known gas price with a safety margin
+    /// if the gas price cannot be fetched.
+    async fn current_gas_price(&self) -> Result<u128> {
+        let margin_percent = self
+            .config
+            .lock_all()
+            .context("Failed to read config")?
+            .market
+            .gas_price_fallback_margin_percent;
+        self.throttle_rpc().await;
+        let gas_price = self.gas_price_source().gas_price().await;
+        let mut last_gas_price =
+            self.last_gas_price.lock().map_err(|_| anyhow::anyhow!("Gas price lock poisoned"))?;
+        let gas_price = gas_price_or_fallback(gas_price, &mut last_gas_price, margin_percent)?;
+        self.record_tick(|tick| tick.gas_price = Some(gas_price));
+        Ok(gas_price)
+    }
 
-        let order_cost_wei = U256::from(gas_price) * order_gas_units;
+    /// Returns whether fewer than `urgent_deadline_secs` remain before the order expires.
+    ///
+    /// Measured against the monitor's clock, as the time remaining before other deadlines is, so
+    /// that orders are urgent consistently wherever they are checked.
+    fn is_urgent(&self, order: &OrderRequest, urgent_deadline_secs: u64) -> bool {
+        order.expiry().saturating_sub(self.now()) < urgent_deadline_secs
+    }
 
-        Ok(order_cost_wei)
+    /// Returns the current UNIX timestamp according to the monitor's clock.
+    fn now(&self) -> u64 {
+        self.clock.now()
     }
 
-    async fn apply_capacity_limits(
-    &self,
-    orders: Vec<Arc<OrderRequest>>,
-    config: &OrderMonitorConfig,
-    prev_orders_by_status: &mut String,
-) -> Result<Vec<Arc<OrderRequest>>> {
-    let num_orders = orders.len();
-
-    // Prioritize primary orders first
-    let mut orders = orders; // make mutable
-    orders.sort_by(|a, b| {
-        let a_priority = if a.is_primary() { 0 } else { 1 };
-        let b_priority = if b.is_primary() { 0 } else { 1 };
-        a_priority
-            .cmp(&b_priority)
-            .then(a.expiration().cmp(&b.expiration()))
-    });
-
-    let capacity = self
-        .get_proving_order_capacity(config.max_concurrent_proofs, prev_orders_by_status)
-        .await?;
-    let capacity_granted: usize = capacity
-        .request_capacity(num_orders.try_into().expect("Failed to convert order count to u32"))
-        as usize;
+    /// Waits until an RPC call may be made without exceeding `max_rpc_calls_per_sec`, if set.
+    async fn throttle_rpc(&self) {
+        let max_calls_per_sec =
+            self.config.lock_all().ok().and_then(|config| config.market.max_rpc_calls_per_sec);
+        if let Some(max_calls_per_sec) = max_calls_per_sec {
+            self.rpc_rate_limiter.acquire(max_calls_per_sec).await;
+        }
+    }
 
-    tracing::info!(
-        "Num orders ready for locking and/or proving: {}. Total capacity available: {capacity:?}, Capacity granted: {capacity_granted:?}",
-        num_orders
-    );
+    /// Returns the configured gas price source, falling back to the node's gas price.
+    fn gas_price_source(&self) -> GasPriceSourceObj {
+        self.gas_price_source
+            .clone()
+            .unwrap_or_else(|| self.chain_monitor.clone() as GasPriceSourceObj)
+    }
 
-    let mut final_orders: Vec<Arc<OrderRequest>> = Vec::with_capacity(capacity_granted);
+    /// Raises the priority gas of a lock transaction by how much the injected gas price source
+    /// exceeds the node's gas price, as lock transactions are priced from the node's fee estimate.
+    async fn priority_gas_from_source(&self, priority_gas: Option<u64>) -> Option<u64> {
+        let Some(gas_price_source) = &self.gas_price_source else {
+            return priority_gas;
+        };
+        let (source_price, node_price) = match (
+            gas_price_source.gas_price().await,
+            self.chain_monitor.current_gas_price().await,
+        ) {
+            (Ok(source_price), Ok(node_price)) => (source_price, node_price),
+            (Err(err), _) | (_, Err(err)) => {
+                tracing::warn!("Failed to compare gas price source to the node: {err:?}");
+                return priority_gas;
+            }
+        };
+        let bump = u64::try_from(source_price.saturating_sub(node_price)).unwrap_or(u64::MAX);
+        if bump == 0 {
+            return priority_gas;
+        }
+        tracing::debug!(
+            "Gas price source reports {source_price} wei above the node's {node_price} wei, raising lock priority gas by {bump}"
+        );
+        Some(priority_gas.unwrap_or_default().saturating_add(bump))
+    }
 
-    let gas_price = self
-        .chain_monitor
-        .current_gas_price()
-        .await
-        .context("Failed to get gas price")?;
-    let available_balance_wei = self
-        .provider
-        .get_balance(self.provider.default_signer_address())
-        .await
-        .map_err(|err| OrderMonitorErr::RpcErr(err.into()))?;
+    /// Returns the gas units needed to lock and/or fulfill an order, reusing the estimate cached
+    /// within the last [GAS_ESTIMATE_TTL] if any.
+    async fn order_gas_units(&self, order: &OrderRequest) -> Result<u64, OrderMonitorErr> {
+        let estimate_key = gas_estimate_key(order);
+        if let Some(gas_units) = self.gas_estimates.get(&estimate_key).await {
+            return Ok(gas_units);
+        }
 
-    let committed_orders = self.db.get_committed_orders().await?;
-    let committed_gas_units = futures::future::try_join_all(committed_orders.iter().map(|order| {
-        utils::estimate_gas_to_fulfill(
+        // Calculate gas units needed for this order (lock + fulfill)
+        let fulfill_gas_units = utils::estimate_gas_to_fulfill(
             &self.config,
             &self.supported_selectors,
             &order.request,
+            order.chain_id,
         )
-    }))
-    .await?
-    .iter()
-    .sum::<u64>();
+        .await?;
+        let gas_units = if order.fulfillment_type == FulfillmentType::LockAndFulfill {
+            utils::estimate_gas_to_lock(&self.config, order)
+                .await?
+                .saturating_add(fulfill_gas_units)
+        } else {
+            fulfill_gas_units
+        };
 
-    let committed_cost_wei = U256::from(gas_price) * U256::from(committed_gas_units);
+        self.gas_estimates.insert(estimate_key, gas_units).await;
+        Ok(gas_units)
+    }
 
-    let mut running_cost = committed_cost_wei;
-    for order in orders {
-        if final_orders.len() >= capacity_granted {
-            break;
+    /// Estimates the gas of cached orders whose target timestamp is within `prewarm_secs`, so the
+    /// estimates are ready by the time the orders are admitted.
+    async fn prewarm_gas_estimates(&self, block_timestamp: u64, prewarm_secs: u64) {
+        let orders = self.lock_and_prove_cache.iter().chain(self.prove_cache.iter());
+        for (_, order) in orders {
+            let Some(target_timestamp) = order.target_timestamp else {
+                continue;
+            };
+            if target_timestamp <= block_timestamp
+                || target_timestamp - block_timestamp > prewarm_secs
+            {
+                continue;
+            }
+            if let Err(err) = self.order_gas_units(&order).await {
+                tracing::debug!("Failed to pre-warm gas estimate for order {}: {err}", order.id());
+            }
         }
+    }
 
-        let gas_units = utils::estimate_gas_to_fulfill(
-            &self.config,
-            &self.supported_selectors,
-            &order.request,
-        )
-        .await?;
+    /// Calculate the gas units needed for an order and the corresponding cost in wei
+    async fn calculate_order_gas_cost_wei(
+        &self,
+        order: &OrderRequest,
+        gas_price: u128,
+    ) -> Result<U256, OrderMonitorErr> {
+        let order_gas_units = U256::from(self.order_gas_units(order).await?);
+        let order_cost_wei = U256::from(gas_price) * order_gas_units;
+
+        Ok(order_cost_wei)
+    }
 
-        let total_cost = U256::from(gas_price) * U256::from(gas_units);
-        if running_cost + total_cost > available_balance_wei {
-            continue;
+    /// Calculate the cost in wei of an order, including any surcharge configured for its proof
+    /// type.
+    async fn calculate_order_cost_wei(
+        &self,
+        config: &OrderMonitorConfig,
+        order: &OrderRequest,
+        gas_price: u128,
+    ) -> Result<U256, OrderMonitorErr> {
+        let surcharge_gas = match self.proof_type_policy(config, order) {
+            Some(ProofTypePolicy::SurchargeGas(gas)) => gas,
+            _ => 0,
+        };
+        let cost = self.calculate_order_gas_cost_wei(order, gas_price).await?;
+        Ok(cost.saturating_add(U256::from(gas_price) * U256::from(surcharge_gas)))
+    }
+
+    /// Returns the balance of the prover, including not yet mined transactions if
+    /// `use_pending_balance` is set.
+    async fn prover_balance(&self, config: &OrderMonitorConfig) -> Result<U256> {
+        if let Some(replay_conditions) = &self.replay_conditions {
+            return replay_conditions.balance_wei();
         }
+        self.throttle_rpc().await;
+        let balance = self.provider.get_balance(self.provider.default_signer_address());
+        let balance = if config.use_pending_balance { balance.pending() } else { balance };
+        let balance = balance.await.context("Failed to get balance")?;
+        self.record_tick(|tick| tick.balance_wei = Some(balance));
+        Ok(balance)
+    }
 
-        running_cost += total_cost;
-        final_orders.push(order);
+    /// Returns the gas units required to fulfill the committed orders.
+    async fn committed_gas_units(&self, committed_orders: &[Order]) -> Result<u64> {
+        Ok(futures::future::try_join_all(committed_orders.iter().map(|order| {
+            utils::estimate_gas_to_fulfill(
+                &self.config,
+                &self.supported_selectors,
+                &order.request,
+                order.chain_id,
+            )
+        }))
+        .await?
+        .iter()
+        .sum::<u64>())
     }
 
-    Ok(final_orders)
-}
+    /// Returns the record of an admission decision taken on the order now.
+    fn admission_audit_entry(
+        &self,
+        order: &OrderRequest,
+        gas_cost_wei: U256,
+        decision: AdmissionDecision,
+        reason: Option<&str>,
+    ) -> AdmissionAuditEntry {
+        let now = self.now();
+        AdmissionAuditEntry {
+            order_id: order.id(),
+            request_id: order.request.id,
+            decision,
+            reason: reason.map(str::to_string),
+            price_wei: order.request.offer.price_at(now).unwrap_or(order.request.offer.minPrice),
+            gas_cost_wei,
+            timestamp: now,
+        }
+    }
 
-impl<P> RetryTask for OrderMonitor<P>
-where
-    P: Provider<Ethereum> + WalletProvider + 'static + Clone,
-{
-    type Error = OrderMonitorErr;
-    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
-        let monitor_clone = self.clone();
-        Box::pin(async move {
-            tracing::info!("Starting order monitor");
-            monitor_clone.start_monitor(cancel_token).await.map_err(SupervisorErr::Recover)?;
-            Ok(())
-        })
+    /// Records the admission decisions of an iteration in the DB, pruning the decisions older than
+    /// `retention_secs` if non-zero.
+    async fn save_admission_audit(&self, audit: &[AdmissionAuditEntry], retention_secs: u64) {
+        if !audit.is_empty() {
+            if let Err(err) = self.db.insert_admission_audit(audit).await {
+                tracing::warn!("Failed to record {} admission decisions: {err:?}", audit.len());
+            }
+        }
+        if retention_secs > 0 {
+            let before = self.now().saturating_sub(retention_secs);
+            if let Err(err) = self.db.prune_admission_audit(before).await {
+                tracing::warn!("Failed to prune admission decisions before {before}: {err:?}");
+            }
+        }
     }
-}
 
-#[cfg(test)]
-pub(crate) mod tests {
-    use super::*;
-    use crate::OrderStatus;
-    use crate::{db::SqliteDb, now_timestamp, FulfillmentType};
-    use alloy::node_bindings::AnvilInstance;
-    use alloy::{
-        network::EthereumWallet,
-        node_bindings::Anvil,
-        primitives::{Address, U256},
-        providers::{
-            fillers::{
-                BlobGasFiller, ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller,
-                WalletFiller,
+    /// Returns the balance new orders may be admitted against, after the cost of the committed
+    /// orders and the configured reserve, including any expected incoming balance.
+    fn admissible_balance_wei(
+        &self,
+        config: &OrderMonitorConfig,
+        available_balance_wei: U256,
+        committed_cost_wei: U256,
+        num_committed_orders: usize,
+    ) -> U256 {
+        let mut balance_wei = available_balance_wei.saturating_sub(committed_cost_wei);
+
+        // Keep a reserve for fulfill transactions beyond the estimates of the committed orders, at
+        // the average cost of a committed order.
+        if let Some(reserve_txs) =
+            config.balance_reserve_per_committed_order.filter(|_| num_committed_orders > 0)
+        {
+            let average_cost_wei = committed_cost_wei / U256::from(num_committed_orders);
+            let reserve_wei = average_cost_wei.saturating_mul(U256::from(reserve_txs));
+            tracing::debug!(
+                "Reserving {} ether of balance for {reserve_txs} fulfill transactions at the average cost of {num_committed_orders} committed orders",
+                format_ether(reserve_wei)
+            );
+            balance_wei = balance_wei.saturating_sub(reserve_wei);
+        }
+
+        // Only new orders may rely on balance that has not arrived yet, and only until the
+        // expectation expires.
+        if let Some(incoming) = config.expected_incoming_balance {
+            if self.now() <= incoming.expires_at {
+                let incoming_wei = U256::from(incoming.amount_wei);
+                tracing::info!(
+                    "Including expected incoming balance of {} ether, expiring at {}, when admitting orders",
+                    format_ether(incoming_wei),
+                    incoming.expires_at
+                );
+                balance_wei = balance_wei.saturating_add(incoming_wei);
+            } else {
+                tracing::warn!(
+                    "Expected incoming balance expired at {}, not including it when admitting orders",
+                    incoming.expires_at
+                );
+            }
+        }
+        balance_wei
+    }
+
+    async fn apply_capacity_limits(
+        &self,
+        mut orders: Vec<Arc<OrderRequest>>,
+        config: &OrderMonitorConfig,
+        prev_orders_by_status: &mut String,
+    ) -> Result<Vec<Arc<OrderRequest>>> {
+        // Break ties between equally prioritized orders deterministically, so selection does not
+        // depend on the order in which orders were observed.
+        break_priority_ties(
+            &mut orders,
+            config.order_commitment_priority,
+            config.priority_addresses.as_deref(),
+            config.tie_breaker_seed,
+            config.deprioritize_unresolved_orders,
+        );
+
+        let num_orders = orders.len();
+        // Get our current capacity for proving orders given our config and the number of orders that are currently committed to be proven + fulfilled.
+        let capacity = self.get_proving_order_capacity(config, prev_orders_by_status).await?;
+        self.trace_tick(|trace| trace.capacity = Some(format!("{capacity:?}")));
+        let mut capacity_granted =
+            capacity.request_capacity(saturating_count(num_orders), self.max_proving_batch_size);
+        let committed_orders = self.db.get_committed_orders().await?;
+        if let Some(max_backlog) = config.max_settlement_backlog {
+            capacity_granted = self
+                .throttle_for_settlement_backlog(capacity_granted, max_backlog, &committed_orders)
+                .await;
+        }
+
+        self.log_on_change(
+            "orders ready",
+            format!(
+                "Num orders ready for locking and/or proving: {}. Total capacity available based on max_concurrent_proofs: {capacity:?}, Capacity granted this iteration: {capacity_granted:?}",
+                num_orders
+            ),
+            config.verbose_loop_logs,
+        );
+
+        let mut final_orders: Vec<Arc<OrderRequest>> =
+            Vec::with_capacity(capacity_granted as usize);
+
+        // Get current gas price and available balance
+        let gas_price = self.current_gas_price().await?;
+        let available_balance_wei =
+            self.prover_balance(config).await.map_err(OrderMonitorErr::RpcErr)?;
+
+        // Calculate gas units required for committed orders
+        let committed_gas_units = self.committed_gas_units(&committed_orders).await?;
+
+        // Calculate cost in wei for committed orders
+        let committed_cost_wei = U256::from(gas_price) * U256::from(committed_gas_units);
+
+        tracing::debug!(
+            "Cost for {} committed orders: {} ether",
+            committed_orders.len(),
+            format_ether(committed_cost_wei),
+        );
+
+        // Ensure we have enough for committed orders
+        let underwater = committed_cost_wei > available_balance_wei;
+        self.alert_balance_underwater(underwater, available_balance_wei, committed_cost_wei)?;
+        if underwater {
+            tracing::error!(
+                "Insufficient balance for committed orders. Current balance: {} ether, Total gas cost for committed orders: {} ether",
+                format_ether(available_balance_wei),
+                format_ether(committed_cost_wei)
+            );
+            if config.admission_audit {
+                // The orders are deferred before their gas cost is estimated.
+                let audit: Vec<_> = orders
+                    .iter()
+                    .map(|order| {
+                        self.admission_audit_entry(
+                            order,
+                            U256::ZERO,
+                            AdmissionDecision::Deferred,
+                            Some("insufficient balance"),
+                        )
+                    })
+                    .collect();
+                self.save_admission_audit(&audit, config.admission_audit_retention_secs).await;
+            }
+            return Ok(Vec::new());
+        }
+
+        let mut remaining_balance_wei = self.admissible_balance_wei(
+            config,
+            available_balance_wei,
+            committed_cost_wei,
+            committed_orders.len(),
+        );
+
+        // Lock stake posted by committed orders, counted against the total stake cap.
+        let mut committed_stake = committed_lock_stake(&committed_orders);
+
+        // Cycles that can still be committed to, if capacity is limited by a cycle budget.
+        let mut remaining_cycles = match capacity {
+            Capacity::Cycles(cycles) => Some(cycles),
+            _ => None,
+        };
+
+        // Estimate when the prover will be available given the peak khz limit, if specified.
+        let num_commited_orders = committed_orders.len();
+        let mut prover_available_at = config.peak_prove_khz.map(|peak_prove_khz| {
+            let total_commited_cycles = committed_orders
+                .iter()
+                .map(|order| {
+                    budgeted_cycles(order.total_cycles, config.fallback_cycles)
+                        + config.additional_proof_cycles
+                })
+                .sum::<u64>();
+
+            let now = self.now();
+            // Estimate the time the prover will be available given our current committed orders.
+            let started_proving_at = committed_orders
+                .iter()
+                .map(|order| order.proving_started_at.unwrap())
+                .min()
+                .unwrap_or(now);
+
+            let proof_time_seconds = total_commited_cycles.div_ceil(1_000).div_ceil(peak_prove_khz);
+            let mut prover_available_at = started_proving_at + proof_time_seconds;
+            if prover_available_at < now {
+                let seconds_behind = now - prover_available_at;
+                tracing::warn!("Proofs are behind what is estimated from peak_prove_khz config by {} seconds. Consider lowering this value to avoid overlocking orders.", seconds_behind);
+                prover_available_at = now;
+            }
+
+            tracing::debug!("Already committed to {} orders, with a total cycle count of {}, a peak khz limit of {}, started working on them at {}, we estimate the prover will be available in {} seconds",
+                num_commited_orders,
+                total_commited_cycles,
+                peak_prove_khz,
+                started_proving_at,
+                prover_available_at.saturating_sub(now),
+            );
+
+            prover_available_at
+        });
+
+        // Estimate the gas cost (lock + fulfill) of every candidate order concurrently up front,
+        // including any surcharge configured for the proof type of the order.
+        let costed_orders =
+            estimate_order_costs(orders, config.gas_estimation_concurrency, |order| async move {
+                self.calculate_order_cost_wei(config, &order, gas_price).await
+            })
+            .await?;
+
+        // Rank orders by their return on stake now that the gas price is known.
+        let mut costed_orders = if config.order_commitment_priority
+            == OrderCommitmentPriority::RoiAdjusted
+        {
+            let order_costs: HashMap<String, U256> =
+                costed_orders.iter().map(|(cost, order)| (order.id(), *cost)).collect();
+            let mut scored_orders: Vec<_> = costed_orders
+                .into_iter()
+                .map(|(cost, order)| (roi_adjusted_score(&order, cost), order))
+                .collect();
+            sort_orders_by_roi(&mut scored_orders, config.priority_addresses.as_deref());
+            scored_orders.into_iter().map(|(_, order)| (order_costs[&order.id()], order)).collect()
+        } else {
+            costed_orders
+        };
+        // Zero-stake orders are moved behind the others before the tiers are applied, so that they
+        // are deprioritized within their tier.
+        if config.zero_stake_policy == Some(ZeroStakePolicy::Deprioritize) {
+            deprioritize_zero_stake(&mut costed_orders);
+        }
+        sort_orders_by_tier(&mut costed_orders);
+
+        // Decisions on the evaluated orders, recorded in the DB if configured.
+        let mut audit = Vec::new();
+
+        // Select the orders that maximize the number proven before their expiration rather than
+        // admitting greedily in priority order.
+        let costed_orders = match (config.optimize_khz_allocation, config.peak_prove_khz) {
+            (true, Some(peak_prove_khz)) => {
+                let available_at = prover_available_at.unwrap_or_else(|| self.now());
+                let jobs: Vec<(u64, u64)> = costed_orders
+                    .iter()
+                    .map(|(_, order)| {
+                        let order_cycles =
+                            budgeted_cycles(order.total_cycles, config.fallback_cycles)
+                                + config.additional_proof_cycles;
+                        (
+                            order_cycles.div_ceil(1_000).div_ceil(peak_prove_khz),
+                            order.expiry().saturating_sub(
+                                config.batch_buffer_time_secs
+                                    + config.fulfillment_safety_margin_secs,
+                            ),
+                        )
+                    })
+                    .collect();
+                let selected = max_jobs_before_deadlines(&jobs, available_at);
+
+                let mut costed_orders: Vec<_> = costed_orders.into_iter().map(Some).collect();
+                let mut allocated_orders: Vec<_> =
+                    selected.iter().filter_map(|&idx| costed_orders[idx].take()).collect();
+                for (idx, costed_order) in costed_orders.into_iter().enumerate() {
+                    let Some(costed_order) = costed_order else {
+                        continue;
+                    };
+                    let (duration, deadline) = jobs[idx];
+                    if costed_order.1.urgent {
+                        // Our stake is at risk, so it is never deferred for other orders.
+                        allocated_orders.push(costed_order);
+                    } else if available_at + duration > deadline {
+                        // Cannot be completed in time regardless, left to be skipped below.
+                        allocated_orders.push(costed_order);
+                    } else {
+                        tracing::debug!(
+                            "Order {} deferred, proving it would prevent more orders from completing before their expiration",
+                            costed_order.1.id()
+                        );
+                        if config.admission_audit {
+                            audit.push(self.admission_audit_entry(
+                                &costed_order.1,
+                                costed_order.0,
+                                AdmissionDecision::Deferred,
+                                Some("khz allocation"),
+                            ));
+                        }
+                    }
+                }
+                allocated_orders
+            }
+            _ => costed_orders,
+        };
+
+        // Admit orders close to their deadline first regardless of their priority, so that they
+        // are not starved by orders that can still wait.
+        let mut costed_orders = match config.urgent_deadline_secs {
+            Some(urgent_secs) => {
+                let (mut urgent_orders, other_orders): (Vec<_>, Vec<_>) = costed_orders
+                    .into_iter()
+                    .partition(|(_, order)| self.is_urgent(order, urgent_secs));
+                if !urgent_orders.is_empty() {
+                    tracing::debug!(
+                        "Prioritizing {} orders expiring within {urgent_secs} seconds: {:?}",
+                        urgent_orders.len(),
+                        urgent_orders.iter().map(|(_, order)| order.id()).collect::<Vec<_>>()
+                    );
+                }
+                urgent_orders.sort_by_key(|(_, order)| order.expiry());
+                urgent_orders.extend(other_orders);
+                urgent_orders
+            }
+            None => costed_orders,
+        };
+        // Orders locked by us close to expiring go ahead of every other order, as our stake is at
+        // risk if they are not proven in time.
+        costed_orders.sort_by_key(|(_, order)| !order.urgent);
+
+        // Withhold the salvage reserve from lock-and-fulfill orders, but only as far as orders
+        // whose lock expired compete for it so that capacity does not go idle.
+        let lock_and_fulfill_limit = match (config.salvage_capacity_reserve, capacity) {
+            (Some(reserve), Capacity::Available(_)) => {
+                let salvage_orders = costed_orders
+                    .iter()
+                    .filter(|(_, order)| {
+                        order.fulfillment_type == FulfillmentType::FulfillAfterLockExpire
+                    })
+                    .count();
+                let reserved_slots =
+                    reserve.slots(config.max_concurrent_proofs.unwrap_or_default());
+                (capacity_granted as usize)
+                    .saturating_sub((reserved_slots as usize).min(salvage_orders))
+            }
+            _ => capacity_granted as usize,
+        };
+        let mut lock_and_fulfill_admitted = 0;
+
+        let mut record = |order: &OrderRequest, gas_cost_wei, decision, reason: Option<&str>| {
+            if config.admission_audit {
+                audit.push(self.admission_audit_entry(order, gas_cost_wei, decision, reason));
+            }
+        };
+        // Orders skipped for transient reasons are only deferred if they are retained.
+        let transient_skip = if config.retain_transient_skips {
+            AdmissionDecision::Deferred
+        } else {
+            AdmissionDecision::Skipped
+        };
+
+        // For each order in consideration, check if it can be completed before its expiration
+        // and that there is enough gas to pay for the lock and fulfillment of all orders
+        // including the committed orders.
+        for (order_cost_wei, order) in costed_orders {
+            if final_orders.len() >= capacity_granted as usize {
+                record(&order, order_cost_wei, AdmissionDecision::Deferred, Some("capacity"));
+                continue;
+            }
+
+            let is_lock_and_fulfill = order.fulfillment_type == FulfillmentType::LockAndFulfill;
+            if is_lock_and_fulfill && lock_and_fulfill_admitted >= lock_and_fulfill_limit {
+                tracing::debug!(
+                    "Order {} deferred, remaining capacity is reserved for orders whose lock expired",
+                    order.id()
+                );
+                record(
+                    &order,
+                    order_cost_wei,
+                    AdmissionDecision::Deferred,
+                    Some("capacity reserved for salvage"),
+                );
+                continue;
+            }
+
+            // Skip if not enough balance for this order
+            if order_cost_wei > remaining_balance_wei {
+                tracing::warn!(
+                    "Insufficient balance for order {}. Required: {} ether, Remaining: {} ether",
+                    order.id(),
+                    format_ether(order_cost_wei),
+                    format_ether(remaining_balance_wei)
+                );
+                record(
+                    &order,
+                    order_cost_wei,
+                    AdmissionDecision::Deferred,
+                    Some("insufficient balance"),
+                );
+                continue;
+            }
+
+            // Skip if locking the order would commit more stake than allowed in total.
+            if let (true, Some(max_total_stake)) =
+                (is_lock_and_fulfill, config.max_total_stake_committed)
+            {
+                let total_stake = committed_stake.saturating_add(order.request.offer.lockStake);
+                if total_stake > max_total_stake {
+                    tracing::info!(
+                        "Order {} lock stake of {} would take the total committed stake to {total_stake}, above the maximum of {max_total_stake}. Skipping",
+                        order.id(),
+                        order.request.offer.lockStake
+                    );
+                    self.skip_order_transient(
+                        &order,
+                        "stake cap reached",
+                        config.retain_transient_skips,
+                    )
+                    .await;
+                    record(&order, order_cost_wei, transient_skip, Some("stake cap reached"));
+                    continue;
+                }
+            }
+
+            // Skip if the order is expected to be less profitable than required after gas and
+            // proving costs, at the lowest price it may be locked at.
+            if let (Some(min_profit_wei), Some(profit_wei)) = (
+                config.min_profit_wei_for(order.chain_id),
+                config.expected_profit_wei(&order, order_cost_wei),
+            ) {
+                if profit_wei < min_profit_wei {
+                    tracing::info!(
+                        "Order {} expected profit after gas and {} wei of proving cost of {profit_wei} wei is below the minimum of {min_profit_wei} wei. Skipping",
+                        order.id(),
+                        config.proving_cost_wei(&order)
+                    );
+                    self.skip_order_transient(
+                        &order,
+                        "below minimum profit",
+                        config.retain_transient_skips,
+                    )
+                    .await;
+                    record(&order, order_cost_wei, transient_skip, Some("below minimum profit"));
+                    continue;
+                }
+            }
+
+            if order.total_cycles.is_none()
+                && config.fallback_cycles.is_none()
+                && (remaining_cycles.is_some() || config.peak_prove_khz.is_some())
+            {
+                tracing::warn!(
+                    "Order {} has an unknown cycle count and no fallback_order_cycles is configured, budgeting it as zero cycles",
+                    order.id()
+                );
+            }
+
+            // Defer if the order does not fit within the remaining cycle budget this iteration,
+            // smaller orders may still be admitted.
+            let order_cycles = budgeted_cycles(order.total_cycles, config.fallback_cycles)
+                + config.additional_proof_cycles;
+            if let Some(remaining) = remaining_cycles {
+                if order_cycles > remaining {
+                    tracing::debug!(
+                        "Order {} requires {order_cycles} cycles, exceeding the remaining cycle budget of {remaining}. Deferring",
+                        order.id()
+                    );
+                    record(
+                        &order,
+                        order_cost_wei,
+                        AdmissionDecision::Deferred,
+                        Some("exceeds cycle budget"),
+                    );
+                    continue;
+                }
+            }
+
+            if let (Some(peak_prove_khz), Some(available_at)) =
+                (config.peak_prove_khz, prover_available_at)
+            {
+                let proof_time_seconds = order_cycles.div_ceil(1_000).div_ceil(peak_prove_khz);
+                let completion_time = available_at + proof_time_seconds;
+                let expiration = order.expiry();
+
+                if completion_time
+                    + config.batch_buffer_time_secs
+                    + config.fulfillment_safety_margin_secs
+                    > expiration
+                {
+                    // If the order cannot be completed before its expiration, skip it permanently.
+                    tracing::info!("Order 0x{:x} cannot be completed before its expiration at {}, proof estimated to take {} seconds and complete at {}. Skipping",
+                        order.request.id,
+                        expiration,
+                        proof_time_seconds,
+                        completion_time
+                    );
+                    self.skip_order(&order, "cannot be completed before expiration").await;
+                    record(
+                        &order,
+                        order_cost_wei,
+                        AdmissionDecision::Skipped,
+                        Some("cannot be completed before expiration"),
+                    );
+                    continue;
+                }
+
+                prover_available_at = Some(completion_time);
+            }
+
+            remaining_balance_wei -= order_cost_wei;
+            if let Some(remaining) = remaining_cycles.as_mut() {
+                *remaining -= order_cycles;
+            }
+            if is_lock_and_fulfill {
+                lock_and_fulfill_admitted += 1;
+                committed_stake = committed_stake.saturating_add(order.request.offer.lockStake);
+            }
+            record(&order, order_cost_wei, AdmissionDecision::Admitted, None);
+            final_orders.push(order);
+        }
+
+        if config.admission_audit {
+            self.save_admission_audit(&audit, config.admission_audit_retention_secs).await;
+        }
+
+        self.log_on_change(
+            "orders admitted",
+            format!(
+                "Started with {} orders ready to be locked and/or proven. Already commited to {} orders. After applying capacity limits of {} max concurrent proofs and {} peak khz, filtered to {} orders: {:?}",
+                num_orders,
+                num_commited_orders,
+                if let Some(max_concurrent_proofs) = config.max_concurrent_proofs {
+                    max_concurrent_proofs.to_string()
+                } else {
+                    "unlimited".to_string()
+                },
+                if let Some(peak_prove_khz) = config.peak_prove_khz {
+                    peak_prove_khz.to_string()
+                } else {
+                    "unlimited".to_string()
+                },
+                final_orders.len(),
+                final_orders.iter().map(|order| order.id()).collect::<Vec<_>>()
+            ),
+            config.verbose_loop_logs,
+        );
+
+        Ok(final_orders)
+    }
+
+    async fn handle_new_order_result(&self, order: Box<OrderRequest>) -> Result<()> {
+        if let Some(session_recorder) = &self.session_recorder {
+            session_recorder.record_order(&order);
+        }
+        let config = self.received_order_config()?;
+        if let Some(chain_id) = self.chain_id {
+            if self.is_mismatched_chain(&order) {
+                if config.allow_mismatched_chain_orders {
+                    tracing::warn!(
+                        "Order {} is for chain {} and market {}, while connected to chain {chain_id} and market {}",
+                        order.id(),
+                        order.chain_id,
+                        order.boundless_market_address,
+                        self.market_addr
+                    );
+                } else {
+                    tracing::warn!(
+                        "Rejecting order {}, it is for chain {} and market {} while connected to chain {chain_id} and market {}",
+                        order.id(),
+                        order.chain_id,
+                        order.boundless_market_address,
+                        self.market_addr
+                    );
+                    self.skip_order(&order, "chain or market mismatch").await;
+                    return Ok(());
+                }
+            }
+        }
+        if config.denied_in_prove_only(&order) {
+            tracing::debug!("Dropping order {}, it requires a lock in prove-only mode", order.id());
+            self.skip_order(&order, "prove-only mode").await;
+            return Ok(());
+        }
+        // Orders are keyed by their request id, so it must belong to the client that signed the
+        // request.
+        if let Err(err) = verify_request_id(&order) {
+            tracing::warn!(
+                "Rejecting order {}, the request id does not match the client that signed it: {err}",
+                order.id()
+            );
+            self.skip_order(&order, "invalid request id").await;
+            return Ok(());
+        }
+        if config.beyond_target_horizon(&order, self.now()) {
+            tracing::debug!(
+                "Rejecting order {}, its target timestamp {} is more than {} seconds ahead",
+                order.id(),
+                order.target_timestamp.unwrap_or_default(),
+                config.max_target_timestamp_horizon_secs.unwrap_or_default()
+            );
+            self.skip_order(&order, "target beyond horizon").await;
+            return Ok(());
+        }
+        if config.reject_unprofitable_at_ingestion && self.below_cost_floor(&order, &config).await {
+            self.skip_order(&order, "max price below cost floor").await;
+            return Ok(());
+        }
+        // Note: this could be done without waiting for the batch to minimize latency, but
+        //       avoiding more complicated logic for checking capacity for each order.
+        self.cache_order(Arc::from(order)).await;
+        Ok(())
+    }
+
+    /// Returns whether the order is for another chain or market than the one the monitor is
+    /// connected to. Orders are never mismatched if the chain is not known.
+    fn is_mismatched_chain(&self, order: &OrderRequest) -> bool {
+        self.chain_id.is_some_and(|chain_id| {
+            order.chain_id != chain_id || order.boundless_market_address != self.market_addr
+        })
+    }
+
+    /// Returns whether the order's max price can never cover the minimum profit and its
+    /// estimated gas and proving costs at the current gas price, however far its price ramps up.
+    ///
+    /// Only lock-and-fulfill orders are paid their offer price, so other orders are never below
+    /// the floor. Orders whose costs cannot be estimated are left to the checks of each iteration.
+    async fn below_cost_floor(&self, order: &OrderRequest, config: &OrderMonitorConfig) -> bool {
+        if order.fulfillment_type != FulfillmentType::LockAndFulfill {
+            return false;
+        }
+        let Some(min_profit_wei) = config.min_profit_wei_for(order.chain_id) else {
+            return false;
+        };
+        let gas_cost_wei = match self.current_gas_price().await {
+            Ok(gas_price) => self.calculate_order_cost_wei(config, order, gas_price).await,
+            Err(err) => Err(OrderMonitorErr::UnexpectedError(err)),
+        };
+        let gas_cost_wei = match gas_cost_wei {
+            Ok(gas_cost_wei) => gas_cost_wei,
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to estimate the cost of order {} at ingestion: {err:?}",
+                    order.id()
+                );
+                return false;
+            }
+        };
+        let max_profit_wei = config.max_profit_wei(order, gas_cost_wei);
+        if max_profit_wei >= min_profit_wei {
+            return false;
+        }
+        tracing::info!(
+            "Rejecting order {}, its max price of {} leaves a profit of at most {max_profit_wei} wei, below the minimum of {min_profit_wei} wei",
+            order.id(),
+            format_wei(order.request.offer.maxPrice)
+        );
+        true
+    }
+
+    /// Sends an order whose lock expired before we locked it back to the order picker, to be
+    /// priced as an order that can be fulfilled after its lock expired.
+    fn reingest_lock_expired_order(&self, order: &OrderRequest) {
+        let Some(reingest_tx) = &self.reingest_tx else {
+            return;
+        };
+        let reingested = OrderRequest::new(
+            order.request.clone(),
+            order.client_sig.clone(),
+            FulfillmentType::FulfillAfterLockExpire,
+            order.boundless_market_address,
+            order.chain_id,
+        );
+        match reingest_tx.try_send(Box::new(reingested)) {
+            Ok(()) => tracing::info!(
+                "Re-ingesting request 0x{:x} to fulfill after its lock expired",
+                order.request.id
+            ),
+            Err(err) => tracing::warn!(
+                "Failed to re-ingest request 0x{:x} after its lock expired: {err}",
+                order.request.id
+            ),
+        }
+    }
+
+    /// Inserts the order into the cache for its fulfillment type.
+    async fn cache_order(&self, order: Arc<OrderRequest>) {
+        let (cache, stats) = match order.fulfillment_type {
+            FulfillmentType::LockAndFulfill => {
+                (&self.lock_and_prove_cache, &self.lock_and_prove_cache_stats)
+            }
+            FulfillmentType::FulfillAfterLockExpire | FulfillmentType::FulfillWithoutLocking => {
+                (&self.prove_cache, &self.prove_cache_stats)
+            }
+        };
+        stats.inserts.fetch_add(1, Ordering::Relaxed);
+        cache.insert(order.id(), order).await;
+    }
+
+    /// Returns the totals of the changes made to the order caches.
+    pub fn cache_stats(&self) -> OrderCacheStats {
+        OrderCacheStats {
+            lock_and_prove: self.lock_and_prove_cache_stats.snapshot(&self.lock_and_prove_cache),
+            prove: self.prove_cache_stats.snapshot(&self.prove_cache),
+        }
+    }
+
+    /// Applies `f` to the trace of the current iteration, if one is being collected.
+    fn trace_tick(&self, f: impl FnOnce(&mut TickTrace)) {
+        if let Ok(mut trace) = self.tick_trace.lock() {
+            if let Some(trace) = trace.as_mut() {
+                f(trace);
+            }
+        }
+    }
+
+    /// Applies `f` to the recorded tick of the current iteration, if a session is being recorded.
+    fn record_tick(&self, f: impl FnOnce(&mut RecordedTick)) {
+        if let Some(session_recorder) = &self.session_recorder {
+            session_recorder.update_tick(f);
+        }
+    }
+
+    /// Reads the config applied to received orders, falling back to the last valid config if the
+    /// current one is invalid, so that a bad hot reload does not stop the ingestion of orders.
+    fn received_order_config(&self) -> Result<Arc<OrderMonitorConfig>, OrderMonitorErr> {
+        let Ok(mut last_valid_config) = self.last_valid_config.lock() else {
+            return Ok(Arc::new(self.load_monitor_config()?));
+        };
+        match self.load_monitor_config() {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *last_valid_config = Some(config.clone());
+                Ok(config)
+            }
+            Err(err) => match last_valid_config.as_ref() {
+                Some(config) => {
+                    tracing::warn!("Failed to load config, applying the last valid one: {err}");
+                    Ok(config.clone())
+                }
+                None => Err(err),
             },
-            ProviderBuilder, RootProvider,
-        },
-        signers::local::PrivateKeySigner,
-    };
-    use boundless_market::contracts::{
-        Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput, RequestInputType,
-        Requirements,
-    };
-    use boundless_market_test_utils::{
-        deploy_boundless_market, deploy_hit_points, ASSESSOR_GUEST_ID, ASSESSOR_GUEST_PATH,
-    };
+        }
+    }
 
-    use risc0_zkvm::Digest;
-    use std::{future::Future, sync::Arc};
-    use tokio::task::JoinSet;
-    use tracing_test::traced_test;
+    /// Reads the parts of the config relevant to the monitor.
+    fn load_monitor_config(&self) -> Result<OrderMonitorConfig, OrderMonitorErr> {
+        let config = self.config.lock_all().context("Failed to read config")?;
+        Ok(OrderMonitorConfig {
+            min_deadline: config.market.min_deadline,
+            peak_prove_khz: config.market.peak_prove_khz,
+            max_concurrent_proofs: config.market.max_concurrent_proofs,
+            max_committed_cycles: config.market.max_committed_cycles,
+            additional_proof_cycles: config.market.additional_proof_cycles,
+            batch_buffer_time_secs: config.batcher.block_deadline_buffer_secs,
+            order_commitment_priority: config.market.order_commitment_priority,
+            priority_addresses: config.market.priority_requestor_addresses.clone(),
+            gas_estimation_concurrency: config.market.gas_estimation_concurrency,
+            loop_deadline: config.market.monitor_loop_deadline_secs.map(Duration::from_secs),
+            min_profit_wei: config.market.min_profit_wei,
+            tie_breaker_seed: config.market.order_tie_breaker_seed,
+            salvage_capacity_reserve: config.market.salvage_capacity_reserve,
+            fallback_cycles: config.market.fallback_order_cycles,
+            chains: config.market.chains.clone(),
+            proof_type_policies: config.market.proof_type_policies.clone(),
+            max_target_horizon_secs: config.market.max_target_horizon_secs,
+            optimize_khz_allocation: config.market.optimize_khz_allocation,
+            max_single_order_lock_stake: config
+                .market
+                .max_single_order_lock_stake
+                .as_ref()
+                .map(|s| {
+                    parse_units(s, self.stake_token_decimals).map(Into::into).with_context(|| {
+                        format!("Invalid market.max_single_order_lock_stake in config: {s:?}")
+                    })
+                })
+                .transpose()?,
+            min_deadline_fraction: config.market.min_deadline_fraction,
+            expected_incoming_balance: config.market.expected_incoming_balance,
+            urgent_deadline_secs: config.market.urgent_deadline_secs,
+            max_total_stake_committed: config
+                .market
+                .max_total_stake_committed
+                .as_ref()
+                .map(|s| {
+                    parse_units(s, self.stake_token_decimals).map(Into::into).with_context(|| {
+                        format!("Invalid market.max_total_stake_committed in config: {s:?}")
+                    })
+                })
+                .transpose()?,
+            lock_record_min_age_secs: config.market.lock_record_min_age_secs,
+            lock_price_window_secs: config.market.lock_price_window_secs.unwrap_or_default(),
+            reingest_lock_expired_orders: config.market.reingest_lock_expired_orders,
+            gas_estimate_prewarm_secs: config.market.gas_estimate_prewarm_secs,
+            deprioritize_unresolved_orders: config.market.deprioritize_unresolved_orders,
+            use_pending_balance: config.market.use_pending_balance,
+            max_chain_head_age_secs: config.market.max_chain_head_age_secs,
+            proving_cost_per_cycle_wei: config.market.proving_cost_per_cycle_wei,
+            retain_transient_skips: config.market.retain_transient_skips,
+            min_concurrent_proofs: config.market.min_concurrent_proofs,
+            max_lock_risk: config.market.max_lock_risk,
+            skip_unchanged_admission: config.market.skip_unchanged_admission,
+            reject_unprofitable_at_ingestion: config.market.reject_unprofitable_at_ingestion,
+            stake_topup: config
+                .market
+                .auto_stake_topup
+                .as_ref()
+                .map(|topup| {
+                    let parse_stake = |s: &str, field: &str| {
+                        parse_units(s, self.stake_token_decimals)
+                            .map(Into::into)
+                            .with_context(|| format!("Invalid market.{field} in config: {s:?}"))
+                    };
+                    let threshold = config.market.stake_balance_warn_threshold.as_deref().context(
+                        "market.auto_stake_topup requires market.stake_balance_warn_threshold",
+                    )?;
+                    Ok::<_, anyhow::Error>(StakeTopUpConfig {
+                        threshold: parse_stake(threshold, "stake_balance_warn_threshold")?,
+                        target: parse_stake(&topup.target, "auto_stake_topup.target")?,
+                        cooldown: Duration::from_secs(topup.cooldown_secs),
+                    })
+                })
+                .transpose()?,
+            max_target_timestamp_horizon_secs: config.market.max_target_timestamp_horizon_secs,
+            verbose_loop_logs: config.market.verbose_loop_logs,
+            balance_reserve_per_committed_order: config.market.balance_reserve_per_committed_order,
+            capacity_accounting: config.market.capacity_accounting,
+            zero_stake_policy: config.market.zero_stake_policy,
+            admission_audit: config.market.admission_audit,
+            admission_audit_retention_secs: config.market.admission_audit_retention_secs,
+            fulfillment_safety_margin_secs: config.market.fulfillment_safety_margin_secs,
+            allow_mismatched_chain_orders: config.market.allow_mismatched_chain_orders,
+            prove_only: config.market.prove_only,
+            max_settlement_backlog: config.market.max_settlement_backlog,
+            stake_balance_warn_threshold: config
+                .market
+                .stake_balance_warn_threshold
+                .as_ref()
+                .map(|s| {
+                    parse_units(s, self.stake_token_decimals).map(Into::into).with_context(|| {
+                        format!("Invalid market.stake_balance_warn_threshold in config: {s:?}")
+                    })
+                })
+                .transpose()?,
+            stake_balance_error_threshold: config
+                .market
+                .stake_balance_error_threshold
+                .as_ref()
+                .map(|s| {
+                    parse_units(s, self.stake_token_decimals).map(Into::into).with_context(|| {
+                        format!("Invalid market.stake_balance_error_threshold in config: {s:?}")
+                    })
+                })
+                .transpose()?,
+        })
+    }
 
-    type TestProvider = FillProvider<
-        JoinFill<
-            JoinFill<
-                alloy::providers::Identity,
-                JoinFill<GasFiller, JoinFill<BlobGasFiller, JoinFill<NonceFiller, ChainIdFiller>>>,
-            >,
-            WalletFiller<EthereumWallet>,
-        >,
-        RootProvider,
-    >;
+    /// Immediately re-runs an iteration of the monitor if the config relevant to it changed since
+    /// `applied_config`, logging the changed fields. Returns whether an iteration was run.
+    async fn reevaluate_on_config_change(
+        &self,
+        applied_config: &mut Option<OrderMonitorConfig>,
+        prev_orders_by_status: &mut String,
+    ) -> Result<bool, OrderMonitorErr> {
+        let Some(prev_config) = applied_config.as_ref() else {
+            return Ok(false);
+        };
+        let monitor_config = self.load_monitor_config()?;
+        let changed_fields = prev_config.changed_fields(&monitor_config);
+        if changed_fields.is_empty() {
+            return Ok(false);
+        }
 
-    pub struct TestCtx {
-        pub monitor: OrderMonitor<TestProvider>,
-        pub anvil: AnvilInstance,
-        pub db: DbObj,
-        pub market_address: Address,
-        #[allow(dead_code)]
-        pub config: ConfigLock,
-        pub priced_order_tx: mpsc::Sender<Box<OrderRequest>>,
-        pub signer: PrivateKeySigner,
-        pub market_service: BoundlessMarketService<Arc<TestProvider>>,
-        next_order_id: u32, // Counter to assign unique order IDs
+        tracing::info!(
+            "Order monitor config changed ({}), re-evaluating orders",
+            changed_fields.join(", ")
+        );
+        self.reset_admission_fingerprint();
+        let ChainHead { block_number, block_timestamp } =
+            self.chain_monitor.current_chain_head().await?;
+        *applied_config = Some(monitor_config);
+        self.run_tick(
+            block_number,
+            block_timestamp,
+            applied_config.as_ref().unwrap(),
+            prev_orders_by_status,
+        )
+        .await?;
+        Ok(true)
     }
 
-    impl TestCtx {
-        // Convert the standalone function to a method on TestCtx
-        pub async fn create_test_order(
-            &mut self,
-            fulfillment_type: FulfillmentType,
-            bidding_start: u64,
-            lock_timeout: u64,
-            timeout: u64,
-        ) -> Box<OrderRequest> {
-            let request_id = self.next_order_id;
-            self.next_order_id += 1;
+    /// Replays a recorded session, returning the session recorded while replaying it so that its
+    /// admitted orders can be compared to the recorded ones.
+    ///
+    /// The recorded orders are received and the iterations run against the recorded clock, gas
+    /// prices and balances. No transaction is sent, locks are assumed to succeed at the price of
+    /// the order at the time, so the replay diverges from the recording where a lock failed. Orders
+    /// are only prioritized deterministically if the commitment priority is not random.
+    pub(crate) async fn replay(self, session: &RecordedSession) -> Result<RecordedSession> {
+        let replay_conditions = Arc::new(ReplayConditions::default());
+        let session_recorder = Arc::new(SessionRecorder::default());
+        let monitor = Self {
+            replay_conditions: Some(replay_conditions.clone()),
+            session_recorder: Some(session_recorder.clone()),
+            ..self
+        }
+        .with_clock(replay_conditions.clone())
+        .with_gas_price_source(replay_conditions.clone());
+
+        let mut prev_orders_by_status = String::new();
+        for tick in &session.ticks {
+            replay_conditions.set(tick);
+            for order in &tick.orders {
+                monitor.handle_new_order_result(Box::new(order.clone())).await?;
+            }
+            let monitor_config = monitor.load_monitor_config()?;
+            monitor
+                .run_tick(
+                    tick.block_number,
+                    tick.block_timestamp,
+                    &monitor_config,
+                    &mut prev_orders_by_status,
+                )
+                .await?;
+        }
 
-            let request = ProofRequest::new(
-                RequestId::new(self.signer.address(), request_id),
-                Requirements::new(
-                    Digest::ZERO,
-                    Predicate {
-                        predicateType: PredicateType::PrefixMatch,
-                        data: Default::default(),
-                    },
-                ),
-                "http://risczero.com/image",
-                RequestInput { inputType: RequestInputType::Inline, data: Default::default() },
-                Offer {
-                    minPrice: U256::from(1),
+        Ok(session_recorder.session())
+    }
+
+    /// Runs a single iteration of the monitor, collecting and emitting a [TickTrace] if a trace
+    /// receiver is registered or the tick history is enabled.
+    async fn run_tick(
+        &self,
+        block_number: u64,
+        block_timestamp: u64,
+        monitor_config: &OrderMonitorConfig,
+        prev_orders_by_status: &mut String,
+    ) -> Result<(), OrderMonitorErr> {
+        if let Some(session_recorder) = &self.session_recorder {
+            session_recorder.start_tick(block_number, block_timestamp, self.now());
+        }
+        let history_enabled = self.tick_history.lock().is_ok_and(|history| history.capacity > 0);
+        if self.tick_trace_tx.is_some() || history_enabled {
+            let pulled = self
+                .prove_cache
+                .iter()
+                .chain(self.lock_and_prove_cache.iter())
+                .map(|(order_id, _)| order_id.to_string())
+                .collect();
+            if let Ok(mut trace) = self.tick_trace.lock() {
+                *trace =
+                    Some(TickTrace { block_number, block_timestamp, pulled, ..Default::default() });
+            }
+        }
+
+        self.spawn_stake_topup(monitor_config);
+
+        let res = self.process_orders(block_timestamp, monitor_config, prev_orders_by_status).await;
+        tracing::trace!("Order cache stats: {:?}", self.cache_stats());
+        if let Some(session_recorder) = &self.session_recorder {
+            session_recorder.finish_tick();
+        }
+
+        let trace = self.tick_trace.lock().ok().and_then(|mut trace| trace.take());
+        if let Some(trace) = trace {
+            if history_enabled {
+                if let Ok(mut history) = self.tick_history.lock() {
+                    history.record(TickSummary::from(&trace));
+                }
+            }
+            if let Some(tick_trace_tx) = &self.tick_trace_tx {
+                if let Err(err) = tick_trace_tx.try_send(trace) {
+                    tracing::warn!("Failed to emit tick trace for block {block_number}: {err}");
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Spawns a deposit of stake restoring the stake balance to the target of `auto_stake_topup`
+    /// once it falls below `stake_balance_warn_threshold`, so that locking is not held up while the
+    /// deposit confirms. Nothing is spawned while a top-up is in progress or within the cooldown of
+    /// the last deposit.
+    fn spawn_stake_topup(&self, config: &OrderMonitorConfig) -> Option<JoinHandle<()>> {
+        let topup = config.stake_topup?;
+        // A standby leaves depositing from the shared wallet to the active monitor, and stake is
+        // only used to lock, which never happens in prove-only mode.
+        if self.role() == MonitorRole::Standby || config.prove_only {
+            return None;
+        }
+        let Ok(mut last_stake_topup) = self.last_stake_topup.clone().try_lock_owned() else {
+            return None;
+        };
+        if last_stake_topup.is_some_and(|deposited_at| deposited_at.elapsed() < topup.cooldown) {
+            return None;
+        }
+
+        let market = self.market.clone();
+        let prover_addr = self.prover_addr;
+        let stake_token_decimals = self.stake_token_decimals;
+        Some(tokio::spawn(async move {
+            let res = top_up_stake(
+                &market,
+                prover_addr,
+                topup,
+                stake_token_decimals,
+                &mut last_stake_topup,
+            )
+            .await;
+            if let Err(err) = res {
+                tracing::error!("Failed to top up stake balance: {err:?}");
+            }
+        }))
+    }
+
+    /// Filters, prioritizes and applies capacity limits to the cached orders, then locks and/or
+    /// proves the resulting orders.
+    async fn process_orders(
+        &self,
+        block_timestamp: u64,
+        monitor_config: &OrderMonitorConfig,
+        prev_orders_by_status: &mut String,
+    ) -> Result<(), OrderMonitorErr> {
+        if self.check_chain_stalled(block_timestamp, monitor_config) {
+            // Leave the orders in the caches to be evaluated once the chain head advances.
+            return Ok(());
+        }
+
+        let deadline =
+            monitor_config.loop_deadline.map(|loop_deadline| Instant::now() + loop_deadline);
+        if let Some(prewarm_secs) = monitor_config.gas_estimate_prewarm_secs {
+            self.prewarm_gas_estimates(block_timestamp, prewarm_secs).await;
+        }
+        let valid_orders = self.get_valid_orders(block_timestamp, monitor_config, deadline).await?;
+
+        if valid_orders.is_empty() {
+            return Ok(());
+        }
+
+        let fingerprint = if monitor_config.skip_unchanged_admission {
+            let fingerprint = self.admission_fingerprint_of(&valid_orders).await?;
+            let unchanged = self.admission_fingerprint.lock().is_ok_and(|last| {
+                last.is_some_and(|(last_fingerprint, last_timestamp)| {
+                    last_fingerprint == fingerprint
+                        && block_timestamp < last_timestamp + MAX_UNCHANGED_ADMISSION_SKIP.as_secs()
+                })
+            });
+            if unchanged {
+                tracing::debug!("Orders unchanged since the last admission pass, skipping it");
+                return Ok(());
+            }
+            Some(fingerprint)
+        } else {
+            None
+        };
+
+        let prioritized_orders = self.prioritize_orders(
+            valid_orders,
+            monitor_config.order_commitment_priority,
+            monitor_config.priority_addresses.as_deref(),
+        );
+        self.trace_tick(|trace| {
+            trace.prioritized = prioritized_orders.iter().map(|order| order.id()).collect()
+        });
+
+        let final_orders = self
+            .apply_capacity_limits(prioritized_orders, monitor_config, prev_orders_by_status)
+            .await?;
+
+        self.trace_tick(|trace| {
+            trace.admitted = final_orders.iter().map(|order| order.id()).collect()
+        });
+        self.record_tick(|tick| {
+            tick.admitted = final_orders.iter().map(|order| order.id()).collect()
+        });
+
+        if !final_orders.is_empty() {
+            self.lock_and_prove_orders(&final_orders).await?;
+        }
+
+        // Only recorded once the pass completed, so a failed pass is retried.
+        if let (Some(fingerprint), Ok(mut last)) = (fingerprint, self.admission_fingerprint.lock())
+        {
+            *last = Some((fingerprint, block_timestamp));
+        }
+
+        Ok(())
+    }
+
+    /// Returns a fingerprint of the inputs to the admission pass that are cheap to gather: the
+    /// valid orders, the committed orders and whether locking is paused.
+    ///
+    /// The valid orders already account for orders whose target timestamp was reached.
+    async fn admission_fingerprint_of(
+        &self,
+        valid_orders: &[Arc<OrderRequest>],
+    ) -> Result<u64, OrderMonitorErr> {
+        let mut valid_order_ids: Vec<_> = valid_orders.iter().map(|order| order.id()).collect();
+        valid_order_ids.sort();
+        let mut committed_orders: Vec<_> = self
+            .db
+            .get_committed_orders()
+            .await
+            .map_err(|e| OrderMonitorErr::UnexpectedError(e.into()))?
+            .iter()
+            .map(|order| format!("{:?}-{}", order.status, order.id()))
+            .collect();
+        committed_orders.sort();
+        let locking_paused =
+            self.config.lock_all().context("Failed to read config")?.market.pause_locking;
+
+        let mut hasher = std::hash::DefaultHasher::new();
+        (valid_order_ids, committed_orders, locking_paused).hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    /// Forces the next admission pass to run, even if the orders are unchanged.
+    fn reset_admission_fingerprint(&self) {
+        if let Ok(mut last) = self.admission_fingerprint.lock() {
+            *last = None;
+        }
+    }
+
+    /// Returns the orders currently committed to, earliest started proving first, along with the
+    /// stake and gas they commit and when they are estimated to be proven.
+    pub(crate) async fn committed_orders_snapshot(&self) -> Result<Vec<CommittedOrderSnapshot>> {
+        let config = self.load_monitor_config()?;
+        let mut committed_orders =
+            self.db.get_committed_orders().await.context("Failed to get committed orders")?;
+        committed_orders.sort_by_key(|order| {
+            (order.proving_started_at.is_none(), order.proving_started_at, order.id())
+        });
+
+        let completions = config.peak_prove_khz.map(|peak_prove_khz| {
+            estimated_completions(
+                &committed_orders,
+                peak_prove_khz,
+                config.additional_proof_cycles,
+                config.fallback_cycles,
+                self.now(),
+            )
+        });
+        let mut snapshot = Vec::with_capacity(committed_orders.len());
+        for (idx, order) in committed_orders.iter().enumerate() {
+            let stake = match order.fulfillment_type {
+                FulfillmentType::LockAndFulfill => order.request.offer.lockStake,
+                _ => U256::ZERO,
+            };
+            snapshot.push(CommittedOrderSnapshot {
+                order_id: order.id(),
+                fulfillment_type: order.fulfillment_type,
+                status: order.status,
+                total_cycles: order.total_cycles,
+                proving_started_at: order.proving_started_at,
+                estimated_completion: completions.as_ref().map(|completions| completions[idx]),
+                stake,
+                gas_units: self.committed_gas_units(std::slice::from_ref(order)).await?,
+                labels: order.labels.clone(),
+            });
+        }
+        Ok(snapshot)
+    }
+
+    /// Estimates how long the balance lasts at the rate gas was paid by the lock and fulfillment
+    /// transactions confirmed within the last [GAS_SPEND_WINDOW]. Returns [Duration::MAX] if no
+    /// transactions were confirmed.
+    pub async fn estimated_runway(&self) -> Result<Duration> {
+        let balance_wei = self
+            .provider
+            .get_balance(self.provider.default_signer_address())
+            .await
+            .context("Failed to get balance")?;
+        let mut gas_spends =
+            self.gas_spends.lock().map_err(|_| anyhow::anyhow!("Gas spends lock poisoned"))?;
+        Ok(gas_spends.runway(balance_wei, self.now()))
+    }
+
+    /// Returns the record of gas paid by confirmed transactions, to be shared with the submitter
+    /// so fulfillment transactions count towards the runway.
+    pub(crate) fn gas_spends(&self) -> Arc<std::sync::Mutex<GasSpends>> {
+        self.gas_spends.clone()
+    }
+
+    /// Records the gas paid by a confirmed transaction of ours towards the runway estimate.
+    fn record_gas_spend(&self, receipt: &TransactionReceipt) {
+        let cost_wei = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+        match self.gas_spends.lock() {
+            Ok(mut gas_spends) => gas_spends.record(self.now(), cost_wei),
+            Err(_) => tracing::warn!("Gas spends lock poisoned, not recording gas spend"),
+        }
+    }
+
+    /// Returns the state of the monitor for operators.
+    pub(crate) async fn status(&self) -> Result<MonitorStatus> {
+        let runway = self.estimated_runway().await?;
+        Ok(MonitorStatus {
+            timestamp: self.now(),
+            recent_ticks: self.recent_ticks(usize::MAX),
+            chain_stalled: self.is_chain_stalled(),
+            wasted_gas_wei: self.wasted_gas_wei(),
+            runway_secs: (runway != Duration::MAX).then(|| runway.as_secs()),
+            committed_orders: self.committed_orders_snapshot().await?,
+        })
+    }
+
+    /// Writes the [MonitorStatus] to the given path as JSON, replacing the file atomically so that
+    /// readers never observe a partial write.
+    pub(crate) async fn write_status(&self, path: &Path) -> Result<()> {
+        let status = serde_json::to_vec_pretty(&self.status().await?)
+            .context("Failed to serialize status")?;
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, status)
+            .await
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, path)
+            .await
+            .with_context(|| format!("Failed to replace {}", path.display()))
+    }
+
+    /// Explains whether the order would be admitted for locking and/or proving as of the current
+    /// block, reporting every check the monitor applies along with the values it was computed
+    /// from. Unlike an iteration of the monitor, neither the caches nor the database are modified.
+    ///
+    /// The order is checked on its own, so other orders competing for the same capacity and
+    /// balance are not accounted for.
+    pub async fn explain_order(&self, order: &OrderRequest) -> OrderExplanation {
+        let mut explanation = OrderExplanation { order_id: order.id(), ..Default::default() };
+        if let Err(err) = self.run_order_checks(order, &mut explanation).await {
+            explanation.checks.push(OrderCheck::new("error", false, format!("{err:?}")));
+        }
+        explanation
+    }
+
+    /// Runs the admission checks of [OrderMonitor::explain_order], recording each in the
+    /// explanation as it completes.
+    async fn run_order_checks(
+        &self,
+        order: &OrderRequest,
+        explanation: &mut OrderExplanation,
+    ) -> Result<()> {
+        let config = self.load_monitor_config()?;
+        let ChainHead { block_timestamp, .. } = self.chain_monitor.current_chain_head().await?;
+        explanation.block_timestamp = block_timestamp;
+        let checks = &mut explanation.checks;
+        let is_lock_and_fulfill = order.fulfillment_type == FulfillmentType::LockAndFulfill;
+
+        // Checks applied when the order is received.
+        let mismatched = self.is_mismatched_chain(order);
+        checks.push(OrderCheck::new(
+            "chain and market",
+            !mismatched || config.allow_mismatched_chain_orders,
+            format!(
+                "chain {} and market {}, connected to chain {:?} and market {}",
+                order.chain_id, order.boundless_market_address, self.chain_id, self.market_addr
+            ),
+        ));
+
+        let request_id_check = verify_request_id(order);
+        checks.push(OrderCheck::new(
+            "request id",
+            request_id_check.is_ok(),
+            match request_id_check {
+                Ok(()) => "matches the client that signed the request".to_string(),
+                Err(err) => err.to_string(),
+            },
+        ));
+
+        checks.push(OrderCheck::new(
+            "prove-only mode",
+            !config.denied_in_prove_only(order),
+            format!("prove only: {}, requires a lock: {is_lock_and_fulfill}", config.prove_only),
+        ));
+
+        checks.push(OrderCheck::new(
+            "target horizon",
+            !config.beyond_target_horizon(order, self.now()),
+            format!(
+                "target timestamp {:?}, horizon {:?} seconds",
+                order.target_timestamp, config.max_target_timestamp_horizon_secs
+            ),
+        ));
+
+        // Checks applied on every iteration.
+        let policy = self.proof_type_policy(&config, order);
+        checks.push(OrderCheck::new(
+            "proof type",
+            policy != Some(ProofTypePolicy::Deny),
+            format!("policy: {policy:?}"),
+        ));
+
+        let request_id = U256::from(order.request.id);
+        let fulfilled = self.db.is_request_fulfilled(request_id).await?;
+        checks.push(OrderCheck::new(
+            "not fulfilled",
+            !fulfilled,
+            format!("fulfilled: {fulfilled}"),
+        ));
+
+        if is_lock_and_fulfill {
+            let lock_stake = order.request.offer.lockStake;
+            checks.push(OrderCheck::new(
+                "lock stake",
+                !config.exceeds_max_lock_stake(order),
+                format!(
+                    "lock stake {lock_stake}, maximum {:?}",
+                    config.max_single_order_lock_stake
+                ),
+            ));
+
+            checks.push(OrderCheck::new(
+                "zero stake",
+                !config.skips_zero_stake(order),
+                format!("lock stake {lock_stake}, policy {:?}", config.zero_stake_policy),
+            ));
+
+            let lock_expires_at = order.request.lock_expires_at();
+            checks.push(OrderCheck::new(
+                "lock not expired",
+                lock_expires_at >= block_timestamp,
+                format!("lock expires at {lock_expires_at}"),
+            ));
+
+            // A lock by another prover that may still be reorged defers the order rather than
+            // skipping it, either way it is not admitted.
+            checks.push(match self.db.get_request_locked(request_id).await? {
+                Some((locker, lock_block)) if !self.is_our_lock(&locker) => {
+                    let settled = self.is_lock_settled(lock_block, block_timestamp, &config).await;
+                    OrderCheck::new(
+                        "not locked by another prover",
+                        false,
+                        format!(
+                            "locker: {locker} in block {lock_block}, settled: {settled}, minimum age {:?} seconds",
+                            config.lock_record_min_age_secs
+                        ),
+                    )
+                }
+                Some((locker, _)) => OrderCheck::new(
+                    "not locked by another prover",
+                    true,
+                    format!("locker: {locker}"),
+                ),
+                None => OrderCheck::new("not locked by another prover", true, "locker: none"),
+            });
+
+            let risk = self.lock_risk(order, &config, block_timestamp);
+            checks.push(OrderCheck::new(
+                "lock risk",
+                self.exceeded_lock_risk(order, &config, block_timestamp).is_none(),
+                format!("lock risk {risk:.4}, maximum {:?}", config.max_lock_risk),
+            ));
+        }
+
+        let expiry = order.expiry();
+        let min_deadline = config.required_deadline_secs(order);
+        let remaining_secs = expiry.saturating_sub(self.now());
+        checks.push(OrderCheck::new(
+            "deadline",
+            is_within_deadline(order, block_timestamp, self.now(), &config),
+            format!(
+                "expires at {expiry}, {remaining_secs} seconds remaining, minimum {min_deadline} seconds"
+            ),
+        ));
+
+        checks.push(OrderCheck::new(
+            "commit horizon",
+            !is_beyond_target_horizon(order, block_timestamp, &config),
+            format!(
+                "target timestamp {:?}, horizon {:?} seconds",
+                order.target_timestamp, config.max_target_horizon_secs
+            ),
+        ));
+
+        checks.push(match is_target_time_reached(order, block_timestamp) {
+            Ok(reached) => OrderCheck::new(
+                "target timestamp",
+                reached,
+                format!("target timestamp {}", order.target_timestamp.unwrap_or_default()),
+            ),
+            Err(err) => OrderCheck::new("target timestamp", false, err.to_string()),
+        });
+
+        // Checks applied when admitting the orders of an iteration.
+        let committed_orders = self.db.get_committed_orders().await?;
+        let capacity = self.get_proving_order_capacity(&config, &mut String::new()).await?;
+        let mut capacity_granted = capacity.request_capacity(1, self.max_proving_batch_size);
+        if let Some(max_backlog) = config.max_settlement_backlog {
+            capacity_granted = self
+                .throttle_for_settlement_backlog(capacity_granted, max_backlog, &committed_orders)
+                .await;
+        }
+        let order_cycles = budgeted_cycles(order.total_cycles, config.fallback_cycles)
+            + config.additional_proof_cycles;
+        let has_capacity = capacity_granted > 0
+            && match capacity {
+                Capacity::Cycles(cycles) => order_cycles <= cycles,
+                Capacity::Available(_) | Capacity::Unlimited => true,
+            };
+        checks.push(OrderCheck::new(
+            "capacity",
+            has_capacity,
+            format!(
+                "{capacity:?}, {capacity_granted} orders granted capacity, order requires {order_cycles} cycles"
+            ),
+        ));
+
+        let gas_price = self.gas_price_source().gas_price().await?;
+        let order_cost_wei = self.calculate_order_cost_wei(&config, order, gas_price).await?;
+        checks.push(OrderCheck::new(
+            "gas",
+            true,
+            format!("{} ether at a gas price of {gas_price} wei", format_ether(order_cost_wei)),
+        ));
+
+        let min_profit_wei = config.min_profit_wei_for(order.chain_id);
+        checks.push(match min_profit_wei {
+            Some(min_profit_wei)
+                if is_lock_and_fulfill && config.reject_unprofitable_at_ingestion =>
+            {
+                let max_profit_wei = config.max_profit_wei(order, order_cost_wei);
+                OrderCheck::new(
+                    "cost floor",
+                    max_profit_wei >= min_profit_wei,
+                    format!(
+                        "profit at the max price {max_profit_wei} wei, minimum {min_profit_wei} wei"
+                    ),
+                )
+            }
+            _ => OrderCheck::new("cost floor", true, "not enforced for the order"),
+        });
+
+        let balance_wei = self.prover_balance(&config).await?;
+        let committed_cost_wei =
+            U256::from(gas_price) * U256::from(self.committed_gas_units(&committed_orders).await?);
+        let admissible_balance_wei = self.admissible_balance_wei(
+            &config,
+            balance_wei,
+            committed_cost_wei,
+            committed_orders.len(),
+        );
+        checks.push(OrderCheck::new(
+            "balance",
+            committed_cost_wei <= balance_wei && order_cost_wei <= admissible_balance_wei,
+            format!(
+                "balance {} ether, committed orders cost {} ether, {} ether admissible after reserves",
+                format_ether(balance_wei),
+                format_ether(committed_cost_wei),
+                format_ether(admissible_balance_wei)
+            ),
+        ));
+
+        if is_lock_and_fulfill {
+            let total_stake = committed_lock_stake(&committed_orders)
+                .saturating_add(order.request.offer.lockStake);
+            checks.push(OrderCheck::new(
+                "total stake",
+                config.max_total_stake_committed.is_none_or(|max| total_stake <= max),
+                format!(
+                    "total committed stake {total_stake}, maximum {:?}",
+                    config.max_total_stake_committed
+                ),
+            ));
+        }
+
+        checks.push(
+            match (min_profit_wei, config.expected_profit_wei(order, order_cost_wei)) {
+                (Some(min_profit_wei), Some(profit_wei)) => OrderCheck::new(
+                    "profitability",
+                    profit_wei >= min_profit_wei,
+                    format!(
+                        "profit after gas and {} wei of proving cost {profit_wei} wei, minimum {min_profit_wei} wei",
+                        config.proving_cost_wei(order)
+                    ),
+                ),
+                (Some(_), None) => {
+                    OrderCheck::new("profitability", true, "reward is not paid in wei")
+                }
+                (None, _) => OrderCheck::new("profitability", true, "no minimum profit"),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Reconciles committed orders that have been proving for longer than the configured maximum
+    /// proving duration, such as orders left behind by a previous process that crashed, so they
+    /// no longer consume proving capacity.
+    ///
+    /// Orders that were fulfilled on chain are marked complete. Orders are only marked failed once
+    /// their status confirms they can no longer be fulfilled by us, i.e. they expired or we lost
+    /// the lock of a lock-and-fulfill order to another prover. Orders whose status could not be
+    /// fetched are retried on the next pass.
+    async fn reconcile_stale_orders(&self) -> Result<(), OrderMonitorErr> {
+        let Some(max_proving_duration_secs) = self
+            .config
+            .lock_all()
+            .context("Failed to read config")?
+            .market
+            .max_proving_duration_secs
+        else {
+            return Ok(());
+        };
+
+        let now = self.now();
+        let committed_orders = self
+            .db
+            .get_committed_orders()
+            .await
+            .map_err(|e| OrderMonitorErr::UnexpectedError(e.into()))?;
+        for order in committed_orders {
+            let Some(proving_started_at) = order.proving_started_at else {
+                continue;
+            };
+            if now.saturating_sub(proving_started_at) <= max_proving_duration_secs {
+                continue;
+            }
+
+            let order_id = order.id();
+            let status = match self
+                .market
+                .get_status(order.request.id, Some(order.request.expires_at()))
+                .await
+            {
+                Ok(status) => status,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to get the status of stale order {order_id}, retrying on the next pass: {err:?}"
+                    );
+                    continue;
+                }
+            };
+            // Other provers hold the lock of orders fulfilled after their lock expired, so only
+            // the lock of lock-and-fulfill orders is expected to be ours.
+            let lost_to = match status {
+                RequestStatus::Locked
+                    if order.fulfillment_type == FulfillmentType::LockAndFulfill =>
+                {
+                    match self.db.get_request_locked(U256::from(order.request.id)).await {
+                        Ok(lock) => lock
+                            .map(|(locker, _)| locker)
+                            .filter(|locker| !self.is_our_lock(locker)),
+                        Err(err) => {
+                            tracing::warn!(
+                                "Failed to get the locker of stale order {order_id}, retrying on the next pass: {err:?}"
+                            );
+                            continue;
+                        }
+                    }
+                }
+                _ => None,
+            };
+            let res = match (status, lost_to) {
+                (RequestStatus::Fulfilled, _) => {
+                    tracing::info!(
+                        "Stale order {order_id} started proving at {proving_started_at} but was fulfilled, marking complete"
+                    );
+                    self.db.set_order_complete(&order_id).await
+                }
+                (RequestStatus::Expired, _) => {
+                    tracing::warn!(
+                        "Stale order {order_id} started proving at {proving_started_at}, exceeding the max proving duration of {max_proving_duration_secs} seconds, and expired, marking failed"
+                    );
+                    self.db.set_order_failure(&order_id, "Exceeded max proving duration").await
+                }
+                (RequestStatus::Locked, Some(locker)) => {
+                    tracing::warn!(
+                        "Stale order {order_id} started proving at {proving_started_at}, exceeding the max proving duration of {max_proving_duration_secs} seconds, and is locked by another prover ({locker}), marking failed"
+                    );
+                    self.db.set_order_failure(&order_id, "Exceeded max proving duration").await
+                }
+                (status, _) => {
+                    tracing::warn!(
+                        "Stale order {order_id} started proving at {proving_started_at}, exceeding the max proving duration of {max_proving_duration_secs} seconds, but can still be fulfilled ({status:?}), leaving it to prove"
+                    );
+                    continue;
+                }
+            };
+            if let Err(err) = res {
+                tracing::error!("Failed to reconcile stale order {order_id}: {err:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn start_monitor(
+        &self,
+        cancel_token: CancellationToken,
+    ) -> Result<(), OrderMonitorErr> {
+        let mut last_block = 0;
+        let mut first_block = 0;
+        let mut interval = tokio::time::interval_at(
+            tokio::time::Instant::now(),
+            tokio::time::Duration::from_secs(self.block_time),
+        );
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        let mut config_check = tokio::time::interval(CONFIG_CHECK_INTERVAL);
+        config_check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        if self.load_monitor_config().is_ok_and(|config| config.prove_only) {
+            tracing::info!(
+                "Running in prove-only mode, orders that require a lock are dropped and never locked"
+            );
+        }
+
+        let mut new_orders = self.priced_order_rx.lock().await;
+        let mut prev_orders_by_status = String::new();
+        // Config applied in the last iteration, used to detect changes between iterations.
+        let mut applied_config: Option<OrderMonitorConfig> = None;
+        // Stale orders are reconciled once the monitor is first active, as a standby must not
+        // write to the shared database.
+        let mut reconciled = false;
+        // Whether the pricing stage closed the channel of priced orders, after which the monitor
+        // only works through the cached orders.
+        let mut upstream_closed = false;
+
+        loop {
+            tokio::select! {
+                biased;
+
+                order = new_orders.recv(), if !upstream_closed => match order {
+                    Some(order) => self.handle_new_order_result(order).await?,
+                    None => {
+                        tracing::warn!(
+                            "Priced order channel closed, processing cached orders before shutting down"
+                        );
+                        upstream_closed = true;
+                    }
+                },
+
+                _ = interval.tick() => {
+                    let ChainHead { block_number, block_timestamp } =
+                        self.chain_monitor.current_chain_head().await?;
+                    if block_number != last_block {
+                        last_block = block_number;
+                        if first_block == 0 {
+                            first_block = block_number;
+                        }
+
+                        if !reconciled && self.role() == MonitorRole::Active {
+                            self.reconcile_stale_orders().await?;
+                            reconciled = true;
+                        }
+
+                        let monitor_config = self.load_monitor_config()?;
+                        self.run_tick(
+                            block_number,
+                            block_timestamp,
+                            &monitor_config,
+                            &mut prev_orders_by_status,
+                        )
+                        .await?;
+                        applied_config = Some(monitor_config);
+                    }
+                }
+
+                _ = self.refresh.notified() => {
+                    let ChainHead { block_number, block_timestamp } =
+                        self.chain_monitor.current_chain_head().await?;
+                    tracing::debug!("Refreshing orders at block {block_number}");
+                    let monitor_config = self.load_monitor_config()?;
+                    self.run_tick(
+                        block_number,
+                        block_timestamp,
+                        &monitor_config,
+                        &mut prev_orders_by_status,
+                    )
+                    .await?;
+                    applied_config = Some(monitor_config);
+                }
+
+                _ = config_check.tick() => {
+                    self.reevaluate_on_config_change(&mut applied_config, &mut prev_orders_by_status)
+                        .await?;
+                }
+
+                _ = cancel_token.cancelled() => {
+                    tracing::info!("Order monitor received cancellation, shutting down gracefully");
+                    break;
+                }
+            }
+
+            if upstream_closed && self.caches_drained() {
+                tracing::info!(
+                    "Order monitor processed all cached orders after the priced order channel closed, shutting down"
+                );
+                // The cancellation token is shared with the other non-critical services, which keep
+                // running until the broker shuts down.
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether no orders are left to lock or prove in the caches.
+    fn caches_drained(&self) -> bool {
+        self.lock_and_prove_cache.iter().next().is_none()
+            && self.prove_cache.iter().next().is_none()
+    }
+}
+
+impl<P> RetryTask for OrderMonitor<P>
+where
+    P: Provider<Ethereum> + WalletProvider + 'static + Clone,
+{
+    type Error = OrderMonitorErr;
+    fn spawn(&self, cancel_token: CancellationToken) -> RetryRes<Self::Error> {
+        let monitor_clone = self.clone();
+        Box::pin(async move {
+            tracing::info!("Starting order monitor");
+            monitor_clone.start_monitor(cancel_token).await.map_err(SupervisorErr::Recover)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{alerts::AlertSink, db::SqliteDb, now_timestamp, FulfillmentType};
+    use alloy::node_bindings::AnvilInstance;
+    use alloy::{
+        network::EthereumWallet,
+        node_bindings::Anvil,
+        primitives::{Address, FixedBytes, U256},
+        providers::{
+            fillers::{
+                BlobGasFiller, ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller,
+                WalletFiller,
+            },
+            ProviderBuilder, RootProvider,
+        },
+        signers::local::PrivateKeySigner,
+    };
+    use boundless_market::contracts::{
+        IBoundlessMarket, Offer, Predicate, PredicateType, ProofRequest, RequestId, RequestInput,
+        RequestInputType, Requirements,
+    };
+    use boundless_market_test_utils::{
+        deploy_boundless_market, deploy_hit_points, ASSESSOR_GUEST_ID, ASSESSOR_GUEST_PATH,
+    };
+
+    use risc0_ethereum_contracts::selector::Selector;
+    use risc0_zkvm::Digest;
+    use std::{future::Future, sync::Arc};
+    use tokio::task::JoinSet;
+    use tracing_test::traced_test;
+
+    type TestProvider = FillProvider<
+        JoinFill<
+            JoinFill<
+                alloy::providers::Identity,
+                JoinFill<GasFiller, JoinFill<BlobGasFiller, JoinFill<NonceFiller, ChainIdFiller>>>,
+            >,
+            WalletFiller<EthereumWallet>,
+        >,
+        RootProvider,
+    >;
+
+    pub struct TestCtx {
+        pub monitor: OrderMonitor<TestProvider>,
+        pub anvil: AnvilInstance,
+        pub db: DbObj,
+        pub market_address: Address,
+        #[allow(dead_code)]
+        pub config: ConfigLock,
+        pub priced_order_tx: mpsc::Sender<Box<OrderRequest>>,
+        pub signer: PrivateKeySigner,
+        pub market_service: BoundlessMarketService<Arc<TestProvider>>,
+        next_order_id: u32, // Counter to assign unique order IDs
+    }
+
+    impl TestCtx {
+        // Convert the standalone function to a method on TestCtx
+        pub async fn create_test_order(
+            &mut self,
+            fulfillment_type: FulfillmentType,
+            bidding_start: u64,
+            lock_timeout: u64,
+            timeout: u64,
+        ) -> Box<OrderRequest> {
+            let request_id = self.next_order_id;
+            self.next_order_id += 1;
+
+            let request = ProofRequest::new(
+                RequestId::new(self.signer.address(), request_id),
+                Requirements::new(
+                    Digest::ZERO,
+                    Predicate {
+                        predicateType: PredicateType::PrefixMatch,
+                        data: Default::default(),
+                    },
+                ),
+                "http://risczero.com/image",
+                RequestInput { inputType: RequestInputType::Inline, data: Default::default() },
+                Offer {
+                    minPrice: U256::from(1),
                     maxPrice: U256::from(2),
                     biddingStart: bidding_start,
                     rampUpPeriod: 1,
@@ -802,443 +4887,4249 @@ pub(crate) mod tests {
                     lockTimeout: lock_timeout as u32,
                     lockStake: U256::from(0),
                 },
-            );
-
-            let client_sig = request
-                .sign_request(&self.signer, self.market_address, self.anvil.chain_id())
-                .await
-                .unwrap()
-                .as_bytes()
-                .into();
+            );
+
+            let client_sig = request
+                .sign_request(&self.signer, self.market_address, self.anvil.chain_id())
+                .await
+                .unwrap()
+                .as_bytes()
+                .into();
+
+            Box::new(OrderRequest {
+                target_timestamp: Some(0),
+                request,
+                image_id: None,
+                input_id: None,
+                expire_timestamp: None,
+                labels: Vec::new(),
+                priority_tier: None,
+                urgent: false,
+                client_sig,
+                fulfillment_type,
+                boundless_market_address: self.market_address,
+                chain_id: self.anvil.chain_id(),
+                total_cycles: None,
+            })
+        }
+    }
+
+    pub async fn setup_om_test_context() -> TestCtx {
+        let anvil = Anvil::new().spawn();
+        let signer: PrivateKeySigner = anvil.keys()[0].clone().into();
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .wallet(EthereumWallet::from(signer.clone()))
+                .connect(&anvil.endpoint())
+                .await
+                .unwrap(),
+        );
+
+        // Deploy contracts
+        let hit_points = deploy_hit_points(signer.address(), provider.clone()).await.unwrap();
+
+        let market_address = deploy_boundless_market(
+            signer.address(),
+            provider.clone(),
+            Address::ZERO,
+            hit_points,
+            Digest::from(ASSESSOR_GUEST_ID),
+            format!("file://{ASSESSOR_GUEST_PATH}"),
+            Some(signer.address()),
+        )
+        .await
+        .unwrap();
+
+        // Set up market service
+        let market_service = BoundlessMarketService::new(
+            market_address,
+            provider.clone(),
+            provider.default_signer_address(),
+        );
+
+        // Deposit ETH into the contract for the prover to use when locking orders
+        // Using 10 ETH to ensure plenty of funds for tests
+        let stake_token_decimals = market_service.stake_token_decimals().await.unwrap();
+        market_service
+            .deposit(parse_units("10.0", stake_token_decimals).unwrap().into())
+            .await
+            .unwrap();
+
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        let config = ConfigLock::default();
+
+        config.load_write().unwrap().market.min_deadline = 50;
+        config.load_write().unwrap().market.lockin_gas_estimate = 200_000;
+        config.load_write().unwrap().market.fulfill_gas_estimate = 300_000;
+        config.load_write().unwrap().market.groth16_verify_gas_estimate = 50_000;
+
+        let block_time = 2;
+
+        let chain_monitor = Arc::new(ChainMonitorService::new(provider.clone()).await.unwrap());
+        tokio::spawn(chain_monitor.spawn(Default::default()));
+
+        // Create required channels for tests
+        let (priced_order_tx, priced_order_rx) = mpsc::channel(16);
+
+        let monitor = OrderMonitor::new(
+            db.clone(),
+            provider.clone(),
+            chain_monitor.clone(),
+            config.clone(),
+            block_time,
+            signer.address(),
+            market_address,
+            priced_order_rx,
+            stake_token_decimals,
+            RpcRetryConfig { retry_count: 2, retry_sleep_ms: 500 },
+        )
+        .unwrap();
+
+        TestCtx {
+            monitor,
+            anvil,
+            db,
+            market_address,
+            config,
+            priced_order_tx,
+            signer,
+            market_service,
+            next_order_id: 1, // Initialize with 1 instead of 0
+        }
+    }
+
+    async fn run_with_monitor<P, F, T>(monitor: OrderMonitor<P>, f: F) -> T
+    where
+        P: Provider + WalletProvider + Clone + 'static,
+        F: Future<Output = T>,
+    {
+        // A JoinSet automatically aborts all its tasks when dropped
+        let mut tasks = JoinSet::new();
+        // Spawn the monitor
+        tasks.spawn(async move { monitor.start_monitor(Default::default()).await });
+
+        tokio::select! {
+            result = f => result,
+            monitor_task_result = tasks.join_next() => {
+                panic!("Monitor exited unexpectedly: {:?}", monitor_task_result.unwrap());
+            },
+        }
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn monitor_block() {
+        let mut ctx = setup_om_test_context().await;
+
+        // Create a test order using the TestCtx helper method
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+
+        let order_id = order.id();
+
+        let _request_id =
+            ctx.market_service.submit_request(&order.request, &ctx.signer).await.unwrap();
+
+        // Send the order to the monitor
+        ctx.priced_order_tx.send(order).await.unwrap();
+
+        run_with_monitor(ctx.monitor, async move {
+            // loop for 20 seconds
+            for _ in 0..20 {
+                let order = ctx.db.get_order(&order_id).await.unwrap();
+                if order.is_some() {
+                    assert_eq!(order.unwrap().status, OrderStatus::PendingProving);
+                    break;
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            }
+
+            let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+            assert_eq!(order.status, OrderStatus::PendingProving);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_priced_order_channel_closed() {
+        let mut ctx = setup_om_test_context().await;
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let order_id = order.id();
+        ctx.priced_order_tx.send(order).await.unwrap();
+        drop(ctx.priced_order_tx);
+
+        let cancel_token = CancellationToken::new();
+        tokio::time::timeout(
+            Duration::from_secs(20),
+            ctx.monitor.start_monitor(cancel_token.clone()),
+        )
+        .await
+        .expect("monitor did not exit after the channel closed")
+        .unwrap();
+
+        // The shared cancellation token is left to the broker.
+        assert!(!cancel_token.is_cancelled());
+        assert!(logs_contain("Priced order channel closed"));
+        // The cached order was still locked before exiting.
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::PendingProving);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_now() {
+        let mut ctx = setup_om_test_context().await;
+        let (tick_trace_tx, mut tick_trace_rx) = mpsc::channel(16);
+        // Blocks are far apart, so only the first iteration runs without a refresh.
+        ctx.monitor.block_time = 1_000;
+        let monitor = ctx.monitor.clone().with_tick_trace_sender(tick_trace_tx);
+
+        run_with_monitor(monitor.clone(), async move {
+            let first = tick_trace_rx.recv().await.unwrap();
+            assert!(tokio::time::timeout(Duration::from_millis(500), tick_trace_rx.recv())
+                .await
+                .is_err());
+
+            monitor.refresh_now();
+            let refreshed = tokio::time::timeout(Duration::from_secs(5), tick_trace_rx.recv())
+                .await
+                .expect("refresh did not trigger an iteration")
+                .unwrap();
+            assert!(refreshed.block_number >= first.block_number);
+        })
+        .await;
+    }
+
+    type MockResult<T> = Result<T, MarketError>;
+
+    /// Mock market whose calls are answered by configurable closures.
+    ///
+    /// By default requests are unknown to the market, locks succeed in block 1 without a locker
+    /// recorded by the lock event, requests are already submitted, the stake balance is zero and
+    /// deposits succeed.
+    struct MockMarket {
+        /// Status of the request, as of the given block if any rather than the latest block.
+        status: Box<dyn Fn(U256, Option<u64>) -> MockResult<RequestStatus> + Send + Sync>,
+        /// Block number and transaction hash of the lock of the request.
+        lock: Box<dyn Fn(U256) -> MockResult<(u64, TxHash)> + Send + Sync>,
+        /// Time a lock takes before returning.
+        lock_delay: Duration,
+        locker: Box<dyn Fn(U256) -> MockResult<Option<Address>> + Send + Sync>,
+        submit: Box<dyn Fn(U256) -> MockResult<bool> + Send + Sync>,
+        stake_balance: Box<dyn Fn() -> MockResult<U256> + Send + Sync>,
+        deposit: Box<dyn Fn(U256) -> MockResult<()> + Send + Sync>,
+        deposit_stake: Box<dyn Fn(U256) -> MockResult<()> + Send + Sync>,
+        locks_in_flight: Arc<std::sync::atomic::AtomicUsize>,
+        /// Maximum number of locks in flight at once.
+        max_locks_in_flight: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Default for MockMarket {
+        fn default() -> Self {
+            Self {
+                status: Box::new(|_, _| Ok(RequestStatus::Unknown)),
+                lock: Box::new(|_| Ok((1, TxHash::ZERO))),
+                lock_delay: Duration::ZERO,
+                locker: Box::new(|_| Ok(None)),
+                submit: Box::new(|_| Ok(false)),
+                stake_balance: Box::new(|| Ok(U256::ZERO)),
+                deposit: Box::new(|_| Ok(())),
+                deposit_stake: Box::new(|_| Ok(())),
+                locks_in_flight: Default::default(),
+                max_locks_in_flight: Default::default(),
+            }
+        }
+    }
+
+    impl MockMarket {
+        /// Mock market whose calls fail, as if the node were unreachable.
+        fn unreachable() -> Self {
+            let err = || MarketError::Error(anyhow::anyhow!("connection refused"));
+            Self {
+                status: Box::new(move |_, _| Err(err())),
+                lock: Box::new(|_| panic!("request should not be locked")),
+                locker: Box::new(move |_| Err(err())),
+                submit: Box::new(move |_| Err(err())),
+                stake_balance: Box::new(move || Err(err())),
+                deposit: Box::new(move |_| Err(err())),
+                deposit_stake: Box::new(move |_| Err(err())),
+                ..Default::default()
+            }
+        }
+
+        fn with_status(
+            self,
+            status: impl Fn(U256, Option<u64>) -> MockResult<RequestStatus> + Send + Sync + 'static,
+        ) -> Self {
+            Self { status: Box::new(status), ..self }
+        }
+
+        fn with_lock(
+            self,
+            lock: impl Fn(U256) -> MockResult<(u64, TxHash)> + Send + Sync + 'static,
+        ) -> Self {
+            Self { lock: Box::new(lock), ..self }
+        }
+
+        fn with_lock_delay(self, lock_delay: Duration) -> Self {
+            Self { lock_delay, ..self }
+        }
+
+        fn with_locker(
+            self,
+            locker: impl Fn(U256) -> MockResult<Option<Address>> + Send + Sync + 'static,
+        ) -> Self {
+            Self { locker: Box::new(locker), ..self }
+        }
+
+        fn with_stake_balance(self, stake_balance: U256) -> Self {
+            Self { stake_balance: Box::new(move || Ok(stake_balance)), ..self }
+        }
+
+        fn with_deposit_stake(
+            self,
+            deposit_stake: impl Fn(U256) -> MockResult<()> + Send + Sync + 'static,
+        ) -> Self {
+            Self { deposit_stake: Box::new(deposit_stake), ..self }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl crate::market_client::MarketClient for MockMarket {
+        async fn get_status(
+            &self,
+            request_id: U256,
+            _expires_at: Option<u64>,
+        ) -> Result<RequestStatus, MarketError> {
+            (self.status)(request_id, None)
+        }
+
+        async fn get_status_at(
+            &self,
+            request_id: U256,
+            _expires_at: Option<u64>,
+            block_number: u64,
+        ) -> Result<RequestStatus, MarketError> {
+            (self.status)(request_id, Some(block_number))
+        }
+
+        async fn lock_request(
+            &self,
+            request: &ProofRequest,
+            _client_sig: alloy::primitives::Bytes,
+            _priority_gas: Option<u64>,
+        ) -> Result<(u64, TxHash), MarketError> {
+            let in_flight = self.locks_in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_locks_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(self.lock_delay).await;
+            self.locks_in_flight.fetch_sub(1, Ordering::SeqCst);
+            (self.lock)(request.id)
+        }
+
+        async fn get_locker(
+            &self,
+            request_id: U256,
+            _block_number: u64,
+        ) -> Result<Option<Address>, MarketError> {
+            (self.locker)(request_id)
+        }
+
+        async fn submit_request_if_absent(
+            &self,
+            request: &ProofRequest,
+            _client_sig: alloy::primitives::Bytes,
+        ) -> Result<bool, MarketError> {
+            (self.submit)(request.id)
+        }
+
+        async fn deposit(&self, value: U256) -> Result<(), MarketError> {
+            (self.deposit)(value)
+        }
+
+        async fn balance_of_stake(&self, _account: Address) -> Result<U256, MarketError> {
+            (self.stake_balance)()
+        }
+
+        async fn deposit_stake(&self, value: U256) -> Result<(), MarketError> {
+            (self.deposit_stake)(value)
+        }
+
+        async fn stake_token_decimals(&self) -> Result<u8, MarketError> {
+            Ok(6)
+        }
+    }
+
+    /// Creates an order monitor backed by a mock market, without a running chain.
+    async fn setup_mock_market_monitor(
+        market: impl crate::market_client::MarketClient + 'static,
+    ) -> (OrderMonitor<TestProvider>, DbObj) {
+        let signer = PrivateKeySigner::random();
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .wallet(EthereumWallet::from(signer.clone()))
+                .connect_http("http://localhost:1".parse().unwrap()),
+        );
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        let chain_monitor = Arc::new(ChainMonitorService::new(provider.clone()).await.unwrap());
+        let (_priced_order_tx, priced_order_rx) = mpsc::channel(16);
+
+        let monitor = OrderMonitor::new(
+            db.clone(),
+            provider,
+            chain_monitor,
+            ConfigLock::default(),
+            2,
+            signer.address(),
+            Address::ZERO,
+            priced_order_rx,
+            6,
+            RpcRetryConfig { retry_count: 0, retry_sleep_ms: 0 },
+        )
+        .unwrap()
+        .with_market_client(Arc::new(market));
+
+        (monitor, db)
+    }
+
+    #[tokio::test]
+    async fn test_new_invalid_stake_balance_threshold() {
+        let signer = PrivateKeySigner::random();
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .wallet(EthereumWallet::from(signer.clone()))
+                .connect_http("http://localhost:1".parse().unwrap()),
+        );
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        let chain_monitor = Arc::new(ChainMonitorService::new(provider.clone()).await.unwrap());
+        let (_priced_order_tx, priced_order_rx) = mpsc::channel(16);
+        let config = ConfigLock::default();
+        config.load_write().unwrap().market.stake_balance_error_threshold =
+            Some("not a number".into());
+
+        let err = OrderMonitor::new(
+            db,
+            provider,
+            chain_monitor,
+            config,
+            2,
+            signer.address(),
+            Address::ZERO,
+            priced_order_rx,
+            6,
+            RpcRetryConfig { retry_count: 0, retry_sleep_ms: 0 },
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("market.stake_balance_error_threshold"));
+    }
+
+    #[tokio::test]
+    async fn test_new_contradictory_config() {
+        let signer = PrivateKeySigner::random();
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .wallet(EthereumWallet::from(signer.clone()))
+                .connect_http("http://localhost:1".parse().unwrap()),
+        );
+        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
+        let chain_monitor = Arc::new(ChainMonitorService::new(provider.clone()).await.unwrap());
+        let (_priced_order_tx, priced_order_rx) = mpsc::channel(16);
+        let config = ConfigLock::default();
+        {
+            let mut config = config.load_write().unwrap();
+            config.market.stake_balance_warn_threshold = Some("1".into());
+            config.market.stake_balance_error_threshold = Some("2".into());
+        }
+
+        let err = OrderMonitor::new(
+            db,
+            provider,
+            chain_monitor,
+            config,
+            2,
+            signer.address(),
+            Address::ZERO,
+            priced_order_rx,
+            6,
+            RpcRetryConfig { retry_count: 0, retry_sleep_ms: 0 },
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("is above market.stake_balance_warn_threshold"));
+    }
+
+    #[test]
+    fn test_config_validate() {
+        let valid_config = || OrderMonitorConfig {
+            min_deadline: 300,
+            batch_buffer_time_secs: 120,
+            peak_prove_khz: Some(100),
+            max_concurrent_proofs: Some(4),
+            min_concurrent_proofs: Some(1),
+            min_deadline_fraction: Some(0.2),
+            stake_balance_warn_threshold: Some(U256::from(10)),
+            stake_balance_error_threshold: Some(U256::from(5)),
+            stake_topup: Some(StakeTopUpConfig {
+                threshold: U256::from(10),
+                target: U256::from(20),
+                cooldown: Duration::from_secs(60),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(valid_config().validate(), Ok(()));
+        assert_eq!(
+            OrderMonitorConfig { prove_only: true, ..valid_config() }.validate(),
+            Err(vec![ConfigIssue::StakeTopUpInProveOnly])
+        );
+
+        let invalid = OrderMonitorConfig {
+            min_deadline: 60,
+            min_concurrent_proofs: Some(8),
+            min_deadline_fraction: Some(1.5),
+            stake_balance_error_threshold: Some(U256::from(20)),
+            ..valid_config()
+        };
+        assert_eq!(
+            invalid.validate(),
+            Err(vec![
+                ConfigIssue::MinConcurrentProofsAboveMax { min: 8, max: 4 },
+                ConfigIssue::MinDeadlineBelowBatchBuffer { min_deadline: 60, buffer: 120 },
+                ConfigIssue::MinDeadlineFractionOutOfRange(1.5),
+                ConfigIssue::StakeErrorThresholdAboveWarn {
+                    error: U256::from(20),
+                    warn: U256::from(10)
+                },
+            ])
+        );
+
+        let invalid = OrderMonitorConfig {
+            max_concurrent_proofs: Some(0),
+            peak_prove_khz: Some(0),
+            stake_topup: Some(StakeTopUpConfig {
+                threshold: U256::from(10),
+                target: U256::from(10),
+                cooldown: Duration::from_secs(60),
+            }),
+            ..valid_config()
+        };
+        assert_eq!(
+            invalid.validate(),
+            Err(vec![
+                ConfigIssue::NoConcurrentProofs,
+                ConfigIssue::NoProvingThroughput,
+                ConfigIssue::StakeTopUpTargetNotAboveThreshold {
+                    target: U256::from(10),
+                    threshold: U256::from(10)
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_max_proving_batch_env_var() {
+        assert_eq!(max_proving_batch_size(None).unwrap(), MAX_PROVING_BATCH_SIZE);
+        assert_eq!(max_proving_batch_size(Some("3")).unwrap(), 3);
+        // The variable can only lower the hard limit.
+        assert_eq!(max_proving_batch_size(Some("100")).unwrap(), MAX_PROVING_BATCH_SIZE);
+        assert!(max_proving_batch_size(Some("three")).is_err());
+
+        let capacity = Capacity::Available(50);
+        assert_eq!(capacity.request_capacity(10, max_proving_batch_size(Some("3")).unwrap()), 3);
+    }
+
+    fn mock_order(fulfillment_type: FulfillmentType) -> Arc<OrderRequest> {
+        let request = ProofRequest::new(
+            RequestId::new(Address::ZERO, 1),
+            Requirements::new(
+                Digest::ZERO,
+                Predicate { predicateType: PredicateType::PrefixMatch, data: Default::default() },
+            ),
+            "http://risczero.com/image",
+            RequestInput { inputType: RequestInputType::Inline, data: Default::default() },
+            Offer {
+                minPrice: U256::from(1),
+                maxPrice: U256::from(2),
+                biddingStart: now_timestamp(),
+                rampUpPeriod: 1,
+                timeout: 200,
+                lockTimeout: 100,
+                lockStake: U256::from(0),
+            },
+        );
+        let mut order =
+            OrderRequest::new(request, Default::default(), fulfillment_type, Address::ZERO, 31337);
+        order.target_timestamp = Some(0);
+        Arc::new(order)
+    }
+
+    #[tokio::test]
+    async fn test_reject_mismatched_request_id() {
+        let mut ctx = setup_om_test_context().await;
+        let mut order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        // Signed by a client other than the one embedded in the request id.
+        order.client_sig = order
+            .request
+            .sign_request(&PrivateKeySigner::random(), ctx.market_address, ctx.anvil.chain_id())
+            .await
+            .unwrap()
+            .as_bytes()
+            .into();
+        let order_id = order.id();
+
+        ctx.monitor.handle_new_order_result(order).await.unwrap();
+
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_reject_mismatched_chain() {
+        let mut ctx = setup_om_test_context().await;
+        ctx.monitor = ctx.monitor.with_chain_id(ctx.anvil.chain_id());
+
+        let mut other_chain_order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        other_chain_order.chain_id = ctx.anvil.chain_id() + 1;
+        let other_chain_order_id = other_chain_order.id();
+        let mut other_market_order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        other_market_order.boundless_market_address = Address::repeat_byte(1);
+        let other_market_order_id = other_market_order.id();
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let order_id = order.id();
+
+        ctx.monitor.handle_new_order_result(other_chain_order).await.unwrap();
+        ctx.monitor.handle_new_order_result(other_market_order).await.unwrap();
+        ctx.monitor.handle_new_order_result(order).await.unwrap();
+
+        for order_id in [other_chain_order_id, other_market_order_id] {
+            assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+            let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+            assert_eq!(order.status, OrderStatus::Skipped);
+        }
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_received_order_uses_last_valid_config() {
+        let mut ctx = setup_om_test_context().await;
+        ctx.config.load_write().unwrap().market.prove_only = true;
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let order_id = order.id();
+        ctx.monitor.handle_new_order_result(order).await.unwrap();
+        assert_eq!(
+            ctx.db.get_order(&order_id).await.unwrap().unwrap().status,
+            OrderStatus::Skipped
+        );
+
+        // A bad hot reload neither fails the ingestion nor drops the previously applied config.
+        {
+            let mut config = ctx.config.load_write().unwrap();
+            config.market.prove_only = false;
+            config.market.max_single_order_lock_stake = Some("not a number".to_string());
+        }
+        assert!(ctx.monitor.load_monitor_config().is_err());
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let order_id = order.id();
+        ctx.monitor.handle_new_order_result(order).await.unwrap();
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+        assert_eq!(
+            ctx.db.get_order(&order_id).await.unwrap().unwrap().status,
+            OrderStatus::Skipped
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prove_only_never_locks() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Cached before prove-only mode was enabled.
+        let cached_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let cached_order_id = cached_order.id();
+        let cached_request_id = U256::from(cached_order.request.id);
+        ctx.monitor.handle_new_order_result(cached_order).await.unwrap();
+        assert!(ctx.monitor.lock_and_prove_cache.get(&cached_order_id).await.is_some());
+
+        ctx.config.load_write().unwrap().market.prove_only = true;
+        let lock_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let lock_order_id = lock_order.id();
+        let lock_request_id = U256::from(lock_order.request.id);
+        let prove_order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 100, 200)
+            .await;
+        let prove_order_id = prove_order.id();
+        ctx.monitor.handle_new_order_result(lock_order).await.unwrap();
+        ctx.monitor.handle_new_order_result(prove_order).await.unwrap();
+        assert!(ctx.monitor.lock_and_prove_cache.get(&lock_order_id).await.is_none());
+        assert!(ctx.monitor.prove_cache.get(&prove_order_id).await.is_some());
+
+        let config = ctx.monitor.load_monitor_config().unwrap();
+        ctx.monitor.process_orders(current_timestamp, &config, &mut String::new()).await.unwrap();
+
+        for order_id in [cached_order_id, lock_order_id] {
+            assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+            let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+            assert_eq!(order.status, OrderStatus::Skipped);
+        }
+        for request_id in [cached_request_id, lock_request_id] {
+            assert!(!ctx.market_service.is_locked(request_id).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deadline_per_fulfillment_type() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        let config = OrderMonitorConfig { min_deadline: 300, ..Default::default() };
+
+        // The lock expires within the minimum deadline, while the request does not.
+        let lock_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 1_000)
+            .await;
+        let lock_order_id = lock_order.id();
+        let prove_order = ctx
+            .create_test_order(
+                FulfillmentType::FulfillAfterLockExpire,
+                current_timestamp,
+                100,
+                1_000,
+            )
+            .await;
+        let prove_order_id = prove_order.id();
+        ctx.monitor.lock_and_prove_cache.insert(lock_order_id.clone(), Arc::from(lock_order)).await;
+        ctx.monitor.prove_cache.insert(prove_order_id.clone(), Arc::from(prove_order)).await;
+
+        let valid_orders =
+            ctx.monitor.get_valid_orders(current_timestamp, &config, None).await.unwrap();
+        assert_eq!(
+            valid_orders.iter().map(|order| order.id()).collect::<Vec<_>>(),
+            [prove_order_id.clone()]
+        );
+        let order = ctx.db.get_order(&lock_order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+
+        // The lock has plenty of time left, but the request expires within the minimum deadline.
+        let lock_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 1_000, 1_000)
+            .await;
+        let lock_order_id = lock_order.id();
+        let prove_order = ctx
+            .create_test_order(
+                FulfillmentType::FulfillAfterLockExpire,
+                current_timestamp - 800,
+                100,
+                1_000,
+            )
+            .await;
+        let prove_order_id = prove_order.id();
+        ctx.monitor.prove_cache.invalidate_all();
+        ctx.monitor.lock_and_prove_cache.insert(lock_order_id.clone(), Arc::from(lock_order)).await;
+        ctx.monitor.prove_cache.insert(prove_order_id.clone(), Arc::from(prove_order)).await;
+
+        let valid_orders =
+            ctx.monitor.get_valid_orders(current_timestamp, &config, None).await.unwrap();
+        assert_eq!(
+            valid_orders.iter().map(|order| order.id()).collect::<Vec<_>>(),
+            [lock_order_id]
+        );
+        let order = ctx.db.get_order(&prove_order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_reject_unprofitable_at_ingestion() {
+        let mut ctx = setup_om_test_context().await;
+        {
+            let mut config = ctx.config.load_write().unwrap();
+            config.market.min_profit_wei = Some(0);
+            config.market.reject_unprofitable_at_ingestion = true;
+        }
+        let gas_price = ctx.monitor.current_gas_price().await.unwrap();
+
+        // A max price of 2 wei never covers the gas to lock and fulfill the order.
+        let unprofitable_order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let unprofitable_order_id = unprofitable_order.id();
+        ctx.monitor.handle_new_order_result(unprofitable_order).await.unwrap();
+        assert!(ctx.monitor.lock_and_prove_cache.get(&unprofitable_order_id).await.is_none());
+        let order = ctx.db.get_order(&unprofitable_order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+
+        // An order that starts below its costs but ramps up to cover them is kept.
+        let mut order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let gas_cost = ctx.monitor.calculate_order_gas_cost_wei(&order, gas_price).await.unwrap();
+        order.request.offer.maxPrice = gas_cost * U256::from(2);
+        order.client_sig = order
+            .request
+            .sign_request(&ctx.signer, ctx.market_address, ctx.anvil.chain_id())
+            .await
+            .unwrap()
+            .as_bytes()
+            .into();
+        let order_id = order.id();
+        ctx.monitor.handle_new_order_result(order).await.unwrap();
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_some());
+        assert!(ctx.db.get_order(&order_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_max_target_timestamp_horizon() {
+        let mut ctx = setup_om_test_context().await;
+        ctx.config.load_write().unwrap().market.max_target_timestamp_horizon_secs = Some(600);
+        let current_timestamp = now_timestamp();
+
+        let mut distant_order = ctx
+            .create_test_order(
+                FulfillmentType::FulfillAfterLockExpire,
+                current_timestamp,
+                100,
+                7200,
+            )
+            .await;
+        distant_order.target_timestamp = Some(current_timestamp + 3600);
+        let distant_order_id = distant_order.id();
+        ctx.monitor.handle_new_order_result(distant_order).await.unwrap();
+        assert!(ctx.monitor.prove_cache.get(&distant_order_id).await.is_none());
+        let order = ctx.db.get_order(&distant_order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+
+        let mut order = ctx
+            .create_test_order(
+                FulfillmentType::FulfillAfterLockExpire,
+                current_timestamp,
+                100,
+                7200,
+            )
+            .await;
+        order.target_timestamp = Some(current_timestamp + 300);
+        let order_id = order.id();
+        ctx.monitor.handle_new_order_result(order).await.unwrap();
+        assert!(ctx.monitor.prove_cache.get(&order_id).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_cache_stats() {
+        let (monitor, _db) = setup_mock_market_monitor(MockMarket::default()).await;
+
+        let mut expiring_order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+        expiring_order.expire_timestamp = Some(now_timestamp() + 1);
+        monitor.cache_order(Arc::new(expiring_order)).await;
+        let mut order = (*mock_order(FulfillmentType::FulfillAfterLockExpire)).clone();
+        order.expire_timestamp = Some(now_timestamp() + 1_000);
+        let order_id = order.id();
+        monitor.cache_order(Arc::new(order)).await;
+        monitor.prove_cache.invalidate(&order_id).await;
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        monitor.lock_and_prove_cache.run_pending_tasks().await;
+        monitor.prove_cache.run_pending_tasks().await;
+
+        let stats = monitor.cache_stats();
+        assert_eq!(
+            stats.lock_and_prove,
+            CacheStatsSnapshot { inserts: 1, invalidations: 0, expiries: 1, size: 0 }
+        );
+        assert_eq!(
+            stats.prove,
+            CacheStatsSnapshot { inserts: 1, invalidations: 1, expiries: 0, size: 0 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lock_order_request_status() {
+        let statuses: [(fn() -> RequestStatus, &str); 3] = [
+            (|| RequestStatus::Locked, "[B-OM-009]"),
+            (|| RequestStatus::Fulfilled, "[B-OM-017]"),
+            (|| RequestStatus::Expired, "[B-OM-016]"),
+        ];
+        for (status, expected_code) in statuses {
+            let (monitor, db) = setup_mock_market_monitor(
+                MockMarket::default()
+                    .with_status(move |_, _| Ok(status()))
+                    .with_lock(|_| panic!("request should not be locked")),
+            )
+            .await;
+
+            let order = mock_order(FulfillmentType::LockAndFulfill);
+            let err = monitor.lock_order(&order).await.unwrap_err();
+            assert_eq!(err.code(), expected_code, "status {:?}", status());
+
+            monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+            let order = db.get_order(&order.id()).await.unwrap().unwrap();
+            assert_eq!(order.status, OrderStatus::Skipped);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lock_order_mock_market_already_locked() {
+        let (monitor, db) = setup_mock_market_monitor(
+            MockMarket::default()
+                .with_lock(|request_id| Err(MarketError::RequestAlreadyLocked(request_id))),
+        )
+        .await;
+
+        let order = mock_order(FulfillmentType::LockAndFulfill);
+        let result = monitor.lock_order(&order).await;
+        assert!(matches!(result, Err(OrderMonitorErr::AlreadyLocked)));
+
+        monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+        let order = db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_lock_outcomes_recorded() {
+        let (monitor, db) =
+            setup_mock_market_monitor(MockMarket::default().with_lock(|request_id| {
+                if request_id == U256::from(1) {
+                    Err(MarketError::RequestAlreadyLocked(request_id))
+                } else {
+                    Err(MarketError::Error(anyhow::anyhow!("nonce too low")))
+                }
+            }))
+            .await;
+
+        let lost_order = mock_order(FulfillmentType::LockAndFulfill);
+        let mut failed_order = (*lost_order).clone();
+        failed_order.request.id = U256::from(2);
+        let since = now_timestamp();
+        monitor.lock_and_prove_orders(&[lost_order, Arc::new(failed_order)]).await.unwrap();
+
+        assert_eq!(
+            db.get_lock_outcome_stats(since).await.unwrap(),
+            LockOutcomeStats { wins: 0, losses: 1, errors: 1, top_winners: Vec::new() }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_explain_expired_order() {
+        let mut ctx = setup_om_test_context().await;
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, now_timestamp() - 1_000, 100, 200)
+            .await;
+
+        let explanation = ctx.monitor.explain_order(&order).await;
+
+        assert_eq!(explanation.order_id, order.id());
+        assert!(!explanation.admissible());
+        let failures: Vec<_> = explanation
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name)
+            .collect();
+        assert_eq!(failures, vec!["lock not expired", "deadline"]);
+        let passed: Vec<_> = explanation
+            .checks
+            .iter()
+            .filter(|check| check.passed)
+            .map(|check| check.name)
+            .collect();
+        assert_eq!(
+            passed,
+            vec![
+                "chain and market",
+                "request id",
+                "prove-only mode",
+                "target horizon",
+                "proof type",
+                "not fulfilled",
+                "lock stake",
+                "zero stake",
+                "not locked by another prover",
+                "lock risk",
+                "commit horizon",
+                "target timestamp",
+                "capacity",
+                "gas",
+                "cost floor",
+                "balance",
+                "total stake",
+                "profitability"
+            ]
+        );
+
+        assert!(explanation.to_string().contains("[FAIL] lock not expired"));
+
+        // Explaining the order leaves no trace in the database.
+        assert!(ctx.db.get_order(&order.id()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_explain_order_reports_ingestion_checks() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        {
+            let mut config = ctx.config.load_write().unwrap();
+            config.market.prove_only = true;
+            config.market.zero_stake_policy = Some(ZeroStakePolicy::Skip);
+            config.market.max_target_timestamp_horizon_secs = Some(60);
+        }
+        let mut order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order.target_timestamp = Some(current_timestamp + 3_600);
+        // Invalidates the client signature.
+        order.request.offer.maxPrice = U256::from(3);
+
+        let explanation = ctx.monitor.explain_order(&order).await;
+
+        let failures: Vec<_> = explanation
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name)
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                "request id",
+                "prove-only mode",
+                "target horizon",
+                "zero stake",
+                "target timestamp"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lost_lock_recorded_with_winner() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let request_id = U256::from(order.request.id);
+
+        // Another prover locks the request first.
+        let competitor: PrivateKeySigner = ctx.anvil.keys()[1].clone().into();
+        let competitor_provider = Arc::new(
+            ProviderBuilder::new()
+                .wallet(EthereumWallet::from(competitor.clone()))
+                .connect(&ctx.anvil.endpoint())
+                .await
+                .unwrap(),
+        );
+        let block_number = BoundlessMarketService::new(
+            ctx.market_address,
+            competitor_provider,
+            competitor.address(),
+        )
+        .lock_request(&order.request, order.client_sig.clone(), None)
+        .await
+        .unwrap();
+        ctx.db
+            .set_request_locked(request_id, &competitor.address().to_string(), block_number)
+            .await
+            .unwrap();
+
+        let order: Arc<OrderRequest> = Arc::from(order);
+        ctx.monitor.lock_and_prove_cache.insert(order.id(), order.clone()).await;
+        ctx.monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+
+        let order = ctx.db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+
+        let stats = ctx.db.get_lock_outcome_stats(current_timestamp).await.unwrap();
+        assert_eq!(stats.wins, 0);
+        assert_eq!(stats.losses, 1);
+        assert_eq!(stats.top_winners, vec![(competitor.address().to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_adapted_priority_gas() {
+        let band = PriorityGasBand { min: 1_000, max: 5_000 };
+        assert_eq!(adapted_priority_gas(band, 0.0), 1_000);
+        assert_eq!(adapted_priority_gas(band, 0.5), 3_000);
+        assert_eq!(adapted_priority_gas(band, 1.0), 5_000);
+        // An inverted band is normalized.
+        assert_eq!(adapted_priority_gas(PriorityGasBand { min: 5_000, max: 1_000 }, 0.25), 2_000);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_priority_gas_from_lock_outcomes() {
+        let (monitor, db) = setup_mock_market_monitor(MockMarket::default()).await;
+        let band = PriorityGasBand { min: 1_000, max: 5_000 };
+        assert_eq!(monitor.adaptive_priority_gas(band).await, 1_000);
+
+        // Losses, whether observed before or when locking, raise the priority gas.
+        let now = now_timestamp();
+        for (id, outcome) in [(1, LockOutcome::Lost), (2, LockOutcome::Lost), (3, LockOutcome::Won)]
+        {
+            db.insert_lock_outcome(U256::from(id), outcome, None, U256::ZERO, now).await.unwrap();
+        }
+        // Errors are not lock races.
+        db.insert_lock_outcome(U256::from(4), LockOutcome::Error, None, U256::ZERO, now)
+            .await
+            .unwrap();
+        // Races outside of the window are ignored.
+        db.insert_lock_outcome(
+            U256::from(5),
+            LockOutcome::Won,
+            None,
+            U256::ZERO,
+            now - LOCK_OUTCOME_WINDOW.as_secs() - 10,
+        )
+        .await
+        .unwrap();
+        assert_eq!(monitor.adaptive_priority_gas(band).await, 3_667);
+    }
+
+    #[test]
+    fn test_gas_ratios() {
+        let mut ratios = GasRatios::default();
+        assert_eq!(ratios.suggested_multiplier(), None);
+        assert_eq!(ratios.record(0, 100_000), None);
+
+        assert_eq!(ratios.record(200_000, 150_000), Some(0.75));
+        assert_eq!(ratios.record(200_000, 250_000), Some(1.25));
+        assert_eq!(ratios.suggested_multiplier(), Some(1.25));
+
+        // Older ratios are evicted once the window is full.
+        for _ in 0..GAS_RATIO_WINDOW {
+            ratios.record(100_000, 110_000);
+        }
+        assert_eq!(ratios.ratios.len(), GAS_RATIO_WINDOW);
+        assert_eq!(ratios.suggested_multiplier(), Some(1.1));
+    }
+
+    #[test]
+    fn test_gas_spends_runway() {
+        let mut spends = GasSpends::default();
+        let now = 10_000;
+        assert_eq!(spends.runway(U256::from(1_000), now), Duration::MAX);
+
+        // Spent before the window, so not counted towards the rate.
+        spends.record(now - GAS_SPEND_WINDOW.as_secs() - 1, U256::from(1_000_000));
+        // 3600 wei spent over the hour long window is a rate of 1 wei per second.
+        spends.record(now - 600, U256::from(1_200));
+        spends.record(now, U256::from(2_400));
+
+        assert_eq!(spends.runway(U256::from(7_200), now), Duration::from_secs(7_200));
+        assert_eq!(spends.spends.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_estimated_runway() {
+        let ctx = setup_om_test_context().await;
+        assert_eq!(ctx.monitor.estimated_runway().await.unwrap(), Duration::MAX);
+
+        // Spending a quarter of the balance per window lasts four windows.
+        let balance = ctx.monitor.provider.get_balance(ctx.signer.address()).await.unwrap();
+        ctx.monitor.gas_spends.lock().unwrap().record(now_timestamp(), balance / U256::from(4));
+        assert_eq!(ctx.monitor.estimated_runway().await.unwrap(), GAS_SPEND_WINDOW * 4);
+    }
+
+    #[tokio::test]
+    async fn test_gas_spends_recorded_on_lock() {
+        let mut ctx = setup_om_test_context().await;
+        let order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await,
+        );
+
+        // Admitting an order does not spend gas, it may be admitted again in later iterations.
+        let config = OrderMonitorConfig::default();
+        for _ in 0..2 {
+            let admitted = ctx
+                .monitor
+                .apply_capacity_limits(vec![order.clone()], &config, &mut String::new())
+                .await
+                .unwrap();
+            assert_eq!(admitted.len(), 1);
+        }
+        assert!(ctx.monitor.gas_spends.lock().unwrap().spends.is_empty());
+
+        ctx.monitor.lock_and_prove_cache.insert(order.id(), order.clone()).await;
+        ctx.monitor.lock_and_prove_orders(&[order]).await.unwrap();
+        let gas_spends = ctx.monitor.gas_spends.lock().unwrap();
+        assert_eq!(gas_spends.spends.len(), 1);
+        assert!(gas_spends.spends[0].1 > U256::ZERO);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_lock_gas_compared_to_estimate() {
+        let mut ctx = setup_om_test_context().await;
+        let order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await,
+        );
+
+        // Locking alone does not wait on the receipt lookup.
+        ctx.monitor.lock_order(&order).await.unwrap();
+        assert!(ctx.monitor.lock_gas_ratios.lock().unwrap().ratios.is_empty());
+
+        let order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await,
+        );
+        ctx.monitor.lock_and_prove_cache.insert(order.id(), order.clone()).await;
+        ctx.monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+
+        assert_eq!(ctx.monitor.lock_gas_ratios.lock().unwrap().ratios.len(), 1);
+        assert!(logs_contain("lock_gas_estimate_ratio"));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_target_to_lock_delay_recorded() {
+        let mut ctx = setup_om_test_context().await;
+        let mut order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let target_timestamp = now_timestamp() - 30;
+        order.target_timestamp = Some(target_timestamp);
+
+        let (_, lock_tx_hash) = ctx.monitor.lock_order(&order).await.unwrap();
+
+        let provider = &ctx.monitor.provider;
+        let receipt = provider.get_transaction_receipt(lock_tx_hash).await.unwrap().unwrap();
+        let lock_block =
+            provider.get_block_by_number(receipt.block_number.unwrap().into()).await.unwrap();
+        let delay_secs = lock_block.unwrap().header.timestamp - target_timestamp;
+        assert!(delay_secs >= 30);
+        assert!(logs_contain(&format!("target_to_lock_secs={delay_secs}")));
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_lock_txs() {
+        let market = MockMarket::default()
+            .with_lock_delay(Duration::from_millis(50))
+            .with_lock(|request_id| Err(MarketError::RequestAlreadyLocked(request_id)));
+        let max_in_flight = market.max_locks_in_flight.clone();
+        let (monitor, _db) = setup_mock_market_monitor(market).await;
+        let orders = |indices: std::ops::Range<u32>| -> Vec<Arc<OrderRequest>> {
+            indices
+                .map(|idx| {
+                    let mut order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+                    order.request.id = RequestId::new(Address::ZERO, idx).into();
+                    Arc::new(order)
+                })
+                .collect()
+        };
+
+        // A limit of one serializes the lock transactions.
+        monitor.config.load_write().unwrap().market.max_concurrent_lock_txs = Some(1);
+        monitor.lock_and_prove_orders(&orders(0..6)).await.unwrap();
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+
+        monitor.config.load_write().unwrap().market.max_concurrent_lock_txs = Some(2);
+        monitor.lock_and_prove_orders(&orders(6..12)).await.unwrap();
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    /// Estimator that is unlikely to win the lock of requests with an even index.
+    struct StubContestEstimator;
+
+    #[async_trait::async_trait]
+    impl crate::contest::ContestEstimator for StubContestEstimator {
+        async fn win_probability(
+            &self,
+            order: &OrderRequest,
+            _recent: &LockOutcomeStats,
+        ) -> Result<f64> {
+            let index = RequestId::try_from(order.request.id).unwrap().index;
+            Ok(if index % 2 == 0 { 0.1 } else { 0.9 })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_low_win_probability_not_locked() {
+        let (monitor, db) = setup_mock_market_monitor(
+            MockMarket::default()
+                .with_lock(|request_id| Err(MarketError::RequestAlreadyLocked(request_id))),
+        )
+        .await;
+        let monitor = monitor.with_contest_estimator(Arc::new(StubContestEstimator));
+        monitor.config.load_write().unwrap().market.min_lock_win_probability = Some(0.5);
+
+        let orders: Vec<Arc<OrderRequest>> = (0..2)
+            .map(|idx| {
+                let mut order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+                order.request.id = RequestId::new(Address::ZERO, idx).into();
+                Arc::new(order)
+            })
+            .collect();
+        for order in &orders {
+            monitor.lock_and_prove_cache.insert(order.id(), order.clone()).await;
+        }
+        monitor.lock_and_prove_orders(&orders).await.unwrap();
+
+        // The unlikely order is left in the cache without attempting to lock it.
+        assert!(db.get_order(&orders[0].id()).await.unwrap().is_none());
+        assert!(monitor.lock_and_prove_cache.get(&orders[0].id()).await.is_some());
+
+        let contested = db.get_order(&orders[1].id()).await.unwrap().unwrap();
+        assert_eq!(contested.status, OrderStatus::Skipped);
+        assert!(monitor.lock_and_prove_cache.get(&orders[1].id()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_submit_request_before_lock() {
+        let mut ctx = setup_om_test_context().await;
+        ctx.config.load_write().unwrap().market.submit_request_before_lock = true;
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let request_id = U256::from(order.request.id);
+        assert!(matches!(
+            ctx.market_service.get_submitted_request(request_id, None).await,
+            Err(MarketError::RequestNotFound(_))
+        ));
+
+        ctx.monitor.lock_order(&order).await.unwrap();
+
+        let (submitted, _) =
+            ctx.market_service.get_submitted_request(request_id, None).await.unwrap();
+        assert_eq!(submitted.id, order.request.id);
+        assert!(ctx.market_service.is_locked(request_id).await.unwrap());
+
+        // The request is not submitted again.
+        let resubmitted = ctx
+            .monitor
+            .market
+            .submit_request_if_absent(&order.request, order.client_sig.clone())
+            .await
+            .unwrap();
+        assert!(!resubmitted);
+    }
+
+    #[tokio::test]
+    async fn test_lock_rpc_failure() {
+        let (monitor, db) = setup_mock_market_monitor(MockMarket::unreachable()).await;
+
+        let order = mock_order(FulfillmentType::LockAndFulfill);
+        let result = monitor.lock_order(&order).await;
+        assert!(matches!(result, Err(OrderMonitorErr::RpcErr(_))));
+
+        // When retained, no skip is recorded and the order remains cached for the next iteration.
+        monitor.config.load_write().unwrap().market.retain_lock_rpc_failures = true;
+        monitor.lock_and_prove_cache.insert(order.id(), order.clone()).await;
+        let since = now_timestamp();
+        monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+        assert!(db.get_order(&order.id()).await.unwrap().is_none());
+        assert!(monitor.lock_and_prove_cache.get(&order.id()).await.is_some());
+        // The order was never attempted, so the failure is not a lock outcome.
+        assert_eq!(db.get_lock_outcome_stats(since).await.unwrap(), LockOutcomeStats::default());
+
+        // Otherwise, the order is skipped as for any other lock failure.
+        monitor.config.load_write().unwrap().market.retain_lock_rpc_failures = false;
+        monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+        let db_order = db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(db_order.status, OrderStatus::Skipped);
+        assert!(monitor.lock_and_prove_cache.get(&order.id()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_auto_stake_topup() {
+        let deposits = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded_deposits = deposits.clone();
+        let market = MockMarket::default()
+            .with_stake_balance(U256::from(2_000_000))
+            .with_deposit_stake(move |value| {
+                recorded_deposits.lock().unwrap().push(value);
+                Ok(())
+            });
+        let (monitor, _db) = setup_mock_market_monitor(market).await;
+        {
+            let mut config = monitor.config.load_write().unwrap();
+            config.market.stake_balance_warn_threshold = Some("5".to_string());
+            config.market.auto_stake_topup =
+                Some(crate::config::StakeTopUp { target: "10".to_string(), cooldown_secs: 3600 });
+        }
+
+        // A balance of 2 tokens, below the threshold of 5, is restored to the target of 10.
+        // Stake is never deposited in prove-only mode, as it is only used to lock.
+        let config =
+            OrderMonitorConfig { prove_only: true, ..monitor.load_monitor_config().unwrap() };
+        assert!(monitor.spawn_stake_topup(&config).is_none());
+
+        // The deposit runs in the background, and no other is spawned while it is in progress.
+        let config = monitor.load_monitor_config().unwrap();
+        let topup = monitor.spawn_stake_topup(&config).unwrap();
+        assert!(monitor.spawn_stake_topup(&config).is_none());
+        topup.await.unwrap();
+        assert_eq!(*deposits.lock().unwrap(), [U256::from(8_000_000)]);
+
+        // The deposit has not landed yet, but no other deposit is made within the cooldown.
+        assert!(monitor.spawn_stake_topup(&config).is_none());
+        assert_eq!(deposits.lock().unwrap().len(), 1);
+
+        monitor.config.load_write().unwrap().market.auto_stake_topup =
+            Some(crate::config::StakeTopUp { target: "10".to_string(), cooldown_secs: 0 });
+        let config = monitor.load_monitor_config().unwrap();
+        monitor.spawn_stake_topup(&config).unwrap().await.unwrap();
+        assert_eq!(deposits.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retain_transient_skips() {
+        let mut ctx = setup_om_test_context().await;
+        let prover = ctx.signer.address();
+        let monitor =
+            ctx.monitor.clone().with_market_client(Arc::new(MockMarket::default().with_lock(
+                move |_| Err(MarketError::Error(anyhow::anyhow!("InsufficientBalance({prover})"))),
+            )));
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let order_id = order.id();
+        monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+
+        // Without retaining transient skips, the order is dropped.
+        let order = monitor.lock_and_prove_cache.get(&order_id).await.unwrap();
+        monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+        assert!(monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+        assert_eq!(
+            ctx.db.get_order(&order_id).await.unwrap().unwrap().status,
+            OrderStatus::Skipped
+        );
+
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let order_id = order.id();
+        monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+        ctx.config.load_write().unwrap().market.retain_transient_skips = true;
+
+        // The order remains a candidate, and is not recorded as skipped.
+        let order = monitor.lock_and_prove_cache.get(&order_id).await.unwrap();
+        monitor.lock_and_prove_orders(&[order]).await.unwrap();
+        assert!(ctx.db.get_order(&order_id).await.unwrap().is_none());
+        let config = OrderMonitorConfig { min_deadline: 0, ..Default::default() };
+        let valid_orders = monitor.get_valid_orders(now_timestamp(), &config, None).await.unwrap();
+        assert_eq!(
+            valid_orders.iter().map(|order| order.id()).collect::<Vec<_>>(),
+            [order_id.clone()]
+        );
+
+        // Once the balance is topped up, the order is locked by the monitor sharing the cache.
+        ctx.monitor.lock_and_prove_orders(&valid_orders).await.unwrap();
+        assert_eq!(
+            ctx.db.get_order(&order_id).await.unwrap().unwrap().status,
+            OrderStatus::PendingProving
+        );
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lock_block_receipt_fallback() {
+        let mut ctx = setup_om_test_context().await;
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+
+        // Any mined transaction of the prover stands in for the lock.
+        let receipt = ctx
+            .monitor
+            .provider
+            .send_transaction(
+                alloy::rpc::types::TransactionRequest::default()
+                    .to(Address::repeat_byte(0x01))
+                    .value(U256::from(1)),
+            )
+            .await
+            .unwrap()
+            .get_receipt()
+            .await
+            .unwrap();
+        // The lock succeeds, reporting a block the node has not indexed yet.
+        let tx_hash = receipt.transaction_hash;
+        let monitor = ctx.monitor.clone().with_market_client(Arc::new(
+            MockMarket::default().with_lock(move |_| Ok((u64::MAX / 2, tx_hash))),
+        ));
+        {
+            let mut config = ctx.config.load_write().unwrap();
+            config.market.block_fetch_retry_count = Some(2);
+            config.market.block_fetch_retry_sleep_ms = Some(0);
+        }
+
+        // Fetching the reported block by number never succeeds.
+        let result = monitor.lock_order(&order).await;
+        assert!(matches!(result, Err(OrderMonitorErr::UnexpectedError(_))));
+
+        ctx.config.load_write().unwrap().market.block_fetch_receipt_fallback_after = Some(1);
+        let (_, lock_tx_hash) = monitor.lock_order(&order).await.unwrap();
+        assert_eq!(lock_tx_hash, receipt.transaction_hash);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_reverted_lock_wasted_gas() {
+        let mut ctx = setup_om_test_context().await;
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+
+        // Any mined transaction of the prover stands in for the reverted lock.
+        let receipt = ctx
+            .monitor
+            .provider
+            .send_transaction(
+                alloy::rpc::types::TransactionRequest::default()
+                    .to(Address::repeat_byte(0x01))
+                    .value(U256::from(1)),
+            )
+            .await
+            .unwrap()
+            .get_receipt()
+            .await
+            .unwrap();
+        let tx_wei = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+        let tx_hash = receipt.transaction_hash;
+        let monitor = ctx.monitor.clone().with_market_client(Arc::new(
+            MockMarket::default().with_lock(move |_| Err(MarketError::LockRevert(tx_hash))),
+        ));
+
+        for attempt in 1..=2 {
+            let err = monitor.lock_order(&order).await.unwrap_err();
+            assert!(matches!(err, OrderMonitorErr::LockTxFailed { .. }));
+            assert_eq!(monitor.wasted_gas_wei(), tx_wei * U256::from(attempt));
+        }
+        assert!(logs_contain(&format!("wasted_gas_wei={}", tx_wei * U256::from(2))));
+    }
+
+    #[tokio::test]
+    async fn test_db_locked_order_filtered_before_status() {
+        let (monitor, db) = setup_mock_market_monitor(
+            MockMarket::default()
+                .with_status(|_, _| {
+                    panic!("status must not be queried for orders locked in the DB")
+                })
+                .with_lock(|_| panic!("orders locked in the DB must not be locked")),
+        )
+        .await;
+
+        let order = mock_order(FulfillmentType::LockAndFulfill);
+        db.set_request_locked(
+            U256::from(order.request.id),
+            &Address::repeat_byte(0x01).to_string(),
+            1,
+        )
+        .await
+        .unwrap();
+        monitor.lock_and_prove_cache.insert(order.id(), order.clone()).await;
+
+        let since = now_timestamp();
+        monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+
+        let db_order = db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(db_order.status, OrderStatus::Skipped);
+        assert!(monitor.lock_and_prove_cache.get(&order.id()).await.is_none());
+        assert_eq!(
+            db.get_lock_outcome_stats(since).await.unwrap(),
+            LockOutcomeStats { wins: 0, losses: 1, errors: 0, top_winners: Vec::new() }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_db_self_locked_order_not_filtered() {
+        let (monitor, db) = setup_mock_market_monitor(
+            MockMarket::default()
+                .with_status(|_, _| panic!("status must not be queried for orders locked by us"))
+                .with_lock(|_| panic!("orders locked by us must not be locked again")),
+        )
+        .await;
+
+        let mut order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+        order.urgent = true;
+        let order = Arc::new(order);
+        db.set_request_locked(
+            U256::from(order.request.id),
+            &monitor.provider.default_signer_address().to_string(),
+            1,
+        )
+        .await
+        .unwrap();
+
+        let since = now_timestamp();
+        monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+
+        let db_order = db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(db_order.status, OrderStatus::PendingProving);
+        assert_eq!(db.get_lock_outcome_stats(since).await.unwrap(), LockOutcomeStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_lock_not_owned_skipped() {
+        let (monitor, db) = setup_mock_market_monitor(
+            MockMarket::default().with_locker(|_| Ok(Some(Address::repeat_byte(0xcc)))),
+        )
+        .await;
+        monitor.config.load_write().unwrap().market.verify_lock_owner = true;
+
+        let order = mock_order(FulfillmentType::LockAndFulfill);
+        let result = monitor.lock_order(&order).await;
+        assert!(matches!(
+            result,
+            Err(OrderMonitorErr::LockNotOwned(locker)) if locker == Address::repeat_byte(0xcc)
+        ));
+
+        monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+        let order = db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_lock_status_confirmations() {
+        let mut ctx = setup_om_test_context().await;
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let queried_block = Arc::new(std::sync::Mutex::new(None));
+        let recorded_block = queried_block.clone();
+        let market = MockMarket::default()
+            .with_status(move |_, block_number| {
+                if block_number.is_some() {
+                    *recorded_block.lock().unwrap() = block_number;
+                }
+                Ok(RequestStatus::Locked)
+            })
+            .with_lock(|request_id| Err(MarketError::RequestAlreadyLocked(request_id)));
+        let monitor = ctx.monitor.clone().with_market_client(Arc::new(market));
+
+        // The latest block is used by default.
+        let err = monitor.lock_order(&order).await.unwrap_err();
+        assert!(matches!(err, OrderMonitorErr::AlreadyLocked));
+        assert_eq!(*queried_block.lock().unwrap(), None);
+
+        ctx.config.load_write().unwrap().market.lock_status_confirmations = Some(2);
+        let head = ctx.monitor.provider.get_block_number().await.unwrap();
+        let err = monitor.lock_order(&order).await.unwrap_err();
+        assert!(matches!(err, OrderMonitorErr::AlreadyLocked));
+        assert_eq!(*queried_block.lock().unwrap(), Some(head.saturating_sub(2)));
+    }
+
+    #[tokio::test]
+    async fn test_get_status_at_block() {
+        let mut ctx = setup_om_test_context().await;
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let before_lock = ctx.monitor.provider.get_block_number().await.unwrap();
+
+        ctx.monitor.lock_order(&order).await.unwrap();
+
+        let market = &ctx.monitor.market;
+        let expires_at = Some(order.request.expires_at());
+        let lock_block = ctx.monitor.provider.get_block_number().await.unwrap();
+        assert_eq!(
+            market.get_status_at(order.request.id, expires_at, before_lock).await.unwrap(),
+            RequestStatus::Unknown
+        );
+        assert_eq!(
+            market.get_status_at(order.request.id, expires_at, lock_block).await.unwrap(),
+            RequestStatus::Locked
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lock_tx_hash_stored() {
+        let mut ctx = setup_om_test_context().await;
+        let order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await,
+        );
+
+        ctx.monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+
+        let lock_tx_hash = ctx.db.get_order_lock_tx_hash(&order.id()).await.unwrap().unwrap();
+        let receipt =
+            ctx.monitor.provider.get_transaction_receipt(lock_tx_hash).await.unwrap().unwrap();
+        assert!(receipt.inner.logs().iter().any(|log| {
+            log.log_decode::<IBoundlessMarket::RequestLocked>()
+                .is_ok_and(|event| event.inner.data.requestId == order.request.id)
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_lock_price_basis() {
+        let mut ctx = setup_om_test_context().await;
+
+        // The price ramped up from 1 to 2 by now, while the target timestamp of 0 precedes the
+        // start of the bidding.
+        let mut prices = Vec::new();
+        for basis in [LockPriceBasis::Target, LockPriceBasis::Confirmation] {
+            ctx.config.load_write().unwrap().market.lock_price_basis = basis;
+            let order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, now_timestamp() - 10, 100, 200)
+                .await;
+            let (lock_price, _) = ctx.monitor.lock_order(&order).await.unwrap();
+            let recorded =
+                ctx.db.get_lock_prices(U256::from(order.request.id)).await.unwrap().unwrap();
+            prices.push((lock_price, recorded));
+        }
+
+        let expected = LockPrices {
+            submission: U256::from(2),
+            target: Some(U256::from(1)),
+            confirmation: U256::from(2),
+        };
+        assert_eq!(prices, [(U256::from(1), expected.clone()), (U256::from(2), expected)]);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_lock_price_logged_in_ether() {
+        let mut ctx = setup_om_test_context().await;
+        let order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await,
+        );
+
+        ctx.monitor.lock_and_prove_orders(&[order.clone()]).await.unwrap();
+
+        let lock_price = ctx.db.get_order(&order.id()).await.unwrap().unwrap().lock_price.unwrap();
+        assert!(lock_price > U256::ZERO);
+        assert!(logs_contain(&format!(
+            "at price: {lock_price} wei ({} ether)",
+            format_ether(lock_price)
+        )));
+        let stake = format_units(U256::ZERO, ctx.monitor.stake_token_decimals).unwrap();
+        assert!(logs_contain(&format!("for stake: 0 ({stake} stake token)")));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_order_costs_concurrently() {
+        let orders: Vec<Arc<OrderRequest>> = (0..50)
+            .map(|idx| {
+                let mut order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+                order.request.id = RequestId::new(Address::ZERO, idx).into();
+                Arc::new(order)
+            })
+            .collect();
+        let cost_of = |order: &OrderRequest| order.request.id * U256::from(1_000);
+
+        let expected: Vec<(U256, String)> =
+            orders.iter().map(|order| (cost_of(order), order.id())).collect();
+
+        let in_flight = &std::sync::atomic::AtomicUsize::new(0);
+        let max_in_flight = &std::sync::atomic::AtomicUsize::new(0);
+        let concurrency = 4;
+        let costs = estimate_order_costs(orders, concurrency, |order| async move {
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(current, Ordering::SeqCst);
+            // Earlier orders take longer so estimations complete out of order.
+            let delay = 50 - order.request.id.to::<u64>();
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(cost_of(&order))
+        })
+        .await
+        .unwrap();
+
+        let costs: Vec<(U256, String)> =
+            costs.into_iter().map(|(cost, order)| (cost, order.id())).collect();
+        assert_eq!(costs, expected);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), concurrency);
+    }
+
+    #[tokio::test]
+    async fn test_lock_tx_failed_cause() {
+        let cases: [(fn(U256) -> Result<(u64, TxHash), MarketError>, LockFailureCause); 4] = [
+            (
+                |_| Err(MarketError::LockRevert(Default::default())),
+                LockFailureCause::RevertedByCompetitor,
+            ),
+            (
+                |_| {
+                    Err(MarketError::TxnError(TxnErr::MissingData(
+                        -32000,
+                        "replacement transaction underpriced".into(),
+                    )))
+                },
+                LockFailureCause::Underpriced,
+            ),
+            (
+                |_| Err(MarketError::TxnError(TxnErr::MissingData(-32000, "nonce too low".into()))),
+                LockFailureCause::NonceError,
+            ),
+            (|_| Err(MarketError::TxnError(TxnErr::BytesDecode)), LockFailureCause::Other),
+        ];
+
+        for (lock_result, expected_cause) in cases {
+            let (monitor, _db) =
+                setup_mock_market_monitor(MockMarket::default().with_lock(lock_result)).await;
+            let order = mock_order(FulfillmentType::LockAndFulfill);
+            match monitor.lock_order(&order).await {
+                Err(OrderMonitorErr::LockTxFailed { cause, .. }) => {
+                    assert_eq!(cause, expected_cause)
+                }
+                res => {
+                    panic!("Expected lock tx failure with cause {expected_cause:?}, got {res:?}")
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tick_trace() {
+        let mut ctx = setup_om_test_context().await;
+        let (tick_trace_tx, mut tick_trace_rx) = mpsc::channel(1);
+        let monitor = ctx.monitor.clone().with_tick_trace_sender(tick_trace_tx);
+        let current_timestamp = now_timestamp();
+
+        let valid_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let valid_order_id = valid_order.id();
+        let lock_expired_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp - 200, 100, 400)
+            .await;
+        let lock_expired_order_id = lock_expired_order.id();
+        let locked_by_other_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let locked_by_other_order_id = locked_by_other_order.id();
+        ctx.db
+            .set_request_locked(
+                U256::from(locked_by_other_order.request.id),
+                &Address::ZERO.to_string(),
+                current_timestamp,
+            )
+            .await
+            .unwrap();
+
+        for order in [valid_order, lock_expired_order, locked_by_other_order] {
+            monitor.lock_and_prove_cache.insert(order.id(), Arc::from(order)).await;
+        }
+
+        let monitor_config = monitor.load_monitor_config().unwrap();
+        monitor.run_tick(1, current_timestamp, &monitor_config, &mut String::new()).await.unwrap();
+        let mut trace = tick_trace_rx.try_recv().unwrap();
+
+        assert_eq!(trace.block_number, 1);
+        assert_eq!(trace.block_timestamp, current_timestamp);
+        trace.pulled.sort();
+        let mut expected_pulled = vec![
+            valid_order_id.clone(),
+            lock_expired_order_id.clone(),
+            locked_by_other_order_id.clone(),
+        ];
+        expected_pulled.sort();
+        assert_eq!(trace.pulled, expected_pulled);
+        trace.filtered.sort();
+        let mut expected_filtered = vec![
+            (lock_expired_order_id, "lock expired before we locked".to_string()),
+            (locked_by_other_order_id, "locked by another prover".to_string()),
+        ];
+        expected_filtered.sort();
+        assert_eq!(trace.filtered, expected_filtered);
+        assert_eq!(trace.prioritized, vec![valid_order_id.clone()]);
+        assert_eq!(trace.capacity.as_deref(), Some("Unlimited"));
+        assert_eq!(trace.admitted, vec![valid_order_id.clone()]);
+        assert_eq!(trace.locked, vec![valid_order_id]);
+        assert!(trace.failures.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recent_ticks() {
+        let mut ctx = setup_om_test_context().await;
+        let monitor = ctx.monitor.clone().with_tick_history(2);
+        let monitor_config = monitor.load_monitor_config().unwrap();
+        let current_timestamp = now_timestamp();
+
+        // First tick admits a valid order.
+        let valid_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let valid_order_id = valid_order.id();
+        monitor.lock_and_prove_cache.insert(valid_order.id(), Arc::from(valid_order)).await;
+        monitor.run_tick(1, current_timestamp, &monitor_config, &mut String::new()).await.unwrap();
+
+        // Second tick skips an order whose lock expired.
+        let lock_expired_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp - 200, 100, 400)
+            .await;
+        let lock_expired_order_id = lock_expired_order.id();
+        monitor
+            .lock_and_prove_cache
+            .insert(lock_expired_order.id(), Arc::from(lock_expired_order))
+            .await;
+        monitor.run_tick(2, current_timestamp, &monitor_config, &mut String::new()).await.unwrap();
+
+        let ticks = monitor.recent_ticks(10);
+        assert_eq!(ticks.iter().map(|tick| tick.block_number).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(ticks[0].considered, 1);
+        assert_eq!(ticks[0].admitted, vec![valid_order_id]);
+        assert!(ticks[0].skipped.is_empty());
+        assert_eq!(ticks[1].considered, 1);
+        assert!(ticks[1].admitted.is_empty());
+        assert_eq!(
+            ticks[1].skipped,
+            vec![(lock_expired_order_id, "lock expired before we locked".to_string())]
+        );
+
+        // Third tick has nothing to consider and evicts the oldest summary.
+        monitor.run_tick(3, current_timestamp, &monitor_config, &mut String::new()).await.unwrap();
+        let ticks = monitor.recent_ticks(10);
+        assert_eq!(ticks.iter().map(|tick| tick.block_number).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(ticks[1].considered, 0);
+        assert_eq!(monitor.recent_ticks(1), vec![ticks[1].clone()]);
+
+        // Without the history enabled, nothing is kept.
+        ctx.monitor
+            .run_tick(4, current_timestamp, &monitor_config, &mut String::new())
+            .await
+            .unwrap();
+        assert!(ctx.monitor.recent_ticks(10).is_empty());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_monitor_loop_deadline() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        // With a zero deadline, evaluating any order takes longer than the deadline allows.
+        ctx.config.load_write().unwrap().market.monitor_loop_deadline_secs = Some(0);
+
+        let mut order_ids = Vec::new();
+        for _ in 0..3 {
+            let order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await;
+            order_ids.push(order.id());
+            ctx.monitor.lock_and_prove_cache.insert(order.id(), Arc::from(order)).await;
+        }
+
+        let mut num_locked = 0;
+        for iteration in 1..=3 {
+            let monitor_config = ctx.monitor.load_monitor_config().unwrap();
+            ctx.monitor
+                .run_tick(iteration, current_timestamp, &monitor_config, &mut String::new())
+                .await
+                .unwrap();
+
+            // Only a single order is evaluated per iteration, the rest remain cached.
+            let mut locked = 0;
+            for order_id in &order_ids {
+                if let Some(order) = ctx.db.get_order(order_id).await.unwrap() {
+                    assert_eq!(order.status, OrderStatus::PendingProving);
+                    locked += 1;
+                }
+            }
+            assert_eq!(locked, num_locked + 1);
+            num_locked = locked;
+            ctx.monitor.lock_and_prove_cache.run_pending_tasks().await;
+            assert_eq!(ctx.monitor.lock_and_prove_cache.entry_count(), 3 - iteration);
+        }
+        assert!(logs_contain("monitor_loop_truncated=1"));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_reevaluate_on_config_change() {
+        let mut ctx = setup_om_test_context().await;
+        let (tick_trace_tx, mut tick_trace_rx) = mpsc::channel(1);
+        let monitor = ctx.monitor.clone().with_tick_trace_sender(tick_trace_tx);
+        let current_timestamp = now_timestamp();
+        {
+            let mut config = ctx.config.load_write().unwrap();
+            // Keep orders cached between evaluations.
+            config.market.pause_locking = true;
+            config.market.order_commitment_priority = OrderCommitmentPriority::ShortestExpiry;
+        }
+
+        // The order expiring first pays less per unit of stake.
+        let mut order_ids = Vec::new();
+        for (lock_timeout, price) in [(100, "1"), (150, "2")] {
+            let mut order = ctx
+                .create_test_order(
+                    FulfillmentType::LockAndFulfill,
+                    current_timestamp,
+                    lock_timeout,
+                    200,
+                )
+                .await;
+            let price: U256 = parse_units(price, "ether").unwrap().into();
+            order.request.offer.minPrice = price;
+            order.request.offer.maxPrice = price;
+            order.request.offer.lockStake = U256::from(1);
+            order_ids.push(order.id());
+            monitor.lock_and_prove_cache.insert(order.id(), Arc::from(order)).await;
+        }
+
+        let mut prev_orders_by_status = String::new();
+        let mut applied_config = Some(monitor.load_monitor_config().unwrap());
+        monitor
+            .run_tick(
+                1,
+                current_timestamp,
+                applied_config.as_ref().unwrap(),
+                &mut prev_orders_by_status,
+            )
+            .await
+            .unwrap();
+        assert_eq!(tick_trace_rx.try_recv().unwrap().admitted, order_ids);
+
+        // Unchanged config does not trigger a re-evaluation.
+        assert!(!monitor
+            .reevaluate_on_config_change(&mut applied_config, &mut prev_orders_by_status)
+            .await
+            .unwrap());
+
+        ctx.config.load_write().unwrap().market.order_commitment_priority =
+            OrderCommitmentPriority::RoiAdjusted;
+        assert!(monitor
+            .reevaluate_on_config_change(&mut applied_config, &mut prev_orders_by_status)
+            .await
+            .unwrap());
+        order_ids.reverse();
+        assert_eq!(tick_trace_rx.try_recv().unwrap().admitted, order_ids);
+        assert!(logs_contain("Order monitor config changed (order_commitment_priority)"));
+    }
+
+    #[tokio::test]
+    async fn test_standby_does_not_lock_until_promoted() {
+        let mut ctx = setup_om_test_context().await;
+        let monitor = ctx.monitor.clone().with_role(MonitorRole::Standby);
+        let current_timestamp = now_timestamp();
+
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let order_id = order.id();
+        monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+
+        let monitor_config = monitor.load_monitor_config().unwrap();
+        monitor.run_tick(1, current_timestamp, &monitor_config, &mut String::new()).await.unwrap();
+        assert_eq!(monitor.role(), MonitorRole::Standby);
+        assert!(ctx.db.get_order(&order_id).await.unwrap().is_none());
+        assert!(monitor.lock_and_prove_cache.get(&order_id).await.is_some());
+
+        // Promoting any clone of the monitor promotes the running instance.
+        ctx.monitor.promote();
+        assert_eq!(monitor.role(), MonitorRole::Active);
+        monitor.run_tick(2, current_timestamp, &monitor_config, &mut String::new()).await.unwrap();
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::PendingProving);
+        assert!(monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_lock_block_fetch_retry_config() {
+        let (monitor, _db) = setup_mock_market_monitor(MockMarket::default()).await;
+        {
+            let mut config = monitor.config.load_write().unwrap();
+            config.market.block_fetch_retry_count = Some(3);
+            config.market.block_fetch_retry_sleep_ms = Some(0);
+        }
+
+        // The mock monitor's provider is unreachable, so every block fetch fails.
+        let order = mock_order(FulfillmentType::LockAndFulfill);
+        let result = monitor.lock_order(&order).await;
+        assert!(matches!(result, Err(OrderMonitorErr::UnexpectedError(_))));
+        assert!(logs_contain("Operation [get_block_by_number] failed after 3 retries"));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_stale_chain_head_pauses_locking() {
+        let mut ctx = setup_om_test_context().await;
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let order_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+
+        let config = OrderMonitorConfig { max_chain_head_age_secs: Some(60), ..Default::default() };
+        ctx.monitor
+            .process_orders(now_timestamp() - 600, &config, &mut String::new())
+            .await
+            .unwrap();
+        assert!(ctx.monitor.is_chain_stalled());
+        assert!(logs_contain("Chain stalled"));
+        assert!(ctx.db.get_order(&order_id).await.unwrap().is_none());
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_some());
+
+        // Locking resumes once the chain head advances.
+        ctx.monitor.process_orders(now_timestamp(), &config, &mut String::new()).await.unwrap();
+        assert!(!ctx.monitor.is_chain_stalled());
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::PendingProving);
+    }
+
+    #[tokio::test]
+    async fn test_stale_chain_head_no_lock_attempted() {
+        let (monitor, db) = setup_mock_market_monitor(
+            MockMarket::default().with_lock(|_| panic!("request should not be locked")),
+        )
+        .await;
+        let order = mock_order(FulfillmentType::LockAndFulfill);
+        monitor.lock_and_prove_cache.insert(order.id(), order.clone()).await;
+
+        // All admissions are deferred on a stale head, before any status check or lock.
+        let config = OrderMonitorConfig { max_chain_head_age_secs: Some(60), ..Default::default() };
+        monitor.process_orders(now_timestamp() - 600, &config, &mut String::new()).await.unwrap();
+        assert!(monitor.is_chain_stalled());
+        assert!(db.get_order(&order.id()).await.unwrap().is_none());
+        assert!(monitor.lock_and_prove_cache.get(&order.id()).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_session() {
+        let mut ctx = setup_om_test_context().await;
+        ctx.config.load_write().unwrap().market.max_concurrent_proofs = Some(2);
+        ctx.config.load_write().unwrap().market.order_commitment_priority =
+            OrderCommitmentPriority::ShortestExpiry;
+        let path = std::env::temp_dir().join(format!("session-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let session_recorder = Arc::new(SessionRecorder::to_file(&path).unwrap());
+        let monitor = ctx.monitor.clone().with_session_recorder(session_recorder.clone());
+
+        // Three orders compete for two slots in the first tick, leaving no capacity for the order
+        // received before the second tick.
+        let current_timestamp = now_timestamp();
+        for lock_timeout in [100, 120, 140] {
+            let order = ctx
+                .create_test_order(
+                    FulfillmentType::LockAndFulfill,
+                    current_timestamp,
+                    lock_timeout,
+                    200,
+                )
+                .await;
+            monitor.handle_new_order_result(Box::new(order)).await.unwrap();
+        }
+        let config = monitor.load_monitor_config().unwrap();
+        monitor.run_tick(1, current_timestamp, &config, &mut String::new()).await.unwrap();
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        monitor.handle_new_order_result(Box::new(order)).await.unwrap();
+        monitor.run_tick(2, current_timestamp + 2, &config, &mut String::new()).await.unwrap();
+
+        // Finished ticks are written to file rather than kept in memory.
+        assert!(session_recorder.session().ticks.is_empty());
+        let session = RecordedSession::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(session.ticks.len(), 2);
+        assert_eq!(session.ticks[0].orders.len(), 3);
+        assert_eq!(session.ticks[0].admitted.len(), 2);
+        assert!(session.ticks[0].gas_price.is_some());
+        assert!(session.ticks[0].balance_wei.is_some());
+        assert_eq!(session.ticks[1].orders.len(), 1);
+        assert!(session.ticks[1].admitted.is_empty());
+
+        // Replayed offline with the same config, without a chain, the same orders are admitted.
+        let (replay_monitor, replay_db) = setup_mock_market_monitor(
+            MockMarket::default().with_lock(|_| panic!("replayed locks should not be sent")),
+        )
+        .await;
+        {
+            let mut replay_config = replay_monitor.config.load_write().unwrap();
+            let live_config = ctx.config.lock_all().unwrap();
+            replay_config.market.min_deadline = live_config.market.min_deadline;
+            replay_config.market.lockin_gas_estimate = live_config.market.lockin_gas_estimate;
+            replay_config.market.fulfill_gas_estimate = live_config.market.fulfill_gas_estimate;
+            replay_config.market.groth16_verify_gas_estimate =
+                live_config.market.groth16_verify_gas_estimate;
+            replay_config.market.max_concurrent_proofs = live_config.market.max_concurrent_proofs;
+            replay_config.market.order_commitment_priority =
+                live_config.market.order_commitment_priority;
+        }
+        let replayed = replay_monitor.replay(&session).await.unwrap();
+        assert_eq!(replayed.admitted(), session.admitted());
+        for order_id in &session.ticks[0].admitted {
+            let order = replay_db.get_order(order_id).await.unwrap().unwrap();
+            assert_eq!(order.status, OrderStatus::PendingProving);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_skip_unchanged_admission() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        ctx.monitor.lock_and_prove_cache.insert(order.id(), Arc::from(order)).await;
+        let new_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+
+        // Without capacity, the order is evaluated but not locked.
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(0),
+            skip_unchanged_admission: true,
+            ..Default::default()
+        };
+        *ctx.monitor.tick_trace.lock().unwrap() = Some(TickTrace::default());
+        ctx.monitor.process_orders(current_timestamp, &config, &mut String::new()).await.unwrap();
+        let trace = ctx.monitor.tick_trace.lock().unwrap().take().unwrap();
+        assert_eq!(trace.capacity.as_deref(), Some("Available(0)"));
+
+        // With the node gone, any balance or gas price RPC call would fail the iteration.
+        drop(ctx.anvil);
+        *ctx.monitor.tick_trace.lock().unwrap() = Some(TickTrace::default());
+        ctx.monitor.process_orders(current_timestamp, &config, &mut String::new()).await.unwrap();
+        let trace = ctx.monitor.tick_trace.lock().unwrap().take().unwrap();
+        assert!(trace.capacity.is_none());
+
+        // A new order triggers the admission pass again.
+        ctx.monitor.lock_and_prove_cache.insert(new_order.id(), Arc::from(new_order)).await;
+        assert!(ctx
+            .monitor
+            .process_orders(current_timestamp, &config, &mut String::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_stale_orders() {
+        let (monitor, db) = setup_mock_market_monitor(
+            MockMarket::default().with_status(|_, _| Ok(RequestStatus::Expired)),
+        )
+        .await;
+        {
+            let mut config = monitor.config.load_write().unwrap();
+            config.market.max_proving_duration_secs = Some(600);
+        }
+        let now = now_timestamp();
+
+        let mut orders = Vec::new();
+        for (request_idx, proving_started_at) in [(1, now - 1_000), (2, now - 10)] {
+            let mut order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+            order.request.id = RequestId::new(Address::ZERO, request_idx).into();
+            let mut order = order.to_proving_order(U256::ZERO);
+            order.status = OrderStatus::Proving;
+            order.proving_started_at = Some(proving_started_at);
+            db.add_order(&order).await.unwrap();
+            orders.push(order);
+        }
+        let (stale_order, fresh_order) = (&orders[0], &orders[1]);
+
+        let config = OrderMonitorConfig { max_concurrent_proofs: Some(2), ..Default::default() };
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(0));
+
+        monitor.reconcile_stale_orders().await.unwrap();
+
+        let order = db.get_order(&stale_order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Failed);
+        let order = db.get_order(&fresh_order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Proving);
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(1));
+    }
+
+    /// Adds an order to the DB that started proving long enough ago to exceed a max proving
+    /// duration of 600 seconds.
+    async fn add_stale_order(db: &DbObj, request_idx: u32) -> Order {
+        let mut order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+        order.request.id = RequestId::new(Address::ZERO, request_idx).into();
+        let mut order = order.to_proving_order(U256::ZERO);
+        order.status = OrderStatus::Proving;
+        order.proving_started_at = Some(now_timestamp() - 1_000);
+        db.add_order(&order).await.unwrap();
+        order
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_stale_orders_status_unavailable() {
+        let (monitor, db) = setup_mock_market_monitor(MockMarket::unreachable()).await;
+        monitor.config.load_write().unwrap().market.max_proving_duration_secs = Some(600);
+        let order = add_stale_order(&db, 1).await;
+
+        // A transient RPC failure leaves the order to be reconciled on the next pass.
+        monitor.reconcile_stale_orders().await.unwrap();
+
+        let order = db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Proving);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_stale_locked_orders() {
+        let (monitor, db) = setup_mock_market_monitor(
+            MockMarket::default().with_status(|_, _| Ok(RequestStatus::Locked)),
+        )
+        .await;
+        monitor.config.load_write().unwrap().market.max_proving_duration_secs = Some(600);
+
+        let locked_by_us = add_stale_order(&db, 1).await;
+        db.set_request_locked(
+            U256::from(locked_by_us.request.id),
+            &monitor.provider.default_signer_address().to_string(),
+            1,
+        )
+        .await
+        .unwrap();
+        let locked_by_other = add_stale_order(&db, 2).await;
+        db.set_request_locked(
+            U256::from(locked_by_other.request.id),
+            &Address::repeat_byte(0x01).to_string(),
+            1,
+        )
+        .await
+        .unwrap();
+
+        monitor.reconcile_stale_orders().await.unwrap();
+
+        // Our stake is at risk while the order is locked by us, so it is still proven.
+        let order = db.get_order(&locked_by_us.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Proving);
+        let order = db.get_order(&locked_by_other.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Failed);
+    }
+
+    /// Worker pool with a settable number of idle workers.
+    #[derive(Default)]
+    struct MockWorkerPool {
+        idle: Arc<std::sync::atomic::AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::worker_pool::WorkerPool for MockWorkerPool {
+        async fn idle_workers(&self) -> Result<u32> {
+            Ok(self.idle.load(Ordering::SeqCst))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_capacity_from_worker_pool() {
+        let (monitor, db) = setup_mock_market_monitor(MockMarket::default()).await;
+        let config = OrderMonitorConfig { max_concurrent_proofs: Some(10), ..Default::default() };
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(10));
+
+        let worker_pool = MockWorkerPool::default();
+        let idle = worker_pool.idle.clone();
+        let monitor = monitor.with_worker_pool(Arc::new(worker_pool));
+
+        idle.store(4, Ordering::SeqCst);
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(4));
+
+        // A worker crashed.
+        idle.store(3, Ordering::SeqCst);
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(3));
+
+        // An order waiting to be proven will take one of the idle workers.
+        let order = mock_order(FulfillmentType::LockAndFulfill).to_proving_order(U256::ZERO);
+        db.add_order(&order).await.unwrap();
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(2));
+
+        idle.store(0, Ordering::SeqCst);
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(0));
+    }
+
+    #[tokio::test]
+    async fn test_capacity_from_resource_probe() {
+        let (monitor, db) = setup_mock_market_monitor(MockMarket::default()).await;
+        let order = mock_order(FulfillmentType::LockAndFulfill).to_proving_order(U256::ZERO);
+        db.add_order(&order).await.unwrap();
+
+        // A quarter of the resources supports 2 of the 8 concurrent proofs, one of which is taken.
+        let monitor = monitor.with_resource_probe(Arc::new(ConstantResourceProbe(0.25)));
+        let config = OrderMonitorConfig { max_concurrent_proofs: Some(8), ..Default::default() };
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(1));
+
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(8),
+            min_concurrent_proofs: Some(4),
+            ..Default::default()
+        };
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(3));
+
+        let monitor = monitor.with_resource_probe(Arc::new(ConstantResourceProbe::default()));
+        let capacity =
+            monitor.get_proving_order_capacity(&config, &mut String::new()).await.unwrap();
+        assert_eq!(capacity, Capacity::Available(7));
+    }
+
+    /// Settlement stage with a settable backlog.
+    #[derive(Default)]
+    struct MockSettlementBacklog {
+        pending: Arc<std::sync::atomic::AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::settlement_backlog::SettlementBacklog for MockSettlementBacklog {
+        async fn pending_settlement(&self, _committed_orders: &[Order]) -> Result<u32> {
+            Ok(self.pending.load(Ordering::SeqCst))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admission_throttled_by_settlement_backlog() {
+        let mut ctx = setup_om_test_context().await;
+        let settlement_backlog = MockSettlementBacklog::default();
+        let pending = settlement_backlog.pending.clone();
+        ctx.monitor = ctx.monitor.with_settlement_backlog(Arc::new(settlement_backlog));
+
+        let mut orders: Vec<Arc<OrderRequest>> = Vec::new();
+        for _ in 0..3 {
+            let order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200)
+                .await;
+            orders.push(Arc::from(order));
+        }
+        let config = OrderMonitorConfig { max_settlement_backlog: Some(10), ..Default::default() };
+
+        pending.store(2, Ordering::SeqCst);
+        let selected = ctx
+            .monitor
+            .apply_capacity_limits(orders.clone(), &config, &mut String::new())
+            .await
+            .unwrap();
+        assert_eq!(selected.len(), 3);
+
+        // Only 2 more orders fit before the backlog reaches the limit.
+        pending.store(8, Ordering::SeqCst);
+        let selected = ctx
+            .monitor
+            .apply_capacity_limits(orders.clone(), &config, &mut String::new())
+            .await
+            .unwrap();
+        assert_eq!(selected.len(), 2);
+
+        pending.store(12, Ordering::SeqCst);
+        let selected =
+            ctx.monitor.apply_capacity_limits(orders, &config, &mut String::new()).await.unwrap();
+        assert!(selected.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_admission_throttled_by_db_settlement_backlog() {
+        let mut ctx = setup_om_test_context().await;
+        let now = now_timestamp();
+        // Only the orders proven and awaiting settlement count towards the backlog.
+        for status in
+            [OrderStatus::PendingAgg, OrderStatus::PendingSubmission, OrderStatus::Proving]
+        {
+            let order =
+                ctx.create_test_order(FulfillmentType::LockAndFulfill, now, 3600, 7200).await;
+            let mut order = order.to_proving_order(Default::default());
+            order.status = status;
+            ctx.db.add_order(&order).await.unwrap();
+        }
+
+        let mut orders: Vec<Arc<OrderRequest>> = Vec::new();
+        for _ in 0..3 {
+            let order = ctx.create_test_order(FulfillmentType::LockAndFulfill, now, 100, 200).await;
+            orders.push(Arc::from(order));
+        }
+        let config = OrderMonitorConfig { max_settlement_backlog: Some(3), ..Default::default() };
+        let selected =
+            ctx.monitor.apply_capacity_limits(orders, &config, &mut String::new()).await.unwrap();
+        assert_eq!(selected.len(), 1);
+    }
+
+    #[test]
+    fn test_scaled_concurrent_proofs() {
+        assert_eq!(scaled_concurrent_proofs(10, None, 1.0), 10);
+        assert_eq!(scaled_concurrent_proofs(10, None, 0.55), 5);
+        assert_eq!(scaled_concurrent_proofs(10, None, 0.0), 0);
+        assert_eq!(scaled_concurrent_proofs(10, Some(2), 0.0), 2);
+        assert_eq!(scaled_concurrent_proofs(10, Some(20), 0.5), 10);
+        assert_eq!(scaled_concurrent_proofs(10, None, 1.5), 10);
+    }
+
+    // Capacity tests
+    #[test]
+    fn test_capacity_unlimited() {
+        let capacity = Capacity::Unlimited;
+        assert_eq!(capacity.request_capacity(0, MAX_PROVING_BATCH_SIZE), 0);
+        assert_eq!(capacity.request_capacity(15, MAX_PROVING_BATCH_SIZE), MAX_PROVING_BATCH_SIZE);
+        assert_eq!(
+            capacity.request_capacity(MAX_PROVING_BATCH_SIZE, MAX_PROVING_BATCH_SIZE),
+            MAX_PROVING_BATCH_SIZE
+        );
+    }
+
+    #[test]
+    fn test_capacity_proving() {
+        let capacity = Capacity::Available(50);
+        assert_eq!(capacity.request_capacity(0, MAX_PROVING_BATCH_SIZE), 0);
+        assert_eq!(capacity.request_capacity(4, MAX_PROVING_BATCH_SIZE), 4);
+        assert_eq!(capacity.request_capacity(10, MAX_PROVING_BATCH_SIZE), MAX_PROVING_BATCH_SIZE);
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_gas_price_fallback() {
+        let mut last_gas_price = None;
+
+        // Without a previously known gas price, the failure is returned.
+        let result = gas_price_or_fallback(Err(anyhow::anyhow!("rpc")), &mut last_gas_price, 20);
+        assert!(result.is_err());
+
+        assert_eq!(gas_price_or_fallback(Ok(100), &mut last_gas_price, 20).unwrap(), 100);
+        assert_eq!(last_gas_price, Some(100));
+
+        // A failure falls back to the cached value with the safety margin applied.
+        let result = gas_price_or_fallback(Err(anyhow::anyhow!("rpc")), &mut last_gas_price, 20);
+        assert_eq!(result.unwrap(), 120);
+        assert!(logs_contain("using last known gas price 100"));
+    }
+
+    // Filtering tests
+    #[tokio::test]
+    #[traced_test]
+    async fn test_filter_expired_orders() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Create an expired order
+        let expired_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp - 100, 50, 50)
+            .await;
+        let expired_order_id = expired_order.id();
+        ctx.monitor
+            .lock_and_prove_cache
+            .insert(expired_order_id.clone(), Arc::from(expired_order))
+            .await;
+
+        let result = ctx
+            .monitor
+            .get_valid_orders(
+                current_timestamp,
+                &OrderMonitorConfig { min_deadline: 0, ..Default::default() },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_empty());
+
+        let order = ctx.db.get_order(&expired_order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_lock_expired_order_reingested() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        let (reingest_tx, mut reingest_rx) = mpsc::channel(1);
+        let monitor = ctx.monitor.clone().with_reingest_sender(reingest_tx);
+
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp - 200, 100, 400)
+            .await;
+        let order_id = order.id();
+        let request_id = order.request.id;
+        monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+
+        let config = OrderMonitorConfig {
+            min_deadline: 0,
+            reingest_lock_expired_orders: true,
+            ..Default::default()
+        };
+        let result = monitor.get_valid_orders(current_timestamp, &config, None).await.unwrap();
+        assert!(result.is_empty());
+
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+        let reingested = reingest_rx.try_recv().unwrap();
+        assert_eq!(reingested.request.id, request_id);
+        assert_eq!(reingested.fulfillment_type, FulfillmentType::FulfillAfterLockExpire);
+        assert_eq!(reingested.target_timestamp, None);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_filter_insufficient_deadline() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Create an order with insufficient deadline
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 45, 45).await;
+        let order_1_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(order_1_id.clone(), Arc::from(order)).await;
+
+        // Create an order with insufficient deadline
+        let order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 1, 45)
+            .await;
+        let order_2_id = order.id();
+        ctx.monitor.prove_cache.insert(order_2_id.clone(), Arc::from(order)).await;
+
+        let result = ctx
+            .monitor
+            .get_valid_orders(
+                current_timestamp,
+                &OrderMonitorConfig { min_deadline: 100, ..Default::default() },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_empty());
+
+        let order = ctx.db.get_order(&order_1_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+
+        let order = ctx.db.get_order(&order_2_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_target_beyond_horizon_held() {
+        let (monitor, db) = setup_mock_market_monitor(MockMarket::default()).await;
+        let current_timestamp = now_timestamp();
+
+        let mut order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+        order.target_timestamp = Some(current_timestamp + 60);
+        let order_id = order.id();
+        monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::new(order)).await;
+
+        let config = OrderMonitorConfig { max_target_horizon_secs: Some(30), ..Default::default() };
+        *monitor.tick_trace.lock().unwrap() = Some(TickTrace::default());
+        let result = monitor.get_valid_orders(current_timestamp, &config, None).await.unwrap();
+        assert!(result.is_empty());
+        let trace = monitor.tick_trace.lock().unwrap().take().unwrap();
+        assert_eq!(trace.filtered, vec![(order_id.clone(), "target beyond horizon".to_string())]);
+
+        // Within the horizon the order is still held until its target is reached.
+        let result = monitor.get_valid_orders(current_timestamp + 40, &config, None).await.unwrap();
+        assert!(result.is_empty());
+
+        let result = monitor.get_valid_orders(current_timestamp + 60, &config, None).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].id(), order_id);
+        assert!(monitor.lock_and_prove_cache.get(&order_id).await.is_some());
+        assert!(db.get_order(&order_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_filter_denied_proof_type() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let any_order_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(any_order_id.clone(), Arc::from(order)).await;
+
+        let mut order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order.request.requirements.selector = FixedBytes::from(Selector::Groth16V2_2 as u32);
+        let groth16_order_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(groth16_order_id.clone(), Arc::from(order)).await;
+
+        let proof_type_policies = HashMap::from([("groth16".into(), ProofTypePolicy::Deny)]);
+        let result = ctx
+            .monitor
+            .get_valid_orders(
+                current_timestamp,
+                &OrderMonitorConfig { proof_type_policies, ..Default::default() },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].id(), any_order_id);
+
+        let order = ctx.db.get_order(&groth16_order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_filter_lock_stake_too_high() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let mut order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order.request.offer.lockStake = U256::from(100);
+        let normal_order_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(normal_order_id.clone(), Arc::from(order)).await;
+
+        let mut order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order.request.offer.lockStake = U256::from(101);
+        let high_stake_order_id = order.id();
+        ctx.monitor
+            .lock_and_prove_cache
+            .insert(high_stake_order_id.clone(), Arc::from(order))
+            .await;
+
+        let result = ctx
+            .monitor
+            .get_valid_orders(
+                current_timestamp,
+                &OrderMonitorConfig {
+                    max_single_order_lock_stake: Some(U256::from(100)),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].id(), normal_order_id);
+
+        let order = ctx.db.get_order(&high_stake_order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_fresh_foreign_lock_deferred() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let order_id = order.id();
+        let request_id = U256::from(order.request.id);
+        ctx.monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+
+        let lock_block = ctx.monitor.provider.get_block_number().await.unwrap();
+        let lock_timestamp = ctx
+            .monitor
+            .provider
+            .get_block_by_number(lock_block.into())
+            .await
+            .unwrap()
+            .unwrap()
+            .header
+            .timestamp;
+        ctx.db
+            .set_request_locked(request_id, &Address::repeat_byte(0x01).to_string(), lock_block)
+            .await
+            .unwrap();
+
+        let config =
+            OrderMonitorConfig { lock_record_min_age_secs: Some(30), ..Default::default() };
+
+        // The lock record is too fresh to rely on, so the order is re-checked later.
+        let result = ctx.monitor.get_valid_orders(lock_timestamp + 1, &config, None).await.unwrap();
+        assert!(result.is_empty());
+        assert!(ctx.db.get_order(&order_id).await.unwrap().is_none());
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_some());
+
+        // Once the grace period has elapsed the order is skipped.
+        let result =
+            ctx.monitor.get_valid_orders(lock_timestamp + 30, &config, None).await.unwrap();
+        assert!(result.is_empty());
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_get_valid_orders_mixed_caches() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        let prover = ctx.monitor.provider.default_signer_address();
+
+        // Orders that are ready to be locked or proven, and orders skipped for reasons specific
+        // to their cache, with the source they are pulled from.
+        let mut orders = Vec::new();
+        for (source, fulfillment_type, lock_timeout, timeout) in [
+            (OrderSource::LockAndProve, FulfillmentType::LockAndFulfill, 100, 200),
+            (OrderSource::Prove, FulfillmentType::FulfillAfterLockExpire, 100, 200),
+            (OrderSource::LockAndProve, FulfillmentType::LockAndFulfill, 100, 200),
+            (OrderSource::Prove, FulfillmentType::FulfillAfterLockExpire, 100, 200),
+            (OrderSource::Prove, FulfillmentType::FulfillAfterLockExpire, 1, 45),
+            (OrderSource::LockAndProve, FulfillmentType::LockAndFulfill, 45, 45),
+        ] {
+            let order: Arc<OrderRequest> = Arc::from(
+                ctx.create_test_order(fulfillment_type, current_timestamp, lock_timeout, timeout)
+                    .await,
+            );
+            let cache = match source {
+                OrderSource::LockAndProve => &ctx.monitor.lock_and_prove_cache,
+                OrderSource::Prove => &ctx.monitor.prove_cache,
+            };
+            cache.insert(order.id(), order.clone()).await;
+            orders.push(order);
+        }
+        let [lock_order, prove_order, locked_by_us, fulfilled, prove_expiring, lock_expiring] =
+            orders.try_into().unwrap();
+        ctx.db
+            .set_request_locked(U256::from(locked_by_us.request.id), &prover.to_string(), 1)
+            .await
+            .unwrap();
+        ctx.db.set_request_fulfilled(U256::from(fulfilled.request.id), 1).await.unwrap();
+
+        let config = OrderMonitorConfig { min_deadline: 100, ..Default::default() };
+        *ctx.monitor.tick_trace.lock().unwrap() = Some(TickTrace::default());
+        let result = ctx.monitor.get_valid_orders(current_timestamp, &config, None).await.unwrap();
+        let trace = ctx.monitor.tick_trace.lock().unwrap().take().unwrap();
+
+        let mut result_ids: Vec<_> = result.iter().map(|order| order.id()).collect();
+        result_ids.sort();
+        let mut expected_ids = vec![lock_order.id(), prove_order.id(), locked_by_us.id()];
+        expected_ids.sort();
+        assert_eq!(result_ids, expected_ids);
+
+        let mut filtered = trace.filtered;
+        filtered.sort();
+        let mut expected_filtered = vec![
+            (fulfilled.id(), "was fulfilled by other".to_string()),
+            (prove_expiring.id(), "expired".to_string()),
+            (lock_expiring.id(), "insufficient deadline".to_string()),
+        ];
+        expected_filtered.sort();
+        assert_eq!(filtered, expected_filtered);
+    }
+
+    #[tokio::test]
+    async fn test_filter_min_deadline_fraction() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Long-lived order with a lock deadline 1000 seconds from now, 6000 seconds after bidding
+        // started.
+        let order = ctx
+            .create_test_order(
+                FulfillmentType::LockAndFulfill,
+                current_timestamp - 5_000,
+                6_000,
+                7_000,
+            )
+            .await;
+        let order_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+
+        let config = OrderMonitorConfig { min_deadline: 100, ..Default::default() };
+        let result = ctx.monitor.get_valid_orders(current_timestamp, &config, None).await.unwrap();
+        assert_eq!(result.len(), 1);
+
+        // 20% of the lifetime is 1200 seconds, more than the time remaining.
+        let config = OrderMonitorConfig { min_deadline_fraction: Some(0.2), ..config };
+        let result = ctx.monitor.get_valid_orders(current_timestamp, &config, None).await.unwrap();
+        assert!(result.is_empty());
+
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_filter_per_chain_min_deadline() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+        let other_chain_id = ctx.anvil.chain_id() + 1;
+
+        // Both orders have a lock deadline 100 seconds from now.
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let order_1_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(order_1_id.clone(), Arc::from(order)).await;
+
+        let mut order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order.chain_id = other_chain_id;
+        let order_2_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(order_2_id.clone(), Arc::from(order)).await;
+
+        let chains = HashMap::from([
+            (
+                ctx.anvil.chain_id().to_string(),
+                ChainOverrides { min_deadline: Some(50), ..Default::default() },
+            ),
+            (
+                other_chain_id.to_string(),
+                ChainOverrides { min_deadline: Some(150), ..Default::default() },
+            ),
+        ]);
+        let result = ctx
+            .monitor
+            .get_valid_orders(
+                current_timestamp,
+                &OrderMonitorConfig { chains, ..Default::default() },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].id(), order_1_id);
+
+        let order = ctx.db.get_order(&order_2_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_filter_locked_by_others() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Create an order that's locked by another prover
+        let order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let order_id = order.id();
+        ctx.db
+            .set_request_locked(
+                U256::from(order.request.id),
+                &Address::ZERO.to_string(),
+                current_timestamp,
+            )
+            .await
+            .unwrap();
+        ctx.monitor.lock_and_prove_cache.insert(order.id(), Arc::from(order)).await;
+
+        let result = ctx
+            .monitor
+            .get_valid_orders(
+                current_timestamp,
+                &OrderMonitorConfig { min_deadline: current_timestamp + 100, ..Default::default() },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_empty());
+
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[test]
+    fn test_saturating_count() {
+        assert_eq!(saturating_count(0), 0);
+        assert_eq!(saturating_count(u32::MAX as usize), u32::MAX);
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(saturating_count(u32::MAX as usize + 1), u32::MAX);
+    }
+
+    #[test]
+    fn test_lock_risk_score() {
+        // More stake, more cycles or less time each make an order riskier.
+        let base = lock_risk_score(1.0, 10_000_000, 100);
+        assert_eq!(base, 0.1);
+        assert!(lock_risk_score(2.0, 10_000_000, 100) > base);
+        assert!(lock_risk_score(1.0, 20_000_000, 100) > base);
+        assert!(lock_risk_score(1.0, 10_000_000, 50) > base);
+        assert_eq!(lock_risk_score(0.0, 10_000_000, 100), 0.0);
+        assert_eq!(lock_risk_score(1.0, 10_000_000, 0), f64::INFINITY);
+    }
+
+    #[tokio::test]
+    async fn test_max_lock_risk() {
+        let (monitor, db) = setup_mock_market_monitor(MockMarket::default()).await;
+
+        // 10 tokens staked on a billion cycles, with about 100 seconds to prove them.
+        let mut risky_order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+        risky_order.request.id = RequestId::new(Address::ZERO, 1).into();
+        risky_order.request.offer.lockStake = U256::from(10_000_000);
+        risky_order.total_cycles = Some(1_000_000_000);
+        // 0.1 tokens staked on a million cycles, with the same time to prove them.
+        let mut safe_order = (*mock_order(FulfillmentType::LockAndFulfill)).clone();
+        safe_order.request.id = RequestId::new(Address::ZERO, 2).into();
+        safe_order.request.offer.lockStake = U256::from(100_000);
+        safe_order.total_cycles = Some(1_000_000);
+        let (risky_order_id, safe_order_id) = (risky_order.id(), safe_order.id());
+        monitor.cache_order(Arc::new(risky_order)).await;
+        monitor.cache_order(Arc::new(safe_order)).await;
+
+        let config =
+            OrderMonitorConfig { min_deadline: 0, max_lock_risk: Some(1.0), ..Default::default() };
+        *monitor.tick_trace.lock().unwrap() = Some(TickTrace::default());
+        let valid_orders = monitor.get_valid_orders(now_timestamp(), &config, None).await.unwrap();
+        let trace = monitor.tick_trace.lock().unwrap().take().unwrap();
+
+        assert_eq!(
+            valid_orders.iter().map(|order| order.id()).collect::<Vec<_>>(),
+            [safe_order_id]
+        );
+        assert_eq!(
+            trace.filtered,
+            [(risky_order_id.clone(), "risk threshold exceeded".to_string())]
+        );
+        let order = db.get_order(&risky_order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_urgent_self_locked_order_first() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let distant_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 1000, 2000)
+            .await;
+        // Expires before the order locked by us, but it is not ours to lose stake on.
+        let other_urgent_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 20, 2000)
+            .await;
+        let urgent_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 30, 2000)
+            .await;
+        let urgent_order_id = urgent_order.id();
+        ctx.db
+            .set_request_locked(
+                U256::from(urgent_order.request.id),
+                &ctx.signer.address().to_string(),
+                current_timestamp,
+            )
+            .await
+            .unwrap();
+        for order in [distant_order, other_urgent_order, urgent_order] {
+            ctx.monitor.lock_and_prove_cache.insert(order.id(), Arc::from(order)).await;
+        }
+
+        *ctx.monitor.tick_trace.lock().unwrap() = Some(TickTrace::default());
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(1),
+            urgent_deadline_secs: Some(60),
+            ..Default::default()
+        };
+        let valid_orders =
+            ctx.monitor.get_valid_orders(current_timestamp, &config, None).await.unwrap();
+        assert_eq!(valid_orders.len(), 3);
+        let trace = ctx.monitor.tick_trace.lock().unwrap().take().unwrap();
+        assert_eq!(trace.urgent, vec![urgent_order_id.clone()]);
+
+        let prioritized_orders =
+            ctx.monitor.prioritize_orders(valid_orders, config.order_commitment_priority, None);
+        let admitted_orders = ctx
+            .monitor
+            .apply_capacity_limits(prioritized_orders, &config, &mut String::new())
+            .await
+            .unwrap();
+        assert_eq!(admitted_orders.len(), 1);
+        assert_eq!(admitted_orders[0].id(), urgent_order_id);
+
+        // The urgency is kept on the order picked up by the prover, without locking it again.
+        ctx.monitor.lock_and_prove_orders(&admitted_orders).await.unwrap();
+        let order = ctx.db.get_order(&urgent_order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::PendingProving);
+        assert!(order.urgent);
+    }
+
+    #[tokio::test]
+    async fn test_urgency_measured_against_monitor_clock() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let urgent_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 30, 2000)
+            .await;
+        let urgent_order_id = urgent_order.id();
+        ctx.db
+            .set_request_locked(
+                U256::from(urgent_order.request.id),
+                &ctx.signer.address().to_string(),
+                current_timestamp,
+            )
+            .await
+            .unwrap();
+        ctx.monitor
+            .lock_and_prove_cache
+            .insert(urgent_order_id.clone(), Arc::from(urgent_order))
+            .await;
+
+        // The chain head lags the clock, by which the order is urgent as it is when admitted.
+        *ctx.monitor.tick_trace.lock().unwrap() = Some(TickTrace::default());
+        let config = OrderMonitorConfig { urgent_deadline_secs: Some(60), ..Default::default() };
+        let valid_orders =
+            ctx.monitor.get_valid_orders(current_timestamp - 40, &config, None).await.unwrap();
+        assert_eq!(valid_orders.len(), 1);
+        assert!(valid_orders[0].urgent);
+        let trace = ctx.monitor.tick_trace.lock().unwrap().take().unwrap();
+        assert_eq!(trace.urgent, vec![urgent_order_id]);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_filter_missing_target_timestamp() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let mut order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order.target_timestamp = None;
+        let order_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+
+        let result = ctx
+            .monitor
+            .get_valid_orders(
+                current_timestamp,
+                &OrderMonitorConfig { min_deadline: 0, ..Default::default() },
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(result.is_empty());
+
+        // The order should be skipped and removed from the cache rather than lingering.
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+        assert!(logs_contain("[B-OM-012]"));
+    }
+
+    // Processing tests
+    #[tokio::test]
+    #[traced_test]
+    async fn test_process_fulfill_after_lock_expire_orders() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 100, 200)
+            .await;
+        let order_id = order.id();
+
+        ctx.monitor.lock_and_prove_orders(&[Arc::from(order)]).await.unwrap();
+
+        let updated_order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(updated_order.status, OrderStatus::PendingProving);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_requestor_insufficient_balance_defers_lock() {
+        let mut ctx = setup_om_test_context().await;
+        ctx.config.load_write().unwrap().market.requestor_balance_retry_attempts = 1;
+        ctx.config.load_write().unwrap().market.requestor_balance_retry_backoff_secs = 100;
+
+        // Use a requestor that has not deposited any funds into the market.
+        ctx.signer = crate::PrivateKeySigner::random();
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let order_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+
+        let order = ctx.monitor.lock_and_prove_cache.get(&order_id).await.unwrap();
+        ctx.monitor.lock_and_prove_orders(&[order]).await.unwrap();
+
+        // The order should be deferred rather than skipped.
+        assert!(logs_contain("[B-OM-013]"));
+        assert!(ctx.db.get_order(&order_id).await.unwrap().is_none());
+        let deferred = ctx.monitor.lock_and_prove_cache.get(&order_id).await.unwrap();
+        assert!(deferred.target_timestamp.unwrap() >= now_timestamp() + 99);
+
+        // Once the retry attempts are exhausted, the order is skipped.
+        ctx.monitor.lock_and_prove_orders(&[deferred]).await.unwrap();
+        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_none());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_pause_locking() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let lock_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let lock_order_id = lock_order.id();
+        ctx.market_service.submit_request(&lock_order.request, &ctx.signer).await.unwrap();
+        let lock_order: Arc<OrderRequest> = Arc::from(lock_order);
+        ctx.monitor.lock_and_prove_cache.insert(lock_order_id.clone(), lock_order.clone()).await;
+
+        let prove_order = ctx
+            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 100, 200)
+            .await;
+        let prove_order_id = prove_order.id();
+
+        ctx.config.load_write().unwrap().market.pause_locking = true;
+        ctx.monitor
+            .lock_and_prove_orders(&[lock_order.clone(), Arc::from(prove_order)])
+            .await
+            .unwrap();
+        assert!(logs_contain("Locking of new orders is paused"));
+
+        // No lock should occur while paused, but fulfill-only work continues.
+        assert!(ctx.db.get_order(&lock_order_id).await.unwrap().is_none());
+        assert!(ctx.monitor.lock_and_prove_cache.get(&lock_order_id).await.is_some());
+        let prove_order = ctx.db.get_order(&prove_order_id).await.unwrap().unwrap();
+        assert_eq!(prove_order.status, OrderStatus::PendingProving);
+
+        ctx.config.load_write().unwrap().market.pause_locking = false;
+        ctx.monitor.lock_and_prove_orders(&[lock_order]).await.unwrap();
+        assert!(logs_contain("Locking of new orders resumed"));
+        let lock_order = ctx.db.get_order(&lock_order_id).await.unwrap().unwrap();
+        assert_eq!(lock_order.status, OrderStatus::PendingProving);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_apply_capacity_limits_unlimited() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Create multiple orders
+        let mut orders = Vec::new();
+        for _ in 1..=5 {
+            let order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await;
+
+            // Submit requests to blockchain
+            let _request_id =
+                ctx.market_service.submit_request(&order.request, &ctx.signer).await.unwrap();
+
+            orders.push(Arc::from(order));
+        }
+
+        // Set unlimited capacity in config
+        ctx.config.load_write().unwrap().market.max_concurrent_proofs = None;
+
+        // Process all orders with unlimited capacity
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(
+                orders.clone(),
+                &OrderMonitorConfig::default(),
+                &mut String::new(),
+            )
+            .await
+            .unwrap();
+        let result = ctx.monitor.lock_and_prove_orders(&filtered_orders).await;
+        assert!(result.is_ok(), "lock_and_prove_orders should succeed");
+
+        // All orders should be processed since capacity is unlimited
+        let mut processed_count = 0;
+        for order in orders {
+            if let Some(order) = ctx.db.get_order(&order.id()).await.unwrap() {
+                processed_count += 1;
+                assert_eq!(order.status, OrderStatus::PendingProving);
+            }
+        }
+
+        // Should process all 5 orders
+        assert_eq!(
+            processed_count, 5,
+            "Should have processed all 5 orders with unlimited capacity"
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_apply_capacity_limits_proving() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Add a committed order to simulate existing workload
+        let committed_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let mut committed_order = committed_order.to_proving_order(Default::default());
+        committed_order.status = OrderStatus::Proving;
+        committed_order.proving_started_at = Some(current_timestamp);
+        ctx.db.add_order(&committed_order).await.unwrap();
+
+        // Create multiple new orders
+        let mut orders = Vec::new();
+        for _ in 1..=5 {
+            let order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await;
+
+            // Submit requests to blockchain
+            let _request_id =
+                ctx.market_service.submit_request(&order.request, &ctx.signer).await.unwrap();
+
+            orders.push(Arc::from(order));
+        }
+
+        // Process orders with limited capacity
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(
+                orders,
+                &OrderMonitorConfig {
+                    max_concurrent_proofs: Some(3),
+                    order_commitment_priority: OrderCommitmentPriority::ShortestExpiry,
+                    ..Default::default()
+                },
+                &mut String::new(),
+            )
+            .await
+            .unwrap();
+        ctx.monitor.lock_and_prove_orders(&filtered_orders).await.unwrap();
 
-            Box::new(OrderRequest {
-                target_timestamp: Some(0),
-                request,
-                image_id: None,
-                input_id: None,
-                expire_timestamp: None,
-                client_sig,
-                fulfillment_type,
-                boundless_market_address: self.market_address,
-                chain_id: self.anvil.chain_id(),
-                total_cycles: None,
-            })
+        // Count processed orders
+        let mut processed_count = 0;
+        for order in filtered_orders {
+            if let Some(order) = ctx.db.get_order(&order.id()).await.unwrap() {
+                processed_count += 1;
+                assert_eq!(order.status, OrderStatus::PendingProving);
+            }
         }
-    }
 
-    pub async fn setup_om_test_context() -> TestCtx {
-        let anvil = Anvil::new().spawn();
-        let signer: PrivateKeySigner = anvil.keys()[0].clone().into();
-        let provider = Arc::new(
-            ProviderBuilder::new()
-                .wallet(EthereumWallet::from(signer.clone()))
-                .connect(&anvil.endpoint())
-                .await
-                .unwrap(),
+        // Should only process 2 more orders (3 total with 1 already committed)
+        assert_eq!(
+            processed_count, 2,
+            "Should have processed only 2 more orders due to concurrent proving capacity limit"
         );
+    }
 
-        // Deploy contracts
-        let hit_points = deploy_hit_points(signer.address(), provider.clone()).await.unwrap();
+    #[tokio::test]
+    #[traced_test]
+    async fn test_apply_capacity_limits_committed_work_too_large() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
 
-        let market_address = deploy_boundless_market(
-            signer.address(),
-            provider.clone(),
-            Address::ZERO,
-            hit_points,
-            Digest::from(ASSESSOR_GUEST_ID),
-            format!("file://{ASSESSOR_GUEST_PATH}"),
-            Some(signer.address()),
-        )
-        .await
-        .unwrap();
+        // Add a large committed order to simulate existing workload
+        let committed_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        let mut committed_order = committed_order.to_proving_order(Default::default());
+        committed_order.status = OrderStatus::Proving;
+        committed_order.total_cycles = Some(10_000_000_000_000_000);
+        committed_order.proving_started_at = Some(current_timestamp);
+        ctx.db.add_order(&committed_order).await.unwrap();
 
-        // Set up market service
-        let market_service = BoundlessMarketService::new(
-            market_address,
-            provider.clone(),
-            provider.default_signer_address(),
-        );
+        let mut orders = Vec::new();
 
-        // Deposit ETH into the contract for the prover to use when locking orders
-        // Using 10 ETH to ensure plenty of funds for tests
-        let stake_token_decimals = market_service.stake_token_decimals().await.unwrap();
-        market_service
-            .deposit(parse_units("10.0", stake_token_decimals).unwrap().into())
+        let mut order1 = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order1.total_cycles = Some(1000);
+
+        orders.push(Arc::from(order1));
+
+        let mut order2 = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order2.total_cycles = Some(100);
+        orders.push(Arc::from(order2));
+
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(
+                orders,
+                &OrderMonitorConfig { peak_prove_khz: Some(100), ..Default::default() },
+                &mut String::new(),
+            )
             .await
             .unwrap();
 
-        let db: DbObj = Arc::new(SqliteDb::new("sqlite::memory:").await.unwrap());
-        let config = ConfigLock::default();
+        assert_eq!(filtered_orders.len(), 0);
+        assert!(logs_contain("cannot be completed before its expiration"));
+        assert!(logs_contain("Started with 2 orders"));
+        assert!(logs_contain("filtered to 0 orders: []"));
+    }
 
-        config.load_write().unwrap().market.min_deadline = 50;
-        config.load_write().unwrap().market.lockin_gas_estimate = 200_000;
-        config.load_write().unwrap().market.fulfill_gas_estimate = 300_000;
-        config.load_write().unwrap().market.groth16_verify_gas_estimate = 50_000;
+    #[tokio::test]
+    async fn test_committed_orders_snapshot() {
+        let mut ctx = setup_om_test_context().await;
+        let now = now_timestamp();
+        assert!(ctx.monitor.committed_orders_snapshot().await.unwrap().is_empty());
+
+        let mut order_ids = Vec::new();
+        for (total_cycles, proving_started_at) in [(2_000_000, now - 1), (1_000_000, now - 2)] {
+            let order =
+                ctx.create_test_order(FulfillmentType::LockAndFulfill, now, 3600, 7200).await;
+            let mut order = order.to_proving_order(Default::default());
+            order.request.offer.lockStake = U256::from(10);
+            order.total_cycles = Some(total_cycles);
+            order.proving_started_at = Some(proving_started_at);
+            ctx.db.add_order(&order).await.unwrap();
+            order_ids.push(order.id());
+        }
 
-        let block_time = 2;
+        // Without a peak khz limit, no completion is estimated.
+        let snapshot = ctx.monitor.committed_orders_snapshot().await.unwrap();
+        assert_eq!(snapshot.len(), 2);
+        assert!(snapshot.iter().all(|order| order.estimated_completion.is_none()));
 
-        let chain_monitor = Arc::new(ChainMonitorService::new(provider.clone()).await.unwrap());
-        tokio::spawn(chain_monitor.spawn(Default::default()));
+        {
+            let mut config = ctx.config.load_write().unwrap();
+            config.market.peak_prove_khz = Some(1_000);
+            config.market.additional_proof_cycles = 0;
+        }
+        let snapshot = ctx.monitor.committed_orders_snapshot().await.unwrap();
+        // Orders are reported earliest started proving first.
+        assert_eq!(snapshot[0].order_id, order_ids[1]);
+        assert_eq!(snapshot[1].order_id, order_ids[0]);
+        assert_eq!(snapshot[0].estimated_completion, Some(now - 2 + 1));
+        assert_eq!(snapshot[1].estimated_completion, Some(now - 2 + 3));
+        for order in &snapshot {
+            assert_eq!(order.status, OrderStatus::PendingProving);
+            assert_eq!(order.stake, U256::from(10));
+            assert!(order.gas_units > 0);
+        }
 
-        // Create required channels for tests
-        let (priced_order_tx, priced_order_rx) = mpsc::channel(16);
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let decoded: Vec<CommittedOrderSnapshot> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, snapshot);
+    }
 
-        let monitor = OrderMonitor::new(
-            db.clone(),
-            provider.clone(),
-            chain_monitor.clone(),
-            config.clone(),
-            block_time,
-            signer.address(),
-            market_address,
-            priced_order_rx,
-            stake_token_decimals,
-            RpcRetryConfig { retry_count: 2, retry_sleep_ms: 500 },
-        )
-        .unwrap();
+    #[tokio::test]
+    async fn test_write_status() {
+        let ctx = setup_om_test_context().await;
+        let monitor = ctx.monitor.with_tick_history(4);
+        monitor.tick_history.lock().unwrap().record(TickSummary {
+            block_number: 1,
+            admitted: vec!["order".to_string()],
+            ..Default::default()
+        });
+        *monitor.wasted_gas_wei.lock().unwrap() = U256::from(5);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        monitor.write_status(&path).await.unwrap();
+
+        let status: MonitorStatus =
+            serde_json::from_slice(&tokio::fs::read(&path).await.unwrap()).unwrap();
+        assert_eq!(status.recent_ticks, monitor.recent_ticks(4));
+        assert_eq!(status.wasted_gas_wei, U256::from(5));
+        assert!(!status.chain_stalled);
+        assert_eq!(status.runway_secs, None);
+        assert!(status.committed_orders.is_empty());
+    }
 
-        TestCtx {
-            monitor,
-            anvil,
-            db,
-            market_address,
-            config,
-            priced_order_tx,
-            signer,
-            market_service,
-            next_order_id: 1, // Initialize with 1 instead of 0
+    #[tokio::test]
+    #[traced_test]
+    async fn test_orders_ready_log_on_change() {
+        let mut ctx = setup_om_test_context().await;
+        let config = OrderMonitorConfig::default();
+        let mut prev_orders_by_status = String::new();
+        let assert_ready_logs = |expected: usize| {
+            logs_assert(|lines: &[&str]| {
+                let count = lines
+                    .iter()
+                    .filter(|line| line.contains("INFO") && line.contains("Num orders ready"))
+                    .count();
+                if count == expected {
+                    Ok(())
+                } else {
+                    Err(format!("expected {expected} orders ready logs, got {count}"))
+                }
+            });
+        };
+
+        for _ in 0..3 {
+            ctx.monitor
+                .apply_capacity_limits(vec![], &config, &mut prev_orders_by_status)
+                .await
+                .unwrap();
         }
-    }
+        assert_ready_logs(1);
 
-    async fn run_with_monitor<P, F, T>(monitor: OrderMonitor<P>, f: F) -> T
-    where
-        P: Provider + WalletProvider + Clone + 'static,
-        F: Future<Output = T>,
-    {
-        // A JoinSet automatically aborts all its tasks when dropped
-        let mut tasks = JoinSet::new();
-        // Spawn the monitor
-        tasks.spawn(async move { monitor.start_monitor(Default::default()).await });
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        ctx.monitor
+            .apply_capacity_limits(vec![Arc::from(order)], &config, &mut prev_orders_by_status)
+            .await
+            .unwrap();
+        assert_ready_logs(2);
 
-        tokio::select! {
-            result = f => result,
-            monitor_task_result = tasks.join_next() => {
-                panic!("Monitor exited unexpectedly: {:?}", monitor_task_result.unwrap());
-            },
+        // Every iteration is logged when verbose logging is forced.
+        let config = OrderMonitorConfig { verbose_loop_logs: true, ..Default::default() };
+        for _ in 0..2 {
+            ctx.monitor
+                .apply_capacity_limits(vec![], &config, &mut prev_orders_by_status)
+                .await
+                .unwrap();
         }
+        assert_ready_logs(4);
+    }
+
+    #[test]
+    fn test_remaining_committed_cycles() {
+        let now = now_timestamp();
+        let order = |total_cycles, proving_started_at| {
+            let mut order =
+                mock_order(FulfillmentType::LockAndFulfill).to_proving_order(U256::ZERO);
+            order.total_cycles = Some(total_cycles);
+            order.proving_started_at = Some(proving_started_at);
+            order
+        };
+        let committed = [order(1_000_000, now - 10), order(500_000, now)];
+
+        // Without a peak khz limit, all committed cycles are assumed to remain.
+        assert_eq!(remaining_committed_cycles(&committed, None, 0, None, now), 1_500_000);
+        assert_eq!(remaining_committed_cycles(&committed, None, 100, None, now), 1_500_200);
+        // 10 seconds at 50 khz since the earliest order started proving.
+        assert_eq!(remaining_committed_cycles(&committed, Some(50), 0, None, now), 1_000_000);
+        assert_eq!(remaining_committed_cycles(&committed, Some(1_000), 0, None, now), 0);
+        assert_eq!(remaining_committed_cycles(&[], Some(50), 0, None, now), 0);
+
+        // Orders with an unknown cycle count are budgeted using the fallback.
+        let mut unknown = order(0, now);
+        unknown.total_cycles = None;
+        assert_eq!(remaining_committed_cycles(&[unknown.clone()], None, 0, None, now), 0);
+        assert_eq!(remaining_committed_cycles(&[unknown], None, 0, Some(2_000), now), 2_000);
     }
 
     #[tokio::test]
     #[traced_test]
-    async fn monitor_block() {
+    async fn test_apply_capacity_limits_cycle_budget_partial_commitment() {
         let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
 
-        // Create a test order using the TestCtx helper method
-        let order =
-            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        // A large committed order, of which 100s at 1000 khz are estimated to be proven.
+        let committed_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 3600, 7200)
+            .await;
+        let mut committed_order = committed_order.to_proving_order(Default::default());
+        committed_order.status = OrderStatus::Proving;
+        committed_order.total_cycles = Some(1_000_000_000);
+        committed_order.proving_started_at = Some(current_timestamp - 100);
+        ctx.db.add_order(&committed_order).await.unwrap();
 
-        let order_id = order.id();
+        let mut orders = Vec::new();
+        let mut large_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 3600, 7200)
+            .await;
+        large_order.total_cycles = Some(500_000_000);
+        let large_order_id = large_order.id();
+        orders.push(Arc::from(large_order));
+        for _ in 0..3 {
+            let mut small_order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 3600, 7200)
+                .await;
+            small_order.total_cycles = Some(20_000_000);
+            orders.push(Arc::from(small_order));
+        }
 
-        let _request_id =
-            ctx.market_service.submit_request(&order.request, &ctx.signer).await.unwrap();
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(
+                orders,
+                &OrderMonitorConfig {
+                    max_committed_cycles: Some(1_000_000_000),
+                    peak_prove_khz: Some(1_000),
+                    ..Default::default()
+                },
+                &mut String::new(),
+            )
+            .await
+            .unwrap();
 
-        // Send the order to the monitor
-        ctx.priced_order_tx.send(order).await.unwrap();
+        // The small orders fit in the budget freed by the partially proven committed order, while
+        // the large order is deferred rather than skipped.
+        assert_eq!(filtered_orders.len(), 3);
+        assert!(filtered_orders.iter().all(|order| order.total_cycles == Some(20_000_000)));
+        assert!(ctx.db.get_order(&large_order_id).await.unwrap().is_none());
+        assert!(logs_contain("exceeding the remaining cycle budget"));
+    }
 
-        run_with_monitor(ctx.monitor, async move {
-            // loop for 20 seconds
-            for _ in 0..20 {
-                let order = ctx.db.get_order(&order_id).await.unwrap();
-                if order.is_some() {
-                    assert_eq!(order.unwrap().status, OrderStatus::PendingProving);
-                    break;
-                }
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    #[tokio::test]
+    #[traced_test]
+    async fn test_apply_capacity_limits_deterministic_tie_breaker() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Orders with the same expiry are equal under the shortest expiry priority.
+        let mut orders: Vec<Arc<OrderRequest>> = Vec::new();
+        for _ in 0..5 {
+            let order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await;
+            orders.push(Arc::from(order));
+        }
+
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(2),
+            order_commitment_priority: OrderCommitmentPriority::ShortestExpiry,
+            ..Default::default()
+        };
+        let mut selections = Vec::new();
+        for rotation in 0..orders.len() {
+            let mut candidates = orders.clone();
+            candidates.rotate_left(rotation);
+            if rotation % 2 == 1 {
+                candidates.reverse();
             }
+            let selected = ctx
+                .monitor
+                .apply_capacity_limits(candidates, &config, &mut String::new())
+                .await
+                .unwrap();
+            selections.push(selected.iter().map(|order| order.id()).collect::<Vec<_>>());
+        }
 
-            let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
-            assert_eq!(order.status, OrderStatus::PendingProving);
-        })
-        .await;
+        assert_eq!(selections[0].len(), 2);
+        assert!(selections.iter().all(|selection| *selection == selections[0]));
     }
 
-    // Capacity tests
-    #[test]
-    fn test_capacity_unlimited() {
-        let capacity = Capacity::Unlimited;
-        assert_eq!(capacity.request_capacity(0), 0);
-        assert_eq!(capacity.request_capacity(15), MAX_PROVING_BATCH_SIZE);
-        assert_eq!(capacity.request_capacity(MAX_PROVING_BATCH_SIZE), MAX_PROVING_BATCH_SIZE);
-    }
+    #[tokio::test]
+    async fn test_apply_capacity_limits_deprioritize_unresolved() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
 
-    #[test]
-    fn test_capacity_proving() {
-        let capacity = Capacity::Available(50);
-        assert_eq!(capacity.request_capacity(0), 0);
-        assert_eq!(capacity.request_capacity(4), 4);
-        assert_eq!(capacity.request_capacity(10), MAX_PROVING_BATCH_SIZE);
+        // Orders with the same expiry are equal under the shortest expiry priority.
+        let mut orders: Vec<Arc<OrderRequest>> = Vec::new();
+        for i in 0..4 {
+            let mut order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await;
+            if i % 2 == 0 {
+                order.image_id = Some(format!("image-{i}"));
+                order.input_id = Some(format!("input-{i}"));
+            }
+            orders.push(Arc::from(order));
+        }
+        let mut resolved_ids: Vec<String> = orders
+            .iter()
+            .filter(|order| order.image_id.is_some())
+            .map(|order| order.id())
+            .collect();
+        resolved_ids.sort();
+
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(2),
+            order_commitment_priority: OrderCommitmentPriority::ShortestExpiry,
+            deprioritize_unresolved_orders: true,
+            ..Default::default()
+        };
+        for rotation in 0..orders.len() {
+            let mut candidates = orders.clone();
+            candidates.rotate_left(rotation);
+            let selected = ctx
+                .monitor
+                .apply_capacity_limits(candidates, &config, &mut String::new())
+                .await
+                .unwrap();
+            let mut selected_ids: Vec<String> = selected.iter().map(|order| order.id()).collect();
+            selected_ids.sort();
+            assert_eq!(selected_ids, resolved_ids);
+        }
     }
 
-    // Filtering tests
     #[tokio::test]
-    #[traced_test]
-    async fn test_filter_expired_orders() {
+    async fn test_apply_capacity_limits_priority_tier() {
         let mut ctx = setup_om_test_context().await;
         let current_timestamp = now_timestamp();
 
-        // Create an expired order
-        let expired_order = ctx
-            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp - 100, 50, 50)
+        let early_order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await,
+        );
+        let mut late_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 150, 300)
             .await;
-        let expired_order_id = expired_order.id();
-        ctx.monitor
-            .lock_and_prove_cache
-            .insert(expired_order_id.clone(), Arc::from(expired_order))
+        late_order.priority_tier = Some(1);
+        let late_order: Arc<OrderRequest> = Arc::from(late_order);
+        let mut tier_zero_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 120, 300)
             .await;
+        tier_zero_order.priority_tier = Some(0);
+        let tier_zero_order: Arc<OrderRequest> = Arc::from(tier_zero_order);
 
-        let result = ctx.monitor.get_valid_orders(current_timestamp, 0).await.unwrap();
-
-        assert!(result.is_empty());
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(2),
+            order_commitment_priority: OrderCommitmentPriority::ShortestExpiry,
+            ..Default::default()
+        };
+        let orders = ctx.monitor.prioritize_orders(
+            vec![early_order.clone(), tier_zero_order.clone(), late_order.clone()],
+            config.order_commitment_priority,
+            None,
+        );
+        assert_eq!(orders[0].id(), early_order.id());
 
-        let order = ctx.db.get_order(&expired_order_id).await.unwrap().unwrap();
-        assert_eq!(order.status, OrderStatus::Skipped);
+        // The tier takes precedence over the expiry, and orders without a tier come last.
+        let selected =
+            ctx.monitor.apply_capacity_limits(orders, &config, &mut String::new()).await.unwrap();
+        assert_eq!(
+            selected.iter().map(|order| order.id()).collect::<Vec<_>>(),
+            [late_order.id(), tier_zero_order.id()]
+        );
     }
 
     #[tokio::test]
-    #[traced_test]
-    async fn test_filter_insufficient_deadline() {
+    async fn test_zero_stake_policy() {
         let mut ctx = setup_om_test_context().await;
         let current_timestamp = now_timestamp();
 
-        // Create an order with insufficient deadline
-        let order =
-            ctx.create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 45, 45).await;
-        let order_1_id = order.id();
-        ctx.monitor.lock_and_prove_cache.insert(order_1_id.clone(), Arc::from(order)).await;
-
-        // Create an order with insufficient deadline
-        let order = ctx
-            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 1, 45)
+        let zero_stake_order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await,
+        );
+        let mut staked_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 150, 300)
             .await;
-        let order_2_id = order.id();
-        ctx.monitor.prove_cache.insert(order_2_id.clone(), Arc::from(order)).await;
-
-        let result = ctx.monitor.get_valid_orders(current_timestamp, 100).await.unwrap();
+        staked_order.request.offer.lockStake = U256::from(100);
+        let staked_order: Arc<OrderRequest> = Arc::from(staked_order);
+
+        // Deprioritized zero-stake orders are only admitted after staked orders, despite expiring
+        // first.
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(1),
+            order_commitment_priority: OrderCommitmentPriority::ShortestExpiry,
+            zero_stake_policy: Some(ZeroStakePolicy::Deprioritize),
+            ..Default::default()
+        };
+        let orders = ctx.monitor.prioritize_orders(
+            vec![staked_order.clone(), zero_stake_order.clone()],
+            config.order_commitment_priority,
+            None,
+        );
+        assert_eq!(orders[0].id(), zero_stake_order.id());
+        let selected =
+            ctx.monitor.apply_capacity_limits(orders, &config, &mut String::new()).await.unwrap();
+        assert_eq!(
+            selected.iter().map(|order| order.id()).collect::<Vec<_>>(),
+            [staked_order.id()]
+        );
 
-        assert!(result.is_empty());
+        // Skipped zero-stake orders are recorded as skipped, while staked orders remain valid.
+        for order in [&zero_stake_order, &staked_order] {
+            ctx.monitor.lock_and_prove_cache.insert(order.id(), order.clone()).await;
+        }
+        let config = OrderMonitorConfig {
+            zero_stake_policy: Some(ZeroStakePolicy::Skip),
+            ..Default::default()
+        };
+        let result = ctx.monitor.get_valid_orders(current_timestamp, &config, None).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].id(), staked_order.id());
 
-        let order = ctx.db.get_order(&order_1_id).await.unwrap().unwrap();
+        let order = ctx.db.get_order(&zero_stake_order.id()).await.unwrap().unwrap();
         assert_eq!(order.status, OrderStatus::Skipped);
+    }
 
-        let order = ctx.db.get_order(&order_2_id).await.unwrap().unwrap();
-        assert_eq!(order.status, OrderStatus::Skipped);
+    #[tokio::test]
+    async fn test_admission_audit() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let one_ether = U256::from(10).pow(U256::from(18));
+        let mut orders = Vec::new();
+        for (price, total_cycles) in
+            [(one_ether, 1_000), (U256::ZERO, 1_000), (one_ether, 10_000_000)]
+        {
+            let mut order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await;
+            order.request.offer.minPrice = price;
+            order.request.offer.maxPrice = price;
+            order.total_cycles = Some(total_cycles);
+            orders.push(Arc::<OrderRequest>::from(order));
+        }
+        let (admitted_order, unprofitable_order, large_order) =
+            (orders[0].clone(), orders[1].clone(), orders[2].clone());
+
+        let config = OrderMonitorConfig {
+            min_profit_wei: Some(0),
+            max_committed_cycles: Some(1_000_000),
+            admission_audit: true,
+            ..Default::default()
+        };
+        let selected =
+            ctx.monitor.apply_capacity_limits(orders, &config, &mut String::new()).await.unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].id(), admitted_order.id());
+
+        let audit = ctx.db.admission_audit(current_timestamp).await.unwrap();
+        let decisions: HashMap<_, _> = audit
+            .iter()
+            .map(|entry| (entry.order_id.clone(), (entry.decision, entry.reason.as_deref())))
+            .collect();
+        assert_eq!(decisions.len(), 3);
+        assert_eq!(decisions[&admitted_order.id()], (AdmissionDecision::Admitted, None));
+        assert_eq!(
+            decisions[&unprofitable_order.id()],
+            (AdmissionDecision::Skipped, Some("below minimum profit"))
+        );
+        assert_eq!(
+            decisions[&large_order.id()],
+            (AdmissionDecision::Deferred, Some("exceeds cycle budget"))
+        );
+        let admitted = audit.iter().find(|entry| entry.order_id == admitted_order.id()).unwrap();
+        assert_eq!(admitted.request_id, admitted_order.request.id);
+        assert_eq!(admitted.price_wei, one_ether);
+        assert!(admitted.gas_cost_wei > U256::ZERO);
+
+        // Nothing is recorded unless configured.
+        let order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await,
+        );
+        let config = OrderMonitorConfig::default();
+        ctx.monitor.apply_capacity_limits(vec![order], &config, &mut String::new()).await.unwrap();
+        assert_eq!(ctx.db.admission_audit(current_timestamp).await.unwrap().len(), 3);
     }
 
     #[tokio::test]
-    async fn test_filter_locked_by_others() {
+    async fn test_admission_audit_capacity_and_retention() {
         let mut ctx = setup_om_test_context().await;
         let current_timestamp = now_timestamp();
 
-        // Create an order that's locked by another prover
-        let order = ctx
+        // Decisions older than the retention are pruned when new ones are recorded.
+        let stale_order = ctx
             .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
             .await;
-        let order_id = order.id();
+        let stale = ctx.monitor.admission_audit_entry(
+            &stale_order,
+            U256::ZERO,
+            AdmissionDecision::Deferred,
+            Some("capacity"),
+        );
         ctx.db
-            .set_request_locked(
-                U256::from(order.request.id),
-                &Address::ZERO.to_string(),
+            .insert_admission_audit(&[AdmissionAuditEntry {
+                timestamp: current_timestamp - 1_000,
+                ..stale
+            }])
+            .await
+            .unwrap();
+
+        let mut orders = Vec::new();
+        for _ in 0..2 {
+            let order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await;
+            orders.push(Arc::<OrderRequest>::from(order));
+        }
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(1),
+            admission_audit: true,
+            admission_audit_retention_secs: 100,
+            ..Default::default()
+        };
+        let selected = ctx
+            .monitor
+            .apply_capacity_limits(orders.clone(), &config, &mut String::new())
+            .await
+            .unwrap();
+        assert_eq!(selected.len(), 1);
+
+        // Orders beyond the capacity granted are recorded as deferred rather than left out.
+        let audit = ctx.db.admission_audit(0).await.unwrap();
+        assert_eq!(audit.len(), 2);
+        let deferred_order = orders.iter().find(|order| order.id() != selected[0].id()).unwrap();
+        let deferred = audit.iter().find(|entry| entry.order_id == deferred_order.id()).unwrap();
+        assert_eq!(deferred.decision, AdmissionDecision::Deferred);
+        assert_eq!(deferred.reason.as_deref(), Some("capacity"));
+    }
+
+    #[tokio::test]
+    async fn test_pessimistic_capacity_accounting() {
+        let mut ctx = setup_om_test_context().await;
+        ctx.monitor.max_proving_batch_size = 2;
+        let mut orders: Vec<Arc<OrderRequest>> = Vec::new();
+        for _ in 0..6 {
+            let order = ctx
+                .create_test_order(
+                    FulfillmentType::FulfillAfterLockExpire,
+                    now_timestamp(),
+                    100,
+                    200,
+                )
+                .await;
+            orders.push(Arc::from(order));
+        }
+
+        // Optimistically, a full batch is admitted every loop regardless of the orders admitted
+        // by the previous loop still waiting to be proven.
+        let config = OrderMonitorConfig::default();
+        for batch in orders[..4].chunks(2) {
+            let admitted = ctx
+                .monitor
+                .apply_capacity_limits(batch.to_vec(), &config, &mut String::new())
+                .await
+                .unwrap();
+            assert_eq!(admitted.len(), 2);
+            ctx.monitor.lock_and_prove_orders(&admitted).await.unwrap();
+        }
+        // The prover picks up the orders admitted so far.
+        while ctx.db.get_proving_order().await.unwrap().is_some() {}
+
+        let config = OrderMonitorConfig {
+            capacity_accounting: CapacityAccounting::Pessimistic,
+            ..Default::default()
+        };
+        let admitted = ctx
+            .monitor
+            .apply_capacity_limits(orders[4..].to_vec(), &config, &mut String::new())
+            .await
+            .unwrap();
+        assert_eq!(admitted.len(), 2);
+        ctx.monitor.lock_and_prove_orders(&admitted).await.unwrap();
+
+        // The orders admitted by the previous loop have not been picked up by the prover yet.
+        let mut retried = Vec::new();
+        for _ in 0..2 {
+            retried.push(Arc::from(
+                ctx.create_test_order(
+                    FulfillmentType::FulfillAfterLockExpire,
+                    now_timestamp(),
+                    100,
+                    200,
+                )
+                .await,
+            ));
+        }
+        let filtered = ctx
+            .monitor
+            .apply_capacity_limits(retried.clone(), &config, &mut String::new())
+            .await
+            .unwrap();
+        assert!(filtered.is_empty());
+
+        // Orders picked up by the prover release their room in the batch.
+        while ctx.db.get_proving_order().await.unwrap().is_some() {}
+        let admitted =
+            ctx.monitor.apply_capacity_limits(retried, &config, &mut String::new()).await.unwrap();
+        assert_eq!(admitted.len(), 2);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_apply_capacity_limits_salvage_reserve() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Lock-and-fulfill orders expire first, so are prioritized over the salvage order.
+        let mut lock_orders: Vec<Arc<OrderRequest>> = Vec::new();
+        for _ in 0..3 {
+            let order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+                .await;
+            lock_orders.push(Arc::from(order));
+        }
+        let salvage_order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(
+                FulfillmentType::FulfillAfterLockExpire,
                 current_timestamp,
+                100,
+                200,
             )
+            .await,
+        );
+
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(2),
+            order_commitment_priority: OrderCommitmentPriority::ShortestExpiry,
+            salvage_capacity_reserve: Some(CapacityReserve::Count(1)),
+            ..Default::default()
+        };
+
+        // The reserve is honored when both order types compete.
+        let mut orders = lock_orders.clone();
+        orders.push(salvage_order.clone());
+        let selected =
+            ctx.monitor.apply_capacity_limits(orders, &config, &mut String::new()).await.unwrap();
+        assert_eq!(selected.len(), 2);
+        assert_eq!(
+            selected
+                .iter()
+                .filter(|order| order.fulfillment_type == FulfillmentType::LockAndFulfill)
+                .count(),
+            1
+        );
+        assert!(selected.iter().any(|order| order.id() == salvage_order.id()));
+
+        // Without salvage work the reserve is released to lock-and-fulfill orders.
+        let selected = ctx
+            .monitor
+            .apply_capacity_limits(lock_orders, &config, &mut String::new())
             .await
             .unwrap();
-        ctx.monitor.lock_and_prove_cache.insert(order.id(), Arc::from(order)).await;
+        assert_eq!(selected.len(), 2);
+    }
 
-        let result =
-            ctx.monitor.get_valid_orders(current_timestamp, current_timestamp + 100).await.unwrap();
+    #[tokio::test]
+    #[traced_test]
+    async fn test_apply_capacity_limits_fallback_cycles() {
+        let mut ctx = setup_om_test_context().await;
+        let order: Arc<OrderRequest> = Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await,
+        );
+        assert_eq!(order.total_cycles, None);
 
-        assert!(result.is_empty());
+        let config = |fallback_cycles| OrderMonitorConfig {
+            max_committed_cycles: Some(1_000_000),
+            fallback_cycles: Some(fallback_cycles),
+            ..Default::default()
+        };
 
-        let order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
-        assert_eq!(order.status, OrderStatus::Skipped);
+        // The order is budgeted using the fallback, which exceeds the cycle budget.
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(vec![order.clone()], &config(2_000_000), &mut String::new())
+            .await
+            .unwrap();
+        assert!(filtered_orders.is_empty());
+        assert!(logs_contain("requires 2000000 cycles"));
+
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(vec![order], &config(500_000), &mut String::new())
+            .await
+            .unwrap();
+        assert_eq!(filtered_orders.len(), 1);
+    }
+
+    #[test]
+    fn test_max_jobs_before_deadlines() {
+        assert!(max_jobs_before_deadlines(&[], 0).is_empty());
+        // The long job is dropped so that both short jobs complete in time.
+        assert_eq!(max_jobs_before_deadlines(&[(30, 60), (90, 100), (30, 65)], 0), vec![0, 2]);
+        // Jobs that cannot complete in time on their own are never selected.
+        assert_eq!(max_jobs_before_deadlines(&[(10, 20), (50, 40)], 10), vec![0]);
     }
 
-    // Processing tests
     #[tokio::test]
-    #[traced_test]
-    async fn test_process_fulfill_after_lock_expire_orders() {
+    async fn test_apply_capacity_limits_optimize_khz_allocation() {
         let mut ctx = setup_om_test_context().await;
         let current_timestamp = now_timestamp();
 
-        let order = ctx
-            .create_test_order(FulfillmentType::FulfillAfterLockExpire, current_timestamp, 100, 200)
+        // At 1 khz, the large order takes 90 seconds and each small order 30 seconds. The small
+        // orders can all complete before their expiration, but not after the large order.
+        let mut large_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
             .await;
-        let order_id = order.id();
+        large_order.total_cycles = Some(90_000);
+        let mut orders = vec![Arc::<OrderRequest>::from(large_order)];
+        for _ in 0..3 {
+            let mut small_order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 105, 200)
+                .await;
+            small_order.total_cycles = Some(30_000);
+            orders.push(Arc::from(small_order));
+        }
 
-        ctx.monitor.lock_and_prove_orders(&[Arc::from(order)]).await.unwrap();
+        let config = |optimize_khz_allocation| OrderMonitorConfig {
+            peak_prove_khz: Some(1),
+            order_commitment_priority: OrderCommitmentPriority::ShortestExpiry,
+            optimize_khz_allocation,
+            ..Default::default()
+        };
 
-        let updated_order = ctx.db.get_order(&order_id).await.unwrap().unwrap();
-        assert_eq!(updated_order.status, OrderStatus::PendingProving);
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(orders.clone(), &config(false), &mut String::new())
+            .await
+            .unwrap();
+        assert_eq!(filtered_orders.len(), 1);
+        assert_eq!(filtered_orders[0].id(), orders[0].id());
+
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(orders.clone(), &config(true), &mut String::new())
+            .await
+            .unwrap();
+        let filtered_ids: Vec<_> = filtered_orders.iter().map(|order| order.id()).collect();
+        let small_ids: Vec<_> = orders[1..].iter().map(|order| order.id()).collect();
+        assert_eq!(filtered_orders.len(), 3);
+        assert!(small_ids.iter().all(|id| filtered_ids.contains(id)));
     }
 
     #[tokio::test]
     #[traced_test]
-    async fn test_apply_capacity_limits_unlimited() {
+    async fn test_apply_capacity_limits_min_profit() {
         let mut ctx = setup_om_test_context().await;
         let current_timestamp = now_timestamp();
+        let gas_price = ctx.monitor.current_gas_price().await.unwrap();
 
-        // Create multiple orders
         let mut orders = Vec::new();
-        for _ in 1..=5 {
-            let order = ctx
+        for margin in [U256::ZERO, U256::from(1)] {
+            let mut order = ctx
                 .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
                 .await;
-
-            // Submit requests to blockchain
-            let _request_id =
-                ctx.market_service.submit_request(&order.request, &ctx.signer).await.unwrap();
-
-            orders.push(Arc::from(order));
+            let gas_cost =
+                ctx.monitor.calculate_order_gas_cost_wei(&order, gas_price).await.unwrap();
+            order.request.offer.minPrice = gas_cost - margin;
+            order.request.offer.maxPrice = gas_cost - margin;
+            orders.push(Arc::<OrderRequest>::from(order));
         }
+        let (break_even_order, unprofitable_order) = (orders[0].clone(), orders[1].clone());
 
-        // Set unlimited capacity in config
-        ctx.config.load_write().unwrap().market.max_concurrent_proofs = None;
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(
+                orders,
+                &OrderMonitorConfig { min_profit_wei: Some(0), ..Default::default() },
+                &mut String::new(),
+            )
+            .await
+            .unwrap();
 
-        // Process all orders with unlimited capacity
+        assert_eq!(filtered_orders.len(), 1);
+        assert_eq!(filtered_orders[0].id(), break_even_order.id());
+        let order = ctx.db.get_order(&unprofitable_order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+        assert!(logs_contain("is below the minimum of 0 wei"));
+
+        // A negative minimum profit allows committing to the order at a loss.
         let filtered_orders = ctx
             .monitor
             .apply_capacity_limits(
-                orders.clone(),
-                &OrderMonitorConfig::default(),
+                vec![unprofitable_order.clone()],
+                &OrderMonitorConfig { min_profit_wei: Some(-1), ..Default::default() },
                 &mut String::new(),
             )
             .await
             .unwrap();
-        let result = ctx.monitor.lock_and_prove_orders(&filtered_orders).await;
-        assert!(result.is_ok(), "lock_and_prove_orders should succeed");
+        assert_eq!(filtered_orders.len(), 1);
+    }
 
-        // All orders should be processed since capacity is unlimited
-        let mut processed_count = 0;
-        for order in orders {
-            if let Some(order) = ctx.db.get_order(&order.id()).await.unwrap() {
-                processed_count += 1;
-                assert_eq!(order.status, OrderStatus::PendingProving);
-            }
-        }
+    /// Alert sink recording the requests reported to have their stake at risk.
+    #[derive(Default)]
+    struct RecordingAlertSink(std::sync::Mutex<Vec<(U256, U256)>>);
 
-        // Should process all 5 orders
-        assert_eq!(
-            processed_count, 5,
-            "Should have processed all 5 orders with unlimited capacity"
-        );
+    #[async_trait::async_trait]
+    impl AlertSink for RecordingAlertSink {
+        async fn on_stake_at_risk(&self, request_id: U256, lock_price: U256) {
+            self.0.lock().unwrap().push((request_id, lock_price));
+        }
     }
 
     #[tokio::test]
     #[traced_test]
-    async fn test_apply_capacity_limits_proving() {
+    async fn test_stake_at_risk_alert() {
         let mut ctx = setup_om_test_context().await;
-        let current_timestamp = now_timestamp();
+        let alert_sink = Arc::new(RecordingAlertSink::default());
+        let monitor = ctx.monitor.clone().with_alert_sink(alert_sink.clone());
 
-        // Add a committed order to simulate existing workload
-        let committed_order = ctx
-            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
-            .await;
-        let mut committed_order = committed_order.to_proving_order(Default::default());
-        committed_order.status = OrderStatus::Proving;
-        committed_order.proving_started_at = Some(current_timestamp);
-        ctx.db.add_order(&committed_order).await.unwrap();
-
-        // Create multiple new orders
-        let mut orders = Vec::new();
-        for _ in 1..=5 {
-            let order = ctx
-                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
-                .await;
+        let order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let request_id = U256::from(order.request.id);
+        // An order already recorded under the same id makes recording the lock fail once the
+        // request is locked on chain.
+        ctx.db.add_order(&order.to_proving_order(U256::ZERO)).await.unwrap();
+        monitor.lock_and_prove_orders(&[Arc::from(order)]).await.unwrap();
+
+        assert!(logs_contain("FATAL STAKE AT RISK"));
+        let alerts = alert_sink.0.lock().unwrap().clone();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].0, request_id);
+        assert!(alerts[0].1 > U256::ZERO);
+    }
 
-            // Submit requests to blockchain
-            let _request_id =
-                ctx.market_service.submit_request(&order.request, &ctx.signer).await.unwrap();
+    /// Gas price oracle reporting a fixed price.
+    struct FixedGasPriceSource(u128);
 
-            orders.push(Arc::from(order));
+    #[async_trait::async_trait]
+    impl GasPriceSource for FixedGasPriceSource {
+        async fn gas_price(&self) -> Result<u128> {
+            Ok(self.0)
         }
+    }
+
+    #[tokio::test]
+    async fn test_gas_price_source() {
+        let mut ctx = setup_om_test_context().await;
+        let node_gas_price = ctx.monitor.current_gas_price().await.unwrap();
+
+        // The order pays for its gas at the node's gas price, but not at the oracle's.
+        let mut order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let gas_cost =
+            ctx.monitor.calculate_order_gas_cost_wei(&order, node_gas_price).await.unwrap();
+        order.request.offer.minPrice = gas_cost;
+        order.request.offer.maxPrice = gas_cost;
+        let order = Arc::<OrderRequest>::from(order);
+        let config = OrderMonitorConfig { min_profit_wei: Some(0), ..Default::default() };
 
-        // Process orders with limited capacity
         let filtered_orders = ctx
             .monitor
-            .apply_capacity_limits(
-                orders,
-                &OrderMonitorConfig {
-                    max_concurrent_proofs: Some(3),
-                    order_commitment_priority: OrderCommitmentPriority::ShortestExpiry,
-                    ..Default::default()
-                },
-                &mut String::new(),
-            )
+            .apply_capacity_limits(vec![order.clone()], &config, &mut String::new())
             .await
             .unwrap();
-        ctx.monitor.lock_and_prove_orders(&filtered_orders).await.unwrap();
+        assert_eq!(filtered_orders.len(), 1);
 
-        // Count processed orders
-        let mut processed_count = 0;
-        for order in filtered_orders {
-            if let Some(order) = ctx.db.get_order(&order.id()).await.unwrap() {
-                processed_count += 1;
-                assert_eq!(order.status, OrderStatus::PendingProving);
-            }
-        }
+        let oracle_gas_price = node_gas_price * 10;
+        let monitor = ctx
+            .monitor
+            .clone()
+            .with_gas_price_source(Arc::new(FixedGasPriceSource(oracle_gas_price)));
+        assert_eq!(monitor.current_gas_price().await.unwrap(), oracle_gas_price);
+        let filtered_orders = monitor
+            .apply_capacity_limits(vec![order.clone()], &config, &mut String::new())
+            .await
+            .unwrap();
+        assert!(filtered_orders.is_empty());
+        let order = ctx.db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
 
-        // Should only process 2 more orders (3 total with 1 already committed)
+        // Lock transactions are priced up to the oracle's gas price.
         assert_eq!(
-            processed_count, 2,
-            "Should have processed only 2 more orders due to concurrent proving capacity limit"
+            monitor.priority_gas_from_source(None).await,
+            Some((oracle_gas_price - node_gas_price) as u64)
         );
+        assert_eq!(ctx.monitor.priority_gas_from_source(None).await, None);
     }
 
     #[tokio::test]
-    #[traced_test]
-    async fn test_apply_capacity_limits_committed_work_too_large() {
+    async fn test_apply_capacity_limits_proving_cost() {
         let mut ctx = setup_om_test_context().await;
-        let current_timestamp = now_timestamp();
+        let gas_price = ctx.monitor.current_gas_price().await.unwrap();
 
-        // Add a large committed order to simulate existing workload
-        let committed_order = ctx
-            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
-            .await;
-        let mut committed_order = committed_order.to_proving_order(Default::default());
-        committed_order.status = OrderStatus::Proving;
-        committed_order.total_cycles = Some(10_000_000_000_000_000);
-        committed_order.proving_started_at = Some(current_timestamp);
-        ctx.db.add_order(&committed_order).await.unwrap();
+        // The order pays 1000 wei above its gas cost, but takes a billion cycles to prove.
+        let mut order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let gas_cost = ctx.monitor.calculate_order_gas_cost_wei(&order, gas_price).await.unwrap();
+        order.request.offer.minPrice = gas_cost + U256::from(1000);
+        order.request.offer.maxPrice = gas_cost + U256::from(1000);
+        order.total_cycles = Some(1_000_000_000);
+        let order = Arc::<OrderRequest>::from(order);
 
-        let mut orders = Vec::new();
+        let config = OrderMonitorConfig { min_profit_wei: Some(0), ..Default::default() };
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(vec![order.clone()], &config, &mut String::new())
+            .await
+            .unwrap();
+        assert_eq!(filtered_orders.len(), 1);
 
-        let mut order1 = ctx
-            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
-            .await;
-        order1.total_cycles = Some(1000);
+        // Even the cheapest proving cost outweighs the margin over gas.
+        let config = OrderMonitorConfig {
+            min_profit_wei: None,
+            proving_cost_per_cycle_wei: Some(1),
+            ..Default::default()
+        };
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(vec![order.clone()], &config, &mut String::new())
+            .await
+            .unwrap();
+        assert!(filtered_orders.is_empty());
+        let order = ctx.db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
 
-        orders.push(Arc::from(order1));
+    #[tokio::test]
+    async fn test_apply_capacity_limits_lock_price_window() {
+        let mut ctx = setup_om_test_context().await;
+        let gas_price = ctx.monitor.current_gas_price().await.unwrap();
 
-        let mut order2 = ctx
-            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
-            .await;
-        order2.total_cycles = Some(100);
-        orders.push(Arc::from(order2));
+        // The order breaks even at its target, shortly before its lock deadline.
+        let mut order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let gas_cost = ctx.monitor.calculate_order_gas_cost_wei(&order, gas_price).await.unwrap();
+        order.request.offer.minPrice = gas_cost;
+        order.request.offer.maxPrice = gas_cost;
+        order.target_timestamp = Some(order.request.offer.lock_deadline() - 10);
+        let order = Arc::<OrderRequest>::from(order);
 
+        let config = OrderMonitorConfig { min_profit_wei: Some(0), ..Default::default() };
         let filtered_orders = ctx
             .monitor
-            .apply_capacity_limits(
-                orders,
-                &OrderMonitorConfig { peak_prove_khz: Some(100), ..Default::default() },
-                &mut String::new(),
-            )
+            .apply_capacity_limits(vec![order.clone()], &config, &mut String::new())
             .await
             .unwrap();
+        assert_eq!(filtered_orders.len(), 1);
 
-        assert_eq!(filtered_orders.len(), 0);
-        assert!(logs_contain("cannot be completed before its expiration"));
-        assert!(logs_contain("Started with 2 orders"));
-        assert!(logs_contain("filtered to 0 orders: []"));
+        // Locking later within the window would miss the lock deadline and earn nothing.
+        let config = OrderMonitorConfig { lock_price_window_secs: 30, ..config };
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(vec![order.clone()], &config, &mut String::new())
+            .await
+            .unwrap();
+        assert!(filtered_orders.is_empty());
+        let order = ctx.db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
     }
 
     #[tokio::test]
@@ -1291,6 +9182,38 @@ pub(crate) mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_fulfillment_safety_margin() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Proving takes 50 seconds at 1 khz, finishing about 50 seconds before the order expires.
+        let mut order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order.total_cycles = Some(50_000);
+        let order: Arc<OrderRequest> = Arc::from(order);
+
+        let config = OrderMonitorConfig { peak_prove_khz: Some(1), ..Default::default() };
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(vec![order.clone()], &config, &mut String::new())
+            .await
+            .unwrap();
+        assert_eq!(filtered_orders.len(), 1);
+
+        // A margin exceeding the slack makes the order too tight to complete.
+        let config = OrderMonitorConfig { fulfillment_safety_margin_secs: 60, ..config };
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(vec![order.clone()], &config, &mut String::new())
+            .await
+            .unwrap();
+        assert!(filtered_orders.is_empty());
+        let db_order = ctx.db.get_order(&order.id()).await.unwrap().unwrap();
+        assert_eq!(db_order.status, OrderStatus::Skipped);
+    }
+
     #[tokio::test]
     async fn test_gas_estimation_functions() {
         let mut ctx = setup_om_test_context().await;
@@ -1376,6 +9299,260 @@ pub(crate) mod tests {
         assert_eq!(filtered_orders[3].total_cycles, Some(4_000_000));
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn test_balance_underwater_alert_once() {
+        let mut ctx = setup_om_test_context().await;
+        ctx.config.load_write().unwrap().market.balance_underwater_alert =
+            Some(BalanceUnderwaterAlert::Metric);
+
+        // A single committed order costs more gas than the whole balance.
+        let balance = ctx.monitor.provider.get_balance(ctx.signer.address()).await.unwrap();
+        let gas_price = ctx.monitor.provider.get_gas_price().await.unwrap();
+        let gas_remaining: u64 = (balance / U256::from(gas_price)).try_into().unwrap();
+        ctx.config.load_write().unwrap().market.fulfill_gas_estimate = gas_remaining * 2;
+
+        let committed_order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        let mut committed_order = committed_order.to_proving_order(Default::default());
+        committed_order.status = OrderStatus::Proving;
+        ctx.db.add_order(&committed_order).await.unwrap();
+
+        let orders = vec![Arc::from(
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await,
+        )];
+        for _ in 0..3 {
+            let filtered_orders = ctx
+                .monitor
+                .apply_capacity_limits(
+                    orders.clone(),
+                    &OrderMonitorConfig::default(),
+                    &mut String::new(),
+                )
+                .await
+                .unwrap();
+            assert!(filtered_orders.is_empty());
+        }
+
+        logs_assert(|lines: &[&str]| {
+            let alerts = lines
+                .iter()
+                .filter(|line| line.contains("Balance underwater for committed obligations"))
+                .collect::<Vec<_>>();
+            match alerts.as_slice() {
+                [alert] if alert.contains("balance_underwater=1") => Ok(()),
+                alerts => Err(format!("expected a single underwater alert, got {alerts:?}")),
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_apply_capacity_limits_urgent_order_first() {
+        let mut ctx = setup_om_test_context().await;
+        let now = now_timestamp();
+
+        // The safe order is more valuable and ranked ahead of the urgent order.
+        let mut safe_order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now, 1_000, 2_000).await;
+        safe_order.request.offer.lockStake = U256::from(1);
+        safe_order.request.offer.minPrice = U256::from(1_000);
+        safe_order.request.offer.maxPrice = U256::from(1_000);
+        let mut urgent_order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now, 30, 2_000).await;
+        urgent_order.request.offer.lockStake = U256::from(1);
+        let urgent_order_id = urgent_order.id();
+        let orders = vec![Arc::from(safe_order), Arc::from(urgent_order)];
+
+        let config = OrderMonitorConfig {
+            max_concurrent_proofs: Some(1),
+            order_commitment_priority: OrderCommitmentPriority::RoiAdjusted,
+            ..Default::default()
+        };
+        let filtered_orders = ctx
+            .monitor
+            .apply_capacity_limits(orders.clone(), &config, &mut String::new())
+            .await
+            .unwrap();
+        assert_ne!(filtered_orders[0].id(), urgent_order_id);
+
+        let config = OrderMonitorConfig { urgent_deadline_secs: Some(60), ..config };
+        let filtered_orders =
+            ctx.monitor.apply_capacity_limits(orders, &config, &mut String::new()).await.unwrap();
+        assert_eq!(filtered_orders.len(), 1);
+        assert_eq!(filtered_orders[0].id(), urgent_order_id);
+    }
+
+    #[tokio::test]
+    async fn test_apply_capacity_limits_total_stake_cap() {
+        let mut ctx = setup_om_test_context().await;
+
+        // A committed order already holds part of the stake.
+        let mut committed_order =
+            ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200).await;
+        committed_order.request.offer.lockStake = U256::from(40);
+        ctx.db.insert_accepted_request(&committed_order, U256::ZERO).await.unwrap();
+
+        let mut orders = Vec::new();
+        for _ in 0..3 {
+            let mut order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200)
+                .await;
+            order.request.offer.lockStake = U256::from(30);
+            orders.push(Arc::from(order));
+        }
+        let order_ids: Vec<_> = orders.iter().map(|order| order.id()).collect();
+
+        let config = OrderMonitorConfig {
+            max_total_stake_committed: Some(U256::from(100)),
+            ..Default::default()
+        };
+        let filtered_orders =
+            ctx.monitor.apply_capacity_limits(orders, &config, &mut String::new()).await.unwrap();
+
+        // Only two orders fit alongside the committed stake, the remaining one is skipped.
+        assert_eq!(filtered_orders.len(), 2);
+        let skipped_id = order_ids
+            .iter()
+            .find(|id| !filtered_orders.iter().any(|order| &order.id() == *id))
+            .unwrap();
+        let order = ctx.db.get_order(skipped_id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_expected_incoming_balance() {
+        let mut ctx = setup_om_test_context().await;
+
+        // Each order costs more than half of the balance.
+        let balance = ctx.monitor.provider.get_balance(ctx.signer.address()).await.unwrap();
+        let gas_price = ctx.monitor.provider.get_gas_price().await.unwrap();
+        let gas_remaining: u64 = (balance / U256::from(gas_price)).try_into().unwrap();
+        ctx.config.load_write().unwrap().market.fulfill_gas_estimate = gas_remaining / 2;
+        ctx.config.load_write().unwrap().market.lockin_gas_estimate = gas_remaining / 3;
+
+        let mut orders = Vec::new();
+        for _ in 0..2 {
+            orders.push(Arc::from(
+                ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200)
+                    .await,
+            ));
+        }
+
+        let mut admitted = Vec::new();
+        for expires_at in [None, Some(now_timestamp() + 600), Some(now_timestamp() - 1)] {
+            let config = OrderMonitorConfig {
+                expected_incoming_balance: expires_at.map(|expires_at| ExpectedIncomingBalance {
+                    amount_wei: balance.to(),
+                    expires_at,
+                }),
+                ..Default::default()
+            };
+            let filtered_orders = ctx
+                .monitor
+                .apply_capacity_limits(orders.clone(), &config, &mut String::new())
+                .await
+                .unwrap();
+            admitted.push(filtered_orders.len());
+        }
+        // The incoming balance covers the second order until it expires.
+        assert_eq!(admitted, [1, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_balance_reserve_per_committed_order() {
+        let mut ctx = setup_om_test_context().await;
+
+        // A committed order costs an eighth of the balance to fulfill, a new order slightly more.
+        let balance = ctx.monitor.provider.get_balance(ctx.signer.address()).await.unwrap();
+        let gas_price = ctx.monitor.provider.get_gas_price().await.unwrap();
+        let gas_remaining: u64 = (balance / U256::from(gas_price)).try_into().unwrap();
+        ctx.config.load_write().unwrap().market.fulfill_gas_estimate = gas_remaining / 8;
+        ctx.config.load_write().unwrap().market.lockin_gas_estimate = gas_remaining / 100;
+
+        let mut orders = Vec::new();
+        for _ in 0..6 {
+            orders.push(Arc::from(
+                ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200)
+                    .await,
+            ));
+        }
+
+        let mut admitted = Vec::new();
+        for num_committed in 1..=2 {
+            let committed_order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200)
+                .await;
+            ctx.db.insert_accepted_request(&committed_order, U256::ZERO).await.unwrap();
+            for reserve in [None, Some(1), Some(2)] {
+                let config = OrderMonitorConfig {
+                    balance_reserve_per_committed_order: reserve,
+                    ..Default::default()
+                };
+                let filtered_orders = ctx
+                    .monitor
+                    .apply_capacity_limits(orders.clone(), &config, &mut String::new())
+                    .await
+                    .unwrap();
+                admitted.push((num_committed, reserve, filtered_orders.len()));
+            }
+        }
+        // Each reserved fulfill transaction withholds the average cost of a committed order,
+        // however many orders are committed.
+        assert_eq!(
+            admitted,
+            [
+                (1, None, 6),
+                (1, Some(1), 5),
+                (1, Some(2), 4),
+                (2, None, 5),
+                (2, Some(1), 4),
+                (2, Some(2), 3),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pending_balance() {
+        let mut ctx = setup_om_test_context().await;
+
+        // Each order costs more than half of the balance.
+        let balance = ctx.monitor.provider.get_balance(ctx.signer.address()).await.unwrap();
+        let gas_price = ctx.monitor.provider.get_gas_price().await.unwrap();
+        let gas_remaining: u64 = (balance / U256::from(gas_price)).try_into().unwrap();
+        ctx.config.load_write().unwrap().market.fulfill_gas_estimate = gas_remaining / 2;
+        ctx.config.load_write().unwrap().market.lockin_gas_estimate = gas_remaining / 3;
+
+        let mut orders = Vec::new();
+        for _ in 0..2 {
+            orders.push(Arc::from(
+                ctx.create_test_order(FulfillmentType::LockAndFulfill, now_timestamp(), 100, 200)
+                    .await,
+            ));
+        }
+
+        // Leave a deposit to the prover pending.
+        let provider = &ctx.monitor.provider;
+        provider.raw_request::<_, ()>("evm_setAutomine".into(), (false,)).await.unwrap();
+        let deposit = alloy::rpc::types::TransactionRequest::default()
+            .from(ctx.anvil.addresses()[1])
+            .to(ctx.signer.address())
+            .value(balance);
+        provider.raw_request::<_, TxHash>("eth_sendTransaction".into(), (deposit,)).await.unwrap();
+
+        let mut admitted = Vec::new();
+        for use_pending_balance in [false, true] {
+            let config = OrderMonitorConfig { use_pending_balance, ..Default::default() };
+            let filtered_orders = ctx
+                .monitor
+                .apply_capacity_limits(orders.clone(), &config, &mut String::new())
+                .await
+                .unwrap();
+            admitted.push(filtered_orders.len());
+        }
+        // The pending deposit covers the second order only if pending state is used.
+        assert_eq!(admitted, [1, 2]);
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_insufficient_balance_committed_orders() {
@@ -1441,6 +9618,62 @@ pub(crate) mod tests {
         assert!(filtered_orders.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_gas_estimate_prewarmed_before_target() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let mut order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 200, 300)
+            .await;
+        order.target_timestamp = Some(current_timestamp + 30);
+        let order_id = order.id();
+        ctx.monitor.lock_and_prove_cache.insert(order_id.clone(), Arc::from(order)).await;
+        let mut far_order = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 200, 300)
+            .await;
+        far_order.target_timestamp = Some(current_timestamp + 120);
+        let far_order_id = far_order.id();
+        ctx.monitor.lock_and_prove_cache.insert(far_order_id.clone(), Arc::from(far_order)).await;
+
+        let config = OrderMonitorConfig {
+            min_deadline: 0,
+            gas_estimate_prewarm_secs: Some(60),
+            ..Default::default()
+        };
+        ctx.monitor.process_orders(current_timestamp, &config, &mut String::new()).await.unwrap();
+
+        // Neither order was admitted, but the one approaching its target was estimated.
+        assert!(ctx.monitor.lock_and_prove_cache.get(&order_id).await.is_some());
+        let gas_units = ctx.monitor.gas_estimates.get(&order_id).await.unwrap();
+        let order = ctx.monitor.lock_and_prove_cache.get(&order_id).await.unwrap();
+        let lock_gas = utils::estimate_gas_to_lock(&ctx.config, &order).await.unwrap();
+        assert!(gas_units > lock_gas);
+        assert_eq!(ctx.monitor.gas_estimates.get(&far_order_id).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_gas_estimate_shared_by_image() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        let mut orders = Vec::new();
+        for image_id in [Some("image"), Some("image"), None] {
+            let mut order = ctx
+                .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 200, 300)
+                .await;
+            order.image_id = image_id.map(String::from);
+            orders.push(order);
+        }
+
+        let gas_units = ctx.monitor.order_gas_units(&orders[0]).await.unwrap();
+        // Estimating again would pick up the new fulfill estimate.
+        ctx.config.load_write().unwrap().market.fulfill_gas_estimate += 1_000;
+
+        assert_eq!(ctx.monitor.order_gas_units(&orders[1]).await.unwrap(), gas_units);
+        assert_eq!(ctx.monitor.order_gas_units(&orders[2]).await.unwrap(), gas_units + 1_000);
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_target_timestamp_prevents_early_locking() {
@@ -1490,7 +9723,15 @@ pub(crate) mod tests {
 
         // Call get_valid_orders with current timestamp - this should NOT return either order
         // because their target_timestamp is in the future
-        let valid_orders = ctx.monitor.get_valid_orders(current_timestamp, 50).await.unwrap();
+        let valid_orders = ctx
+            .monitor
+            .get_valid_orders(
+                current_timestamp,
+                &OrderMonitorConfig { min_deadline: 50, ..Default::default() },
+                None,
+            )
+            .await
+            .unwrap();
 
         assert!(
             valid_orders.is_empty(),
@@ -1510,8 +9751,15 @@ pub(crate) mod tests {
         );
 
         // Now test with future timestamp - both orders should be valid
-        let valid_orders_in_future =
-            ctx.monitor.get_valid_orders(future_timestamp + 1, 50).await.unwrap();
+        let valid_orders_in_future = ctx
+            .monitor
+            .get_valid_orders(
+                future_timestamp + 1,
+                &OrderMonitorConfig { min_deadline: 50, ..Default::default() },
+                None,
+            )
+            .await
+            .unwrap();
 
         assert_eq!(
             valid_orders_in_future.len(),
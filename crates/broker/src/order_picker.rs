@@ -406,6 +406,7 @@ where
                     &self.config,
                     &self.supported_selectors,
                     &order.request,
+                    order.chain_id,
                 )
                 .await?,
             )
@@ -416,6 +417,7 @@ where
                         &self.config,
                         &self.supported_selectors,
                         &order.request,
+                        order.chain_id,
                     )
                     .await?,
             )
@@ -801,6 +803,7 @@ where
                 &self.config,
                 &self.supported_selectors,
                 &order.request,
+                order.chain_id,
             )
             .await?;
             gas += gas_estimate;
@@ -1437,6 +1440,9 @@ pub(crate) mod tests {
                 image_id: None,
                 input_id: None,
                 expire_timestamp: None,
+                labels: Vec::new(),
+                priority_tier: None,
+                urgent: false,
                 client_sig: Bytes::new(),
                 fulfillment_type: params.fulfillment_type,
                 boundless_market_address: *boundless_market_address,
@@ -1497,6 +1503,9 @@ pub(crate) mod tests {
                 image_id: None,
                 input_id: None,
                 expire_timestamp: None,
+                labels: Vec::new(),
+                priority_tier: None,
+                urgent: false,
                 client_sig: Bytes::new(),
                 fulfillment_type: params.fulfillment_type,
                 boundless_market_address: *boundless_market_address,
@@ -2433,6 +2442,9 @@ pub(crate) mod tests {
             total_cycles: order1.total_cycles,
             target_timestamp: order1.target_timestamp,
             expire_timestamp: order1.expire_timestamp,
+            labels: order1.labels.clone(),
+            priority_tier: order1.priority_tier,
+            urgent: order1.urgent,
         });
 
         assert_eq!(order1.id(), order2.id(), "Both orders should have the same ID");
@@ -16,11 +16,12 @@ use crate::{
     config::{OrderCommitmentPriority, OrderPricingPriority},
     order_monitor::OrderMonitor,
     order_picker::OrderPicker,
-    OrderRequest,
+    FulfillmentType, OrderRequest,
 };
 
+use alloy::primitives::{keccak256, B256, U256};
 use rand::seq::SliceRandom;
-use std::sync::Arc;
+use std::{cmp::Reverse, sync::Arc};
 
 /// Unified priority mode for both pricing and commitment
 #[derive(Debug, Clone, Copy)]
@@ -45,6 +46,9 @@ impl From<OrderCommitmentPriority> for UnifiedPriorityMode {
         match mode {
             OrderCommitmentPriority::Random => UnifiedPriorityMode::Random,
             OrderCommitmentPriority::ShortestExpiry => UnifiedPriorityMode::ShortestExpiry,
+            // ROI ranking depends on gas costs and is applied when capacity limits are applied,
+            // shortest expiry is used as the initial ordering so that ties are broken by deadline.
+            OrderCommitmentPriority::RoiAdjusted => UnifiedPriorityMode::ShortestExpiry,
         }
     }
 }
@@ -87,6 +91,135 @@ where
     }
 }
 
+/// Fixed-point scale used when computing the return on locked stake.
+const ROI_SCALE: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+/// Returns the expected return on the stake put at risk by committing to an order, i.e.
+/// `(price_at(target) - gas_cost) / lockStake`, scaled by [ROI_SCALE].
+///
+/// Orders that do not put any stake at risk are treated as maximally efficient.
+pub(crate) fn roi_adjusted_score(order: &OrderRequest, gas_cost_wei: U256) -> U256 {
+    let lock_stake = order.request.offer.lockStake;
+    let Some(price) = expected_reward_wei(order) else {
+        return U256::MAX;
+    };
+    if lock_stake.is_zero() {
+        return U256::MAX;
+    }
+
+    price.saturating_sub(gas_cost_wei).saturating_mul(ROI_SCALE) / lock_stake
+}
+
+/// Returns the reward in wei expected for fulfilling an order, i.e. its price at the target
+/// timestamp, falling back to the minimum price.
+///
+/// Returns None for orders that are not locked by us, as these are rewarded with part of the
+/// stake of the prover that failed to fulfill them rather than in wei.
+pub(crate) fn expected_reward_wei(order: &OrderRequest) -> Option<U256> {
+    if order.fulfillment_type != FulfillmentType::LockAndFulfill {
+        return None;
+    }
+
+    Some(
+        order
+            .target_timestamp
+            .and_then(|target| order.request.offer.price_at(target).ok())
+            .unwrap_or(order.request.offer.minPrice),
+    )
+}
+
+/// Returns the lowest reward in wei an order may pay if locked between its target timestamp and
+/// `window_secs` later, falling back to the minimum price.
+///
+/// Returns None for orders that are not locked by us, see [expected_reward_wei].
+pub(crate) fn worst_case_reward_wei(order: &OrderRequest, window_secs: u64) -> Option<U256> {
+    if order.fulfillment_type != FulfillmentType::LockAndFulfill {
+        return None;
+    }
+
+    let offer = &order.request.offer;
+    Some(
+        order
+            .target_timestamp
+            .and_then(|target| offer.price_schedule(&[target, target + window_secs]).ok())
+            .and_then(|schedule| schedule.into_iter().min())
+            .unwrap_or(offer.minPrice),
+    )
+}
+
+/// Sorts scored orders by descending return on stake, keeping orders from priority addresses first.
+///
+/// The sort is stable, so orders with equal scores keep their existing relative order.
+pub(crate) fn sort_orders_by_roi(
+    scored_orders: &mut [(U256, Arc<OrderRequest>)],
+    priority_addresses: Option<&[alloy::primitives::Address]>,
+) {
+    let is_priority = |order: &OrderRequest| {
+        priority_addresses.is_some_and(|addrs| addrs.contains(&order.request.client_address()))
+    };
+    scored_orders.sort_by(|(a_score, a), (b_score, b)| {
+        is_priority(b).cmp(&is_priority(a)).then(b_score.cmp(a_score))
+    });
+}
+
+/// Moves orders tagged with a higher priority tier by the pricing stage ahead of the others,
+/// keeping the existing order within each tier. Orders without a tier come last.
+pub(crate) fn sort_orders_by_tier<T>(orders: &mut [(T, Arc<OrderRequest>)]) {
+    orders.sort_by_key(|(_, order)| Reverse(order.priority_tier));
+}
+
+/// Moves lock-and-fulfill orders without a lock stake behind the others, keeping the existing
+/// order otherwise.
+pub(crate) fn deprioritize_zero_stake<T>(orders: &mut [(T, Arc<OrderRequest>)]) {
+    orders.sort_by_key(|(_, order)| {
+        order.fulfillment_type == FulfillmentType::LockAndFulfill
+            && order.request.offer.lockStake.is_zero()
+    });
+}
+
+/// Returns the key used to deterministically break ties between equally prioritized orders, a
+/// hash of the request ID salted with `seed`.
+fn tie_breaker_key(order: &OrderRequest, seed: u64) -> B256 {
+    let mut preimage = [0u8; 40];
+    preimage[..8].copy_from_slice(&seed.to_be_bytes());
+    preimage[8..].copy_from_slice(&order.request.id.to_be_bytes::<32>());
+    keccak256(preimage)
+}
+
+/// Returns whether the order's image or input still has to be resolved before it can be proven.
+fn needs_resolution(order: &OrderRequest) -> bool {
+    order.image_id.is_none() || order.input_id.is_none()
+}
+
+/// Reorders runs of adjacent orders that are equal under the commitment priority by
+/// [tie_breaker_key], so the same orders are selected across runs regardless of the order in
+/// which they were observed. If `deprioritize_unresolved` is set, orders whose image or input is
+/// yet to be resolved are moved behind the other orders of their run.
+///
+/// Expects the orders to be sorted by [OrderMonitor::prioritize_orders]. Random ordering is left
+/// untouched.
+pub(crate) fn break_priority_ties(
+    orders: &mut [Arc<OrderRequest>],
+    priority_mode: OrderCommitmentPriority,
+    priority_addresses: Option<&[alloy::primitives::Address]>,
+    seed: u64,
+    deprioritize_unresolved: bool,
+) {
+    if priority_mode == OrderCommitmentPriority::Random {
+        return;
+    }
+    let is_priority = |order: &OrderRequest| {
+        priority_addresses.is_some_and(|addrs| addrs.contains(&order.request.client_address()))
+    };
+    for run in
+        orders.chunk_by_mut(|a, b| is_priority(a) == is_priority(b) && a.expiry() == b.expiry())
+    {
+        run.sort_by_cached_key(|order| {
+            (deprioritize_unresolved && needs_resolution(order), tie_breaker_key(order, seed))
+        });
+    }
+}
+
 impl<P> OrderPicker<P> {
     #[allow(clippy::vec_box)]
     pub(crate) fn select_pricing_orders(
@@ -383,6 +516,47 @@ mod tests {
         assert!(orders[3].id() == order_2_id);
     }
 
+    #[tokio::test]
+    async fn test_prioritize_orders_roi_adjusted() {
+        let mut ctx = setup_om_test_context().await;
+        let current_timestamp = now_timestamp();
+
+        // Higher price, but requires a large stake.
+        let mut order1 = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order1.request.offer.minPrice = U256::from(1000);
+        order1.request.offer.maxPrice = U256::from(1000);
+        order1.request.offer.lockStake = U256::from(100);
+        let order_1_id = order1.id();
+
+        // Lower price, but a much better return on the stake at risk.
+        let mut order2 = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 200, 300)
+            .await;
+        order2.request.offer.minPrice = U256::from(500);
+        order2.request.offer.maxPrice = U256::from(500);
+        order2.request.offer.lockStake = U256::from(10);
+        let order_2_id = order2.id();
+
+        let gas_cost = U256::from(100);
+        let mut scored: Vec<_> = [Arc::from(order1), Arc::from(order2)]
+            .into_iter()
+            .map(|order: Arc<OrderRequest>| (roi_adjusted_score(&order, gas_cost), order))
+            .collect();
+        sort_orders_by_roi(&mut scored, None);
+
+        assert_eq!(scored[0].1.id(), order_2_id);
+        assert_eq!(scored[1].1.id(), order_1_id);
+
+        // Orders without stake are treated as maximally efficient.
+        let mut order3 = ctx
+            .create_test_order(FulfillmentType::LockAndFulfill, current_timestamp, 100, 200)
+            .await;
+        order3.request.offer.lockStake = U256::ZERO;
+        assert_eq!(roi_adjusted_score(&order3, gas_cost), U256::MAX);
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_expired_order_fulfillment_priority_random() {
@@ -507,6 +507,8 @@ mod tests {
             lock_price: None,
             fulfillment_type,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
@@ -660,6 +662,8 @@ mod tests {
             lock_price: None,
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
@@ -0,0 +1,91 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Mutex;
+
+use tokio::time::{Duration, Instant};
+
+/// Token bucket smoothing bursts of RPC calls to stay under the quota of rate-limited providers.
+///
+/// The bucket holds up to a second's worth of calls, so bursts up to the rate are let through
+/// immediately while further calls are delayed until enough time has passed. The rate is given
+/// on every call, so that it follows changes to the config.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        // The bucket starts full, and is capped at the rate once it is known.
+        Self { bucket: Mutex::new(Bucket { tokens: f64::INFINITY, updated_at: Instant::now() }) }
+    }
+}
+
+impl RateLimiter {
+    /// Waits until a call may be made without exceeding `calls_per_sec`.
+    pub(crate) async fn acquire(&self, calls_per_sec: u32) {
+        let rate = f64::from(calls_per_sec.max(1));
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let refill = now.duration_since(bucket.updated_at).as_secs_f64() * rate;
+            bucket.tokens = (bucket.tokens + refill).min(rate) - 1.0;
+            bucket.updated_at = now;
+            if bucket.tokens >= 0.0 {
+                return;
+            }
+            // The call is owed the missing token, so later calls queue up behind it.
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter_throttles_bursts() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+
+        // A burst up to the rate is let through immediately.
+        for _ in 0..10 {
+            limiter.acquire(10).await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(1));
+
+        // Further calls are spread out at the rate.
+        for _ in 0..20 {
+            limiter.acquire(10).await;
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(1_990), "{elapsed:?}");
+        assert!(elapsed <= Duration::from_millis(2_010), "{elapsed:?}");
+
+        // Concurrent calls are throttled the same way.
+        let start = Instant::now();
+        futures::future::join_all((0..20).map(|_| limiter.acquire(10))).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(1_990), "{elapsed:?}");
+    }
+}
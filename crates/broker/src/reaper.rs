@@ -190,6 +190,8 @@ mod tests {
             lock_price: Some(U256::from(1)),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: Address::ZERO,
             chain_id: 1,
             total_cycles: None,
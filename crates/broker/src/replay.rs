@@ -0,0 +1,193 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use alloy::primitives::U256;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{clock::Clock, gas_price::GasPriceSource, OrderRequest};
+
+/// Stream of orders and chain conditions observed by the order monitor, recorded to replay its
+/// decisions offline, e.g. when investigating an incident. Written to file as a JSON line per tick.
+///
+/// Replaying a session starts from the state of the replaying monitor's DB, so orders committed to
+/// before the recording started are only accounted for if they are in that DB.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct RecordedSession {
+    pub ticks: Vec<RecordedTick>,
+}
+
+impl RecordedSession {
+    /// Loads a session recorded to file by a [SessionRecorder].
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open recorded session {}", path.display()))?;
+        let mut ticks = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.context("Failed to read recorded session")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            ticks.push(serde_json::from_str(&line).context("Failed to parse recorded tick")?);
+        }
+        Ok(Self { ticks })
+    }
+
+    /// Returns the average block time in seconds over the session, defaulting to 2 seconds if it
+    /// spans less than two blocks.
+    pub(crate) fn block_time(&self) -> u64 {
+        match (self.ticks.first(), self.ticks.last()) {
+            (Some(first), Some(last)) if last.block_number > first.block_number => {
+                let elapsed = last.block_timestamp.saturating_sub(first.block_timestamp);
+                (elapsed / (last.block_number - first.block_number)).max(1)
+            }
+            _ => 2,
+        }
+    }
+
+    /// Returns the orders admitted in each tick of the session.
+    pub(crate) fn admitted(&self) -> Vec<Vec<String>> {
+        self.ticks.iter().map(|tick| tick.admitted.clone()).collect()
+    }
+}
+
+/// An iteration of the order monitor, along with the orders received since the previous one.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct RecordedTick {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    /// UNIX timestamp of the monitor's clock at the start of the iteration.
+    pub timestamp: u64,
+    /// Gas price in wei, if it was fetched during the iteration.
+    pub gas_price: Option<u128>,
+    /// Balance of the prover in wei, if it was fetched during the iteration.
+    pub balance_wei: Option<U256>,
+    /// Orders received since the previous iteration, in the order they were received.
+    pub orders: Vec<OrderRequest>,
+    /// Orders admitted for locking and/or proving during the iteration.
+    pub admitted: Vec<String>,
+}
+
+/// Records the iterations of an order monitor into a [RecordedSession].
+#[derive(Default)]
+pub(crate) struct SessionRecorder {
+    session: Mutex<RecordedSession>,
+    /// Orders received since the last iteration started.
+    pending_orders: Mutex<Vec<OrderRequest>>,
+    /// File finished iterations are appended to, rather than kept in memory.
+    file: Option<Mutex<File>>,
+}
+
+/// Locks the mutex, recovering the data if a panic poisoned it, as a partially updated recording
+/// is still worth keeping.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl SessionRecorder {
+    /// Creates a recorder appending the iterations to the given file as they finish.
+    pub(crate) fn to_file(path: &Path) -> Result<Self> {
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open session recording {}", path.display()))?;
+        Ok(Self { file: Some(Mutex::new(file)), ..Default::default() })
+    }
+
+    /// Records an order received by the monitor, attributed to the next iteration.
+    pub(crate) fn record_order(&self, order: &OrderRequest) {
+        lock(&self.pending_orders).push(order.clone());
+    }
+
+    /// Starts recording an iteration, along with the orders received since the previous one.
+    pub(crate) fn start_tick(&self, block_number: u64, block_timestamp: u64, timestamp: u64) {
+        let orders = std::mem::take(&mut *lock(&self.pending_orders));
+        lock(&self.session).ticks.push(RecordedTick {
+            block_number,
+            block_timestamp,
+            timestamp,
+            orders,
+            ..Default::default()
+        });
+    }
+
+    /// Updates the iteration being recorded, if any.
+    pub(crate) fn update_tick(&self, update: impl FnOnce(&mut RecordedTick)) {
+        if let Some(tick) = lock(&self.session).ticks.last_mut() {
+            update(tick);
+        }
+    }
+
+    /// Finishes recording the current iteration, appending it to the file if recording to one.
+    pub(crate) fn finish_tick(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let Some(tick) = lock(&self.session).ticks.pop() else {
+            return;
+        };
+        let res = serde_json::to_string(&tick)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| Ok(writeln!(lock(file), "{line}")?));
+        if let Err(err) = res {
+            tracing::warn!("Failed to record tick for block {}: {err:?}", tick.block_number);
+        }
+    }
+
+    /// Returns the session recorded so far, excluding the iterations already written to file.
+    pub(crate) fn session(&self) -> RecordedSession {
+        lock(&self.session).clone()
+    }
+}
+
+/// Conditions of the tick being replayed, standing in for the clock, the gas price source and the
+/// prover's balance.
+#[derive(Default)]
+pub(crate) struct ReplayConditions {
+    tick: Mutex<(u64, Option<u128>, Option<U256>)>,
+}
+
+impl ReplayConditions {
+    /// Replays the conditions recorded for the given tick.
+    pub(crate) fn set(&self, tick: &RecordedTick) {
+        *lock(&self.tick) = (tick.timestamp, tick.gas_price, tick.balance_wei);
+    }
+
+    /// Returns the balance of the prover recorded for the tick.
+    pub(crate) fn balance_wei(&self) -> Result<U256> {
+        lock(&self.tick).2.context("Balance was not recorded for the replayed tick")
+    }
+}
+
+impl Clock for ReplayConditions {
+    fn now(&self) -> u64 {
+        lock(&self.tick).0
+    }
+}
+
+#[async_trait]
+impl GasPriceSource for ReplayConditions {
+    async fn gas_price(&self) -> Result<u128> {
+        lock(&self.tick).1.context("Gas price was not recorded for the replayed tick")
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+/// Reports the resources, such as memory and GPU, currently available to the prover.
+///
+/// Used by the order monitor to scale down `max_concurrent_proofs` when the prover is under
+/// resource pressure, down to `min_concurrent_proofs`.
+#[async_trait]
+pub(crate) trait ResourceProbe: Send + Sync {
+    /// Returns the fraction, between 0 and 1, of the prover's resources that are available. Where
+    /// several resources are tracked, this is the fraction of the scarcest one.
+    async fn available_fraction(&self) -> Result<f64>;
+}
+
+pub(crate) type ResourceProbeObj = Arc<dyn ResourceProbe>;
+
+/// Reports a fixed fraction of resources available, fully available by default so the configured
+/// `max_concurrent_proofs` applies unchanged.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ConstantResourceProbe(pub f64);
+
+impl Default for ConstantResourceProbe {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+#[async_trait]
+impl ResourceProbe for ConstantResourceProbe {
+    async fn available_fraction(&self) -> Result<f64> {
+        Ok(self.0)
+    }
+}
+
+/// Reports the fraction of system memory available, read from `/proc/meminfo`.
+pub(crate) struct MemInfoResourceProbe {
+    path: PathBuf,
+}
+
+impl Default for MemInfoResourceProbe {
+    fn default() -> Self {
+        Self { path: PathBuf::from("/proc/meminfo") }
+    }
+}
+
+#[async_trait]
+impl ResourceProbe for MemInfoResourceProbe {
+    async fn available_fraction(&self) -> Result<f64> {
+        let meminfo = tokio::fs::read_to_string(&self.path)
+            .await
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        parse_meminfo_available_fraction(&meminfo)
+    }
+}
+
+/// Parses the fraction of memory available, `MemAvailable` over `MemTotal`, from the contents of
+/// `/proc/meminfo`.
+fn parse_meminfo_available_fraction(meminfo: &str) -> Result<f64> {
+    let field = |name: &str| -> Result<u64> {
+        let line = meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .with_context(|| format!("{name} missing from meminfo"))?;
+        let kb = line.trim().trim_end_matches("kB").trim();
+        kb.parse().with_context(|| format!("Invalid {name} in meminfo: {line}"))
+    };
+    let total = field("MemTotal")?;
+    anyhow::ensure!(total > 0, "MemTotal is zero in meminfo");
+    Ok(field("MemAvailable")? as f64 / total as f64)
+}
+
+/// Scales `max_concurrent_proofs` by the fraction of resources available, rounding down, without
+/// going below `min_concurrent_proofs` or above `max_concurrent_proofs`.
+pub(crate) fn scaled_concurrent_proofs(
+    max_concurrent_proofs: u32,
+    min_concurrent_proofs: Option<u32>,
+    available_fraction: f64,
+) -> u32 {
+    let scaled = (available_fraction.clamp(0.0, 1.0) * max_concurrent_proofs as f64).floor() as u32;
+    scaled.max(min_concurrent_proofs.unwrap_or(0)).min(max_concurrent_proofs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meminfo_available_fraction() {
+        let meminfo = "MemTotal:       16000000 kB\n\
+                       MemFree:         1000000 kB\n\
+                       MemAvailable:    4000000 kB\n";
+        assert_eq!(parse_meminfo_available_fraction(meminfo).unwrap(), 0.25);
+        assert!(parse_meminfo_available_fraction("MemTotal: 16000000 kB\n").is_err());
+    }
+}
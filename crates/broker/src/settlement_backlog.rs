@@ -0,0 +1,121 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{Order, OrderStatus};
+
+/// Timeout of a request querying the backlog of a batcher.
+const SETTLEMENT_BACKLOG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reports the backlog of the settlement stage, i.e. the aggregation and submission of proofs
+/// by the batcher.
+///
+/// Used by the order monitor to reduce admission while settlement is backed up, so proven orders
+/// do not pile up waiting to be fulfilled past their deadlines.
+#[async_trait]
+pub(crate) trait SettlementBacklog: Send + Sync {
+    /// Returns the number of proven orders that are not fulfilled yet, given the orders of the DB
+    /// that are committed to be proven and fulfilled.
+    async fn pending_settlement(&self, committed_orders: &[Order]) -> Result<u32>;
+}
+
+pub(crate) type SettlementBacklogObj = Arc<dyn SettlementBacklog>;
+
+/// Reports the committed orders of the DB that are proven but not yet fulfilled as the backlog.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct DbSettlementBacklog;
+
+#[async_trait]
+impl SettlementBacklog for DbSettlementBacklog {
+    async fn pending_settlement(&self, committed_orders: &[Order]) -> Result<u32> {
+        let pending = committed_orders
+            .iter()
+            .filter(|order| {
+                matches!(
+                    order.status,
+                    OrderStatus::PendingAgg
+                        | OrderStatus::Aggregating
+                        | OrderStatus::SkipAggregation
+                        | OrderStatus::PendingSubmission
+                )
+            })
+            .count();
+        Ok(u32::try_from(pending).unwrap_or(u32::MAX))
+    }
+}
+
+/// Queries the backlog of a batcher that reports its own, over HTTP.
+///
+/// The endpoint is expected to respond to a GET request with a JSON body of the form
+/// `{"pending_settlement": 4}`.
+pub(crate) struct HttpSettlementBacklog {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl HttpSettlementBacklog {
+    pub(crate) fn new(url: Url) -> Self {
+        Self { client: reqwest::Client::new(), url }
+    }
+}
+
+#[derive(Deserialize)]
+struct BatcherStatus {
+    pending_settlement: u32,
+}
+
+#[async_trait]
+impl SettlementBacklog for HttpSettlementBacklog {
+    async fn pending_settlement(&self, _committed_orders: &[Order]) -> Result<u32> {
+        let body = self
+            .client
+            .get(self.url.clone())
+            .timeout(SETTLEMENT_BACKLOG_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to query settlement backlog at {}", self.url))?
+            .text()
+            .await
+            .context("Failed to read settlement backlog response")?;
+        let status: BatcherStatus =
+            serde_json::from_str(&body).context("Invalid settlement backlog response")?;
+        Ok(status.pending_settlement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn http_pending_settlement() {
+        let server = MockServer::start();
+        let backlog_mock = server.mock(|when, then| {
+            when.method(GET).path("/backlog");
+            then.status(200).json_body(serde_json::json!({ "pending_settlement": 5 }));
+        });
+
+        let backlog = HttpSettlementBacklog::new(Url::parse(&server.url("/backlog")).unwrap());
+        assert_eq!(backlog.pending_settlement(&[]).await.unwrap(), 5);
+        backlog_mock.assert();
+    }
+}
@@ -12,21 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use alloy::{
     network::Ethereum,
     primitives::{utils::format_ether, Address, B256, U256},
     providers::{Provider, WalletProvider},
+    rpc::types::TransactionReceipt,
     sol_types::{SolStruct, SolValue},
 };
 use anyhow::{anyhow, Context, Result};
 use boundless_market::{
     contracts::{
         boundless_market::{BoundlessMarketService, FulfillmentTx, MarketError, UnlockedRequest},
-        encode_seal, AssessorJournal, AssessorReceipt, Fulfillment,
+        encode_seal, AssessorJournal, AssessorReceipt, Fulfillment, ProofRequest,
     },
-    selector::is_groth16_selector,
+    selector::{is_groth16_selector, SupportedSelectors},
 };
 use risc0_aggregation::{SetInclusionReceipt, SetInclusionReceiptVerifierParameters};
 use risc0_ethereum_contracts::set_verifier::SetVerifierService;
@@ -39,9 +44,10 @@ use crate::{
     config::ConfigLock,
     db::DbObj,
     impl_coded_debug, now_timestamp,
+    order_monitor::{GasRatios, GasSpends},
     provers::ProverObj,
     task::{RetryRes, RetryTask, SupervisorErr},
-    Batch, FulfillmentType, Order,
+    utils, Batch, FulfillmentType, Order,
 };
 use thiserror::Error;
 
@@ -99,6 +105,11 @@ pub struct Submitter<P> {
     set_builder_img_id: Digest,
     prover_address: Address,
     config: ConfigLock,
+    /// Realized-vs-estimated gas ratios of recent fulfillment transactions.
+    fulfill_gas_ratios: Arc<Mutex<GasRatios>>,
+    /// Record of the gas paid by confirmed transactions, used to estimate the runway of the
+    /// balance, see [crate::order_monitor::OrderMonitor::estimated_runway].
+    gas_spends: Option<Arc<Mutex<GasSpends>>>,
 }
 
 impl<P> Submitter<P>
@@ -151,9 +162,17 @@ where
             set_builder_img_id,
             prover_address,
             config,
+            fulfill_gas_ratios: Arc::new(Mutex::new(GasRatios::default())),
+            gas_spends: None,
         })
     }
 
+    /// Records the gas paid by fulfillment transactions into the given record, shared with the
+    /// order monitor to estimate the runway of the balance.
+    pub(crate) fn with_gas_spends(self, gas_spends: Arc<Mutex<GasSpends>>) -> Self {
+        Self { gas_spends: Some(gas_spends), ..self }
+    }
+
     async fn fetch_encode_g16(&self, g16_proof_id: &str) -> Result<Vec<u8>> {
         let groth16_receipt = self
             .prover
@@ -246,6 +265,7 @@ where
             SetInclusionReceiptVerifierParameters { image_id: self.set_builder_img_id };
 
         let mut fulfillments = vec![];
+        let mut fulfilled_requests = vec![];
         let mut requests_to_price: Vec<UnlockedRequest> = vec![];
 
         struct OrderPrice {
@@ -337,6 +357,7 @@ where
                     journal: order_journal.into(),
                     seal: seal.into(),
                 });
+                fulfilled_requests.push(order_request);
                 anyhow::Ok(())
             };
 
@@ -433,12 +454,18 @@ where
             }
         };
 
-        if let Err(err) = self.market.fulfill(fulfillment_tx).await {
-            let order_ids: Vec<&str> =
-                fulfillments.iter().map(|f| *fulfillment_to_order_id.get(&f.id).unwrap()).collect();
-            tracing::warn!("Failed to fulfill batch for orders: {order_ids:?}");
-            self.handle_fulfillment_error(err, batch_id, &fulfillments, &order_ids).await?;
-        }
+        let fulfill_receipt = match self.market.fulfill(fulfillment_tx).await {
+            Ok(receipt) => Some(receipt),
+            Err(err) => {
+                let order_ids: Vec<&str> = fulfillments
+                    .iter()
+                    .map(|f| *fulfillment_to_order_id.get(&f.id).unwrap())
+                    .collect();
+                tracing::warn!("Failed to fulfill batch for orders: {order_ids:?}");
+                self.handle_fulfillment_error(err, batch_id, &fulfillments, &order_ids).await?;
+                None
+            }
+        };
 
         for fulfillment in fulfillments.iter() {
             let order_id = fulfillment_to_order_id.get(&fulfillment.id).unwrap();
@@ -460,6 +487,73 @@ where
             );
         }
 
+        if let Some(receipt) = fulfill_receipt {
+            self.record_gas_spend(&receipt);
+            if let Err(err) =
+                self.reconcile_fulfill_gas(batch_id, &fulfilled_requests, &receipt).await
+            {
+                tracing::warn!(
+                    "Failed to compare realized fulfill gas for batch {batch_id}: {err:?}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records the gas paid by a fulfillment transaction towards the runway estimate.
+    fn record_gas_spend(&self, receipt: &TransactionReceipt) {
+        let Some(gas_spends) = &self.gas_spends else {
+            return;
+        };
+        let cost_wei = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+        match gas_spends.lock() {
+            Ok(mut gas_spends) => gas_spends.record(now_timestamp(), cost_wei),
+            Err(_) => tracing::warn!("Gas spends lock poisoned, not recording gas spend"),
+        }
+    }
+
+    /// Compares the gas used by the fulfillment transaction of a batch to the estimate for its
+    /// requests, to help operators calibrate `fulfill_gas_estimate`.
+    async fn reconcile_fulfill_gas(
+        &self,
+        batch_id: usize,
+        requests: &[ProofRequest],
+        receipt: &TransactionReceipt,
+    ) -> Result<()> {
+        let chain_id = self.market.get_chain_id().await?;
+        let supported_selectors = SupportedSelectors::default();
+        let mut estimated = 0u64;
+        for request in requests {
+            estimated = estimated.saturating_add(
+                utils::estimate_gas_to_fulfill(
+                    &self.config,
+                    &supported_selectors,
+                    request,
+                    chain_id,
+                )
+                .await?,
+            );
+        }
+
+        let (ratio, multiplier) = {
+            let mut ratios = self
+                .fulfill_gas_ratios
+                .lock()
+                .map_err(|_| anyhow!("Fulfill gas ratios lock poisoned"))?;
+            (ratios.record(estimated, receipt.gas_used), ratios.suggested_multiplier())
+        };
+        let Some(ratio) = ratio else {
+            return Ok(());
+        };
+        tracing::info!(
+            fulfill_gas_estimate_ratio = ratio,
+            "Fulfillment of batch {batch_id} with {} requests used {} gas, estimated {estimated} (ratio {ratio:.3}, suggested fulfill_gas_estimate multiplier {:.3})",
+            requests.len(),
+            receipt.gas_used,
+            multiplier.unwrap_or(ratio),
+        );
+
         Ok(())
     }
 
@@ -814,6 +908,8 @@ mod tests {
             lock_price: Some(U256::ZERO),
             fulfillment_type: FulfillmentType::LockAndFulfill,
             error_msg: None,
+            labels: Vec::new(),
+            urgent: false,
             boundless_market_address: market_address,
             chain_id,
             total_cycles: None,
@@ -876,6 +972,17 @@ mod tests {
         process_next_batch(submitter, db, batch_id).await;
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn submit_batch_fulfill_gas_compared_to_estimate() {
+        let config = ConfigLock::default();
+        let (_anvil, submitter, db, batch_id) = build_submitter_and_batch(config).await;
+        process_next_batch(submitter.clone(), db, batch_id).await;
+
+        assert!(submitter.fulfill_gas_ratios.lock().unwrap().suggested_multiplier().is_some());
+        assert!(logs_contain("fulfill_gas_estimate_ratio"));
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn submit_batch_merged_txn() {
@@ -136,6 +136,13 @@ fn broker_args(
         rpc_retry_backoff: 200,
         rpc_retry_cu: 1000,
         log_json: false,
+        standby: false,
+        log_tick_traces: false,
+        record_session: None,
+        replay_session: None,
+        status_file: None,
+        status_interval_secs: 30,
+        explain_order: None,
     }
 }
 
@@ -56,8 +56,10 @@ pub async fn cancel_proof_and_fail_order(
 /// Estimate of gas for locking a single order
 /// Currently just uses the config estimate but this may change in the future
 pub async fn estimate_gas_to_lock(config: &ConfigLock, order: &OrderRequest) -> Result<u64> {
-    let mut estimate =
-        config.lock_all().context("Failed to read config")?.market.lockin_gas_estimate;
+    let mut estimate = config
+        .market_for_chain(order.chain_id)
+        .context("Failed to read config")?
+        .lockin_gas_estimate;
 
     if order.request.is_smart_contract_signed() {
         estimate += ERC1271_MAX_GAS_FOR_CHECK;
@@ -72,11 +74,12 @@ pub async fn estimate_gas_to_fulfill(
     config: &ConfigLock,
     supported_selectors: &SupportedSelectors,
     request: &ProofRequest,
+    chain_id: u64,
 ) -> Result<u64> {
     // TODO: Add gas costs for orders with large journals.
     let (base, groth16) = {
-        let config = config.lock_all().context("Failed to read config")?;
-        (config.market.fulfill_gas_estimate, config.market.groth16_verify_gas_estimate)
+        let config = config.market_for_chain(chain_id).context("Failed to read config")?;
+        (config.fulfill_gas_estimate, config.groth16_verify_gas_estimate)
     };
 
     let mut estimate = base;
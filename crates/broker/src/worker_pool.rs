@@ -0,0 +1,95 @@
+// Copyright 2025 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use url::Url;
+
+/// Timeout of a request querying the idle workers of a pool.
+const WORKER_POOL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pool of proving workers backing the broker.
+///
+/// When wired into the order monitor, the number of idle workers is used as the proving capacity
+/// instead of the static `max_concurrent_proofs` and `max_committed_cycles` config, so admission
+/// follows workers crashing or being added.
+#[async_trait]
+pub(crate) trait WorkerPool: Send + Sync {
+    /// Returns the number of workers that are currently idle.
+    async fn idle_workers(&self) -> Result<u32>;
+}
+
+pub(crate) type WorkerPoolObj = Arc<dyn WorkerPool>;
+
+/// Queries the idle workers of a pool over HTTP.
+///
+/// The endpoint is expected to respond to a GET request with a JSON body of the form
+/// `{"idle_workers": 4}`.
+pub(crate) struct HttpWorkerPool {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl HttpWorkerPool {
+    pub(crate) fn new(url: Url) -> Self {
+        Self { client: reqwest::Client::new(), url }
+    }
+}
+
+#[derive(Deserialize)]
+struct WorkerPoolStatus {
+    idle_workers: u32,
+}
+
+#[async_trait]
+impl WorkerPool for HttpWorkerPool {
+    async fn idle_workers(&self) -> Result<u32> {
+        let body = self
+            .client
+            .get(self.url.clone())
+            .timeout(WORKER_POOL_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to query worker pool at {}", self.url))?
+            .text()
+            .await
+            .context("Failed to read worker pool response")?;
+        let status: WorkerPoolStatus =
+            serde_json::from_str(&body).context("Invalid worker pool response")?;
+        Ok(status.idle_workers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn http_idle_workers() {
+        let server = MockServer::start();
+        let pool_mock = server.mock(|when, then| {
+            when.method(GET).path("/workers");
+            then.status(200).json_body(serde_json::json!({ "idle_workers": 3 }));
+        });
+
+        let pool = HttpWorkerPool::new(Url::parse(&server.url("/workers")).unwrap());
+        assert_eq!(pool.idle_workers().await.unwrap(), 3);
+        pool_mock.assert();
+    }
+}